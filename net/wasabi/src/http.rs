@@ -6,8 +6,10 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use noli::net::{lookup_host, SocketAddr, TcpStream};
 use saba_core::error::Error;
-use saba_core::http::HttpResponse;
+use saba_core::http::{Fetcher, HttpResponse};
+use saba_core::url::Url;
 
+#[derive(Debug)]
 pub struct HttpClient {}
 
 impl HttpClient {
@@ -55,9 +57,22 @@ impl HttpClient {
             }
             received.extend_from_slice(&buf[..bytes_read]);
         }
-        let response = core::str::from_utf8(&received)
-            .map_err(|err| Error::Network(format!("Invalid received response: {}", err)))?;
+        // The body is not necessarily text, e.g. a font.
+        HttpResponse::from_bytes(received)
+    }
+}
 
-        HttpResponse::new(response.to_string())
+impl Fetcher for HttpClient {
+    fn fetch(&self, url: &Url) -> Result<HttpResponse, Error> {
+        let port = url
+            .port()
+            .parse()
+            .map_err(|_| Error::Network(format!("invalid port number: {}", url.port())))?;
+        let mut path = url.path();
+        if !url.searchpart().is_empty() {
+            path.push('?');
+            path.push_str(&url.searchpart());
+        }
+        self.get(url.host(), port, path)
     }
 }
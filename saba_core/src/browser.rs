@@ -1,24 +1,53 @@
 use alloc::rc::Rc;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+use crate::http::Fetcher;
+use crate::renderer::css::cssom::CssStyleSheet;
+use crate::renderer::css::parser::parse_css_stylesheet;
+use crate::renderer::css::user_agent::user_agent_stylesheet;
+use crate::renderer::font::face::FontFace;
+use crate::renderer::font::matching::FontRegistry;
 use crate::renderer::page::Page;
 
+/// The time between frames in milliseconds, i.e. about 60 frames per second.
+pub const FRAME_INTERVAL: f64 = 16.0;
+
 #[derive(Debug, Clone)]
 pub struct Browser {
     active_page_index: usize,
 
     // TODO: does this need to be Rc<RefCell<T>> ?
     pages: Vec<Rc<RefCell<Page>>>,
+
+    fetcher: Option<Rc<dyn Fetcher>>,
+
+    /// <https://www.w3.org/TR/css-cascade-4/#cascade-origin-user>
+    user_stylesheet: Option<Rc<CssStyleSheet>>,
+
+    /// The fonts installed in the browser, which `@font-face` rules of pages add to.
+    fonts: Rc<FontRegistry>,
+
+    /// The time of the document timelines in milliseconds, which drives the animations.
+    clock: f64,
+
+    /// The wall-clock time of the last frame in milliseconds, while the current page needs frames.
+    last_frame_time: Option<f64>,
 }
 
 impl Browser {
     pub fn new() -> Rc<RefCell<Self>> {
-        let mut page = Page::new();
+        let mut page = Page::new(Rc::new(user_agent_stylesheet()));
 
         let browser = Rc::new(RefCell::new(Self {
             active_page_index: 0,
             pages: Vec::new(),
+            fetcher: None,
+            user_stylesheet: None,
+            fonts: Rc::new(FontRegistry::default()),
+            clock: 0.0,
+            last_frame_time: None,
         }));
 
         page.set_browser(Rc::downgrade(&browser));
@@ -30,4 +59,120 @@ impl Browser {
     pub fn current_page(&self) -> Rc<RefCell<Page>> {
         Rc::clone(&self.pages[self.active_page_index])
     }
+
+    /// Sets the network layer used by pages to fetch subresources.
+    /// Without it, subresources such as external stylesheets are not loaded.
+    pub fn set_fetcher(&mut self, fetcher: Rc<dyn Fetcher>) {
+        self.fetcher = Some(fetcher);
+    }
+
+    pub(crate) fn fetcher(&self) -> Option<Rc<dyn Fetcher>> {
+        self.fetcher.clone()
+    }
+
+    /// Sets the stylesheet written by the user, which is cascaded in the user origin,
+    /// i.e. overrides the user-agent stylesheet and is overridden by the pages except for `!important`.
+    /// It takes effect on the pages painted afterwards.
+    pub fn set_user_stylesheet(&mut self, css: String) {
+        self.user_stylesheet = Some(Rc::new(parse_css_stylesheet(css)));
+    }
+
+    pub(crate) fn user_stylesheet(&self) -> Option<Rc<CssStyleSheet>> {
+        self.user_stylesheet.clone()
+    }
+
+    /// Installs a font shipped with the browser, e.g. a BDF font for Japanese text.
+    /// It takes effect on the pages populated afterwards.
+    pub fn add_font(&mut self, face: FontFace) {
+        Rc::make_mut(&mut self.fonts).add(face);
+    }
+
+    /// Maps a generic family, e.g. `sans-serif`, to an installed family.
+    pub fn set_generic_family(&mut self, generic: &str, family: &str) {
+        Rc::make_mut(&mut self.fonts).set_generic_family(generic, family);
+    }
+
+    pub(crate) fn fonts(&self) -> Rc<FontRegistry> {
+        Rc::clone(&self.fonts)
+    }
+
+    /// Advances the clock by the milliseconds, e.g. once per frame.
+    /// The animations of the pages are sampled at the new time when they are painted next.
+    pub fn advance_clock(&mut self, delta: f64) {
+        self.clock += delta;
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/webappapis.html#rendering-opportunity>
+    ///
+    /// Called by the event loop of the UI with the wall-clock time in milliseconds, e.g. at every iteration.
+    /// While transitions or animations of the current page are running, advances the clock
+    /// by the time elapsed since the last frame once [FRAME_INTERVAL] has passed, and returns true
+    /// if so, in which case the UI paints the page again.
+    ///
+    /// It never waits, so that the event loop keeps handling inputs even during an infinite animation.
+    pub fn tick(&mut self, now: f64) -> bool {
+        if !self.current_page().borrow().needs_frame() {
+            self.last_frame_time = None;
+            return false;
+        }
+        let Some(last_frame_time) = self.last_frame_time else {
+            self.last_frame_time = Some(now);
+            return false;
+        };
+        if now - last_frame_time < FRAME_INTERVAL {
+            return false;
+        }
+        self.advance_clock(now - last_frame_time);
+        self.last_frame_time = Some(now);
+        true
+    }
+
+    /// The current time in milliseconds.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/webappapis.html#current-high-resolution-time>
+    pub fn current_time(&self) -> f64 {
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::layout::layout_object::LayoutSize;
+
+    use super::*;
+
+    #[test]
+    fn test_tick_infinite_animation() {
+        let browser = Browser::new();
+        let page = browser.borrow().current_page();
+        page.borrow_mut().populate_frame(
+            "<html><head><style>@keyframes fade { to { color: blue } } \
+             p { animation: fade 1s infinite }</style></head><body><p>a</p></body></html>"
+                .into(),
+        );
+        let viewport = LayoutSize {
+            width: 800,
+            height: 600,
+        };
+        page.borrow_mut().display_items(viewport);
+
+        // What the event loop of the UI does, returning to handle inputs between the frames.
+        let mut frames = 0;
+        for now in 0..=1000 {
+            if browser.borrow_mut().tick(now as f64) {
+                frames += 1;
+                page.borrow_mut().display_items(viewport);
+            }
+        }
+        assert!(page.borrow().needs_frame());
+        // A frame every 16ms from the first tick at 0ms, and the clock follows the wall clock.
+        assert_eq!(frames, 62);
+        assert_eq!(browser.borrow().current_time(), 992.0);
+
+        // No frame until the wall-clock time passes.
+        assert!(!browser.borrow_mut().tick(1000.0));
+        assert_eq!(browser.borrow().current_time(), 992.0);
+        assert!(browser.borrow_mut().tick(1008.0));
+        assert_eq!(browser.borrow().current_time(), 1008.0);
+    }
 }
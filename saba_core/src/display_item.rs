@@ -1,10 +1,16 @@
+use crate::renderer::dom::node::Node;
 use crate::renderer::layout::{
+    box_tree::BoxNode,
     computed_style::ComputedStyle,
     layout_object::{LayoutPoint, LayoutSize},
+    text::GlyphRun,
 };
+use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub enum DisplayItem {
     Rect {
         style: ComputedStyle,
@@ -15,5 +21,85 @@ pub enum DisplayItem {
         text: String,
         style: ComputedStyle,
         layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+        /// The glyphs to draw the text with, positioned relative to `layout_point`.
+        glyph_runs: Vec<GlyphRun>,
+        /// The node whose text this is.
+        node: BoxNode,
     },
 }
+
+/// Items are equal if they are painted the same, whichever nodes they are painted for.
+impl PartialEq for DisplayItem {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                DisplayItem::Rect {
+                    style,
+                    layout_point,
+                    layout_size,
+                },
+                DisplayItem::Rect {
+                    style: other_style,
+                    layout_point: other_layout_point,
+                    layout_size: other_layout_size,
+                },
+            ) => {
+                style == other_style
+                    && layout_point == other_layout_point
+                    && layout_size == other_layout_size
+            }
+            (
+                DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point,
+                    layout_size,
+                    glyph_runs,
+                    node: _,
+                },
+                DisplayItem::Text {
+                    text: other_text,
+                    style: other_style,
+                    layout_point: other_layout_point,
+                    layout_size: other_layout_size,
+                    glyph_runs: other_glyph_runs,
+                    node: _,
+                },
+            ) => {
+                text == other_text
+                    && style == other_style
+                    && layout_point == other_layout_point
+                    && layout_size == other_layout_size
+                    && glyph_runs == other_glyph_runs
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for DisplayItem {}
+
+impl DisplayItem {
+    /// The node painted at the point, if the point is in this item.
+    pub fn hit_test(&self, point: &LayoutPoint) -> Option<Rc<RefCell<Node>>> {
+        match self {
+            DisplayItem::Rect { .. } => None,
+            DisplayItem::Text {
+                layout_point,
+                layout_size,
+                node,
+                ..
+            } => {
+                let inside = (layout_point.x..layout_point.x + layout_size.width)
+                    .contains(&point.x)
+                    && (layout_point.y..layout_point.y + layout_size.height).contains(&point.y);
+                if inside {
+                    node.node()
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
@@ -5,5 +5,23 @@ pub enum Error {
     Network(String),
     UnexpectedInput(String),
     InvalidUI(String),
+    /// An exception thrown by DOM APIs.
+    Dom(DomException),
     Other(String),
 }
+
+/// <https://webidl.spec.whatwg.org/#idl-DOMException-error-names>
+///
+/// TypeError is not a DOMException, but it is listed here since DOM APIs throw it in the same manner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomException {
+    IndexSizeError,
+    HierarchyRequestError,
+    WrongDocumentError,
+    InvalidCharacterError,
+    NotFoundError,
+    NotSupportedError,
+    InvalidStateError,
+    InvalidNodeTypeError,
+    TypeError(String),
+}
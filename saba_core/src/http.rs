@@ -5,6 +5,17 @@ use alloc::{
 };
 
 use crate::error::Error;
+use crate::url::Url;
+
+#[cfg(test)]
+pub mod test_util;
+
+/// The network layer the renderer fetches subresources, e.g. stylesheets, through.
+///
+/// This crate does no I/O by itself, so the embedder provides the implementation.
+pub trait Fetcher: core::fmt::Debug {
+    fn fetch(&self, url: &Url) -> Result<HttpResponse, Error>;
+}
 
 #[derive(Debug, Clone)]
 pub struct HttpResponse {
@@ -12,7 +23,7 @@ pub struct HttpResponse {
     status_code: u32,
     reason: String,
     headers: Vec<Header>,
-    body: String,
+    body: Vec<u8>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,20 +40,37 @@ impl Header {
 
 impl HttpResponse {
     pub fn new(raw_response: String) -> Result<Self, Error> {
-        let preprocessed_response = raw_response.trim_start().replace("\r\n", "\n");
-
-        let (status_line, remaining) =
-            preprocessed_response
-                .split_once('\n')
-                .ok_or(Error::Network(format!(
-                    "invalid http response: {}",
-                    preprocessed_response
-                )))?;
-
-        let (headers, body) = match remaining.split_once("\n\n") {
-            Some((h, b)) => {
+        Self::from_bytes(raw_response.into_bytes())
+    }
+
+    /// Parses a response whose body may be binary, e.g. a font.
+    /// The status line and the headers must be UTF-8, while the body is kept as it is.
+    pub fn from_bytes(raw_response: Vec<u8>) -> Result<Self, Error> {
+        let start = raw_response
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(raw_response.len());
+        let raw_response = &raw_response[start..];
+
+        // The head ends with an empty line, which is either CRLF or LF.
+        let (head, body) = match find_empty_line(raw_response) {
+            Some((head_end, body_start)) => {
+                (&raw_response[..head_end], Some(&raw_response[body_start..]))
+            }
+            None => (raw_response, None),
+        };
+        let head = core::str::from_utf8(head)
+            .map_err(|_| Error::Network("invalid http response head".to_string()))?
+            .replace("\r\n", "\n");
+
+        let (status_line, remaining) = head
+            .split_once('\n')
+            .ok_or(Error::Network(format!("invalid http response: {}", head)))?;
+
+        let (headers, body) = match body {
+            Some(body) => {
                 let mut headers = Vec::new();
-                for header in h.split('\n') {
+                for header in remaining.split('\n').filter(|h| !h.is_empty()) {
                     let (name, value) = header
                         .split_once(':')
                         .ok_or(Error::Network(format!("invalid header: {}", header)))?;
@@ -51,9 +79,9 @@ impl HttpResponse {
                         value.trim().to_string(),
                     ));
                 }
-                (headers, b)
+                (headers, body.to_vec())
             }
-            None => (Vec::new(), remaining),
+            None => (Vec::new(), remaining.as_bytes().to_vec()),
         };
 
         let statuses: Vec<&str> = status_line.splitn(3, ' ').collect();
@@ -64,7 +92,7 @@ impl HttpResponse {
                 .map_err(|_| Error::Network(format!("invalid status code: {}", statuses[1])))?,
             reason: statuses[2].to_string(),
             headers,
-            body: body.to_string(),
+            body,
         })
     }
 
@@ -80,8 +108,14 @@ impl HttpResponse {
         self.reason.clone()
     }
 
+    /// The body decoded as UTF-8, whose line breaks are normalized to LF.
     pub fn body(&self) -> String {
-        self.body.clone()
+        String::from_utf8_lossy(&self.body).replace("\r\n", "\n")
+    }
+
+    /// The body as it is received.
+    pub fn body_bytes(&self) -> &[u8] {
+        &self.body
     }
 
     pub fn headers(&self) -> Vec<Header> {
@@ -97,6 +131,20 @@ impl HttpResponse {
     }
 }
 
+/// Finds the empty line ending the head of a response.
+/// Returns the end of the head, including the line break of its last line, and the start of the body.
+fn find_empty_line(bytes: &[u8]) -> Option<(usize, usize)> {
+    (0..bytes.len()).find_map(|i| {
+        if bytes[i..].starts_with(b"\r\n\r\n") {
+            Some((i + 2, i + 4))
+        } else if bytes[i..].starts_with(b"\n\n") {
+            Some((i + 1, i + 2))
+        } else {
+            None
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +200,14 @@ mod tests {
         let raw = "HTTP/1.1 200 OK".to_string();
         assert!(HttpResponse::new(raw).is_err());
     }
+
+    #[test]
+    fn test_binary_body() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Type: font/ttf\r\n\r\n".to_vec();
+        raw.extend([0x00, 0x01, 0xff, 0x0d, 0x0a]);
+        let res = HttpResponse::from_bytes(raw).unwrap();
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.header_value("Content-Type").unwrap(), "font/ttf");
+        assert_eq!(res.body_bytes(), [0x00, 0x01, 0xff, 0x0d, 0x0a]);
+    }
 }
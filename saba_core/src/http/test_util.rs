@@ -0,0 +1,35 @@
+//! Helpers shared by the tests fetching subresources.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use super::{Fetcher, HttpResponse};
+use crate::error::Error;
+use crate::url::Url;
+
+/// Serves the given resources, keyed by `host:port/path`, and 404 for the others.
+#[derive(Debug)]
+pub struct TestFetcher(Vec<(&'static str, String)>);
+
+impl TestFetcher {
+    pub fn new<B: Into<String>>(resources: Vec<(&'static str, B)>) -> Self {
+        Self(
+            resources
+                .into_iter()
+                .map(|(key, body)| (key, body.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Fetcher for TestFetcher {
+    fn fetch(&self, url: &Url) -> Result<HttpResponse, Error> {
+        let key = format!("{}:{}/{}", url.host(), url.port(), url.path());
+        let response = match self.0.iter().find(|(k, _)| *k == key) {
+            Some((_, body)) => format!("HTTP/1.1 200 OK\n\n{}", body),
+            None => "HTTP/1.1 404 Not Found\n\n".to_string(),
+        };
+        HttpResponse::new(response)
+    }
+}
@@ -2,7 +2,25 @@
 //! - Engine: https://github.com/servo/stylo
 //! - Parser: https://github.com/servo/rust-cssparser
 
+pub mod animation;
+pub mod calc;
+pub mod cascade;
 pub mod cssom;
+pub mod custom_properties;
+pub mod easing;
+pub mod font_face;
+pub mod inline_style;
+pub mod loader;
+pub mod matching;
+pub mod media;
 pub mod parser;
+#[cfg(test)]
+mod parsing_tests;
+pub mod properties;
+pub mod restyle;
+pub mod selector;
+pub mod shorthands;
+pub mod supports;
 pub mod token;
+pub mod user_agent;
 pub mod value;
@@ -0,0 +1,720 @@
+//! CSS transitions and animations, which change the computed values of an element over time.
+//!
+//! <https://www.w3.org/TR/css-transitions-1/>
+//! <https://www.w3.org/TR/css-animations-1/>
+//! <https://www.w3.org/TR/web-animations-1/#timing-model>
+//!
+//! Servo: <https://github.com/servo/servo/blob/main/components/script/animations.rs>
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::renderer::layout::computed_style::{
+    list_item, AnimationDirection, AnimationFillMode, AnimationPlayState, ComputeContext,
+    ComputedStyle, DisplayType, TransitionProperty, INTERPOLABLE_PROPERTIES,
+};
+
+use super::cascade::Stylist;
+use super::cssom::{CssDeclaration, CssKeyframeRule, CssKeyframesRule};
+use super::easing::EasingFunction;
+use super::shorthands;
+use super::value::{Color, ComponentValue};
+
+/// <https://www.w3.org/TR/web-animations-1/#animation-type>
+///
+/// Servo: <https://github.com/servo/stylo/blob/main/style/values/animated/mod.rs>
+pub trait Animate: Sized {
+    /// Interpolates between the values at the progress, which is 0 for `self` and 1 for `other`.
+    /// Returns None if they are not interpolable, e.g. different kinds of values.
+    fn animate(&self, other: &Self, progress: f64) -> Option<Self>;
+}
+
+impl Animate for f64 {
+    fn animate(&self, other: &Self, progress: f64) -> Option<Self> {
+        Some(self + (other - self) * progress)
+    }
+}
+
+/// <https://www.w3.org/TR/css-color-4/#interpolation>
+///
+/// Colors are interpolated in premultiplied sRGB, so that transparent colors do not tint the others.
+impl Animate for Color {
+    fn animate(&self, other: &Self, progress: f64) -> Option<Self> {
+        let alpha = |c: &Color| c.a as f64 / 255.0;
+        let a = alpha(self)
+            .animate(&alpha(other), progress)?
+            .clamp(0.0, 1.0);
+        if a == 0.0 {
+            return Some(Color::TRANSPARENT);
+        }
+        let channel = |from: u8, to: u8| {
+            let from = from as f64 * alpha(self);
+            let to = to as f64 * alpha(other);
+            // `as` saturates the values out of range.
+            (from.animate(&to, progress).unwrap_or(from) / a + 0.5) as u8
+        };
+        Some(Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: (a * 255.0 + 0.5) as u8,
+        })
+    }
+}
+
+/// The environment which the animations of an element are sampled in.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationContext<'a> {
+    /// Where the `@keyframes` rules are looked up.
+    pub stylist: &'a Stylist,
+    /// The cascaded declarations of the element, which the keyframes are computed with.
+    pub declarations: &'a [CssDeclaration],
+    pub parent_style: Option<&'a ComputedStyle>,
+    pub compute_context: &'a ComputeContext,
+    /// The current time of the document timeline in ms.
+    pub now: f64,
+}
+
+/// The transitions and animations running on an element.
+#[derive(Debug, Clone, Default)]
+pub struct ElementAnimations {
+    transitions: Vec<RunningTransition>,
+    animations: Vec<RunningAnimation>,
+    /// Whether any of them changes the style as time goes on.
+    active: bool,
+}
+
+/// <https://www.w3.org/TR/css-transitions-1/#starting>
+#[derive(Debug, Clone)]
+struct RunningTransition {
+    property: &'static str,
+    /// The time the transition starts after its delay, in ms.
+    start_time: f64,
+    /// In ms, which is positive.
+    duration: f64,
+    timing_function: EasingFunction,
+    /// The styles before and after the change, sharing with the other transitions started together.
+    from: Rc<ComputedStyle>,
+    to: Rc<ComputedStyle>,
+}
+
+/// <https://www.w3.org/TR/css-animations-1/#animations>
+#[derive(Debug, Clone)]
+struct RunningAnimation {
+    name: String,
+    /// The time the animation started, in ms, which is pushed back by the time it was paused.
+    start_time: f64,
+    /// The time the animation was paused at, if it is paused.
+    paused_at: Option<f64>,
+}
+
+impl ElementAnimations {
+    /// Whether the style changes as time goes on, so that it needs computing again at the next frame.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Starts and updates the transitions and animations for the style computed from the declarations,
+    /// and returns the style with them applied at the current time.
+    ///
+    /// `previous` is the style returned last time, which transitions start from.
+    pub fn update(
+        &mut self,
+        base: ComputedStyle,
+        previous: Option<&ComputedStyle>,
+        context: &AnimationContext,
+    ) -> ComputedStyle {
+        let now = context.now;
+        // <https://www.w3.org/TR/css-animations-1/#animations>
+        // An element with `display: none` has no animations.
+        if base.display == DisplayType::None {
+            *self = Self::default();
+            return base;
+        }
+
+        self.update_animations(&base, context);
+        let mut style = base.clone();
+        let mut animated = Vec::new();
+        let mut active = false;
+        for (i, name) in base.animation.name.iter().enumerate() {
+            let Some(running) = name
+                .as_ref()
+                .and_then(|name| self.animations.iter().find(|a| a.name == *name))
+            else {
+                continue;
+            };
+            let Some(rule) = context.stylist.keyframes(&running.name) else {
+                continue;
+            };
+            let timing = AnimationTiming::new(&base, i);
+            let local_time = running.paused_at.unwrap_or(now) - running.start_time;
+            let (progress, in_progress) = timing.directed_progress(local_time);
+            active |= in_progress && running.paused_at.is_none();
+            if let Some(progress) = progress {
+                let easing = list_item(&base.animation.timing_function, i);
+                apply_keyframes(
+                    &mut style,
+                    &base,
+                    rule,
+                    progress,
+                    easing,
+                    context,
+                    &mut animated,
+                );
+            }
+        }
+
+        // <https://www.w3.org/TR/css-transitions-1/#starting>
+        // Transitions do not start from or to `display: none`, nor for the animated properties.
+        match previous {
+            Some(previous) if previous.display != DisplayType::None => {
+                self.update_transitions(previous, &style, &animated, now)
+            }
+            _ => self.transitions.clear(),
+        }
+        // The transitions are removed after they finish, so that they are never started again
+        // from the values before the end.
+        self.transitions.retain(|t| now < t.start_time + t.duration);
+        for transition in &self.transitions {
+            // The value before the change is kept during the delay.
+            let progress = ((now - transition.start_time) / transition.duration).clamp(0.0, 1.0);
+            style.interpolate_property(
+                transition.property,
+                &transition.from,
+                &transition.to,
+                transition.timing_function.evaluate(progress),
+            );
+        }
+
+        self.active = active || !self.transitions.is_empty();
+        style
+    }
+
+    /// Starts the animations newly named in `animation-name`, and pauses or resumes the others.
+    fn update_animations(&mut self, base: &ComputedStyle, context: &AnimationContext) {
+        let now = context.now;
+        let mut animations = Vec::new();
+        for (i, name) in base.animation.name.iter().enumerate() {
+            // An animation without `@keyframes` does not run.
+            let Some(name) = name else {
+                continue;
+            };
+            if context.stylist.keyframes(name).is_none() {
+                continue;
+            }
+            // The animation of the same name keeps running, even if it moves in the list.
+            let mut running = match self.animations.iter().position(|a| a.name == *name) {
+                Some(j) => self.animations.remove(j),
+                None => RunningAnimation {
+                    name: name.clone(),
+                    start_time: now,
+                    paused_at: None,
+                },
+            };
+            let paused = list_item(&base.animation.play_state, i) == AnimationPlayState::Paused;
+            match (paused, running.paused_at) {
+                (true, None) => running.paused_at = Some(now),
+                (false, Some(paused_at)) => {
+                    running.start_time += now - paused_at;
+                    running.paused_at = None;
+                }
+                _ => {}
+            }
+            animations.push(running);
+        }
+        self.animations = animations;
+    }
+
+    /// <https://www.w3.org/TR/css-transitions-1/#starting>
+    ///
+    /// Starts the transitions of the properties whose values differ between the previous style
+    /// and the new one, and cancels the ones which no longer apply.
+    fn update_transitions(
+        &mut self,
+        previous: &ComputedStyle,
+        style: &ComputedStyle,
+        animated: &[&str],
+        now: f64,
+    ) {
+        let properties = transition_properties(style);
+        self.transitions.retain(|t| {
+            properties.iter().any(|(p, _)| *p == t.property) && !animated.contains(&t.property)
+        });
+
+        let from = Rc::new(previous.clone());
+        let to = Rc::new(style.clone());
+        for (property, i) in properties {
+            if animated.contains(&property) {
+                continue;
+            }
+            let running = self.transitions.iter().position(|t| t.property == property);
+            // The running transition continues if it ends at the new value.
+            if let Some(j) = running {
+                if self.transitions[j].to.property_eq(style, property) {
+                    continue;
+                }
+                self.transitions.remove(j);
+            }
+            let duration = list_item(&style.transition.duration, i);
+            let delay = list_item(&style.transition.delay, i);
+            if duration <= 0.0 || duration + delay <= 0.0 || previous.property_eq(style, property) {
+                continue;
+            }
+            self.transitions.push(RunningTransition {
+                property,
+                start_time: now + delay,
+                duration,
+                timing_function: list_item(&style.transition.timing_function, i),
+                from: Rc::clone(&from),
+                to: Rc::clone(&to),
+            });
+        }
+    }
+}
+
+/// The longhands in `transition-property` which can be transitioned, with the indices of the items
+/// they are in. The last item wins if a property is in more than one.
+fn transition_properties(style: &ComputedStyle) -> Vec<(&'static str, usize)> {
+    let mut properties: Vec<(&'static str, usize)> = Vec::new();
+    for (i, property) in style.transition.property.iter().enumerate() {
+        let names: Vec<&str> = match property {
+            TransitionProperty::All => INTERPOLABLE_PROPERTIES.to_vec(),
+            TransitionProperty::Property(name) => shorthands::longhands(name)
+                .map_or_else(|| vec![name.as_str()], |longhands| longhands.to_vec()),
+        };
+        for name in names {
+            // Takes the static name of the property.
+            let Some(name) = INTERPOLABLE_PROPERTIES.iter().find(|p| **p == name) else {
+                continue;
+            };
+            properties.retain(|(p, _)| p != name);
+            properties.push((name, i));
+        }
+    }
+    properties
+}
+
+/// The timing properties of an animation.
+#[derive(Debug, Clone, Copy)]
+struct AnimationTiming {
+    /// In ms.
+    duration: f64,
+    delay: f64,
+    iteration_count: f64,
+    direction: AnimationDirection,
+    fill_mode: AnimationFillMode,
+}
+
+impl AnimationTiming {
+    fn new(style: &ComputedStyle, i: usize) -> Self {
+        let animation = &style.animation;
+        Self {
+            duration: list_item(&animation.duration, i),
+            delay: list_item(&animation.delay, i),
+            iteration_count: list_item(&animation.iteration_count, i),
+            direction: list_item(&animation.direction, i),
+            fill_mode: list_item(&animation.fill_mode, i),
+        }
+    }
+
+    /// <https://www.w3.org/TR/web-animations-1/#calculating-the-directed-progress>
+    ///
+    /// Returns the progress in the current iteration at the local time of the animation,
+    /// which is None if the animation has no effect, and whether the animation is yet to finish.
+    fn directed_progress(&self, local_time: f64) -> (Option<f64>, bool) {
+        let active_duration = if self.duration == 0.0 || self.iteration_count == 0.0 {
+            0.0
+        } else {
+            self.duration * self.iteration_count
+        };
+        let active_time = local_time - self.delay;
+        let fills = |modes: [AnimationFillMode; 2]| modes.contains(&self.fill_mode);
+
+        // <https://www.w3.org/TR/web-animations-1/#animation-effect-phases-and-states>
+        let (overall_progress, in_progress) = if active_time < 0.0 {
+            if !fills([AnimationFillMode::Backwards, AnimationFillMode::Both]) {
+                return (None, true);
+            }
+            (0.0, true)
+        } else if active_time < active_duration {
+            (active_time / self.duration, true)
+        } else {
+            if !fills([AnimationFillMode::Forwards, AnimationFillMode::Both]) {
+                return (None, false);
+            }
+            (self.iteration_count, false)
+        };
+
+        // Infinitely many iterations of zero duration end at the end of an iteration,
+        // whose direction is forwards unless the animation is reversed.
+        if overall_progress.is_infinite() {
+            let reversed = self.direction == AnimationDirection::Reverse;
+            return (Some(if reversed { 0.0 } else { 1.0 }), in_progress);
+        }
+        // The progress is not negative, so truncating is flooring.
+        let mut iteration = overall_progress as u64;
+        let mut progress = overall_progress - iteration as f64;
+        // The end of the last iteration is the end of it rather than the start of the next one.
+        if !in_progress && progress == 0.0 && iteration > 0 {
+            iteration -= 1;
+            progress = 1.0;
+        }
+        let reversed = match self.direction {
+            AnimationDirection::Normal => false,
+            AnimationDirection::Reverse => true,
+            AnimationDirection::Alternate => iteration % 2 == 1,
+            AnimationDirection::AlternateReverse => iteration % 2 == 0,
+        };
+        let progress = if reversed { 1.0 - progress } else { progress };
+        (Some(progress), in_progress)
+    }
+}
+
+/// <https://www.w3.org/TR/css-animations-1/#keyframes>
+///
+/// Sets the properties in the keyframes to the values at the progress of the iteration,
+/// interpolated between the keyframes around it which have the property.
+/// The values at 0% and 100% are those of the base style unless specified.
+/// The animated properties are added to `animated`.
+fn apply_keyframes(
+    style: &mut ComputedStyle,
+    base: &ComputedStyle,
+    rule: &CssKeyframesRule,
+    progress: f64,
+    easing: EasingFunction,
+    context: &AnimationContext,
+    animated: &mut Vec<&'static str>,
+) {
+    // The style of each keyframe rule, and the easing function from it to the next keyframe.
+    let styles: Vec<(ComputedStyle, EasingFunction)> = rule
+        .css_rules
+        .iter()
+        .map(|keyframe| {
+            let easing = keyframe
+                .declarations
+                .declarations
+                .iter()
+                .rev()
+                .find_map(|d| match d.value.as_slice() {
+                    [ComponentValue::Easing(easing)]
+                        if d.property_name
+                            .eq_ignore_ascii_case("animation-timing-function") =>
+                    {
+                        Some(*easing)
+                    }
+                    _ => None,
+                })
+                .unwrap_or(easing);
+            (keyframe_style(keyframe, context), easing)
+        })
+        .collect();
+    // The keyframes sorted by the offset, where the later rules come later at the same offset.
+    let mut keyframes: Vec<(f64, usize)> = rule
+        .css_rules
+        .iter()
+        .enumerate()
+        .flat_map(|(i, keyframe)| keyframe.keys.iter().map(move |key| (key / 100.0, i)))
+        .collect();
+    keyframes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(core::cmp::Ordering::Equal));
+
+    for &property in crate::renderer::css::properties::LONGHANDS {
+        let declares = |keyframe: &CssKeyframeRule| {
+            keyframe
+                .declarations
+                .declarations
+                .iter()
+                .any(|d| d.property_name.eq_ignore_ascii_case(property))
+        };
+        // Properties of animations and transitions are ignored in keyframes.
+        if property.starts_with("animation-")
+            || property.starts_with("transition-")
+            || !rule.css_rules.iter().any(declares)
+        {
+            continue;
+        }
+        let mut frames: Vec<(f64, &ComputedStyle, EasingFunction)> = keyframes
+            .iter()
+            .filter(|(_, i)| declares(&rule.css_rules[*i]))
+            .map(|(offset, i)| (*offset, &styles[*i].0, styles[*i].1))
+            .collect();
+        if frames.first().map_or(true, |f| f.0 > 0.0) {
+            frames.insert(0, (0.0, base, easing));
+        }
+        if frames.last().map_or(true, |f| f.0 < 1.0) {
+            frames.push((1.0, base, easing));
+        }
+
+        let i = frames
+            .iter()
+            .rposition(|f| f.0 <= progress)
+            .unwrap_or(0)
+            .min(frames.len() - 2);
+        let ((start, from, easing), (end, to, _)) = (frames[i], frames[i + 1]);
+        let local_progress = if end > start {
+            (progress - start) / (end - start)
+        } else {
+            1.0
+        };
+        if style
+            .interpolate_property(property, from, to, easing.evaluate(local_progress))
+            .is_some()
+        {
+            animated.push(property);
+        }
+    }
+}
+
+/// Computes the style of a keyframe, whose declarations override the normal declarations
+/// of the element but not the important ones.
+///
+/// <https://www.w3.org/TR/css-cascade-4/#cascade-origin-animation>
+fn keyframe_style(keyframe: &CssKeyframeRule, context: &AnimationContext) -> ComputedStyle {
+    let important = context
+        .declarations
+        .iter()
+        .position(|d| d.important)
+        .unwrap_or(context.declarations.len());
+    let mut declarations = context.declarations[..important].to_vec();
+    declarations.extend(keyframe.declarations.declarations.iter().cloned());
+    declarations.extend_from_slice(&context.declarations[important..]);
+    ComputedStyle::compute(&declarations, context.parent_style, context.compute_context)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::renderer::css::media::Device;
+    use crate::renderer::css::parser::{parse_css_stylesheet, parse_style_attribute};
+    use crate::renderer::layout::computed_style::LengthPercentageOrAuto;
+
+    use super::super::cascade::Origin;
+    use super::*;
+
+    const CONTEXT: ComputeContext = ComputeContext {
+        root_font_size: 16.0,
+        viewport_width: 800.0,
+        viewport_height: 600.0,
+    };
+
+    struct Element {
+        stylist: Stylist,
+        animations: ElementAnimations,
+        style: Option<ComputedStyle>,
+    }
+
+    impl Element {
+        fn new(css: &str) -> Self {
+            let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+            stylist.add_stylesheet(&parse_css_stylesheet(css.to_string()), Origin::Author);
+            Self {
+                stylist,
+                animations: ElementAnimations::default(),
+                style: None,
+            }
+        }
+
+        /// Styles the element with the declarations at the time.
+        fn style(&mut self, declarations: &str, now: f64) -> ComputedStyle {
+            // The cascade puts the important declarations last.
+            let mut declarations = parse_style_attribute(declarations.into()).declarations;
+            declarations.sort_by_key(|d| d.important);
+            let context = AnimationContext {
+                stylist: &self.stylist,
+                declarations: &declarations,
+                parent_style: None,
+                compute_context: &CONTEXT,
+                now,
+            };
+            let base = ComputedStyle::compute(&declarations, None, &CONTEXT);
+            let style = self.animations.update(base, self.style.as_ref(), &context);
+            self.style = Some(style.clone());
+            style
+        }
+    }
+
+    #[test]
+    fn test_animate() {
+        let red = Color::rgb(255, 0, 0);
+        let blue = Color::rgb(0, 0, 255);
+        assert_eq!(red.animate(&blue, 0.25), Some(Color::rgb(191, 0, 64)));
+        // The channels of transparent colors do not count.
+        assert_eq!(
+            Color::TRANSPARENT.animate(&blue, 0.5),
+            Some(Color {
+                r: 0,
+                g: 0,
+                b: 255,
+                a: 128
+            })
+        );
+        assert_eq!(1.0_f64.animate(&3.0, 1.5), Some(4.0));
+
+        let from = ComputedStyle {
+            width: LengthPercentageOrAuto::Length(100.0),
+            height: LengthPercentageOrAuto::Auto,
+            ..Default::default()
+        };
+        let to = ComputedStyle {
+            width: LengthPercentageOrAuto::Percentage(50.0),
+            height: LengthPercentageOrAuto::Length(10.0),
+            font_weight: 700,
+            ..Default::default()
+        };
+        let mut style = ComputedStyle::default();
+        style.interpolate_property("width", &from, &to, 0.5);
+        style.interpolate_property("height", &from, &to, 0.4);
+        style.interpolate_property("font-weight", &from, &to, 0.5);
+        assert_eq!(style.width.resolve(100.0), Some(75.0));
+        assert_eq!(style.height, LengthPercentageOrAuto::Auto);
+        assert_eq!(style.font_weight, 550);
+        // Overshooting values are clamped.
+        style.interpolate_property("width", &to, &from, -2.0);
+        assert_eq!(style.width.resolve(100.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut element = Element::new("");
+        let transition = "transition: width 100ms linear 50ms, margin 100ms";
+        let style = element.style(&alloc::format!("{transition}; width: 0px"), 0.0);
+        assert_eq!(style.width, LengthPercentageOrAuto::Length(0.0));
+        assert!(!element.animations.is_active());
+
+        // The value before the change is kept during the delay.
+        let style = element.style(&alloc::format!("{transition}; width: 100px"), 1000.0);
+        assert_eq!(style.width, LengthPercentageOrAuto::Length(0.0));
+        assert!(element.animations.is_active());
+        let style = element.style(&alloc::format!("{transition}; width: 100px"), 1100.0);
+        assert_eq!(style.width, LengthPercentageOrAuto::Length(50.0));
+
+        // Changing the value again starts from the current value.
+        let style = element.style(&alloc::format!("{transition}; width: 0px"), 1100.0);
+        assert_eq!(style.width, LengthPercentageOrAuto::Length(50.0));
+        let style = element.style(&alloc::format!("{transition}; width: 0px"), 1200.0);
+        assert_eq!(style.width, LengthPercentageOrAuto::Length(25.0));
+        let style = element.style(&alloc::format!("{transition}; width: 0px"), 1250.0);
+        assert_eq!(style.width, LengthPercentageOrAuto::Length(0.0));
+        assert!(!element.animations.is_active());
+
+        // Discrete properties and properties not in the list do not transition.
+        let style = element.style(
+            &alloc::format!("{transition}; width: 0px; display: block; height: 10px"),
+            2000.0,
+        );
+        assert_eq!(style.display, DisplayType::Block);
+        assert_eq!(style.height, LengthPercentageOrAuto::Length(10.0));
+        assert!(!element.animations.is_active());
+
+        // Removing the property from the list cancels the transition.
+        element.style(&alloc::format!("{transition}; margin-left: 10px"), 3000.0);
+        assert!(element.animations.is_active());
+        let style = element.style("transition: none; margin-left: 10px", 3010.0);
+        assert_eq!(style.margin.left, LengthPercentageOrAuto::Length(10.0));
+        assert!(!element.animations.is_active());
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let mut element = Element::new(
+            "@keyframes grow { from { width: 0px } 50% { width: 100px; margin-left: 20px } } \
+             @keyframes blink { to { display: none } }",
+        );
+        let animation = "animation: grow 1s linear 2 alternate forwards; width: 10px";
+        // The width goes from 0 to 100 at 50%, and to the base value at 100%.
+        let width = |element: &mut Element, now| element.style(animation, now).width;
+        assert_eq!(
+            width(&mut element, 0.0),
+            LengthPercentageOrAuto::Length(0.0)
+        );
+        assert_eq!(
+            width(&mut element, 250.0),
+            LengthPercentageOrAuto::Length(50.0)
+        );
+        assert_eq!(
+            width(&mut element, 750.0),
+            LengthPercentageOrAuto::Length(55.0)
+        );
+        let style = element.style(animation, 250.0);
+        assert_eq!(style.margin.left, LengthPercentageOrAuto::Length(10.0));
+        // The second iteration is reversed.
+        assert_eq!(
+            width(&mut element, 1250.0),
+            LengthPercentageOrAuto::Length(55.0)
+        );
+        assert!(element.animations.is_active());
+        // Filled forwards with the end of the last iteration, which is reversed.
+        assert_eq!(
+            width(&mut element, 5000.0),
+            LengthPercentageOrAuto::Length(0.0)
+        );
+        assert!(!element.animations.is_active());
+
+        // Pausing stops the time of the animation.
+        let mut element = Element::new("@keyframes grow { to { width: 100px } }");
+        let running = "animation: grow 1s linear; width: 0px";
+        let paused = "animation: grow 1s linear paused; width: 0px";
+        element.style(running, 0.0);
+        assert_eq!(
+            element.style(paused, 500.0).width,
+            LengthPercentageOrAuto::Length(50.0)
+        );
+        assert!(!element.animations.is_active());
+        assert_eq!(
+            element.style(paused, 800.0).width,
+            LengthPercentageOrAuto::Length(50.0)
+        );
+        // Resumed from where it was paused.
+        assert_eq!(
+            element.style(running, 1000.0).width,
+            LengthPercentageOrAuto::Length(50.0)
+        );
+        assert_eq!(
+            element.style(running, 1200.0).width,
+            LengthPercentageOrAuto::Length(70.0)
+        );
+        // Without fill, the animation has no effect after it ends.
+        assert_eq!(
+            element.style(running, 2000.0).width,
+            LengthPercentageOrAuto::Length(0.0)
+        );
+
+        // Important declarations override the keyframes, and discrete values flip at the middle.
+        let mut element = Element::new("@keyframes blink { to { display: none; width: 9px } }");
+        let blink = "animation: blink 1s linear; width: 1px !important; display: block";
+        assert_eq!(element.style(blink, 0.0).display, DisplayType::Block);
+        let style = element.style(blink, 600.0);
+        assert_eq!(style.display, DisplayType::None);
+        assert_eq!(style.width, LengthPercentageOrAuto::Length(1.0));
+    }
+
+    #[test]
+    fn test_directed_progress() {
+        let timing = |iteration_count, direction| AnimationTiming {
+            duration: 0.0,
+            delay: 0.0,
+            iteration_count,
+            direction,
+            fill_mode: AnimationFillMode::Forwards,
+        };
+        assert_eq!(
+            timing(2.0, AnimationDirection::Alternate).directed_progress(0.0),
+            (Some(0.0), false)
+        );
+        // Infinitely many iterations of zero duration are at the end of a forwards iteration.
+        assert_eq!(
+            timing(f64::INFINITY, AnimationDirection::Normal).directed_progress(0.0),
+            (Some(1.0), false)
+        );
+        assert_eq!(
+            timing(f64::INFINITY, AnimationDirection::AlternateReverse).directed_progress(0.0),
+            (Some(1.0), false)
+        );
+        assert_eq!(
+            timing(f64::INFINITY, AnimationDirection::Reverse).directed_progress(0.0),
+            (Some(0.0), false)
+        );
+    }
+}
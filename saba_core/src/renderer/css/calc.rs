@@ -0,0 +1,639 @@
+//! <https://www.w3.org/TR/css-values-4/#math>
+//!
+//! Math functions are parsed into calculation trees, which are type-checked at parse time,
+//! simplified as much as possible at parse time and again at computed-value time,
+//! and finally resolved against the percentage basis during layout.
+//!
+//! Servo: <https://github.com/servo/stylo/blob/main/style/values/specified/calc.rs>
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::parser;
+use super::token::{CssToken, Numeric};
+use super::value::{Length, LengthUnit};
+
+/// The largest magnitude of a top-level calculation, to which infinities are clamped.
+///
+/// It is far below `f64::MAX` so that the arithmetic on it during layout stays finite.
+const MAX_VALUE: f64 = f32::MAX as f64;
+
+/// <https://www.w3.org/TR/css-values-4/#top-level-calculation>
+///
+/// A top-level calculation which would produce NaN produces 0 instead,
+/// and one which would produce an infinity produces the largest finite value of its sign.
+fn censor(value: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(-MAX_VALUE, MAX_VALUE)
+    }
+}
+
+/// <https://www.w3.org/TR/css-values-4/#calculation-tree>
+///
+/// Products and negations are folded into the leaves as they are always multiplied by a number,
+/// and `clamp(MIN, VAL, MAX)` is represented as `max(MIN, min(VAL, MAX))`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcNode {
+    Number(f64),
+    Length(Length),
+    Percentage(f64),
+    Sum(Vec<CalcNode>),
+    Min(Vec<CalcNode>),
+    Max(Vec<CalcNode>),
+}
+
+/// <https://www.w3.org/TR/css-values-4/#determine-the-type-of-a-calculation>
+///
+/// Only the types which can be the value of the supported properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalcType {
+    Number,
+    Length,
+    Percentage,
+    /// A length with percentages, which resolve to lengths.
+    LengthPercentage,
+}
+
+impl CalcType {
+    /// The type of the sum of the two, or of the arguments of `min()` and `max()`.
+    fn add(self, other: Self) -> Option<Self> {
+        use CalcType::*;
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (Number, _) | (_, Number) => None,
+            _ => Some(LengthPercentage),
+        }
+    }
+
+    /// Returns true if a value of this type is allowed where `allowed` is.
+    pub fn matches(self, allowed: Self) -> bool {
+        self == allowed
+            || allowed == Self::LengthPercentage && matches!(self, Self::Length | Self::Percentage)
+    }
+}
+
+impl CalcNode {
+    /// <https://www.w3.org/TR/css-values-4/#determine-the-type-of-a-calculation>
+    pub fn calc_type(&self) -> Option<CalcType> {
+        match self {
+            Self::Number(_) => Some(CalcType::Number),
+            Self::Length(_) => Some(CalcType::Length),
+            Self::Percentage(_) => Some(CalcType::Percentage),
+            Self::Sum(children) | Self::Min(children) | Self::Max(children) => {
+                let mut children = children.iter().map(|c| c.calc_type());
+                let first = children.next()??;
+                children.try_fold(first, |t, c| t.add(c?))
+            }
+        }
+    }
+
+    /// Returns the value if the calculation is a number, which is always simplified to a leaf.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn multiply(self, k: f64) -> Self {
+        let multiply_all =
+            |children: Vec<Self>| children.into_iter().map(|c| c.multiply(k)).collect();
+        match self {
+            Self::Number(n) => Self::Number(n * k),
+            Self::Length(length) => Self::Length(Length {
+                value: length.value * k,
+                unit: length.unit,
+            }),
+            Self::Percentage(p) => Self::Percentage(p * k),
+            Self::Sum(children) => Self::Sum(multiply_all(children)),
+            // Multiplying by a negative number reverses the order.
+            Self::Min(children) if k < 0.0 => Self::Max(multiply_all(children)),
+            Self::Max(children) if k < 0.0 => Self::Min(multiply_all(children)),
+            Self::Min(children) => Self::Min(multiply_all(children)),
+            Self::Max(children) => Self::Max(multiply_all(children)),
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-values-4/#calc-simplification>
+    ///
+    /// Sums are flattened, and the values of the same unit in sums, `min()` and `max()` are combined.
+    fn simplify(self) -> Self {
+        match self {
+            Self::Sum(children) => {
+                let mut leaves: Vec<Self> = Vec::new();
+                let mut others = Vec::new();
+                for child in children.into_iter().map(Self::simplify) {
+                    let flattened = match child {
+                        Self::Sum(grandchildren) => grandchildren,
+                        child => alloc::vec![child],
+                    };
+                    for child in flattened {
+                        match leaves.iter_mut().find(|leaf| leaf.same_unit(&child)) {
+                            Some(leaf) => *leaf = leaf.combine(&child, |a, b| a + b),
+                            None if child.is_leaf() => leaves.push(child),
+                            None => others.push(child),
+                        }
+                    }
+                }
+                leaves.sort_by_key(Self::sort_key);
+                leaves.extend(others);
+                if leaves.len() == 1 {
+                    leaves.remove(0)
+                } else {
+                    Self::Sum(leaves)
+                }
+            }
+            Self::Min(children) => Self::simplify_min_max(children, true),
+            Self::Max(children) => Self::simplify_min_max(children, false),
+            leaf => leaf,
+        }
+    }
+
+    /// The arguments of the same unit are combined, and the function is evaluated if only one is left.
+    fn simplify_min_max(children: Vec<Self>, min: bool) -> Self {
+        let pick = |a: f64, b: f64| if (a < b) == min { a } else { b };
+        let mut result: Vec<Self> = Vec::new();
+        for child in children.into_iter().map(Self::simplify) {
+            match result.iter_mut().find(|c| c.same_unit(&child)) {
+                Some(c) => *c = c.combine(&child, pick),
+                None => result.push(child),
+            }
+        }
+        match (result.len(), min) {
+            (1, _) => result.remove(0),
+            (_, true) => Self::Min(result),
+            (_, false) => Self::Max(result),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        matches!(
+            self,
+            Self::Number(_) | Self::Length(_) | Self::Percentage(_)
+        )
+    }
+
+    fn same_unit(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(_), Self::Number(_)) | (Self::Percentage(_), Self::Percentage(_)) => true,
+            (Self::Length(a), Self::Length(b)) => a.unit == b.unit,
+            _ => false,
+        }
+    }
+
+    /// Combines the values of two leaves of the same unit.
+    fn combine(&self, other: &Self, f: impl Fn(f64, f64) -> f64) -> Self {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => Self::Number(f(*a, *b)),
+            (Self::Percentage(a), Self::Percentage(b)) => Self::Percentage(f(*a, *b)),
+            (Self::Length(a), Self::Length(b)) => Self::Length(Length {
+                value: f(a.value, b.value),
+                unit: a.unit,
+            }),
+            _ => unreachable!("not leaves of the same unit"),
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-values-4/#sort-a-calculations-children>
+    ///
+    /// Numbers, percentages, and then dimensions in the order of their units.
+    fn sort_key(&self) -> (u8, &'static str) {
+        match self {
+            Self::Number(_) => (0, ""),
+            Self::Percentage(_) => (1, ""),
+            Self::Length(length) => (2, length.unit.name()),
+            _ => (3, ""),
+        }
+    }
+
+    /// Censors the leaves of a top-level calculation, which is simplified so that the leaves are
+    /// its numeric parts, except for the ones which cannot be combined until layout.
+    fn censor_leaves(self) -> Self {
+        let censor_all =
+            |children: Vec<Self>| children.into_iter().map(Self::censor_leaves).collect();
+        match self {
+            Self::Number(n) => Self::Number(censor(n)),
+            Self::Length(length) => Self::Length(Length {
+                value: censor(length.value),
+                unit: length.unit,
+            }),
+            Self::Percentage(p) => Self::Percentage(censor(p)),
+            Self::Sum(children) => Self::Sum(censor_all(children)),
+            Self::Min(children) => Self::Min(censor_all(children)),
+            Self::Max(children) => Self::Max(censor_all(children)),
+        }
+    }
+
+    /// Converts the lengths into px, and simplifies the result, at computed-value time.
+    pub fn compute(&self, resolve_length: &dyn Fn(Length) -> f64) -> Self {
+        let compute_all =
+            |children: &[Self]| children.iter().map(|c| c.compute(resolve_length)).collect();
+        let node = match self {
+            Self::Length(length) => Self::Length(Length::px(resolve_length(*length))),
+            Self::Sum(children) => Self::Sum(compute_all(children)),
+            Self::Min(children) => Self::Min(compute_all(children)),
+            Self::Max(children) => Self::Max(compute_all(children)),
+            leaf => leaf.clone(),
+        };
+        node.simplify()
+    }
+
+    /// Evaluates the calculation, where percentages are relative to `percentage_basis`.
+    /// Lengths must have been converted into px.
+    pub fn resolve(&self, percentage_basis: f64) -> f64 {
+        censor(self.evaluate(percentage_basis))
+    }
+
+    fn evaluate(&self, percentage_basis: f64) -> f64 {
+        let (children, init, f): (_, _, fn(f64, f64) -> f64) = match self {
+            Self::Number(n) => return *n,
+            Self::Length(length) => return length.value,
+            Self::Percentage(p) => return percentage_basis * p / 100.0,
+            Self::Sum(children) => (children, 0.0, |a, b| a + b),
+            Self::Min(children) => (children, f64::INFINITY, f64::min),
+            Self::Max(children) => (children, f64::NEG_INFINITY, f64::max),
+        };
+        children
+            .iter()
+            .map(|c| c.evaluate(percentage_basis))
+            .fold(init, f)
+    }
+
+    /// <https://www.w3.org/TR/css-values-4/#combining-values>
+    ///
+    /// Interpolates between two calculations, e.g. a length and a percentage,
+    /// as `calc(self * (1 - progress) + other * progress)`.
+    pub fn interpolate(&self, other: &Self, progress: f64) -> Self {
+        Self::Sum(alloc::vec![
+            self.clone().multiply(1.0 - progress),
+            other.clone().multiply(progress),
+        ])
+        .simplify()
+    }
+
+    /// Clamps the value to be non-negative, for properties which do not allow negative values.
+    ///
+    /// <https://www.w3.org/TR/css-values-4/#calc-range>
+    pub fn clamp_non_negative(self) -> Self {
+        let zero = match self.calc_type() {
+            Some(CalcType::Number) => Self::Number(0.0),
+            Some(CalcType::Percentage) => Self::Percentage(0.0),
+            _ => Self::Length(Length::px(0.0)),
+        };
+        Self::Max(alloc::vec![self, zero]).simplify()
+    }
+
+    fn fmt_node(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Length(length) => write!(f, "{length}"),
+            Self::Percentage(p) => write!(f, "{p}%"),
+            Self::Sum(children) => {
+                for (i, child) in children.iter().enumerate() {
+                    let negated = match child {
+                        Self::Number(n) | Self::Percentage(n) => *n < 0.0,
+                        Self::Length(length) => length.value < 0.0,
+                        _ => false,
+                    };
+                    match (i, negated) {
+                        (0, _) => child.fmt_node(f)?,
+                        (_, true) => {
+                            write!(f, " - ")?;
+                            child.clone().multiply(-1.0).fmt_node(f)?
+                        }
+                        (_, false) => {
+                            write!(f, " + ")?;
+                            child.fmt_node(f)?
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Self::Min(children) | Self::Max(children) => {
+                let name = if matches!(self, Self::Min(_)) {
+                    "min"
+                } else {
+                    "max"
+                };
+                write!(f, "{name}(")?;
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    child.fmt_node(f)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/css-values-4/#serialize-a-math-function>
+impl fmt::Display for CalcNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Min(_) | Self::Max(_) => self.fmt_node(f),
+            _ => {
+                write!(f, "calc(")?;
+                self.fmt_node(f)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Parses a math function whose type matches `allowed`.
+///
+/// <https://www.w3.org/TR/css-values-4/#calc-syntax>
+pub fn parse_math_function(value: &parser::ComponentValue, allowed: CalcType) -> Option<CalcNode> {
+    let parser::ComponentValue::Function(function) = value else {
+        return None;
+    };
+    let node = CalcParser::parse_function(function)?;
+    node.calc_type()?
+        .matches(allowed)
+        .then(|| node.censor_leaves())
+}
+
+struct CalcParser<'a> {
+    values: &'a [parser::ComponentValue],
+    pos: usize,
+}
+
+impl<'a> CalcParser<'a> {
+    /// `calc( <calc-sum> )`, `min( <calc-sum># )`, `max( <calc-sum># )`
+    /// and `clamp( <calc-sum>#{3} )`
+    fn parse_function(function: &'a parser::Function) -> Option<CalcNode> {
+        let mut args = Vec::new();
+        for arg in function
+            .value
+            .split(|v| *v == parser::ComponentValue::PreservedToken(CssToken::Comma))
+        {
+            let mut parser = CalcParser {
+                values: arg,
+                pos: 0,
+            };
+            let node = parser.parse_sum()?;
+            parser.skip_whitespace();
+            if parser.pos != arg.len() {
+                return None;
+            }
+            args.push(node);
+        }
+        let node = match (function.name.to_ascii_lowercase().as_str(), args.len()) {
+            ("calc", 1) => args.remove(0),
+            ("min", _) => CalcNode::Min(args),
+            ("max", _) => CalcNode::Max(args),
+            ("clamp", 3) => {
+                let max = args.pop().unwrap();
+                let value = args.pop().unwrap();
+                let min = args.pop().unwrap();
+                CalcNode::Max(alloc::vec![min, CalcNode::Min(alloc::vec![value, max])])
+            }
+            _ => return None,
+        };
+        // Type errors make the function invalid.
+        node.calc_type()?;
+        Some(node.simplify())
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.values.get(self.pos)
+            == Some(&parser::ComponentValue::PreservedToken(
+                CssToken::Whitespace,
+            ))
+        {
+            self.pos += 1;
+        }
+        self.pos != start
+    }
+
+    /// `<calc-product> [ [ '+' | '-' ] <calc-product> ]*`
+    ///
+    /// `+` and `-` must be surrounded by whitespace.
+    fn parse_sum(&mut self) -> Option<CalcNode> {
+        self.skip_whitespace();
+        let mut children = alloc::vec![self.parse_product()?];
+        loop {
+            let start = self.pos;
+            let sign = match (self.skip_whitespace(), self.next_delim()) {
+                (true, Some('+')) => 1.0,
+                (true, Some('-')) => -1.0,
+                _ => {
+                    self.pos = start;
+                    break;
+                }
+            };
+            if !self.skip_whitespace() {
+                return None;
+            }
+            children.push(self.parse_product()?.multiply(sign));
+        }
+        let node = if children.len() == 1 {
+            children.remove(0)
+        } else {
+            CalcNode::Sum(children)
+        };
+        node.calc_type()?;
+        Some(node)
+    }
+
+    /// `<calc-value> [ [ '*' | '/' ] <calc-value> ]*`
+    ///
+    /// One of the operands of `*` and the right one of `/` must be numbers.
+    fn parse_product(&mut self) -> Option<CalcNode> {
+        let mut node = self.parse_value()?;
+        loop {
+            let start = self.pos;
+            self.skip_whitespace();
+            let op = match self.next_delim() {
+                Some(op @ ('*' | '/')) => op,
+                _ => {
+                    self.pos = start;
+                    return Some(node);
+                }
+            };
+            self.skip_whitespace();
+            let rhs = self.parse_value()?.simplify();
+            node = match (op, node.clone().simplify().as_number(), rhs.as_number()) {
+                ('*', _, Some(k)) => node.multiply(k),
+                ('*', Some(k), None) => rhs.multiply(k),
+                ('/', _, Some(k)) => node.multiply(1.0 / k),
+                _ => return None,
+            };
+        }
+    }
+
+    /// `<number> | <dimension> | <percentage> | <calc-keyword> | ( <calc-sum> )`, or a math function.
+    fn parse_value(&mut self) -> Option<CalcNode> {
+        let value = self.values.get(self.pos)?;
+        self.pos += 1;
+        match value {
+            parser::ComponentValue::PreservedToken(token) => match token {
+                CssToken::Number(Numeric { value: n, .. }) => Some(CalcNode::Number(*n)),
+                CssToken::Percentage(Numeric { value: p, .. }) => Some(CalcNode::Percentage(*p)),
+                CssToken::Dimension(Numeric { value, .. }, unit) => {
+                    Some(CalcNode::Length(Length {
+                        value: *value,
+                        unit: LengthUnit::from_name(unit)?,
+                    }))
+                }
+                // <https://www.w3.org/TR/css-values-4/#calc-constants>
+                CssToken::Ident(name) => match name.to_ascii_lowercase().as_str() {
+                    "e" => Some(CalcNode::Number(core::f64::consts::E)),
+                    "pi" => Some(CalcNode::Number(core::f64::consts::PI)),
+                    _ => None,
+                },
+                _ => None,
+            },
+            parser::ComponentValue::SimpleBlock(block)
+                if block.associated_token == CssToken::OpenParenthesis =>
+            {
+                let mut parser = CalcParser {
+                    values: &block.value,
+                    pos: 0,
+                };
+                let node = parser.parse_sum()?;
+                parser.skip_whitespace();
+                (parser.pos == block.value.len()).then_some(node)
+            }
+            parser::ComponentValue::Function(function) => Self::parse_function(function),
+            _ => None,
+        }
+    }
+
+    fn next_delim(&mut self) -> Option<char> {
+        match self.values.get(self.pos) {
+            Some(parser::ComponentValue::PreservedToken(CssToken::Delim(c))) => {
+                self.pos += 1;
+                Some(*c)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use super::super::parser::StyleSheetParser;
+    use super::super::token::CssTokenizer;
+    use super::*;
+
+    fn parse(value: &str, allowed: CalcType) -> Option<String> {
+        let values = StyleSheetParser::new(CssTokenizer::new(value.to_string()))
+            .parse_list_of_component_values();
+        parse_math_function(&values[0], allowed).map(|node| node.to_string())
+    }
+
+    #[test]
+    fn test_parse() {
+        use CalcType::*;
+        assert_eq!(
+            parse("calc(1px + 2px)", Length).as_deref(),
+            Some("calc(3px)")
+        );
+        assert_eq!(
+            parse("calc(2em - (1px + 10%) * 2 + 3px)", LengthPercentage).as_deref(),
+            Some("calc(-20% + 2em + 1px)")
+        );
+        assert_eq!(
+            parse("calc(10% - 5%)", LengthPercentage).as_deref(),
+            Some("calc(5%)")
+        );
+        assert_eq!(
+            parse("calc(1 + 2 * 3 / 2)", Number).as_deref(),
+            Some("calc(4)")
+        );
+        assert_eq!(
+            parse("min(10px, 2em, 5px)", Length).as_deref(),
+            Some("min(5px, 2em)")
+        );
+        assert_eq!(parse("max(1px, 2px)", Length).as_deref(), Some("calc(2px)"));
+        assert_eq!(
+            parse("clamp(10px, 50%, 100px)", LengthPercentage).as_deref(),
+            Some("max(10px, min(50%, 100px))")
+        );
+        assert_eq!(
+            parse("calc(-1 * min(1px, 1em))", Length).as_deref(),
+            Some("max(-1px, -1em)")
+        );
+        assert_eq!(parse("CALC( 1PX )", Length).as_deref(), Some("calc(1px)"));
+
+        // Type errors.
+        assert_eq!(parse("calc(10%)", Length), None);
+        assert_eq!(parse("calc(1px + 1)", Length), None);
+        assert_eq!(parse("calc(1px * 1px)", Length), None);
+        assert_eq!(parse("calc(1 / 1px)", Length), None);
+        assert_eq!(parse("calc(1px)", Number), None);
+        // Syntax errors.
+        assert_eq!(parse("calc(1px +1px)", Length), None);
+        assert_eq!(parse("calc(1px+ 1px)", Length), None);
+        assert_eq!(parse("calc(1px 1px)", Length), None);
+        assert_eq!(parse("calc(1px, 1px)", Length), None);
+        assert_eq!(parse("clamp(1px, 2px)", Length), None);
+        assert_eq!(parse("calc(1foo)", Length), None);
+        assert_eq!(parse("calc()", Length), None);
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let parse = |value: &str, allowed: CalcType| {
+            let values = StyleSheetParser::new(CssTokenizer::new(value.to_string()))
+                .parse_list_of_component_values();
+            parse_math_function(&values[0], allowed).unwrap()
+        };
+        assert_eq!(
+            parse("calc(1 / 0)", CalcType::Number),
+            CalcNode::Number(MAX_VALUE)
+        );
+        assert_eq!(
+            parse("calc(-1px / 0)", CalcType::Length),
+            CalcNode::Length(Length::px(-MAX_VALUE))
+        );
+        assert_eq!(
+            parse("calc(0 / 0)", CalcType::Number),
+            CalcNode::Number(0.0)
+        );
+        assert_eq!(
+            parse("calc(1px / 0 - 1px / 0)", CalcType::Length),
+            CalcNode::Length(Length::px(0.0))
+        );
+
+        // Percentages are resolved during layout, where the result is censored again.
+        let node = parse("calc(10% / 0 + 1px / 0)", CalcType::LengthPercentage);
+        assert_eq!(node.resolve(100.0), MAX_VALUE);
+        assert_eq!(node.resolve(0.0), MAX_VALUE);
+        assert_eq!(CalcNode::Percentage(f64::INFINITY).resolve(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_compute_and_resolve() {
+        let values = StyleSheetParser::new(CssTokenizer::new("calc(2em + 10% - 1px)".to_string()))
+            .parse_list_of_component_values();
+        let node = parse_math_function(&values[0], CalcType::LengthPercentage).unwrap();
+        let resolve_length = |length: Length| match length.unit {
+            LengthUnit::Em => length.value * 16.0,
+            _ => length.value,
+        };
+        let computed = node.compute(&resolve_length);
+        assert_eq!(computed.to_string(), "calc(10% + 31px)");
+        assert_eq!(computed.resolve(200.0), 51.0);
+
+        let min = CalcNode::Min(alloc::vec![
+            CalcNode::Percentage(50.0),
+            CalcNode::Length(Length::px(100.0))
+        ]);
+        assert_eq!(min.resolve(100.0), 50.0);
+        assert_eq!(min.resolve(400.0), 100.0);
+        assert_eq!(min.clone().clamp_non_negative().resolve(-10.0), 0.0);
+        assert_eq!(
+            CalcNode::Length(Length::px(-1.0)).clamp_non_negative(),
+            CalcNode::Length(Length::px(0.0))
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! <https://www.w3.org/TR/css-cascade-4/#cascading>
+//!
+//! Servo: <https://github.com/servo/stylo/blob/main/style/stylist.rs>
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use crate::renderer::dom::node::{Node, NodeData};
+
+use super::cssom::{
+    CssDeclaration, CssKeyframesRule, CssRule, CssStyleDeclaration, CssStyleSheet, StyleSheetList,
+};
+use super::matching::{MatchingContext, SelectorMap};
+use super::media::{Device, MediaList};
+use super::parser::parse_style_attribute;
+use super::restyle::InvalidationMap;
+use super::selector::Specificity;
+
+/// <https://www.w3.org/TR/css-cascade-4/#cascading-origins>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
+}
+
+/// <https://www.w3.org/TR/css-cascade-4/#cascade-origin>
+///
+/// The origin and importance of a declaration, in the ascending order of precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CascadeLevel {
+    UserAgentNormal,
+    UserNormal,
+    AuthorNormal,
+    AuthorImportant,
+    UserImportant,
+    UserAgentImportant,
+}
+
+impl CascadeLevel {
+    fn new(origin: Origin, important: bool) -> Self {
+        match (origin, important) {
+            (Origin::UserAgent, false) => Self::UserAgentNormal,
+            (Origin::User, false) => Self::UserNormal,
+            (Origin::Author, false) => Self::AuthorNormal,
+            (Origin::Author, true) => Self::AuthorImportant,
+            (Origin::User, true) => Self::UserImportant,
+            (Origin::UserAgent, true) => Self::UserAgentImportant,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RuleData {
+    origin: Origin,
+    declarations: Rc<CssStyleDeclaration>,
+}
+
+/// The style rules of all the stylesheets applied to a document, indexed for matching.
+#[derive(Debug, Clone)]
+pub struct Stylist {
+    /// The device which the media queries in the stylesheets are evaluated against.
+    device: Device,
+    rules: SelectorMap<RuleData>,
+    /// The media query lists evaluated so far, with their results.
+    evaluated_media: Vec<(MediaList, bool)>,
+    /// What the selectors of the rules depend on.
+    invalidation_map: InvalidationMap,
+    /// The `@keyframes` rules by name, where the last one of the same name wins.
+    keyframes: BTreeMap<String, Rc<CssKeyframesRule>>,
+    /// The declarations parsed from the `style` attributes, by the value of the attribute,
+    /// so that an attribute is parsed only once however many times the element is styled.
+    style_attributes: RefCell<BTreeMap<String, Rc<CssStyleDeclaration>>>,
+}
+
+impl Stylist {
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            rules: SelectorMap::new(),
+            evaluated_media: Vec::new(),
+            invalidation_map: InvalidationMap::default(),
+            keyframes: BTreeMap::new(),
+            style_attributes: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Adds the style rules of the document's stylesheets which are enabled and whose media match.
+    pub fn add_style_sheet_list(&mut self, style_sheets: &StyleSheetList) {
+        for style_sheet in style_sheets.enabled() {
+            if self.evaluate_media(&style_sheet.media) {
+                self.add_stylesheet(&style_sheet.css_style_sheet, Origin::Author);
+            }
+        }
+    }
+
+    /// Adds the style rules in the stylesheet, including the ones imported by @import.
+    /// Stylesheets must be added in the order of appearance.
+    pub fn add_stylesheet(&mut self, stylesheet: &CssStyleSheet, origin: Origin) {
+        self.add_rules(&stylesheet.css_rules, origin);
+    }
+
+    fn add_rules(&mut self, rules: &[CssRule], origin: Origin) {
+        for rule in rules {
+            match rule {
+                // <https://www.w3.org/TR/css-cascade-4/#import-processing>
+                // The imported rules are treated as if they were written in place of the @import rule.
+                CssRule::Import(rule) => {
+                    if let Some(imported) = &rule.style_sheet {
+                        if self.evaluate_media(&rule.media) {
+                            self.add_rules(&imported.css_rules, origin);
+                        }
+                    }
+                }
+                // <https://www.w3.org/TR/css-conditional-3/#processing>
+                CssRule::Media(rule) => {
+                    if self.evaluate_media(&rule.media) {
+                        self.add_rules(&rule.css_rules, origin);
+                    }
+                }
+                CssRule::Supports(rule) => {
+                    if rule.condition.matches() {
+                        self.add_rules(&rule.css_rules, origin);
+                    }
+                }
+                CssRule::Style(rule) => {
+                    let declarations = Rc::new(rule.declarations.clone());
+                    for selector in &rule.selector.selectors {
+                        self.invalidation_map.note_selector(selector);
+                        self.rules.insert(
+                            selector.clone(),
+                            RuleData {
+                                origin,
+                                declarations: Rc::clone(&declarations),
+                            },
+                        );
+                    }
+                }
+                // <https://www.w3.org/TR/css-animations-1/#keyframes>
+                CssRule::Keyframes(rule) => {
+                    self.keyframes
+                        .insert(rule.name.clone(), Rc::new(rule.clone()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn evaluate_media(&mut self, media: &MediaList) -> bool {
+        let result = media.matches(&self.device);
+        self.evaluated_media.push((media.clone(), result));
+        result
+    }
+
+    /// Whether any media query list added so far evaluates differently on the device,
+    /// in which case the stylist needs to be rebuilt for it.
+    ///
+    /// <https://drafts.csswg.org/cssom-view/#evaluate-media-queries-and-report-changes>
+    pub fn media_results_differ(&self, device: &Device) -> bool {
+        self.evaluated_media
+            .iter()
+            .any(|(media, result)| media.matches(device) != *result)
+    }
+
+    pub fn invalidation_map(&self) -> &InvalidationMap {
+        &self.invalidation_map
+    }
+
+    /// Returns the `@keyframes` rule of the name, which is case-sensitive.
+    pub fn keyframes(&self, name: &str) -> Option<&CssKeyframesRule> {
+        self.keyframes.get(name).map(|rule| rule.as_ref())
+    }
+
+    fn parse_style_attribute(&self, style: &str) -> Rc<CssStyleDeclaration> {
+        let mut style_attributes = self.style_attributes.borrow_mut();
+        let declarations = style_attributes
+            .entry(style.into())
+            .or_insert_with(|| Rc::new(parse_style_attribute(style.into())));
+        Rc::clone(declarations)
+    }
+
+    /// Returns the declarations that apply to the element, sorted in the ascending order of precedence.
+    ///
+    /// <https://www.w3.org/TR/css-cascade-4/#cascade-sort>
+    ///
+    /// The declarations in the `style` attribute are in the author origin and take precedence over any rule.
+    pub fn cascaded_declarations(
+        &self,
+        element: Rc<RefCell<Node>>,
+        context: &MatchingContext,
+    ) -> Vec<CssDeclaration> {
+        let style_attribute = match &element.borrow().data {
+            NodeData::Element(e) => e
+                .get_attribute("style")
+                .map(|style| self.parse_style_attribute(style)),
+            _ => None,
+        };
+
+        // (level, whether in the style attribute, specificity, the order of the rule)
+        type SortKey = (CascadeLevel, bool, Specificity, usize);
+        let mut declarations: Vec<(SortKey, CssDeclaration)> = Vec::new();
+
+        for entry in self.rules.get_matching_entries(element, context) {
+            for d in &entry.value.declarations.declarations {
+                let level = CascadeLevel::new(entry.value.origin, d.important);
+                declarations.push(((level, false, entry.specificity, entry.order), d.clone()));
+            }
+        }
+        for d in style_attribute.iter().flat_map(|s| &s.declarations) {
+            let level = CascadeLevel::new(Origin::Author, d.important);
+            declarations.push(((level, true, Specificity::default(), 0), d.clone()));
+        }
+
+        // The sort is stable, so declarations in the same rule stay in the order of appearance.
+        declarations.sort_by_key(|(key, _)| *key);
+        declarations.into_iter().map(|(_, d)| d).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    use crate::renderer::css::parser::parse_css_stylesheet;
+    use crate::renderer::css::value::ComponentValue;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::dom::test_util;
+
+    use super::*;
+
+    fn values(declarations: &[CssDeclaration]) -> Vec<String> {
+        declarations
+            .iter()
+            .map(|d| match d.value.as_slice() {
+                [ComponentValue::String(family)] => family.clone(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cascade_order() {
+        let window = test_util::parse(
+            r#"<html><head></head><body><p id="a" class="b" style="font-family: style; font-family: style-important !important">x</p></body></html>"#,
+        );
+        let p = test_util::element(&window, ElementKind::P);
+
+        let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+        stylist.add_stylesheet(
+            &parse_css_stylesheet(
+                "p { font-family: ua-important !important } p { font-family: ua }".to_string(),
+            ),
+            Origin::UserAgent,
+        );
+        stylist.add_stylesheet(
+            &parse_css_stylesheet(
+                "#a { font-family: user-important !important; font-family: user }".to_string(),
+            ),
+            Origin::User,
+        );
+        stylist.add_stylesheet(
+            &parse_css_stylesheet(
+                "#a { font-family: author-id } .b { font-family: author-important !important } p.b { font-family: author-class } p { font-family: author-type }".to_string(),
+            ),
+            Origin::Author,
+        );
+
+        let declarations = stylist.cascaded_declarations(p, &MatchingContext::default());
+        assert_eq!(
+            values(&declarations),
+            vec![
+                "ua",
+                "user",
+                "author-type",
+                "author-class",
+                "author-id",
+                "style",
+                "author-important",
+                "style-important",
+                "user-important",
+                "ua-important",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_media_rules() {
+        let window = test_util::parse(r#"<html><head></head><body><p>x</p></body></html>"#);
+        let p = test_util::element(&window, ElementKind::P);
+        let stylesheet = parse_css_stylesheet(
+            "p { font-family: all } \
+             @media (max-width: 600px) { p { font-family: narrow } } \
+             @media print { p { font-family: print } } \
+             @media (orientation: portrait) { @media (min-height: 1000px) { p { font-family: tall } } }"
+                .to_string(),
+        );
+
+        let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+        stylist.add_stylesheet(&stylesheet, Origin::Author);
+        let declarations =
+            stylist.cascaded_declarations(Rc::clone(&p), &MatchingContext::default());
+        assert_eq!(values(&declarations), vec!["all"]);
+
+        assert!(!stylist.media_results_differ(&Device::new(700.0, 600.0)));
+        assert!(stylist.media_results_differ(&Device::new(500.0, 600.0)));
+        assert!(stylist.media_results_differ(&Device::new(500.0, 1000.0)));
+
+        let mut stylist = Stylist::new(Device::new(500.0, 1000.0));
+        stylist.add_stylesheet(&stylesheet, Origin::Author);
+        let declarations = stylist.cascaded_declarations(p, &MatchingContext::default());
+        assert_eq!(values(&declarations), vec!["all", "narrow", "tall"]);
+    }
+
+    #[test]
+    fn test_supports_rules() {
+        let window = test_util::parse("<html><head></head><body><p>x</p></body></html>");
+        let p = test_util::element(&window, ElementKind::P);
+        let stylesheet = parse_css_stylesheet(
+            "@supports (display: block) { p { font-family: block } } \
+             @supports (display: grid) { p { font-family: grid } } \
+             @supports not (display: grid) { p { font-family: not-grid } } \
+             @supports (display: grid) or (color: red) { @media (min-width: 100px) { p { font-family: nested } } }"
+                .to_string(),
+        );
+
+        let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+        stylist.add_stylesheet(&stylesheet, Origin::Author);
+        let declarations = stylist.cascaded_declarations(p, &MatchingContext::default());
+        assert_eq!(values(&declarations), vec!["block", "not-grid", "nested"]);
+    }
+
+    #[test]
+    fn test_style_attribute() {
+        let window = test_util::parse(
+            r#"<html><head></head><body><p style="font-family: a">x</p><h1 style="font-family: a">y</h1></body></html>"#,
+        );
+        let p = test_util::element(&window, ElementKind::P);
+        let h1 = test_util::element(&window, ElementKind::H1);
+        let stylist = Stylist::new(Device::new(800.0, 600.0));
+        let cascade = |element: &Rc<RefCell<Node>>| {
+            values(&stylist.cascaded_declarations(Rc::clone(element), &MatchingContext::default()))
+        };
+
+        assert_eq!(cascade(&p), vec!["a"]);
+        assert_eq!(cascade(&h1), vec!["a"]);
+        assert_eq!(stylist.style_attributes.borrow().len(), 1);
+
+        Node::set_attribute(Rc::clone(&p), "style", "font-family: b").unwrap();
+        assert_eq!(cascade(&p), vec!["b"]);
+        assert_eq!(cascade(&h1), vec!["a"]);
+    }
+}
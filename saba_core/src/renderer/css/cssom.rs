@@ -1,7 +1,22 @@
-use alloc::string::String;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Weak;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
 
-use super::value::ComponentValue;
+use crate::renderer::dom::node::Node;
+use crate::url::Url;
+
+use super::custom_properties::is_custom_property;
+use super::font_face::FontFaceDescriptors;
+use super::media::MediaList;
+use super::parser::{parse_property_value, parse_style_attribute, serialize_component_values};
+use super::properties;
+use super::shorthands;
+use super::supports::SupportsCondition;
+use super::value::{serialize_identifier, serialize_string, ComponentValue};
 
 /// <https://www.w3.org/TR/cssom-1/#cssstylesheet>
 #[derive(Debug, Clone, PartialEq)]
@@ -9,9 +24,75 @@ pub struct CssStyleSheet {
     pub css_rules: Vec<CssRule>,
 }
 
-// for simplicity
+/// <https://www.w3.org/TR/cssom-1/#the-stylesheet-interface>
+///
+/// A stylesheet associated with a document, along with the state the document keeps for it.
+#[derive(Debug, Clone)]
+pub struct StyleSheet {
+    /// <https://www.w3.org/TR/cssom-1/#concept-css-style-sheet-location>
+    ///
+    /// None for a stylesheet embedded in a `<style>` element.
+    pub location: Option<Url>,
+    /// <https://www.w3.org/TR/cssom-1/#concept-css-style-sheet-owner-node>
+    pub owner_node: Weak<RefCell<Node>>,
+    /// <https://www.w3.org/TR/cssom-1/#concept-css-style-sheet-media>
+    pub media: MediaList,
+    /// <https://www.w3.org/TR/cssom-1/#concept-css-style-sheet-disabled-flag>
+    pub disabled: bool,
+    pub css_style_sheet: CssStyleSheet,
+}
+
+/// <https://www.w3.org/TR/cssom-1/#the-stylesheetlist-interface>
+///
+/// The stylesheets of a document in tree order of their owner nodes.
+#[derive(Debug, Clone, Default)]
+pub struct StyleSheetList {
+    pub style_sheets: Vec<StyleSheet>,
+}
+
+impl StyleSheetList {
+    /// <https://www.w3.org/TR/cssom-1/#dom-stylesheetlist-length>
+    pub fn length(&self) -> usize {
+        self.style_sheets.len()
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-stylesheetlist-item>
+    pub fn item(&self, index: usize) -> Option<&StyleSheet> {
+        self.style_sheets.get(index)
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-stylesheet-disabled>
+    ///
+    /// Returns false if there is no stylesheet at the index.
+    pub fn set_disabled(&mut self, index: usize, disabled: bool) -> bool {
+        match self.style_sheets.get_mut(index) {
+            Some(style_sheet) => {
+                style_sheet.disabled = disabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The stylesheets which are not disabled, in order.
+    /// Whether their media match is up to the device.
+    pub fn enabled(&self) -> impl Iterator<Item = &StyleSheet> {
+        self.style_sheets
+            .iter()
+            .filter(|style_sheet| !style_sheet.disabled)
+    }
+}
+
 /// <https://www.w3.org/TR/cssom-1/#cssrule>
-type CssRule = CssStyleRule;
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssRule {
+    Style(CssStyleRule),
+    Import(CssImportRule),
+    Media(CssMediaRule),
+    FontFace(CssFontFaceRule),
+    Supports(CssSupportsRule),
+    Keyframes(CssKeyframesRule),
+}
 
 /// <https://www.w3.org/TR/cssom-1/#cssstylerule>
 /// <https://github.com/servo/stylo/blob/4b44fbdb7f93c3f57eb99ad5f14cda5e82af4467/style/stylesheets/style_rule.rs#L25>
@@ -21,12 +102,290 @@ pub struct CssStyleRule {
     pub declarations: CssStyleDeclaration,
 }
 
+/// <https://www.w3.org/TR/cssom-1/#cssimportrule>
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssImportRule {
+    pub href: String,
+    pub media: MediaList,
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssimportrule-stylesheet>
+    ///
+    /// None until the stylesheet is fetched, or if it failed to load.
+    pub style_sheet: Option<CssStyleSheet>,
+}
+
+/// <https://www.w3.org/TR/css-conditional-3/#cssmediarule>
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssMediaRule {
+    pub media: MediaList,
+    pub css_rules: Vec<CssRule>,
+}
+
+/// <https://www.w3.org/TR/css-conditional-3/#csssupportsrule>
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssSupportsRule {
+    pub condition: SupportsCondition,
+    pub css_rules: Vec<CssRule>,
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#cssfontfacerule>
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssFontFaceRule {
+    pub descriptors: FontFaceDescriptors,
+}
+
+/// <https://www.w3.org/TR/css-animations-1/#csskeyframesrule>
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssKeyframesRule {
+    pub name: String,
+    pub css_rules: Vec<CssKeyframeRule>,
+}
+
+/// <https://www.w3.org/TR/css-animations-1/#csskeyframerule>
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssKeyframeRule {
+    /// The keyframe selectors in percentages, where `from` is 0 and `to` is 100.
+    pub keys: Vec<f64>,
+    pub declarations: CssStyleDeclaration,
+}
+
 /// <https://www.w3.org/TR/cssom-1/#cssstyledeclaration>
 #[derive(Debug, Clone, PartialEq)]
 pub struct CssStyleDeclaration {
     pub declarations: Vec<CssDeclaration>,
 }
 
+impl CssStyleDeclaration {
+    /// Returns the declaration of the property which takes precedence over the others,
+    /// i.e. the last important one, or the last one if none is important.
+    fn declaration(&self, property: &str) -> Option<&CssDeclaration> {
+        self.declaration_index(property)
+            .map(|index| &self.declarations[index])
+    }
+
+    fn declaration_index(&self, property: &str) -> Option<usize> {
+        let indices = || {
+            self.declarations
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(_, d)| same_property(&d.property_name, property))
+        };
+        indices()
+            .find(|(_, d)| d.important)
+            .or_else(|| indices().next())
+            .map(|(index, _)| index)
+    }
+
+    /// The longhands of the property if it is a shorthand, or the property itself otherwise.
+    fn longhands(property: &str) -> Vec<String> {
+        match shorthands::longhands(property) {
+            Some(longhands) => longhands.iter().map(|l| l.to_string()).collect(),
+            None => alloc::vec![normalize_property(property)],
+        }
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-getpropertypriority>
+    pub fn get_property_priority(&self, property: &str) -> &'static str {
+        let important = Self::longhands(property)
+            .iter()
+            .all(|longhand| self.declaration(longhand).is_some_and(|d| d.important));
+        if important {
+            "important"
+        } else {
+            ""
+        }
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-setproperty>
+    ///
+    /// Returns false if nothing is changed because the priority or the value is invalid.
+    pub fn set_property(&mut self, property: &str, value: &str, priority: &str) -> bool {
+        if value.is_empty() {
+            self.remove_property(property);
+            return true;
+        }
+        if !priority.is_empty() && !priority.eq_ignore_ascii_case("important") {
+            return false;
+        }
+        let declarations = parse_property_value(property, value.into(), !priority.is_empty());
+        if declarations.is_empty() {
+            return false;
+        }
+        for declaration in declarations {
+            self.set_declaration(declaration);
+        }
+        true
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#set-a-css-declaration>
+    ///
+    /// The declaration replaces the one which takes precedence, and the others of the same property are removed.
+    fn set_declaration(&mut self, declaration: CssDeclaration) {
+        let Some(index) = self.declaration_index(&declaration.property_name) else {
+            self.declarations.push(declaration);
+            return;
+        };
+        let name = declaration.property_name.clone();
+        self.declarations[index] = declaration;
+        let mut i = 0;
+        self.declarations.retain(|d| {
+            i += 1;
+            i - 1 == index || !same_property(&d.property_name, &name)
+        });
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-removeproperty>
+    ///
+    /// Returns the value before the removal.
+    pub fn remove_property(&mut self, property: &str) -> String {
+        let value = self.get_property_value(property);
+        let longhands = Self::longhands(property);
+        self.declarations
+            .retain(|d| !longhands.iter().any(|l| same_property(&d.property_name, l)));
+        value
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-csstext>
+    pub fn css_text(&self) -> String {
+        self.serialize()
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-csstext>
+    pub fn set_css_text(&mut self, text: &str) {
+        self.declarations = parse_style_attribute(text.into()).declarations;
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-getpropertyvalue>
+    ///
+    /// Returns an empty string if the property is not set.
+    /// For shorthands, all the longhands must be set with the same importance.
+    pub fn get_property_value(&self, property: &str) -> String {
+        let Some(longhands) = shorthands::longhands(property) else {
+            return self
+                .declaration(property)
+                .map(|d| properties::serialize_value(&d.property_name, &d.value))
+                .unwrap_or_default();
+        };
+        let Some(declarations) = longhands
+            .iter()
+            .map(|longhand| self.declaration(longhand))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return String::new();
+        };
+        if declarations
+            .iter()
+            .any(|d| d.important != declarations[0].important)
+        {
+            return String::new();
+        }
+        let values: Vec<&[ComponentValue]> =
+            declarations.iter().map(|d| d.value.as_slice()).collect();
+        // <https://www.w3.org/TR/css-variables-1/#variables-in-shorthands>
+        // A shorthand containing `var()` is serialized as it is declared,
+        // only if all the longhands still have its pending-substitution value.
+        if values
+            .iter()
+            .any(|v| v.iter().any(|v| matches!(v, ComponentValue::Unparsed(_))))
+        {
+            return match values[0] {
+                [ComponentValue::Unparsed(unparsed)]
+                    if unparsed
+                        .shorthand
+                        .as_ref()
+                        .is_some_and(|s| s.eq_ignore_ascii_case(property))
+                        && values.iter().all(|v| *v == values[0]) =>
+                {
+                    serialize_component_values(&unparsed.tokens)
+                }
+                _ => String::new(),
+            };
+        }
+        shorthands::serialize(property, &values).unwrap_or_default()
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#serialize-a-css-declaration-block>
+    ///
+    /// Longhands are serialized as a shorthand where possible, preferring the one with the most longhands.
+    pub fn serialize(&self) -> String {
+        let mut list: Vec<String> = Vec::new();
+        let mut already_serialized: Vec<String> = Vec::new();
+
+        for (i, declaration) in self.declarations.iter().enumerate() {
+            let property = normalize_property(&declaration.property_name);
+            // Only the declaration which takes precedence is serialized for each property.
+            if already_serialized.contains(&property)
+                || self.declaration_index(&property) != Some(i)
+            {
+                continue;
+            }
+
+            let mut candidates: Vec<(&str, &[&str])> = shorthands::SHORTHANDS
+                .iter()
+                .filter_map(|s| Some((*s, shorthands::longhands(s)?)))
+                .filter(|(_, longhands)| longhands.contains(&property.as_str()))
+                .collect();
+            candidates.sort_by_key(|(_, longhands)| core::cmp::Reverse(longhands.len()));
+
+            let shorthand = candidates.into_iter().find_map(|(shorthand, longhands)| {
+                if longhands
+                    .iter()
+                    .any(|l| already_serialized.iter().any(|s| s == l))
+                {
+                    return None;
+                }
+                let value = self.get_property_value(shorthand);
+                (!value.is_empty()).then_some((shorthand, longhands, value))
+            });
+            if let Some((shorthand, longhands, value)) = shorthand {
+                list.push(serialize_declaration(
+                    shorthand,
+                    &value,
+                    declaration.important,
+                ));
+                already_serialized.extend(longhands.iter().map(|l| l.to_string()));
+                continue;
+            }
+
+            let value = properties::serialize_value(&property, &declaration.value);
+            list.push(serialize_declaration(
+                &property,
+                &value,
+                declaration.important,
+            ));
+            already_serialized.push(property);
+        }
+
+        list.join(" ")
+    }
+}
+
+/// Property names are ASCII case-insensitive, except for custom properties.
+fn same_property(a: &str, b: &str) -> bool {
+    if is_custom_property(a) {
+        a == b
+    } else {
+        a.eq_ignore_ascii_case(b)
+    }
+}
+
+fn normalize_property(property: &str) -> String {
+    if is_custom_property(property) {
+        property.to_string()
+    } else {
+        property.to_ascii_lowercase()
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-css-declaration>
+fn serialize_declaration(property: &str, value: &str, important: bool) -> String {
+    if important {
+        format!("{property}: {value} !important;")
+    } else {
+        format!("{property}: {value};")
+    }
+}
+
 /// <https://www.w3.org/TR/cssom-1/#css-declaration>
 ///
 /// Servo's counterpart is [stylo::properties::generated::PropertyDeclaration](https://docs.rs/stylo/latest/stylo/properties/generated/enum.PropertyDeclaration.html),
@@ -45,34 +404,675 @@ pub struct CssDeclaration {
     /// Now I think this should be crate::renderer::css::parser::ComponentValue.
     /// TODO: reconsider after implementing the styling engine
     pub value: Vec<ComponentValue>,
+
+    /// <https://www.w3.org/TR/cssom-1/#css-declaration-important-flag>
+    pub important: bool,
 }
 
 /// <https://www.w3.org/TR/selectors-4/#typedef-selector-list>
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SelectorList {
     pub selectors: Vec<ComplexSelector>,
 }
 
 /// <https://www.w3.org/TR/selectors-4/#complex>
 /// A complex selector is a sequence of one or more compound selectors separated by combinators.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ComplexSelector {
-    CompoundSelector(CompoundSelector), // TODO: support combinators
+    CompoundSelector(CompoundSelector),
+    /// The compound selector on the right is the subject, e.g. `p > a` is `Combination(p, Child, a)`.
+    Combination(Box<ComplexSelector>, Combinator, CompoundSelector),
+}
+
+/// <https://www.w3.org/TR/selectors-4/#selector-combinator>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    /// <https://www.w3.org/TR/selectors-4/#descendant-combinators>
+    Descendant,
+    /// <https://www.w3.org/TR/selectors-4/#child-combinators>
+    Child,
+    /// <https://www.w3.org/TR/selectors-4/#adjacent-sibling-combinators>
+    NextSibling,
+    /// <https://www.w3.org/TR/selectors-4/#general-sibling-combinators>
+    SubsequentSibling,
+}
+
+/// <https://www.w3.org/TR/selectors-4/#relative-selector>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelativeSelector {
+    pub combinator: Combinator,
+    pub selector: ComplexSelector,
 }
 
 /// <https://www.w3.org/TR/selectors-4/#compound>
 /// A compound selector is a sequence of simple selectors that are not separated by a combinator
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CompoundSelector(pub Vec<SimpleSelector>);
 
 /// <https://www.w3.org/TR/selectors-4/#simple>
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SimpleSelector {
     /// <https://www.w3.org/TR/selectors-4/#type-selector>
+    ///
+    /// The name is in lowercase, as HTML element names are case-insensitive.
     TypeSelector(String),
+    /// <https://www.w3.org/TR/selectors-4/#universal-selector>
+    UniversalSelector,
     /// <https://www.w3.org/TR/selectors-4/#class-selector>
     ClassSelector(String),
     /// <https://www.w3.org/TR/selectors-4/#id-selector>
     IdSelector(String),
-    // TODO: support more simple selectors
+    /// <https://www.w3.org/TR/selectors-4/#attribute-selectors>
+    AttributeSelector(AttributeSelector),
+    /// <https://www.w3.org/TR/selectors-4/#pseudo-classes>
+    PseudoClass(PseudoClass),
+    /// <https://www.w3.org/TR/selectors-4/#pseudo-elements>
+    ///
+    /// Pseudo-elements are not simple selectors in the spec, but they are put at the end of a compound selector here.
+    PseudoElement(PseudoElement),
+}
+
+/// <https://www.w3.org/TR/selectors-4/#attribute-selectors>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeSelector {
+    /// The name is in lowercase, as HTML attribute names are case-insensitive.
+    pub name: String,
+    /// None for `[name]`, which matches if the attribute exists.
+    pub matcher: Option<AttributeMatcher>,
+    /// <https://www.w3.org/TR/selectors-4/#attribute-case>
+    pub case_insensitive: bool,
+}
+
+/// <https://www.w3.org/TR/selectors-4/#attribute-representation>
+/// <https://www.w3.org/TR/selectors-4/#attribute-substrings>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeMatcher {
+    /// `[att=val]`
+    Exact(String),
+    /// `[att~=val]`
+    Includes(String),
+    /// `[att|=val]`
+    DashMatch(String),
+    /// `[att^=val]`
+    Prefix(String),
+    /// `[att$=val]`
+    Suffix(String),
+    /// `[att*=val]`
+    Substring(String),
+}
+
+/// <https://www.w3.org/TR/selectors-4/#pseudo-classes>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PseudoClass {
+    /// <https://www.w3.org/TR/selectors-4/#root-pseudo>
+    Root,
+    /// <https://www.w3.org/TR/selectors-4/#empty-pseudo>
+    Empty,
+    /// <https://www.w3.org/TR/selectors-4/#first-child-pseudo>
+    FirstChild,
+    /// <https://www.w3.org/TR/selectors-4/#last-child-pseudo>
+    LastChild,
+    /// <https://www.w3.org/TR/selectors-4/#only-child-pseudo>
+    OnlyChild,
+    /// <https://www.w3.org/TR/selectors-4/#first-of-type-pseudo>
+    FirstOfType,
+    /// <https://www.w3.org/TR/selectors-4/#last-of-type-pseudo>
+    LastOfType,
+    /// <https://www.w3.org/TR/selectors-4/#only-of-type-pseudo>
+    OnlyOfType,
+    /// <https://www.w3.org/TR/selectors-4/#nth-child-pseudo>
+    NthChild(AnPlusB, Option<SelectorList>),
+    /// <https://www.w3.org/TR/selectors-4/#nth-last-child-pseudo>
+    NthLastChild(AnPlusB, Option<SelectorList>),
+    /// <https://www.w3.org/TR/selectors-4/#nth-of-type-pseudo>
+    NthOfType(AnPlusB),
+    /// <https://www.w3.org/TR/selectors-4/#nth-last-of-type-pseudo>
+    NthLastOfType(AnPlusB),
+    /// <https://www.w3.org/TR/selectors-4/#negation>
+    Not(SelectorList),
+    /// <https://www.w3.org/TR/selectors-4/#matches>
+    Is(SelectorList),
+    /// <https://www.w3.org/TR/selectors-4/#zero-matches>
+    Where(SelectorList),
+    /// <https://www.w3.org/TR/selectors-4/#relational>
+    Has(Vec<RelativeSelector>),
+    /// <https://www.w3.org/TR/selectors-4/#link-pseudo>
+    Link,
+    /// <https://www.w3.org/TR/selectors-4/#visited-pseudo>
+    Visited,
+    /// <https://www.w3.org/TR/selectors-4/#the-any-link-pseudo>
+    AnyLink,
+    /// <https://www.w3.org/TR/selectors-4/#the-hover-pseudo>
+    Hover,
+    /// <https://www.w3.org/TR/selectors-4/#the-active-pseudo>
+    Active,
+    /// <https://www.w3.org/TR/selectors-4/#the-focus-pseudo>
+    Focus,
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#anb-microsyntax>
+///
+/// Represents the indices `a*n + b` for n = 0, 1, 2, ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnPlusB {
+    pub a: i32,
+    pub b: i32,
+}
+
+/// <https://www.w3.org/TR/css-pseudo-4/>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PseudoElement {
+    Before,
+    After,
+    FirstLine,
+    FirstLetter,
+    Marker,
+}
+
+impl CssStyleSheet {
+    /// The rules serialized one per line.
+    pub fn css_text(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for CssStyleSheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, rule) in self.css_rules.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{rule}")?;
+        }
+        Ok(())
+    }
+}
+
+impl CssRule {
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssrule-csstext>
+    pub fn css_text(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-css-rule>
+impl fmt::Display for CssRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Style(rule) => write!(f, "{rule}"),
+            Self::Import(rule) => write!(f, "{rule}"),
+            Self::Media(rule) => write!(f, "{rule}"),
+            Self::FontFace(rule) => write!(f, "{rule}"),
+            Self::Supports(rule) => write!(f, "{rule}"),
+            Self::Keyframes(rule) => write!(f, "{rule}"),
+        }
+    }
+}
+
+/// Serializes a block of declarations, e.g. `{ color: red; }`.
+fn fmt_declaration_block(
+    f: &mut fmt::Formatter<'_>,
+    declarations: &CssStyleDeclaration,
+) -> fmt::Result {
+    let declarations = declarations.serialize();
+    if declarations.is_empty() {
+        write!(f, "{{ }}")
+    } else {
+        write!(f, "{{ {declarations} }}")
+    }
+}
+
+/// Serializes a block of rules, each of which is on its own line.
+fn fmt_rule_block<T: fmt::Display>(f: &mut fmt::Formatter<'_>, rules: &[T]) -> fmt::Result {
+    write!(f, "{{")?;
+    for rule in rules {
+        write!(f, "\n  {rule}")?;
+    }
+    write!(f, "\n}}")
+}
+
+impl CssStyleRule {
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstylerule-selectortext>
+    pub fn selector_text(&self) -> String {
+        self.selector.to_string()
+    }
+}
+
+impl fmt::Display for CssStyleRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.selector)?;
+        fmt_declaration_block(f, &self.declarations)
+    }
+}
+
+impl fmt::Display for CssImportRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@import url({})", serialize_string(&self.href))?;
+        if !self.media.media_queries.is_empty() {
+            write!(f, " {}", self.media)?;
+        }
+        write!(f, ";")
+    }
+}
+
+impl fmt::Display for CssMediaRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@media {} ", self.media)?;
+        fmt_rule_block(f, &self.css_rules)
+    }
+}
+
+impl fmt::Display for CssSupportsRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@supports {} ", self.condition)?;
+        fmt_rule_block(f, &self.css_rules)
+    }
+}
+
+impl fmt::Display for CssFontFaceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let descriptors = self.descriptors.to_string();
+        if descriptors.is_empty() {
+            write!(f, "@font-face {{ }}")
+        } else {
+            write!(f, "@font-face {{ {descriptors} }}")
+        }
+    }
+}
+
+impl fmt::Display for CssKeyframesRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@keyframes {} ", serialize_identifier(&self.name))?;
+        fmt_rule_block(f, &self.css_rules)
+    }
+}
+
+impl fmt::Display for CssKeyframeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{key}%")?;
+        }
+        write!(f, " ")?;
+        fmt_declaration_block(f, &self.declarations)
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-group-of-selectors>
+impl fmt::Display for SelectorList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{selector}")?;
+        }
+        Ok(())
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-selector>
+impl fmt::Display for ComplexSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CompoundSelector(compound) => write!(f, "{compound}"),
+            Self::Combination(left, Combinator::Descendant, right) => {
+                write!(f, "{left} {right}")
+            }
+            Self::Combination(left, combinator, right) => {
+                write!(f, "{left} {combinator} {right}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Combinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Descendant => " ",
+            Self::Child => ">",
+            Self::NextSibling => "+",
+            Self::SubsequentSibling => "~",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl fmt::Display for RelativeSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.combinator {
+            Combinator::Descendant => write!(f, "{}", self.selector),
+            combinator => write!(f, "{combinator} {}", self.selector),
+        }
+    }
+}
+
+impl fmt::Display for CompoundSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|simple| write!(f, "{simple}"))
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-simple-selector>
+impl fmt::Display for SimpleSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TypeSelector(name) => write!(f, "{}", serialize_identifier(name)),
+            Self::UniversalSelector => write!(f, "*"),
+            Self::ClassSelector(name) => write!(f, ".{}", serialize_identifier(name)),
+            Self::IdSelector(name) => write!(f, "#{}", serialize_identifier(name)),
+            Self::AttributeSelector(selector) => write!(f, "{selector}"),
+            Self::PseudoClass(pseudo_class) => write!(f, "{pseudo_class}"),
+            Self::PseudoElement(pseudo_element) => write!(f, "{pseudo_element}"),
+        }
+    }
+}
+
+impl fmt::Display for AttributeSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}", serialize_identifier(&self.name))?;
+        if let Some(matcher) = &self.matcher {
+            let (operator, value) = match matcher {
+                AttributeMatcher::Exact(value) => ("=", value),
+                AttributeMatcher::Includes(value) => ("~=", value),
+                AttributeMatcher::DashMatch(value) => ("|=", value),
+                AttributeMatcher::Prefix(value) => ("^=", value),
+                AttributeMatcher::Suffix(value) => ("$=", value),
+                AttributeMatcher::Substring(value) => ("*=", value),
+            };
+            write!(f, "{operator}{}", serialize_string(value))?;
+            if self.case_insensitive {
+                write!(f, " i")?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+impl fmt::Display for PseudoClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Root => "root",
+            Self::Empty => "empty",
+            Self::FirstChild => "first-child",
+            Self::LastChild => "last-child",
+            Self::OnlyChild => "only-child",
+            Self::FirstOfType => "first-of-type",
+            Self::LastOfType => "last-of-type",
+            Self::OnlyOfType => "only-of-type",
+            Self::Link => "link",
+            Self::Visited => "visited",
+            Self::AnyLink => "any-link",
+            Self::Hover => "hover",
+            Self::Active => "active",
+            Self::Focus => "focus",
+            Self::NthChild(an_plus_b, of) | Self::NthLastChild(an_plus_b, of) => {
+                let name = match self {
+                    Self::NthChild(..) => "nth-child",
+                    _ => "nth-last-child",
+                };
+                write!(f, ":{name}({an_plus_b}")?;
+                if let Some(of) = of {
+                    write!(f, " of {of}")?;
+                }
+                return write!(f, ")");
+            }
+            Self::NthOfType(an_plus_b) => return write!(f, ":nth-of-type({an_plus_b})"),
+            Self::NthLastOfType(an_plus_b) => return write!(f, ":nth-last-of-type({an_plus_b})"),
+            Self::Not(list) => return write!(f, ":not({list})"),
+            Self::Is(list) => return write!(f, ":is({list})"),
+            Self::Where(list) => return write!(f, ":where({list})"),
+            Self::Has(relative_selectors) => {
+                write!(f, ":has(")?;
+                for (i, selector) in relative_selectors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{selector}")?;
+                }
+                return write!(f, ")");
+            }
+        };
+        write!(f, ":{name}")
+    }
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#serialize-an-anb-value>
+impl fmt::Display for AnPlusB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.a == 0 {
+            return write!(f, "{}", self.b);
+        }
+        match self.a {
+            1 => write!(f, "n")?,
+            -1 => write!(f, "-n")?,
+            a => write!(f, "{a}n")?,
+        }
+        match self.b {
+            0 => Ok(()),
+            b if b > 0 => write!(f, "+{b}"),
+            b => write!(f, "{b}"),
+        }
+    }
+}
+
+impl fmt::Display for PseudoElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Before => "before",
+            Self::After => "after",
+            Self::FirstLine => "first-line",
+            Self::FirstLetter => "first-letter",
+            Self::Marker => "marker",
+        };
+        write!(f, "::{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::parse_css_stylesheet;
+    use super::*;
+
+    #[test]
+    fn test_serialize_shorthands() {
+        let style = parse_style_attribute(
+            "margin: 1px 2px; margin-left: 3px; color: red; border: 1px solid; \
+             border-top-color: blue; font: bold 12px serif; padding: 1px; \
+             padding-top: 2px !important"
+                .into(),
+        );
+        assert_eq!(style.get_property_value("margin"), "1px 2px 1px 3px");
+        assert_eq!(style.get_property_value("margin-left"), "3px");
+        assert_eq!(style.get_property_value("border"), "");
+        assert_eq!(style.get_property_value("border-left"), "1px solid");
+        assert_eq!(style.get_property_value("padding"), "");
+        assert_eq!(style.get_property_value("unknown"), "");
+        assert_eq!(
+            style.serialize(),
+            "margin: 1px 2px 1px 3px; color: rgb(255, 0, 0); border-width: 1px; \
+             border-style: solid; border-color: rgb(0, 0, 255) currentcolor currentcolor; \
+             font: bold 12px serif; padding-right: 1px; padding-bottom: 1px; \
+             padding-left: 1px; padding-top: 2px !important;"
+        );
+    }
+
+    #[test]
+    fn test_serialize_custom_properties() {
+        let mut style = parse_style_attribute(
+            "--Main-Color: #fff; --gap:{a:b} ; --x: INHERIT; margin: var(--gap) 1px; \
+             padding-top: calc(var(--gap) * 2)"
+                .into(),
+        );
+        assert_eq!(style.get_property_value("--Main-Color"), "#fff");
+        assert_eq!(style.get_property_value("--main-color"), "");
+        assert_eq!(style.get_property_value("--gap"), "{a:b}");
+        assert_eq!(style.get_property_value("margin"), "var(--gap) 1px");
+        assert_eq!(style.get_property_value("margin-top"), "");
+        assert_eq!(
+            style.get_property_value("padding-top"),
+            "calc(var(--gap) * 2)"
+        );
+        assert_eq!(
+            style.serialize(),
+            "--Main-Color: #fff; --gap: {a:b}; --x: inherit; margin: var(--gap) 1px; \
+             padding-top: calc(var(--gap) * 2);"
+        );
+
+        style.set_property("margin-left", "0", "");
+        assert_eq!(style.get_property_value("margin"), "");
+        assert!(style.set_property("--main-color", "red", ""));
+        assert_eq!(style.remove_property("--Main-Color"), "#fff");
+        assert_eq!(
+            style.serialize(),
+            "--gap: {a:b}; --x: inherit; margin-top: ; margin-right: ; margin-bottom: ; \
+             margin-left: 0px; padding-top: calc(var(--gap) * 2); --main-color: red;"
+        );
+        // Not a supported property.
+        assert!(!style.set_property("foo", "var(--gap)", ""));
+    }
+
+    #[test]
+    fn test_set_and_remove_property() {
+        let mut style =
+            parse_style_attribute("color: red !important; color: blue; margin: 1px".into());
+        assert_eq!(style.get_property_value("color"), "rgb(255, 0, 0)");
+        assert_eq!(style.get_property_priority("color"), "important");
+        assert_eq!(style.get_property_priority("margin"), "");
+
+        assert!(style.set_property("COLOR", "green", ""));
+        assert!(style.set_property("margin-left", "2px", "IMPORTANT"));
+        assert!(!style.set_property("margin-left", "2px", "foo"));
+        assert!(!style.set_property("margin-left", "foo", ""));
+        assert!(!style.set_property("unknown", "1px", ""));
+        assert_eq!(
+            style.css_text(),
+            "color: rgb(0, 128, 0); margin-top: 1px; margin-right: 1px; margin-bottom: 1px; \
+             margin-left: 2px !important;"
+        );
+
+        assert_eq!(style.remove_property("margin"), "");
+        assert_eq!(style.remove_property("margin"), "");
+        assert!(style.set_property("padding", "1px 2px", ""));
+        assert!(style.set_property("color", "", ""));
+        assert_eq!(style.css_text(), "padding: 1px 2px;");
+
+        style.set_css_text("display: block; foo: bar");
+        assert_eq!(style.css_text(), "display: block;");
+    }
+
+    #[test]
+    fn test_serialize_rules() {
+        let style_sheet = parse_css_stylesheet(
+            "@import 'a.css' SCREEN;\n\
+             h1>P ,  .a.b#c , *[href^=http i]::before{color:red;MARGIN:0 1px}\n\
+             li:nth-child( 2n - 1 of .x ):not(.y, :hover) ~ a + b {}\n\
+             @media (min-width:100px){a:has(> img, span){font-weight:bold!important}}\n\
+             @supports (display: grid) { a { color: blue } }\n\
+             @font-face { font-family: foo }\n\
+             @keyframes spin { from { color: red } 50%, TO { color: blue } }"
+                .into(),
+        );
+        assert_eq!(
+            style_sheet.css_rules.iter().map(|rule| rule.css_text()).collect::<Vec<_>>(),
+            [
+                "@import url(\"a.css\") screen;",
+                "h1 > p, .a.b#c, *[href^=\"http\" i]::before { color: rgb(255, 0, 0); margin: 0px 1px; }",
+                "li:nth-child(2n-1 of .x):not(.y, :hover) ~ a + b { }",
+                "@media (min-width: 100px) {\n  a:has(> img, span) { font-weight: bold !important; }\n}",
+                "@supports (display: grid) {\n  a { color: rgb(0, 0, 255); }\n}",
+                "@font-face { font-family: \"foo\"; }",
+                "@keyframes spin {\n  0% { color: rgb(255, 0, 0); }\n  50%, 100% { color: rgb(0, 0, 255); }\n}",
+            ]
+        );
+        match &style_sheet.css_rules[1] {
+            CssRule::Style(rule) => {
+                assert_eq!(
+                    rule.selector_text(),
+                    "h1 > p, .a.b#c, *[href^=\"http\" i]::before"
+                )
+            }
+            rule => panic!("unexpected rule: {rule:?}"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        const SELECTORS: &[&str] = &[
+            "a",
+            "*",
+            ".foo",
+            "#bar",
+            "[href]",
+            "[lang|=en]",
+            "[title~='a b' i]",
+            ":first-child",
+            ":nth-child(odd)",
+            ":nth-last-of-type(-n+3)",
+            ":not(p)",
+            ":is(h1, .x)",
+            ":where(#y)",
+            ":has(+ p)",
+            "::after",
+            "::marker",
+            ".\\31 st",
+            "#a\\:b",
+        ];
+        const COMBINATORS: &[&str] = &[" ", ">", " + ", "~"];
+        const DECLARATIONS: &[&str] = &[
+            "color: #0f0",
+            "margin: 1px auto",
+            "padding-left: 2em",
+            "width: calc(100% - 2px)",
+            "height: min(10px, 5vh)",
+            "font: italic 12px/1.5 'Helvetica Neue', serif",
+            "border: thin dashed",
+            "display: none !important",
+            "--x: { a; b }",
+            "--Y:  1  2 ",
+            "top: var(--x, 3px)",
+            "background-color: transparent",
+            "background-position: .5em 0",
+        ];
+        const MEDIA: &[&str] = &["print", "(max-width: 40em)", "not screen and (color)"];
+
+        // A linear congruential generator, so that the cases are reproducible.
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut random = |n: usize| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (seed >> 33) as usize % n
+        };
+        for _ in 0..200 {
+            let mut source = String::new();
+            for _ in 0..1 + random(4) {
+                let mut selector = String::from(SELECTORS[random(SELECTORS.len())]);
+                for _ in 0..random(3) {
+                    selector.push_str(COMBINATORS[random(COMBINATORS.len())]);
+                    selector.push_str(SELECTORS[random(SELECTORS.len())]);
+                }
+                // Each property is declared at most once, since the duplicates are dropped.
+                let mut declarations = (0..random(4))
+                    .map(|_| DECLARATIONS[random(DECLARATIONS.len())])
+                    .collect::<Vec<_>>();
+                declarations.sort();
+                declarations.dedup();
+                let declarations = declarations.join(";");
+                let rule = format!("{selector}{{{declarations}}}");
+                match random(3) {
+                    0 => {
+                        source.push_str(&format!("@media {}{{{rule}}}", MEDIA[random(MEDIA.len())]))
+                    }
+                    _ => source.push_str(&rule),
+                }
+            }
+
+            let parsed = parse_css_stylesheet(source.clone());
+            let serialized = parsed.css_text();
+            let reparsed = parse_css_stylesheet(serialized.clone());
+            assert_eq!(parsed, reparsed, "{source}\n{serialized}");
+            assert_eq!(serialized, reparsed.css_text(), "{source}");
+        }
+    }
 }
@@ -0,0 +1,315 @@
+//! <https://www.w3.org/TR/css-variables-1/>
+//!
+//! Custom properties hold untyped token streams, which are substituted into other properties
+//! by `var()` at computed-value time.
+//!
+//! Servo: <https://github.com/servo/stylo/blob/main/style/custom_properties.rs>
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::cssom::CssDeclaration;
+use super::parser::{self, trim_whitespace};
+use super::properties;
+use super::shorthands;
+use super::token::CssToken;
+use super::value::ComponentValue;
+
+/// The computed values of the custom properties of an element, keyed by their names.
+///
+/// The map is shared with the parent as long as the element does not declare any custom property.
+/// Guaranteed-invalid values are not stored.
+pub type CustomProperties = Rc<BTreeMap<String, Vec<parser::ComponentValue>>>;
+
+/// A value containing `var()`, which is parsed against the grammar of its property
+/// after the substitution at computed-value time.
+///
+/// <https://www.w3.org/TR/css-variables-1/#variables-in-shorthands>
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnparsedValue {
+    pub tokens: Vec<parser::ComponentValue>,
+    /// The shorthand the value was declared for, if the value is a pending-substitution value
+    /// of one of its longhands.
+    pub shorthand: Option<String>,
+}
+
+/// <https://www.w3.org/TR/css-variables-1/#custom-property>
+pub fn is_custom_property(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+/// Returns true if the value contains `var()` at any depth.
+pub fn contains_var(values: &[parser::ComponentValue]) -> bool {
+    values.iter().any(|v| match v {
+        parser::ComponentValue::Function(f) => {
+            f.name.eq_ignore_ascii_case("var") || contains_var(&f.value)
+        }
+        parser::ComponentValue::SimpleBlock(b) => contains_var(&b.value),
+        parser::ComponentValue::PreservedToken(_) => false,
+    })
+}
+
+/// Computes the custom properties of an element from its cascaded declarations,
+/// which are sorted in the ascending order of precedence, and those of its parent.
+///
+/// <https://www.w3.org/TR/css-variables-1/#cycles>
+///
+/// The properties in a dependency cycle are invalid at computed-value time.
+pub fn compute(declarations: &[CssDeclaration], parent: &CustomProperties) -> CustomProperties {
+    let mut specified = BTreeMap::new();
+    for declaration in declarations {
+        if !is_custom_property(&declaration.property_name) {
+            continue;
+        }
+        let value = match declaration.value.as_slice() {
+            [ComponentValue::Unparsed(unparsed)] => Specified::Tokens(&unparsed.tokens),
+            // Custom properties are inherited, so `inherit` and `unset` are the same.
+            [ComponentValue::Keyword(k)] if k == "inherit" || k == "unset" => Specified::Inherit,
+            // The initial value is the guaranteed-invalid value.
+            [ComponentValue::Keyword(k)] if k == "initial" => Specified::Initial,
+            _ => continue,
+        };
+        specified.insert(declaration.property_name.as_str(), value);
+    }
+    if specified.is_empty() {
+        return Rc::clone(parent);
+    }
+
+    let mut resolver = Resolver {
+        specified: &specified,
+        parent,
+        resolved: BTreeMap::new(),
+        stack: Vec::new(),
+        cyclic: Vec::new(),
+    };
+    let mut computed = (**parent).clone();
+    for name in specified.keys() {
+        match resolver.resolve(name) {
+            Some(value) => computed.insert(name.to_string(), value),
+            None => computed.remove(*name),
+        };
+    }
+    Rc::new(computed)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Specified<'a> {
+    Tokens(&'a [parser::ComponentValue]),
+    Inherit,
+    Initial,
+}
+
+struct Resolver<'a> {
+    specified: &'a BTreeMap<&'a str, Specified<'a>>,
+    parent: &'a CustomProperties,
+    /// The computed values of the properties already resolved. None means guaranteed-invalid.
+    resolved: BTreeMap<String, Option<Vec<parser::ComponentValue>>>,
+    /// The properties being resolved, each referencing the next one.
+    stack: Vec<String>,
+    /// The properties found in a cycle.
+    cyclic: Vec<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, name: &str) -> Option<Vec<parser::ComponentValue>> {
+        if let Some(value) = self.resolved.get(name) {
+            return value.clone();
+        }
+        if let Some(i) = self.stack.iter().position(|n| n == name) {
+            self.cyclic.extend(self.stack[i..].iter().cloned());
+            return None;
+        }
+        let specified = self.specified;
+        let value = match specified.get(name) {
+            None | Some(Specified::Inherit) => self.parent.get(name).cloned(),
+            Some(Specified::Initial) => None,
+            Some(Specified::Tokens(tokens)) => {
+                self.stack.push(name.to_string());
+                let value = substitute(tokens, &mut |name| self.resolve(name));
+                self.stack.pop();
+                value.filter(|_| !self.cyclic.iter().any(|n| n == name))
+            }
+        };
+        self.resolved.insert(name.to_string(), value.clone());
+        value
+    }
+}
+
+/// <https://www.w3.org/TR/css-variables-1/#substitute-a-var>
+///
+/// Replaces `var()` in the tokens with the values looked up by `lookup`, or with their fallbacks.
+/// Returns None if a referenced property is guaranteed-invalid without a fallback,
+/// or `var()` has an invalid syntax.
+pub fn substitute(
+    tokens: &[parser::ComponentValue],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<parser::ComponentValue>>,
+) -> Option<Vec<parser::ComponentValue>> {
+    let mut result = Vec::new();
+    for token in trim_whitespace(tokens) {
+        match token {
+            parser::ComponentValue::Function(f) if f.name.eq_ignore_ascii_case("var") => {
+                let (name, fallback) = parse_var(&f.value)?;
+                match lookup(name) {
+                    Some(value) => result.extend(value),
+                    None => result.extend(substitute(fallback?, lookup)?),
+                }
+            }
+            parser::ComponentValue::Function(f) => {
+                let mut f = f.clone();
+                f.value = substitute_nested(&f.value, lookup)?;
+                result.push(parser::ComponentValue::Function(f));
+            }
+            parser::ComponentValue::SimpleBlock(b) => {
+                let mut b = b.clone();
+                b.value = substitute_nested(&b.value, lookup)?;
+                result.push(parser::ComponentValue::SimpleBlock(b));
+            }
+            token => result.push(token.clone()),
+        }
+    }
+    Some(result)
+}
+
+/// Substitutes the contents of a function or a block, which are kept as they are without `var()`.
+fn substitute_nested(
+    tokens: &[parser::ComponentValue],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<parser::ComponentValue>>,
+) -> Option<Vec<parser::ComponentValue>> {
+    if !contains_var(tokens) {
+        return Some(tokens.to_vec());
+    }
+    substitute(tokens, lookup)
+}
+
+/// <https://www.w3.org/TR/css-variables-1/#funcdef-var>
+///
+/// `var( <custom-property-name> , <declaration-value>? )`
+///
+/// Returns the name and the fallback, if any.
+fn parse_var(args: &[parser::ComponentValue]) -> Option<(&str, Option<&[parser::ComponentValue]>)> {
+    let args = trim_whitespace(args);
+    let (name, rest) = args.split_first()?;
+    let name = match name {
+        parser::ComponentValue::PreservedToken(CssToken::Ident(name))
+            if is_custom_property(name) =>
+        {
+            name
+        }
+        _ => return None,
+    };
+    match trim_whitespace(rest) {
+        [] => Some((name, None)),
+        [parser::ComponentValue::PreservedToken(CssToken::Comma), fallback @ ..] => {
+            Some((name, Some(fallback)))
+        }
+        _ => None,
+    }
+}
+
+/// Substitutes `var()` in a value of a property with the custom properties,
+/// and parses it against the grammar of the property.
+///
+/// Returns None if the declaration is invalid at computed-value time.
+///
+/// <https://www.w3.org/TR/css-variables-1/#invalid-at-computed-value-time>
+pub fn resolve_unparsed(
+    name: &str,
+    value: &UnparsedValue,
+    custom_properties: &CustomProperties,
+) -> Option<Vec<ComponentValue>> {
+    let tokens = substitute(&value.tokens, &mut |name| {
+        custom_properties.get(name).cloned()
+    })?;
+    match &value.shorthand {
+        Some(shorthand) => shorthands::expand(shorthand, &tokens)?
+            .into_iter()
+            .find(|(longhand, _)| longhand.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value),
+        None => properties::parse_declaration_value(name, &tokens),
+    }
+}
+
+/// Returns the declarations of a property whose value contains `var()`, which are validated
+/// after the substitution.
+///
+/// <https://www.w3.org/TR/css-variables-1/#using-variables>
+pub fn parse_declaration(
+    name: &str,
+    value: &[parser::ComponentValue],
+) -> Option<Vec<(String, Vec<ComponentValue>)>> {
+    let unparsed = |shorthand| {
+        vec![ComponentValue::Unparsed(UnparsedValue {
+            tokens: trim_whitespace(value).to_vec(),
+            shorthand,
+        })]
+    };
+    if let Some(longhands) = shorthands::longhands(name) {
+        let shorthand = Some(name.to_ascii_lowercase());
+        return Some(
+            longhands
+                .iter()
+                .map(|longhand| (longhand.to_string(), unparsed(shorthand.clone())))
+                .collect(),
+        );
+    }
+    let name = name.to_ascii_lowercase();
+    if !properties::LONGHANDS.contains(&name.as_str()) {
+        return None;
+    }
+    Some(vec![(name, unparsed(None))])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::{parse_style_attribute, serialize_component_values};
+    use super::*;
+
+    fn compute_style(style: &str, parent: &CustomProperties) -> CustomProperties {
+        compute(&parse_style_attribute(style.into()).declarations, parent)
+    }
+
+    fn get(custom_properties: &CustomProperties, name: &str) -> Option<String> {
+        custom_properties
+            .get(name)
+            .map(|v| serialize_component_values(v))
+    }
+
+    #[test]
+    fn test_compute() {
+        let root = compute_style(
+            "--a: 1px; --b: var(--a) solid; --A: 2px; --c: var(--x, var(--a, 3px)); --d: var(--x)",
+            &CustomProperties::default(),
+        );
+        assert_eq!(get(&root, "--a").as_deref(), Some("1px"));
+        assert_eq!(get(&root, "--A").as_deref(), Some("2px"));
+        assert_eq!(get(&root, "--b").as_deref(), Some("1px solid"));
+        assert_eq!(get(&root, "--c").as_deref(), Some("1px"));
+        assert_eq!(get(&root, "--d"), None);
+
+        // Inherited, and resolved against the element's own values.
+        let child = compute_style("--a: 4px; --e: var(--b) var(--a); --b: initial", &root);
+        assert_eq!(get(&child, "--a").as_deref(), Some("4px"));
+        assert_eq!(get(&child, "--b"), None);
+        assert_eq!(get(&child, "--c").as_deref(), Some("1px"));
+        assert_eq!(get(&child, "--e"), None);
+        assert!(Rc::ptr_eq(&compute_style("color: red", &root), &root));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let style = compute_style(
+            "--a: var(--b); --b: var(--c, 1px); --c: var(--a); --d: var(--a, 2px); --e: var(--e, 3px); --f:;",
+            &CustomProperties::default(),
+        );
+        assert_eq!(get(&style, "--a"), None);
+        assert_eq!(get(&style, "--b"), None);
+        assert_eq!(get(&style, "--c"), None);
+        // Referencing a cycle is not a cycle.
+        assert_eq!(get(&style, "--d").as_deref(), Some("2px"));
+        assert_eq!(get(&style, "--e"), None);
+        assert_eq!(get(&style, "--f").as_deref(), Some(""));
+    }
+}
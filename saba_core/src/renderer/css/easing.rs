@@ -0,0 +1,268 @@
+//! <https://www.w3.org/TR/css-easing-1/>
+//!
+//! Easing functions map the progress of a transition or an animation to the progress of the values.
+//!
+//! Servo: <https://github.com/servo/stylo/blob/main/style/values/computed/easing.rs>
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::parser::{self, trim_whitespace};
+use super::token::CssToken;
+use super::value::{parse_integer, parse_keyword, parse_number};
+
+/// <https://www.w3.org/TR/css-easing-1/#typedef-easing-function>
+///
+/// The keywords are kept, so that they are serialized as specified.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EasingFunction {
+    Linear,
+    Ease,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// <https://www.w3.org/TR/css-easing-1/#cubic-bezier-easing-functions>
+    CubicBezier(f64, f64, f64, f64),
+    /// <https://www.w3.org/TR/css-easing-1/#step-easing-functions>
+    Steps(u32, StepPosition),
+}
+
+/// <https://www.w3.org/TR/css-easing-1/#typedef-step-position>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepPosition {
+    JumpStart,
+    JumpEnd,
+    JumpNone,
+    JumpBoth,
+}
+
+impl EasingFunction {
+    /// Returns the output progress of the input progress, which is in [0, 1].
+    pub fn evaluate(&self, progress: f64) -> f64 {
+        match *self {
+            Self::Linear => progress,
+            Self::Ease => cubic_bezier(0.25, 0.1, 0.25, 1.0, progress),
+            Self::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, progress),
+            Self::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, progress),
+            Self::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, progress),
+            Self::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, progress),
+            Self::Steps(steps, position) => {
+                // <https://www.w3.org/TR/css-easing-1/#step-easing-algo>
+                let mut step = floor(progress * steps as f64);
+                if matches!(position, StepPosition::JumpStart | StepPosition::JumpBoth) {
+                    step += 1;
+                }
+                let jumps = match position {
+                    StepPosition::JumpStart | StepPosition::JumpEnd => steps as i64,
+                    StepPosition::JumpNone => steps as i64 - 1,
+                    StepPosition::JumpBoth => steps as i64 + 1,
+                };
+                step.clamp(0, jumps) as f64 / jumps as f64
+            }
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/css-easing-1/#cubic-bezier-algo>
+///
+/// Finds the parameter of the curve whose x is the progress by bisection, as x is monotonic
+/// in the parameter when x1 and x2 are in [0, 1], and returns the y of the parameter.
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, progress: f64) -> f64 {
+    let bezier = |p1: f64, p2: f64, t: f64| {
+        let s = 1.0 - t;
+        3.0 * s * s * t * p1 + 3.0 * s * t * t * p2 + t * t * t
+    };
+    if progress <= 0.0 || progress >= 1.0 {
+        return progress;
+    }
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut t = progress;
+    for _ in 0..50 {
+        let x = bezier(x1, x2, t);
+        if (x - progress) > -1e-9 && (x - progress) < 1e-9 {
+            break;
+        }
+        if x < progress {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) / 2.0;
+    }
+    bezier(y1, y2, t)
+}
+
+/// Rounds toward negative infinity, which `f64` lacks without `std`.
+fn floor(value: f64) -> i64 {
+    let truncated = value as i64;
+    if (truncated as f64) > value {
+        truncated - 1
+    } else {
+        truncated
+    }
+}
+
+/// Parses an `<easing-function>`.
+pub fn parse_easing_function(value: &parser::ComponentValue) -> Option<EasingFunction> {
+    if let Some(keyword) = parse_keyword(value) {
+        let easing = match keyword.as_str() {
+            "linear" => EasingFunction::Linear,
+            "ease" => EasingFunction::Ease,
+            "ease-in" => EasingFunction::EaseIn,
+            "ease-out" => EasingFunction::EaseOut,
+            "ease-in-out" => EasingFunction::EaseInOut,
+            "step-start" => EasingFunction::Steps(1, StepPosition::JumpStart),
+            "step-end" => EasingFunction::Steps(1, StepPosition::JumpEnd),
+            _ => return None,
+        };
+        return Some(easing);
+    }
+
+    let parser::ComponentValue::Function(function) = value else {
+        return None;
+    };
+    let args: Vec<&[parser::ComponentValue]> = function
+        .value
+        .split(|v| *v == parser::ComponentValue::PreservedToken(CssToken::Comma))
+        .map(trim_whitespace)
+        .collect();
+    let single = |arg: &[parser::ComponentValue]| match arg {
+        [v] => Some(v.clone()),
+        _ => None,
+    };
+    match function.name.to_ascii_lowercase().as_str() {
+        "cubic-bezier" => {
+            let [x1, y1, x2, y2] = args.as_slice() else {
+                return None;
+            };
+            let [x1, y1, x2, y2] =
+                [x1, y1, x2, y2].map(|arg| single(arg).as_ref().and_then(parse_number));
+            let (x1, y1, x2, y2) = (x1?, y1?, x2?, y2?);
+            // The x values must be in [0, 1], so that the curve is a function of the progress.
+            if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                return None;
+            }
+            Some(EasingFunction::CubicBezier(x1, y1, x2, y2))
+        }
+        "steps" => {
+            let (steps, position) = match args.as_slice() {
+                [steps] => (steps, StepPosition::JumpEnd),
+                [steps, position] => {
+                    let position = match parse_keyword(&single(position)?)?.as_str() {
+                        "jump-start" | "start" => StepPosition::JumpStart,
+                        "jump-end" | "end" => StepPosition::JumpEnd,
+                        "jump-none" => StepPosition::JumpNone,
+                        "jump-both" => StepPosition::JumpBoth,
+                        _ => return None,
+                    };
+                    (steps, position)
+                }
+                _ => return None,
+            };
+            let steps = parse_integer(&single(steps)?)?;
+            // The number of steps is positive, and must be at least 2 for `jump-none`.
+            let min = if position == StepPosition::JumpNone {
+                2
+            } else {
+                1
+            };
+            if steps < min {
+                return None;
+            }
+            Some(EasingFunction::Steps(steps as u32, position))
+        }
+        _ => None,
+    }
+}
+
+/// <https://www.w3.org/TR/css-easing-1/#serialization>
+impl fmt::Display for EasingFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Ease => write!(f, "ease"),
+            Self::EaseIn => write!(f, "ease-in"),
+            Self::EaseOut => write!(f, "ease-out"),
+            Self::EaseInOut => write!(f, "ease-in-out"),
+            Self::CubicBezier(x1, y1, x2, y2) => write!(f, "cubic-bezier({x1}, {y1}, {x2}, {y2})"),
+            Self::Steps(steps, StepPosition::JumpEnd) => write!(f, "steps({steps})"),
+            Self::Steps(steps, position) => {
+                let position = match position {
+                    StepPosition::JumpStart => "jump-start",
+                    StepPosition::JumpEnd => "jump-end",
+                    StepPosition::JumpNone => "jump-none",
+                    StepPosition::JumpBoth => "jump-both",
+                };
+                write!(f, "steps({steps}, {position})")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use super::super::parser::StyleSheetParser;
+    use super::super::token::CssTokenizer;
+    use super::*;
+
+    fn parse(value: &str) -> Option<EasingFunction> {
+        let value = StyleSheetParser::new(CssTokenizer::new(value.to_string()))
+            .parse_list_of_component_values();
+        match trim_whitespace(&value) {
+            [value] => parse_easing_function(value),
+            _ => None,
+        }
+    }
+
+    fn serialize(value: &str) -> Option<String> {
+        parse(value).map(|easing| easing.to_string())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(serialize("EASE-IN").as_deref(), Some("ease-in"));
+        assert_eq!(
+            serialize("cubic-bezier(0.1, -0.5, 1, 2)").as_deref(),
+            Some("cubic-bezier(0.1, -0.5, 1, 2)")
+        );
+        assert_eq!(serialize("steps(3, end)").as_deref(), Some("steps(3)"));
+        assert_eq!(
+            serialize("step-start").as_deref(),
+            Some("steps(1, jump-start)")
+        );
+        assert_eq!(
+            serialize("steps(2, jump-none)").as_deref(),
+            Some("steps(2, jump-none)")
+        );
+        assert_eq!(parse("cubic-bezier(1.5, 0, 1, 1)"), None);
+        assert_eq!(parse("cubic-bezier(0, 0, 1)"), None);
+        assert_eq!(parse("steps(0)"), None);
+        assert_eq!(parse("steps(1.5)"), None);
+        assert_eq!(parse("steps(2.0)"), None);
+        assert_eq!(parse("steps(1, jump-none)"), None);
+        assert_eq!(parse("bounce"), None);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let close = |a: f64, b: f64| (a - b) < 1e-6 && (b - a) < 1e-6;
+        assert_eq!(EasingFunction::Linear.evaluate(0.3), 0.3);
+        assert!(close(EasingFunction::EaseInOut.evaluate(0.5), 0.5));
+        assert!(EasingFunction::EaseIn.evaluate(0.25) < 0.25);
+        assert!(EasingFunction::EaseOut.evaluate(0.25) > 0.25);
+        assert_eq!(EasingFunction::Ease.evaluate(0.0), 0.0);
+        assert_eq!(EasingFunction::Ease.evaluate(1.0), 1.0);
+        // The curve overshoots the end.
+        assert!(EasingFunction::CubicBezier(0.5, 2.0, 0.5, 2.0).evaluate(0.5) > 1.0);
+
+        let steps = |n, position, progress| EasingFunction::Steps(n, position).evaluate(progress);
+        assert_eq!(steps(4, StepPosition::JumpEnd, 0.3), 0.25);
+        assert_eq!(steps(4, StepPosition::JumpEnd, 1.0), 1.0);
+        assert_eq!(steps(4, StepPosition::JumpStart, 0.0), 0.25);
+        assert_eq!(steps(3, StepPosition::JumpNone, 0.5), 0.5);
+        assert_eq!(steps(3, StepPosition::JumpBoth, 0.0), 0.25);
+        assert_eq!(steps(3, StepPosition::JumpBoth, 1.0), 1.0);
+    }
+}
@@ -0,0 +1,281 @@
+//! <https://www.w3.org/TR/css-fonts-4/#font-face-rule>
+//!
+//! Servo: <https://github.com/servo/stylo/blob/main/style/font_face.rs>
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::renderer::layout::computed_style::FontStyle;
+
+use super::parser::{self, trim_whitespace, Declaration};
+use super::properties;
+use super::token::CssToken;
+use super::value::{parse_keyword, parse_number, parse_url, serialize_string, ComponentValue};
+
+/// The descriptors of an `@font-face` rule. None means the descriptor is not declared.
+///
+/// `unicode-range` is not supported, and fonts are chosen for each character
+/// by whether they have a glyph for it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FontFaceDescriptors {
+    /// <https://www.w3.org/TR/css-fonts-4/#font-family-desc>
+    pub family: Option<String>,
+    /// <https://www.w3.org/TR/css-fonts-4/#src-desc>
+    pub src: Option<Vec<FontFaceSource>>,
+    /// <https://www.w3.org/TR/css-fonts-4/#font-prop-desc>
+    ///
+    /// The range of the weights, where `auto` is None.
+    pub weight: Option<Option<(u16, u16)>>,
+    /// The style, where `auto` is None. The angles of `oblique` are ignored.
+    pub style: Option<Option<FontStyle>>,
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-face-src-parsing>
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontFaceSource {
+    Url {
+        url: String,
+        /// The format hint, e.g. `truetype`, in lowercase.
+        format: Option<String>,
+    },
+    /// A font installed in the browser.
+    Local(String),
+}
+
+impl FontFaceDescriptors {
+    /// Parses the declarations in the block of an `@font-face` rule.
+    /// Invalid and unknown descriptors are ignored, and the last valid one wins.
+    pub fn parse(declarations: &[Declaration]) -> Self {
+        let mut descriptors = Self::default();
+        for declaration in declarations {
+            let value = trim_whitespace(&declaration.value);
+            match declaration.name.to_ascii_lowercase().as_str() {
+                "font-family" => {
+                    if let Some(family) = parse_family(value) {
+                        descriptors.family = Some(family);
+                    }
+                }
+                "src" => {
+                    if let Some(src) = parse_src(value) {
+                        descriptors.src = Some(src);
+                    }
+                }
+                "font-weight" => {
+                    if let Some(weight) = parse_weight(value) {
+                        descriptors.weight = Some(weight);
+                    }
+                }
+                "font-style" => {
+                    if let Some(style) = parse_style(value) {
+                        descriptors.style = Some(style);
+                    }
+                }
+                _ => {}
+            }
+        }
+        descriptors
+    }
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-family-desc>
+///
+/// A single family name, which can't be a generic family.
+fn parse_family(value: &[parser::ComponentValue]) -> Option<String> {
+    match properties::parse_declaration_value("font-family", value)?.as_slice() {
+        [ComponentValue::String(name)] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#src-desc>
+///
+/// The sources which fail to parse are dropped, and the descriptor is invalid if none is left.
+fn parse_src(value: &[parser::ComponentValue]) -> Option<Vec<FontFaceSource>> {
+    let sources = value
+        .split(|v| *v == parser::ComponentValue::PreservedToken(CssToken::Comma))
+        .filter_map(|source| parse_source(trim_whitespace(source)))
+        .collect::<Vec<_>>();
+    (!sources.is_empty()).then_some(sources)
+}
+
+/// `<url> [ format(<font-format>) ]? | local(<family-name>)`
+fn parse_source(value: &[parser::ComponentValue]) -> Option<FontFaceSource> {
+    let (first, rest) = value.split_first()?;
+    if let parser::ComponentValue::Function(f) = first {
+        if f.name.eq_ignore_ascii_case("local") {
+            if !rest.is_empty() {
+                return None;
+            }
+            return Some(FontFaceSource::Local(parse_family(trim_whitespace(
+                &f.value,
+            ))?));
+        }
+    }
+    let url = parse_url(first)?;
+    let format = match trim_whitespace(rest) {
+        [] => None,
+        [parser::ComponentValue::Function(f)] if f.name.eq_ignore_ascii_case("format") => {
+            match trim_whitespace(&f.value) {
+                [parser::ComponentValue::PreservedToken(
+                    CssToken::String(format) | CssToken::Ident(format),
+                )] => Some(format.to_ascii_lowercase()),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(FontFaceSource::Url { url, format })
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-prop-desc>
+///
+/// `auto | <font-weight-absolute>{1,2}`
+fn parse_weight(value: &[parser::ComponentValue]) -> Option<Option<(u16, u16)>> {
+    let values = value
+        .iter()
+        .filter(|v| **v != parser::ComponentValue::PreservedToken(CssToken::Whitespace))
+        .collect::<Vec<_>>();
+    let absolute = |v: &parser::ComponentValue| match parse_keyword(v).as_deref() {
+        Some("normal") => Some(400),
+        Some("bold") => Some(700),
+        Some(_) => None,
+        None => parse_number(v)
+            .filter(|n| (1.0..=1000.0).contains(n))
+            .map(|n| n as u16),
+    };
+    match values.as_slice() {
+        [v] if parse_keyword(v).as_deref() == Some("auto") => Some(None),
+        [v] => absolute(v).map(|w| Some((w, w))),
+        // A reversed range is swapped.
+        [a, b] => {
+            let (a, b) = (absolute(a)?, absolute(b)?);
+            Some(Some((a.min(b), a.max(b))))
+        }
+        _ => None,
+    }
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-style-desc>
+///
+/// `auto | normal | italic | oblique [ <angle>{1,2} ]?`
+fn parse_style(value: &[parser::ComponentValue]) -> Option<Option<FontStyle>> {
+    let (first, rest) = value.split_first()?;
+    match (parse_keyword(first)?.as_str(), rest) {
+        ("auto", []) => Some(None),
+        ("normal", []) => Some(Some(FontStyle::Normal)),
+        ("italic", []) => Some(Some(FontStyle::Italic)),
+        ("oblique", _) => Some(Some(FontStyle::Oblique)),
+        _ => None,
+    }
+}
+
+/// Serializes the declared descriptors, e.g. `font-family: "foo"; src: url("foo.ttf");`.
+impl fmt::Display for FontFaceDescriptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut declarations = Vec::new();
+        if let Some(family) = &self.family {
+            declarations.push(format!("font-family: {}", serialize_string(family)));
+        }
+        if let Some(src) = &self.src {
+            let src = src
+                .iter()
+                .map(|source| source.to_string())
+                .collect::<Vec<_>>();
+            declarations.push(format!("src: {}", src.join(", ")));
+        }
+        match self.weight {
+            Some(None) => declarations.push("font-weight: auto".into()),
+            Some(Some((a, b))) if a == b => declarations.push(format!("font-weight: {a}")),
+            Some(Some((a, b))) => declarations.push(format!("font-weight: {a} {b}")),
+            None => {}
+        }
+        let style = match self.style {
+            Some(None) => Some("auto"),
+            Some(Some(FontStyle::Normal)) => Some("normal"),
+            Some(Some(FontStyle::Italic)) => Some("italic"),
+            Some(Some(FontStyle::Oblique)) => Some("oblique"),
+            None => None,
+        };
+        if let Some(style) = style {
+            declarations.push(format!("font-style: {style}"));
+        }
+        for (i, declaration) in declarations.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{declaration};")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FontFaceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url { url, format } => {
+                write!(f, "url({})", serialize_string(url))?;
+                if let Some(format) = format {
+                    write!(f, " format({})", serialize_string(format))?;
+                }
+                Ok(())
+            }
+            Self::Local(name) => write!(f, "local({})", serialize_string(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::super::cssom::CssRule;
+    use super::super::parser::parse_css_stylesheet;
+    use super::*;
+
+    fn parse(block: &str) -> FontFaceDescriptors {
+        match &parse_css_stylesheet(format!("@font-face {{ {block} }}")).css_rules[..] {
+            [CssRule::FontFace(rule)] => rule.descriptors.clone(),
+            rules => panic!("unexpected rules: {rules:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let descriptors = parse(
+            "font-family: Noto  Sans JP; src: local(Foo), url(a.ttf) format('TrueType'), \
+             url(b.woff) format(1), url('c.bdf'); font-weight: 700 300; font-style: oblique 10deg",
+        );
+        assert_eq!(descriptors.family.as_deref(), Some("Noto Sans JP"));
+        assert_eq!(
+            descriptors.src,
+            Some(vec![
+                FontFaceSource::Local("Foo".into()),
+                FontFaceSource::Url {
+                    url: "a.ttf".into(),
+                    format: Some("truetype".into())
+                },
+                FontFaceSource::Url {
+                    url: "c.bdf".into(),
+                    format: None
+                },
+            ])
+        );
+        assert_eq!(descriptors.weight, Some(Some((300, 700))));
+        assert_eq!(descriptors.style, Some(Some(FontStyle::Oblique)));
+        assert_eq!(
+            descriptors.to_string(),
+            "font-family: \"Noto Sans JP\"; src: local(\"Foo\"), url(\"a.ttf\") format(\"truetype\"), \
+             url(\"c.bdf\"); font-weight: 300 700; font-style: oblique;"
+        );
+
+        let descriptors = parse(
+            "font-family: serif; font-family: a, b; src: foo; font-weight: 0; font-style: auto",
+        );
+        assert_eq!(descriptors.family, None);
+        assert_eq!(descriptors.src, None);
+        assert_eq!(descriptors.weight, None);
+        assert_eq!(descriptors.style, Some(None));
+    }
+}
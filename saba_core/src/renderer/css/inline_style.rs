@@ -0,0 +1,146 @@
+//! <https://www.w3.org/TR/cssom-1/#the-elementcssinlinestyle-mixin>
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::RefCell;
+
+use crate::renderer::dom::node::{Node, NodeData};
+
+use super::cssom::CssStyleDeclaration;
+use super::parser::parse_style_attribute;
+
+/// The `CSSStyleDeclaration` of the `style` attribute of an element, i.e. `element.style`.
+///
+/// <https://www.w3.org/TR/cssom-1/#dom-elementcssinlinestyle-style>
+///
+/// The declarations are parsed from the attribute each time, so they are always in sync with it.
+/// Mutations are written back to the attribute.
+#[derive(Debug, Clone)]
+pub struct InlineStyle {
+    /// <https://www.w3.org/TR/cssom-1/#cssstyledeclaration-owner-node>
+    owner_node: Rc<RefCell<Node>>,
+}
+
+impl Node {
+    /// <https://www.w3.org/TR/cssom-1/#dom-elementcssinlinestyle-style>
+    ///
+    /// Panics if the node is not an element.
+    pub fn style(node: Rc<RefCell<Node>>) -> InlineStyle {
+        assert!(
+            matches!(node.borrow().data, NodeData::Element(_)),
+            "not an element"
+        );
+        InlineStyle { owner_node: node }
+    }
+}
+
+impl InlineStyle {
+    fn declarations(&self) -> CssStyleDeclaration {
+        let style = match &self.owner_node.borrow().data {
+            NodeData::Element(e) => e.get_attribute("style").map(String::from),
+            _ => unreachable!(),
+        };
+        parse_style_attribute(style.unwrap_or_default())
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#update-style-attribute-for>
+    fn update_style_attribute(&self, declarations: &CssStyleDeclaration) {
+        Node::set_attribute(
+            Rc::clone(&self.owner_node),
+            "style",
+            &declarations.serialize(),
+        )
+        .expect("`style` is a valid attribute name");
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-csstext>
+    pub fn css_text(&self) -> String {
+        self.declarations().css_text()
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-csstext>
+    pub fn set_css_text(&self, text: &str) {
+        let mut declarations = self.declarations();
+        declarations.set_css_text(text);
+        self.update_style_attribute(&declarations);
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-getpropertyvalue>
+    pub fn get_property_value(&self, property: &str) -> String {
+        self.declarations().get_property_value(property)
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-getpropertypriority>
+    pub fn get_property_priority(&self, property: &str) -> &'static str {
+        self.declarations().get_property_priority(property)
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-setproperty>
+    ///
+    /// The attribute is left as is if the priority or the value is invalid.
+    pub fn set_property(&self, property: &str, value: &str, priority: &str) {
+        let mut declarations = self.declarations();
+        if declarations.set_property(property, value, priority) {
+            self.update_style_attribute(&declarations);
+        }
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-cssstyledeclaration-removeproperty>
+    ///
+    /// Returns the value before the removal.
+    pub fn remove_property(&self, property: &str) -> String {
+        let mut declarations = self.declarations();
+        let length = declarations.declarations.len();
+        let value = declarations.remove_property(property);
+        if declarations.declarations.len() != length {
+            self.update_style_attribute(&declarations);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::dom::test_util;
+
+    use super::*;
+
+    fn style_attribute(node: &Rc<RefCell<Node>>) -> Option<String> {
+        match &node.borrow().data {
+            NodeData::Element(e) => e.get_attribute("style").map(String::from),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_inline_style() {
+        let window = test_util::parse(
+            r#"<html><head></head><body><p style="margin: 1px; color: foo">x</p></body></html>"#,
+        );
+        let p = test_util::element(&window, ElementKind::P);
+        let style = Node::style(Rc::clone(&p));
+
+        assert_eq!(style.css_text(), "margin: 1px;");
+        assert_eq!(style.get_property_value("margin-top"), "1px");
+
+        style.set_property("margin-left", "auto", "important");
+        assert_eq!(
+            style_attribute(&p).unwrap(),
+            "margin-top: 1px; margin-right: 1px; margin-bottom: 1px; margin-left: auto !important;"
+        );
+        assert_eq!(style.get_property_priority("margin-left"), "important");
+
+        assert_eq!(style.remove_property("margin-left"), "auto");
+        style.set_property("margin-left", "1px", "");
+        assert_eq!(style_attribute(&p).unwrap(), "margin: 1px;");
+
+        // The attribute is the source of truth.
+        Node::set_attribute(Rc::clone(&p), "style", "display: none").unwrap();
+        assert_eq!(style.get_property_value("display"), "none");
+        assert_eq!(style.get_property_value("margin"), "");
+
+        style.set_css_text("");
+        assert_eq!(style_attribute(&p).unwrap(), "");
+    }
+}
@@ -0,0 +1,246 @@
+//! Loads the stylesheets of a document from `<style>` and `<link rel="stylesheet">` elements.
+//!
+//! <https://html.spec.whatwg.org/multipage/semantics.html#update-a-style-block>
+//! <https://html.spec.whatwg.org/multipage/links.html#link-type-stylesheet>
+//!
+//! Servo: <https://github.com/servo/servo/blob/main/components/script/stylesheet_loader.rs>
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use crate::http::Fetcher;
+use crate::renderer::dom::node::{ElementKind, Node, NodeData};
+use crate::url::Url;
+
+use super::cssom::{CssRule, CssStyleSheet, StyleSheet, StyleSheetList};
+use super::media::MediaList;
+use super::parser::{parse_css_stylesheet, parse_media_query_list};
+
+#[derive(Debug, Clone, Copy)]
+pub struct StyleSheetLoader<'a> {
+    /// The URL of the document, against which the URLs in it are resolved.
+    base_url: Option<&'a Url>,
+    /// Without it, only the stylesheets embedded in the document are loaded.
+    fetcher: Option<&'a dyn Fetcher>,
+}
+
+impl<'a> StyleSheetLoader<'a> {
+    pub fn new(base_url: Option<&'a Url>, fetcher: Option<&'a dyn Fetcher>) -> Self {
+        Self { base_url, fetcher }
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-documentorshadowroot-stylesheets>
+    ///
+    /// Returns the stylesheets of the document in tree order, with their `@import` rules resolved.
+    pub fn load(&self, document: Rc<RefCell<Node>>) -> StyleSheetList {
+        let style_sheets = Node::descendants(document)
+            .filter_map(|node| self.load_element(&node))
+            .collect();
+        StyleSheetList { style_sheets }
+    }
+
+    /// Returns the stylesheet of the element if it is a `<style>` or a `<link rel="stylesheet">`.
+    fn load_element(&self, node: &Rc<RefCell<Node>>) -> Option<StyleSheet> {
+        let (kind, rel, href, media, disabled) = match &node.borrow().data {
+            NodeData::Element(e) => (
+                e.kind.clone(),
+                e.get_attribute("rel").map(String::from),
+                e.get_attribute("href").map(String::from),
+                e.get_attribute("media").map(String::from),
+                e.get_attribute("disabled").is_some(),
+            ),
+            _ => return None,
+        };
+        let media = media.map(parse_media_query_list).unwrap_or_default();
+
+        match kind {
+            // <https://html.spec.whatwg.org/multipage/semantics.html#update-a-style-block>
+            ElementKind::Style => {
+                let mut css_style_sheet = parse_css_stylesheet(node.borrow().text_content());
+                self.load_imports(&mut css_style_sheet, self.base_url, &mut Vec::new());
+                Some(self.style_sheet(node, None, media, false, css_style_sheet))
+            }
+            // <https://html.spec.whatwg.org/multipage/links.html#link-type-stylesheet>
+            ElementKind::Link => {
+                // `rel` is a set of space-separated keywords, which are ASCII case-insensitive.
+                // Alternative stylesheets are not applied unless the user selects them.
+                let rel = rel?;
+                let has_keyword = |keyword| {
+                    rel.split_ascii_whitespace()
+                        .any(|k| k.eq_ignore_ascii_case(keyword))
+                };
+                if !has_keyword("stylesheet") || has_keyword("alternate") {
+                    return None;
+                }
+                let href = href.filter(|href| !href.is_empty())?;
+                let url = self.resolve(self.base_url, &href)?;
+                let mut css_style_sheet = self.fetch(&url)?;
+                self.load_imports(&mut css_style_sheet, Some(&url), &mut vec![url.clone()]);
+                Some(self.style_sheet(node, Some(url), media, disabled, css_style_sheet))
+            }
+            _ => None,
+        }
+    }
+
+    fn style_sheet(
+        &self,
+        owner_node: &Rc<RefCell<Node>>,
+        location: Option<Url>,
+        media: MediaList,
+        disabled: bool,
+        css_style_sheet: CssStyleSheet,
+    ) -> StyleSheet {
+        StyleSheet {
+            location,
+            owner_node: Rc::downgrade(owner_node),
+            media,
+            disabled,
+            css_style_sheet,
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-cascade-4/#fetch-an-import>
+    ///
+    /// `ancestors` are the locations of the stylesheets importing this one,
+    /// which are not imported again to avoid cycles.
+    fn load_imports(
+        &self,
+        css_style_sheet: &mut CssStyleSheet,
+        location: Option<&Url>,
+        ancestors: &mut Vec<Url>,
+    ) {
+        for rule in &mut css_style_sheet.css_rules {
+            // @import rules precede all other rules.
+            let CssRule::Import(rule) = rule else {
+                break;
+            };
+            let Some(url) = self.resolve(location, &rule.href) else {
+                continue;
+            };
+            if ancestors.contains(&url) {
+                continue;
+            }
+            let Some(mut imported) = self.fetch(&url) else {
+                continue;
+            };
+            ancestors.push(url.clone());
+            self.load_imports(&mut imported, Some(&url), ancestors);
+            ancestors.pop();
+            rule.style_sheet = Some(imported);
+        }
+    }
+
+    fn resolve(&self, base_url: Option<&Url>, href: &str) -> Option<Url> {
+        match base_url {
+            Some(base_url) => base_url.join(href).ok(),
+            None => Url::new(href.into()).parse().ok(),
+        }
+    }
+
+    /// Fetches and parses a stylesheet.
+    /// Returns None if the request fails or the response is not successful.
+    fn fetch(&self, url: &Url) -> Option<CssStyleSheet> {
+        let response = self.fetcher?.fetch(url).ok()?;
+        if !(200..300).contains(&response.status_code()) {
+            return None;
+        }
+        Some(parse_css_stylesheet(response.body()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::test_util::TestFetcher;
+    use crate::renderer::css::cssom::{ComplexSelector, CompoundSelector, SimpleSelector};
+    use crate::renderer::dom::test_util;
+
+    use super::*;
+
+    /// The type selectors of the style rules, in order.
+    fn selectors(css_style_sheet: &CssStyleSheet) -> Vec<String> {
+        let mut names = Vec::new();
+        for rule in &css_style_sheet.css_rules {
+            match rule {
+                CssRule::Import(rule) => {
+                    names.extend(rule.style_sheet.iter().flat_map(selectors));
+                }
+                CssRule::Style(rule) => match &rule.selector.selectors[0] {
+                    ComplexSelector::CompoundSelector(CompoundSelector(simple_selectors)) => {
+                        match &simple_selectors[0] {
+                            SimpleSelector::TypeSelector(name) => names.push(name.clone()),
+                            _ => unreachable!(),
+                        }
+                    }
+                    _ => unreachable!(),
+                },
+                _ => {}
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn test_load() {
+        let fetcher = TestFetcher::new(vec![
+            ("example.com:80/css/a.css", "@import '../b.css'; a {}"),
+            (
+                "example.com:80/b.css",
+                "@import 'css/a.css'; @import 'c.css' print; b {}",
+            ),
+            ("example.com:80/c.css", "c {}"),
+            ("example.com:80/css/d.css", "d {}"),
+            ("example.com:80/css/e.css", "e {}"),
+        ]);
+        let base_url = Url::new("http://example.com/css/index.html".into())
+            .parse()
+            .unwrap();
+        let html = r#"<html><head><link rel="stylesheet" href="a.css"><style media="print">p {}</style></head><body><style>@import "/css/e.css"; h1 {}</style><link rel="alternate stylesheet" href="d.css"><link rel="stylesheet" href="missing.css"><link rel="icon" href="d.css"><link rel="StyleSheet" href="d.css" disabled=""></body></html>"#;
+        let window = test_util::parse(html);
+        let document = window.borrow().document();
+
+        let list = StyleSheetLoader::new(Some(&base_url), Some(&fetcher)).load(document);
+        assert_eq!(list.length(), 4);
+        let sheets: Vec<_> = list
+            .style_sheets
+            .iter()
+            .map(|s| selectors(&s.css_style_sheet))
+            .collect();
+        // The import cycle from b.css to a.css is broken.
+        assert_eq!(sheets[0], ["c", "b", "a"]);
+        assert_eq!(sheets[1], ["p"]);
+        assert_eq!(sheets[2], ["e", "h1"]);
+        assert_eq!(sheets[3], ["d"]);
+        assert_eq!(
+            list.item(0).unwrap().location,
+            Some(base_url.join("a.css").unwrap())
+        );
+        assert_eq!(list.item(1).unwrap().location, None);
+
+        let enabled = |list: &StyleSheetList| {
+            list.enabled()
+                .flat_map(|s| selectors(&s.css_style_sheet))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(enabled(&list), ["c", "b", "a", "p", "e", "h1"]);
+        let mut list = list;
+        assert!(list.set_disabled(3, false));
+        assert!(list.set_disabled(0, true));
+        assert!(!list.set_disabled(4, true));
+        assert_eq!(enabled(&list), ["p", "e", "h1", "d"]);
+    }
+
+    #[test]
+    fn test_load_without_fetcher() {
+        let html = r#"<html><head><link rel="stylesheet" href="http://example.com/a.css"><style>@import "http://example.com/b.css"; p {}</style></head><body><style>h1 {}</style></body></html>"#;
+        let window = test_util::parse(html);
+        let document = window.borrow().document();
+
+        let list = StyleSheetLoader::new(None, None).load(document);
+        assert_eq!(list.length(), 2);
+        assert_eq!(selectors(&list.item(0).unwrap().css_style_sheet), ["p"]);
+        assert_eq!(selectors(&list.item(1).unwrap().css_style_sheet), ["h1"]);
+    }
+}
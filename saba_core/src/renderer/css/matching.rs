@@ -0,0 +1,802 @@
+//! Selector matching against the DOM.
+//!
+//! <https://www.w3.org/TR/selectors-4/#match-against-element>
+//!
+//! Complex selectors are matched from right to left, i.e. from the subject to its ancestors and siblings.
+//! To avoid testing every rule against every element, rules are hashed by their rightmost id, class or tag name in a [`SelectorMap`],
+//! and an [`AncestorBloomFilter`] rejects most of the selectors whose ancestors can't match.
+//!
+//! Servo: <https://github.com/servo/stylo/tree/main/selectors>
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use crate::renderer::dom::node::{Element, ElementKind, Node, NodeData};
+
+use super::cssom::{
+    AnPlusB, AttributeMatcher, AttributeSelector, Combinator, ComplexSelector, CompoundSelector,
+    PseudoClass, PseudoElement, RelativeSelector, SelectorList, SimpleSelector,
+};
+use super::selector::Specificity;
+
+/// Options for matching selectors against an element.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchingContext<'a> {
+    /// The filter of the ancestors of the element being matched, if any.
+    pub bloom_filter: Option<&'a AncestorBloomFilter>,
+    /// The pseudo-element to match. Selectors with a pseudo-element only match if this is the same one.
+    pub pseudo_element: Option<PseudoElement>,
+}
+
+/// Returns whether any of the selectors in the list matches the element.
+pub fn matches_selector_list(
+    list: &SelectorList,
+    element: Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    list.selectors
+        .iter()
+        .any(|selector| matches_complex_selector(selector, element.clone(), context))
+}
+
+/// <https://www.w3.org/TR/selectors-4/#match-a-selector-against-an-element>
+pub fn matches_complex_selector(
+    selector: &ComplexSelector,
+    element: Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> bool {
+    if let Some(filter) = context.bloom_filter {
+        if !ancestor_hashes(selector)
+            .into_iter()
+            .all(|hash| filter.might_contain(hash))
+        {
+            return false;
+        }
+    }
+    matches_complex(selector, &element, context.pseudo_element, None)
+}
+
+/// Some node that the leftmost compound selector must be related to,
+/// which is used for relative selectors in :has().
+type Anchor<'a> = Option<(Combinator, &'a Rc<RefCell<Node>>)>;
+
+fn matches_complex(
+    selector: &ComplexSelector,
+    element: &Rc<RefCell<Node>>,
+    pseudo_element: Option<PseudoElement>,
+    anchor: Anchor,
+) -> bool {
+    match selector {
+        ComplexSelector::CompoundSelector(compound) => {
+            matches_compound(compound, element, pseudo_element)
+                && anchor.map_or(true, |(combinator, anchor)| {
+                    is_related(element, combinator, anchor)
+                })
+        }
+        ComplexSelector::Combination(left, combinator, right) => {
+            if !matches_compound(right, element, pseudo_element) {
+                return false;
+            }
+            let candidates: Vec<Rc<RefCell<Node>>> = match combinator {
+                Combinator::Descendant => Node::ancestors(Rc::clone(element))
+                    .take_while(is_element)
+                    .collect(),
+                Combinator::Child => parent_element(element).into_iter().collect(),
+                Combinator::NextSibling => previous_element_sibling(element).into_iter().collect(),
+                Combinator::SubsequentSibling => {
+                    let mut siblings = Vec::new();
+                    let mut current = previous_element_sibling(element);
+                    while let Some(sibling) = current {
+                        current = previous_element_sibling(&sibling);
+                        siblings.push(sibling);
+                    }
+                    siblings
+                }
+            };
+            let matched = candidates
+                .iter()
+                .any(|candidate| matches_complex(left, candidate, None, anchor));
+            matched
+        }
+    }
+}
+
+/// Returns whether `node` is related to `anchor` by `combinator`, e.g. whether `node` is a child of `anchor` for [`Combinator::Child`].
+fn is_related(
+    node: &Rc<RefCell<Node>>,
+    combinator: Combinator,
+    anchor: &Rc<RefCell<Node>>,
+) -> bool {
+    match combinator {
+        Combinator::Descendant => Node::ancestors(Rc::clone(node)).any(|a| Rc::ptr_eq(&a, anchor)),
+        Combinator::Child => node
+            .borrow()
+            .parent
+            .upgrade()
+            .is_some_and(|p| Rc::ptr_eq(&p, anchor)),
+        Combinator::NextSibling => {
+            previous_element_sibling(node).is_some_and(|s| Rc::ptr_eq(&s, anchor))
+        }
+        Combinator::SubsequentSibling => {
+            let mut current = previous_element_sibling(node);
+            while let Some(sibling) = current {
+                if Rc::ptr_eq(&sibling, anchor) {
+                    return true;
+                }
+                current = previous_element_sibling(&sibling);
+            }
+            false
+        }
+    }
+}
+
+fn matches_compound(
+    compound: &CompoundSelector,
+    element: &Rc<RefCell<Node>>,
+    pseudo_element: Option<PseudoElement>,
+) -> bool {
+    let selector_pseudo_element = compound.0.iter().find_map(|s| match s {
+        SimpleSelector::PseudoElement(p) => Some(*p),
+        _ => None,
+    });
+    if selector_pseudo_element != pseudo_element {
+        return false;
+    }
+
+    compound.0.iter().all(|simple| match simple {
+        SimpleSelector::PseudoClass(pseudo_class) => matches_pseudo_class(pseudo_class, element),
+        SimpleSelector::PseudoElement(_) => true,
+        simple => match &element.borrow().data {
+            NodeData::Element(e) => matches_simple(simple, e),
+            _ => false,
+        },
+    })
+}
+
+fn matches_simple(selector: &SimpleSelector, element: &Element) -> bool {
+    match selector {
+        SimpleSelector::TypeSelector(name) => element.kind.to_string() == *name,
+        SimpleSelector::UniversalSelector => true,
+        SimpleSelector::ClassSelector(class) => classes(element).any(|c| c == class),
+        SimpleSelector::IdSelector(id) => element.get_attribute("id") == Some(id.as_str()),
+        SimpleSelector::AttributeSelector(selector) => matches_attribute(selector, element),
+        SimpleSelector::PseudoClass(_) | SimpleSelector::PseudoElement(_) => unreachable!(),
+    }
+}
+
+/// <https://www.w3.org/TR/selectors-4/#attribute-selectors>
+fn matches_attribute(selector: &AttributeSelector, element: &Element) -> bool {
+    let Some(value) = element
+        .attributes
+        .iter()
+        .find(|attr| attr.name.eq_ignore_ascii_case(&selector.name))
+        .map(|attr| attr.value.as_str())
+    else {
+        return false;
+    };
+    let Some(matcher) = &selector.matcher else {
+        return true;
+    };
+
+    let (AttributeMatcher::Exact(expected)
+    | AttributeMatcher::Includes(expected)
+    | AttributeMatcher::DashMatch(expected)
+    | AttributeMatcher::Prefix(expected)
+    | AttributeMatcher::Suffix(expected)
+    | AttributeMatcher::Substring(expected)) = matcher;
+    let (value, expected) = if selector.case_insensitive {
+        (value.to_ascii_lowercase(), expected.to_ascii_lowercase())
+    } else {
+        (value.to_string(), expected.clone())
+    };
+
+    match matcher {
+        AttributeMatcher::Exact(_) => value == expected,
+        AttributeMatcher::Includes(_) => {
+            !expected.is_empty()
+                && !expected.contains(is_ascii_whitespace)
+                && value.split(is_ascii_whitespace).any(|v| v == expected)
+        }
+        AttributeMatcher::DashMatch(_) => {
+            value == expected
+                || value
+                    .strip_prefix(expected.as_str())
+                    .is_some_and(|rest| rest.starts_with('-'))
+        }
+        AttributeMatcher::Prefix(_) => !expected.is_empty() && value.starts_with(&expected),
+        AttributeMatcher::Suffix(_) => !expected.is_empty() && value.ends_with(&expected),
+        AttributeMatcher::Substring(_) => !expected.is_empty() && value.contains(&expected),
+    }
+}
+
+/// <https://www.w3.org/TR/selectors-4/#pseudo-classes>
+fn matches_pseudo_class(pseudo_class: &PseudoClass, element: &Rc<RefCell<Node>>) -> bool {
+    let is_same_type =
+        |a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>| match (&a.borrow().data, &b.borrow().data) {
+            (NodeData::Element(a), NodeData::Element(b)) => a.kind == b.kind,
+            _ => false,
+        };
+    let matches_list = |list: &SelectorList, element: Rc<RefCell<Node>>| {
+        matches_selector_list(list, element, &MatchingContext::default())
+    };
+
+    match pseudo_class {
+        PseudoClass::Root => element
+            .borrow()
+            .parent
+            .upgrade()
+            .is_some_and(|p| p.borrow().data == NodeData::Document),
+        PseudoClass::Empty => element
+            .borrow()
+            .children()
+            .all(|child| match &child.borrow().data {
+                NodeData::Text(text) => text.is_empty(),
+                _ => false,
+            }),
+        PseudoClass::FirstChild => previous_element_sibling(element).is_none(),
+        PseudoClass::LastChild => next_element_sibling(element).is_none(),
+        PseudoClass::OnlyChild => {
+            previous_element_sibling(element).is_none() && next_element_sibling(element).is_none()
+        }
+        PseudoClass::FirstOfType => {
+            !preceding_element_siblings(element).any(|s| is_same_type(&s, element))
+        }
+        PseudoClass::LastOfType => {
+            !following_element_siblings(element).any(|s| is_same_type(&s, element))
+        }
+        PseudoClass::OnlyOfType => {
+            !preceding_element_siblings(element).any(|s| is_same_type(&s, element))
+                && !following_element_siblings(element).any(|s| is_same_type(&s, element))
+        }
+        PseudoClass::NthChild(an_plus_b, of) | PseudoClass::NthLastChild(an_plus_b, of) => {
+            if let Some(of) = of {
+                if !matches_list(of, element.clone()) {
+                    return false;
+                }
+            }
+            let siblings: Vec<_> = if matches!(pseudo_class, PseudoClass::NthChild(..)) {
+                preceding_element_siblings(element).collect()
+            } else {
+                following_element_siblings(element).collect()
+            };
+            let index = siblings
+                .iter()
+                .filter(|s| {
+                    of.as_ref()
+                        .map_or(true, |of| matches_list(of, Rc::clone(s)))
+                })
+                .count()
+                + 1;
+            matches_an_plus_b(an_plus_b, index)
+        }
+        PseudoClass::NthOfType(an_plus_b) | PseudoClass::NthLastOfType(an_plus_b) => {
+            let siblings: Vec<_> = if matches!(pseudo_class, PseudoClass::NthOfType(..)) {
+                preceding_element_siblings(element).collect()
+            } else {
+                following_element_siblings(element).collect()
+            };
+            let index = siblings.iter().filter(|s| is_same_type(s, element)).count() + 1;
+            matches_an_plus_b(an_plus_b, index)
+        }
+        PseudoClass::Not(list) => !matches_list(list, element.clone()),
+        PseudoClass::Is(list) | PseudoClass::Where(list) => matches_list(list, element.clone()),
+        PseudoClass::Has(selectors) => selectors.iter().any(|s| matches_relative(s, element)),
+        // <https://html.spec.whatwg.org/multipage/semantics-other.html#selector-link>
+        // Visited links are not tracked, so every link is unvisited.
+        PseudoClass::Link | PseudoClass::AnyLink => match &element.borrow().data {
+            NodeData::Element(e) => {
+                matches!(e.kind, ElementKind::A | ElementKind::SvgA)
+                    && e.get_attribute("href").is_some()
+            }
+            _ => false,
+        },
+        // User interaction is not supported yet.
+        PseudoClass::Visited | PseudoClass::Hover | PseudoClass::Active | PseudoClass::Focus => {
+            false
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/selectors-4/#relational>
+fn matches_relative(selector: &RelativeSelector, element: &Rc<RefCell<Node>>) -> bool {
+    let candidates: Vec<Rc<RefCell<Node>>> = match selector.combinator {
+        Combinator::Descendant | Combinator::Child => Node::descendants(element.clone()).collect(),
+        Combinator::NextSibling | Combinator::SubsequentSibling => {
+            following_element_siblings(element)
+                .flat_map(Node::preorder)
+                .collect()
+        }
+    };
+    candidates
+        .iter()
+        .filter(|c| is_element(c))
+        .any(|candidate| {
+            matches_complex(
+                &selector.selector,
+                candidate,
+                None,
+                Some((selector.combinator, element)),
+            )
+        })
+}
+
+/// Returns whether `index` (1-based) is `a*n + b` for some n >= 0.
+fn matches_an_plus_b(AnPlusB { a, b }: &AnPlusB, index: usize) -> bool {
+    let (a, diff) = (*a as i64, index as i64 - *b as i64);
+    if a == 0 {
+        diff == 0
+    } else {
+        diff % a == 0 && diff / a >= 0
+    }
+}
+
+fn classes(element: &Element) -> impl Iterator<Item = &str> {
+    element
+        .get_attribute("class")
+        .unwrap_or_default()
+        .split(is_ascii_whitespace)
+        .filter(|c| !c.is_empty())
+}
+
+/// <https://infra.spec.whatwg.org/#ascii-whitespace>
+fn is_ascii_whitespace(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ')
+}
+
+fn is_element(node: &Rc<RefCell<Node>>) -> bool {
+    matches!(node.borrow().data, NodeData::Element(_))
+}
+
+fn parent_element(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    node.borrow().parent.upgrade().filter(is_element)
+}
+
+fn previous_element_sibling(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.borrow().previous_sibling.upgrade();
+    while let Some(sibling) = current {
+        if is_element(&sibling) {
+            return Some(sibling);
+        }
+        current = sibling.borrow().previous_sibling.upgrade();
+    }
+    None
+}
+
+fn next_element_sibling(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.borrow().next_sibling.clone();
+    while let Some(sibling) = current {
+        if is_element(&sibling) {
+            return Some(sibling);
+        }
+        current = sibling.borrow().next_sibling.clone();
+    }
+    None
+}
+
+fn preceding_element_siblings(node: &Rc<RefCell<Node>>) -> impl Iterator<Item = Rc<RefCell<Node>>> {
+    core::iter::successors(previous_element_sibling(node), previous_element_sibling)
+}
+
+fn following_element_siblings(node: &Rc<RefCell<Node>>) -> impl Iterator<Item = Rc<RefCell<Node>>> {
+    core::iter::successors(next_element_sibling(node), next_element_sibling)
+}
+
+/// A counting Bloom filter of the ids, classes and tag names of the ancestors of an element.
+///
+/// While traversing the tree, push each element before visiting its children and pop it afterwards.
+/// If the filter doesn't contain a name that an ancestor of the subject must have,
+/// e.g. `div` in `div p`, the selector can't match.
+#[derive(Debug, Clone)]
+pub struct AncestorBloomFilter {
+    counters: Vec<u8>,
+}
+
+const BLOOM_FILTER_BITS: u32 = 12;
+
+impl Default for AncestorBloomFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AncestorBloomFilter {
+    pub fn new() -> Self {
+        Self {
+            counters: alloc::vec![0; 1 << BLOOM_FILTER_BITS],
+        }
+    }
+
+    pub fn push_element(&mut self, element: &Element) {
+        for hash in element_hashes(element) {
+            for index in Self::indices(hash) {
+                // A saturated counter stays saturated, so that it never produces false negatives.
+                self.counters[index] = self.counters[index].saturating_add(1);
+            }
+        }
+    }
+
+    pub fn pop_element(&mut self, element: &Element) {
+        for hash in element_hashes(element) {
+            for index in Self::indices(hash) {
+                let counter = &mut self.counters[index];
+                debug_assert!(*counter > 0);
+                if *counter != u8::MAX {
+                    *counter -= 1;
+                }
+            }
+        }
+    }
+
+    /// Returns false if no ancestor has the name of the hash.
+    fn might_contain(&self, hash: u32) -> bool {
+        Self::indices(hash)
+            .into_iter()
+            .all(|index| self.counters[index] > 0)
+    }
+
+    fn indices(hash: u32) -> [usize; 2] {
+        let mask = (1 << BLOOM_FILTER_BITS) - 1;
+        [
+            (hash & mask) as usize,
+            ((hash >> BLOOM_FILTER_BITS) & mask) as usize,
+        ]
+    }
+}
+
+fn element_hashes(element: &Element) -> impl Iterator<Item = u32> + '_ {
+    let tag = hash_name(b't', &element.kind.to_string());
+    let id = element.get_attribute("id").map(|id| hash_name(b'#', id));
+    let classes = classes(element).map(|class| hash_name(b'.', class));
+    core::iter::once(tag).chain(id).chain(classes)
+}
+
+/// FNV-1a, prefixed with the kind of the name so that e.g. `.p` and `p` hash differently.
+fn hash_name(kind: u8, name: &str) -> u32 {
+    core::iter::once(kind)
+        .chain(name.bytes())
+        .fold(0x811c9dc5, |hash, b| {
+            (hash ^ b as u32).wrapping_mul(0x01000193)
+        })
+}
+
+/// The hashes of the names that some ancestors of the subject must have.
+///
+/// A compound selector followed by a descendant or child combinator always matches an ancestor of the subject,
+/// even if there are sibling combinators in between, e.g. `a` in `a > b + c`.
+fn ancestor_hashes(selector: &ComplexSelector) -> Vec<u32> {
+    let mut hashes = Vec::new();
+    let mut current = selector;
+    while let ComplexSelector::Combination(left, combinator, _) = current {
+        let compound = rightmost_compound(left);
+        if matches!(combinator, Combinator::Descendant | Combinator::Child) {
+            for simple in &compound.0 {
+                match simple {
+                    SimpleSelector::TypeSelector(name) => hashes.push(hash_name(b't', name)),
+                    SimpleSelector::IdSelector(id) => hashes.push(hash_name(b'#', id)),
+                    SimpleSelector::ClassSelector(class) => hashes.push(hash_name(b'.', class)),
+                    _ => {}
+                }
+            }
+        }
+        current = left;
+    }
+    hashes
+}
+
+fn rightmost_compound(selector: &ComplexSelector) -> &CompoundSelector {
+    match selector {
+        ComplexSelector::CompoundSelector(compound) => compound,
+        ComplexSelector::Combination(_, _, compound) => compound,
+    }
+}
+
+/// A selector in a [`SelectorMap`] with its associated value, e.g. the rule it belongs to.
+#[derive(Debug, Clone)]
+pub struct SelectorMapEntry<T> {
+    pub selector: ComplexSelector,
+    pub specificity: Specificity,
+    pub value: T,
+    /// The order of insertion, which is the source order of the rules.
+    pub order: usize,
+    ancestor_hashes: Vec<u32>,
+}
+
+/// Selectors hashed by the id, class or tag name of their rightmost compound selectors.
+///
+/// Only the selectors in the buckets of the element's id, classes and tag name,
+/// and the selectors without any of them, are tested against the element.
+#[derive(Debug, Clone)]
+pub struct SelectorMap<T> {
+    id: BTreeMap<String, Vec<SelectorMapEntry<T>>>,
+    class: BTreeMap<String, Vec<SelectorMapEntry<T>>>,
+    tag: BTreeMap<String, Vec<SelectorMapEntry<T>>>,
+    other: Vec<SelectorMapEntry<T>>,
+    len: usize,
+}
+
+impl<T> Default for SelectorMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SelectorMap<T> {
+    pub fn new() -> Self {
+        Self {
+            id: BTreeMap::new(),
+            class: BTreeMap::new(),
+            tag: BTreeMap::new(),
+            other: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, selector: ComplexSelector, value: T) {
+        let entry = SelectorMapEntry {
+            specificity: selector.specificity(),
+            ancestor_hashes: ancestor_hashes(&selector),
+            order: self.len,
+            selector,
+            value,
+        };
+        self.len += 1;
+
+        let compound = rightmost_compound(&entry.selector);
+        let key = |f: fn(&SimpleSelector) -> Option<&String>| compound.0.iter().find_map(f);
+        let bucket = if let Some(id) = key(|s| match s {
+            SimpleSelector::IdSelector(id) => Some(id),
+            _ => None,
+        }) {
+            self.id.entry(id.clone()).or_default()
+        } else if let Some(class) = key(|s| match s {
+            SimpleSelector::ClassSelector(class) => Some(class),
+            _ => None,
+        }) {
+            self.class.entry(class.clone()).or_default()
+        } else if let Some(tag) = key(|s| match s {
+            SimpleSelector::TypeSelector(tag) => Some(tag),
+            _ => None,
+        }) {
+            self.tag.entry(tag.clone()).or_default()
+        } else {
+            &mut self.other
+        };
+        bucket.push(entry);
+    }
+
+    /// Returns the entries whose selectors match the element, in the order of insertion.
+    pub fn get_matching_entries(
+        &self,
+        element: Rc<RefCell<Node>>,
+        context: &MatchingContext,
+    ) -> Vec<&SelectorMapEntry<T>> {
+        let mut candidates: Vec<&SelectorMapEntry<T>> = Vec::new();
+        if let NodeData::Element(e) = &element.borrow().data {
+            if let Some(entries) = e.get_attribute("id").and_then(|id| self.id.get(id)) {
+                candidates.extend(entries);
+            }
+            let mut seen_classes: Vec<&str> = Vec::new();
+            for class in classes(e) {
+                if seen_classes.contains(&class) {
+                    continue;
+                }
+                seen_classes.push(class);
+                if let Some(entries) = self.class.get(class) {
+                    candidates.extend(entries);
+                }
+            }
+            if let Some(entries) = self.tag.get(&e.kind.to_string()) {
+                candidates.extend(entries);
+            }
+        } else {
+            return Vec::new();
+        }
+        candidates.extend(&self.other);
+
+        let mut matched: Vec<&SelectorMapEntry<T>> = candidates
+            .into_iter()
+            .filter(|entry| {
+                context.bloom_filter.map_or(true, |filter| {
+                    entry
+                        .ancestor_hashes
+                        .iter()
+                        .all(|h| filter.might_contain(*h))
+                })
+            })
+            .filter(|entry| {
+                matches_complex(&entry.selector, &element, context.pseudo_element, None)
+            })
+            .collect();
+        matched.sort_by_key(|entry| entry.order);
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::renderer::css::parser::StyleSheetParser;
+    use crate::renderer::css::selector::parse_selector_list;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::node::Window;
+    use crate::renderer::dom::test_util;
+
+    use super::*;
+
+    fn parse(selector: &str) -> SelectorList {
+        let values = StyleSheetParser::new(CssTokenizer::new(selector.to_string()))
+            .parse_list_of_component_values();
+        parse_selector_list(&values).unwrap()
+    }
+
+    /// Returns the indices in tree order of the elements that match the selector.
+    fn matching_elements(window: &Rc<RefCell<Window>>, selector: &str) -> Vec<usize> {
+        let list = parse(selector);
+        let document = window.borrow().document();
+        Node::preorder(document)
+            .filter(is_element)
+            .enumerate()
+            .filter(|(_, e)| matches_selector_list(&list, e.clone(), &MatchingContext::default()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn test_combinators() {
+        // 0: html, 1: head, 2: body, 3: p#a, 4: a, 5: p.b, 6: a, 7: p
+        let window = test_util::parse(
+            r#"<html><head></head><body><p id="a"><a>x</a></p><p class="b c"><a href="/">y</a></p><p></p></body></html>"#,
+        );
+        assert_eq!(matching_elements(&window, "p"), vec![3, 5, 7]);
+        assert_eq!(matching_elements(&window, "body a"), vec![4, 6]);
+        assert_eq!(matching_elements(&window, "#a > a"), vec![4]);
+        assert_eq!(matching_elements(&window, "#a + p"), vec![5]);
+        assert_eq!(matching_elements(&window, "#a ~ p"), vec![5, 7]);
+        assert_eq!(matching_elements(&window, "p.c.b"), vec![5]);
+        assert_eq!(matching_elements(&window, "html > body p a"), vec![4, 6]);
+        assert_eq!(
+            matching_elements(&window, "head > p, head + p"),
+            Vec::<usize>::new()
+        );
+        assert_eq!(matching_elements(&window, "*"), (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_attributes_and_pseudo_classes() {
+        // 0: html, 1: head, 2: body, 3..=7: p, 8: a
+        let window = test_util::parse(
+            r#"<html><head></head><body><p lang="en-US">a</p><p class="x y" title="Hello World"></p><p></p><p>b</p><p></p><a href="/">c</a></body></html>"#,
+        );
+        assert_eq!(matching_elements(&window, "[lang|=en]"), vec![3]);
+        assert_eq!(matching_elements(&window, "[class~=y]"), vec![4]);
+        assert_eq!(matching_elements(&window, "[title^=hello i]"), vec![4]);
+        assert_eq!(
+            matching_elements(&window, "[title^=hello]"),
+            Vec::<usize>::new()
+        );
+        assert_eq!(matching_elements(&window, "[title$=World]"), vec![4]);
+        assert_eq!(matching_elements(&window, "[title*=o]"), vec![4]);
+        assert_eq!(matching_elements(&window, ":root"), vec![0]);
+        assert_eq!(matching_elements(&window, "p:empty"), vec![4, 5, 7]);
+        assert_eq!(matching_elements(&window, "p:first-child"), vec![3]);
+        assert_eq!(matching_elements(&window, "body > :last-child"), vec![8]);
+        assert_eq!(matching_elements(&window, "p:last-of-type"), vec![7]);
+        assert_eq!(
+            matching_elements(&window, "p:nth-child(2n+1)"),
+            vec![3, 5, 7]
+        );
+        assert_eq!(matching_elements(&window, "p:nth-last-child(2)"), vec![7]);
+        assert_eq!(
+            matching_elements(&window, ":nth-child(2 of :empty)"),
+            vec![5]
+        );
+        assert_eq!(
+            matching_elements(&window, "p:nth-of-type(-n+2)"),
+            vec![3, 4]
+        );
+        assert_eq!(matching_elements(&window, "p:not(:empty, .x)"), vec![3, 6]);
+        assert_eq!(matching_elements(&window, ":is(a, [lang]):link"), vec![8]);
+        assert_eq!(matching_elements(&window, "body:has(> a)"), vec![2]);
+        assert_eq!(
+            matching_elements(&window, "p:has(+ p:empty)"),
+            vec![3, 4, 6]
+        );
+        assert_eq!(matching_elements(&window, "html:has(body a)"), vec![0]);
+        assert_eq!(
+            matching_elements(&window, "html:has(> a)"),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn test_pseudo_elements() {
+        let window = test_util::parse("<html><head></head><body><p></p></body></html>");
+        let p = test_util::element(&window, ElementKind::P);
+        let selector = &parse("p::before").selectors[0];
+
+        assert!(!matches_complex_selector(
+            selector,
+            p.clone(),
+            &MatchingContext::default()
+        ));
+        let context = MatchingContext {
+            pseudo_element: Some(PseudoElement::Before),
+            ..Default::default()
+        };
+        assert!(matches_complex_selector(selector, p.clone(), &context));
+        assert!(!matches_complex_selector(
+            &parse("p").selectors[0],
+            p,
+            &context
+        ));
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let window = test_util::parse(
+            r#"<html><head></head><body class="main"><p id="x"><a>x</a></p></body></html>"#,
+        );
+        let a = test_util::element(&window, ElementKind::A);
+
+        let mut filter = AncestorBloomFilter::new();
+        let ancestors: Vec<_> = Node::ancestors(Rc::clone(&a)).filter(is_element).collect();
+        for ancestor in ancestors.iter().rev() {
+            if let NodeData::Element(e) = &ancestor.borrow().data {
+                filter.push_element(e);
+            }
+        }
+        let context = MatchingContext {
+            bloom_filter: Some(&filter),
+            ..Default::default()
+        };
+        let matches = |selector: &str| matches_selector_list(&parse(selector), a.clone(), &context);
+
+        assert!(matches(".main #x > a"));
+        assert!(matches("html a"));
+        assert!(!matches("h1 a"));
+        assert!(!matches(".other a"));
+
+        for ancestor in ancestors.iter() {
+            if let NodeData::Element(e) = &ancestor.borrow().data {
+                filter.pop_element(e);
+            }
+        }
+        assert!(filter.counters.iter().all(|c| *c == 0));
+    }
+
+    #[test]
+    fn test_selector_map() {
+        let window = test_util::parse(
+            r#"<html><head></head><body><p id="x" class="a b a">x</p></body></html>"#,
+        );
+        let p = test_util::element(&window, ElementKind::P);
+
+        let mut map = SelectorMap::new();
+        for (i, selector) in ["#x", "h1", ".a", "p.b", "*", "body .a", "#y", "p:empty"]
+            .into_iter()
+            .enumerate()
+        {
+            map.insert(parse(selector).selectors.remove(0), i);
+        }
+        assert_eq!(map.len(), 8);
+
+        let matched = map.get_matching_entries(p, &MatchingContext::default());
+        assert_eq!(
+            matched.iter().map(|e| e.value).collect::<Vec<_>>(),
+            vec![0, 2, 3, 4, 5]
+        );
+        assert_eq!(matched[0].specificity, Specificity { a: 1, b: 0, c: 0 });
+    }
+}
@@ -0,0 +1,808 @@
+//! <https://www.w3.org/TR/mediaqueries-4/>
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::renderer::layout::computed_style::ComputeContext;
+
+use super::parser::{trim_whitespace, ComponentValue};
+use super::token::{CssToken, Numeric};
+use super::value::{parse_length, serialize_identifier, Length};
+
+/// The output device which media queries are evaluated against.
+///
+/// <https://www.w3.org/TR/mediaqueries-4/#media>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Device {
+    /// The size of the viewport in px.
+    pub viewport_width: f64,
+    pub viewport_height: f64,
+    /// The number of device pixels per px.
+    pub resolution: f64,
+    pub color_scheme: ColorScheme,
+}
+
+impl Device {
+    pub fn new(viewport_width: f64, viewport_height: f64) -> Self {
+        Self {
+            viewport_width,
+            viewport_height,
+            resolution: 1.0,
+            color_scheme: ColorScheme::Light,
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/mediaqueries-5/#prefers-color-scheme>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// <https://www.w3.org/TR/cssom-1/#the-medialist-interface>
+///
+/// An empty list matches all media.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MediaList {
+    pub media_queries: Vec<MediaQuery>,
+}
+
+/// <https://www.w3.org/TR/mediaqueries-4/#media-query>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    /// Whether the query is prefixed by `not`.
+    pub negated: bool,
+    pub media_type: MediaType,
+    /// The media condition following `and`, or the query itself if it has no media type.
+    pub condition: Option<MediaCondition>,
+}
+
+/// <https://www.w3.org/TR/mediaqueries-4/#media-types>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaType {
+    All,
+    Print,
+    Screen,
+    /// An unknown or deprecated media type in lowercase, which matches nothing.
+    Unknown(String),
+}
+
+/// <https://www.w3.org/TR/mediaqueries-4/#typedef-media-condition>
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaCondition {
+    Feature(MediaFeature),
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+    /// <https://www.w3.org/TR/mediaqueries-4/#typedef-general-enclosed>
+    ///
+    /// A parenthesized or functional notation not understood, e.g. an unknown media feature,
+    /// which evaluates to unknown. It is kept as it is for serialization.
+    GeneralEnclosed(ComponentValue),
+}
+
+/// <https://www.w3.org/TR/mediaqueries-4/#mq-features>
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaFeature {
+    /// The name without the `min-` or `max-` prefix.
+    pub name: String,
+    /// The comparisons of the feature with values, e.g. `(min-width: 10px)` is `width >= 10px`.
+    /// Empty if the feature is evaluated in the boolean context, e.g. `(width)`.
+    pub comparisons: Vec<(Comparison, MediaFeatureValue)>,
+}
+
+/// <https://www.w3.org/TR/mediaqueries-4/#typedef-mf-comparison>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+/// <https://www.w3.org/TR/mediaqueries-4/#typedef-mf-value>
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaFeatureValue {
+    Number(f64),
+    Length(Length),
+    /// <https://www.w3.org/TR/css-values-4/#resolution-value> in dppx.
+    Resolution(f64),
+    /// <https://www.w3.org/TR/mediaqueries-4/#typedef-ratio>
+    Ratio(f64, f64),
+    /// In lowercase.
+    Ident(String),
+}
+
+/// <https://www.w3.org/TR/mediaqueries-4/#mq-range-context>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FeatureType {
+    /// Can be compared with `min-`, `max-` and the range syntax.
+    Range,
+    Discrete,
+}
+
+const MEDIA_FEATURES: [(&str, FeatureType); 6] = [
+    ("width", FeatureType::Range),
+    ("height", FeatureType::Range),
+    ("aspect-ratio", FeatureType::Range),
+    ("resolution", FeatureType::Range),
+    ("orientation", FeatureType::Discrete),
+    ("prefers-color-scheme", FeatureType::Discrete),
+];
+
+impl MediaList {
+    /// <https://www.w3.org/TR/mediaqueries-4/#mq-syntax>
+    ///
+    /// Parses a `<media-query-list>`. A malformed query becomes `not all`.
+    pub fn parse(values: &[ComponentValue]) -> Self {
+        let values = trim_whitespace(values);
+        if values.is_empty() {
+            return Self::default();
+        }
+        let media_queries = values
+            .split(|v| *v == ComponentValue::PreservedToken(CssToken::Comma))
+            .map(|query| MediaQuery::parse(query).unwrap_or(MediaQuery::NOT_ALL))
+            .collect();
+        Self { media_queries }
+    }
+
+    /// <https://www.w3.org/TR/mediaqueries-4/#mq-list>
+    pub fn matches(&self, device: &Device) -> bool {
+        self.media_queries.is_empty() || self.media_queries.iter().any(|q| q.matches(device))
+    }
+}
+
+impl MediaQuery {
+    const NOT_ALL: Self = Self {
+        negated: true,
+        media_type: MediaType::All,
+        condition: None,
+    };
+
+    fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let values = non_whitespace(values);
+        let ident_at = |index: usize| values.get(index).and_then(|v| ident(v));
+
+        let (negated, type_index) = match ident_at(0).as_deref() {
+            Some("not") if ident_at(1).is_some() => (true, 1),
+            Some("only") => (false, 1),
+            Some("not") | None => {
+                return Some(Self {
+                    negated: false,
+                    media_type: MediaType::All,
+                    condition: Some(MediaCondition::parse(&values, true)?),
+                });
+            }
+            Some(_) => (false, 0),
+        };
+
+        // [ not | only ]? <media-type> [ and <media-condition-without-or> ]?
+        let media_type = match ident_at(type_index)? {
+            t if t == "all" => MediaType::All,
+            t if t == "print" => MediaType::Print,
+            t if t == "screen" => MediaType::Screen,
+            t if ["not", "only", "and", "or", "layer"].contains(&t.as_str()) => return None,
+            t => MediaType::Unknown(t),
+        };
+        let condition = match values.get(type_index + 1) {
+            None => None,
+            Some(_) if ident_at(type_index + 1).as_deref() == Some("and") => {
+                Some(MediaCondition::parse(&values[type_index + 2..], false)?)
+            }
+            Some(_) => return None,
+        };
+        Some(Self {
+            negated,
+            media_type,
+            condition,
+        })
+    }
+
+    /// <https://www.w3.org/TR/mediaqueries-4/#mq-boolean-context>
+    ///
+    /// A condition evaluating to unknown is treated as false, before `not` is applied.
+    fn matches(&self, device: &Device) -> bool {
+        let type_matches = matches!(self.media_type, MediaType::All | MediaType::Screen);
+        let condition_matches = self
+            .condition
+            .as_ref()
+            .map_or(Some(true), |condition| condition.evaluate(device))
+            .unwrap_or(false);
+        (type_matches && condition_matches) != self.negated
+    }
+}
+
+impl MediaCondition {
+    /// Parses a `<media-condition>`, or a `<media-condition-without-or>` if `allow_or` is false.
+    fn parse(values: &[&ComponentValue], allow_or: bool) -> Option<Self> {
+        let (first, rest) = values.split_first()?;
+        if ident(first).as_deref() == Some("not") {
+            return match rest {
+                [value] => Some(Self::Not(Box::new(Self::parse_in_parens(value)?))),
+                _ => None,
+            };
+        }
+
+        let mut conditions = vec![Self::parse_in_parens(first)?];
+        let Some(combinator) = rest.first().and_then(|v| ident(v)) else {
+            return rest.is_empty().then(|| conditions.remove(0));
+        };
+        if !(combinator == "and" || (allow_or && combinator == "or")) {
+            return None;
+        }
+        for pair in rest.chunks(2) {
+            match pair {
+                [keyword, value] if ident(keyword).as_ref() == Some(&combinator) => {
+                    conditions.push(Self::parse_in_parens(value)?)
+                }
+                _ => return None,
+            }
+        }
+        Some(if combinator == "and" {
+            Self::And(conditions)
+        } else {
+            Self::Or(conditions)
+        })
+    }
+
+    /// <https://www.w3.org/TR/mediaqueries-4/#typedef-media-in-parens>
+    fn parse_in_parens(value: &ComponentValue) -> Option<Self> {
+        match value {
+            ComponentValue::SimpleBlock(block)
+                if block.associated_token == CssToken::OpenParenthesis =>
+            {
+                let values = non_whitespace(&block.value);
+                Self::parse(&values, true)
+                    .or_else(|| MediaFeature::parse(&values).map(Self::Feature))
+                    .or_else(|| Some(Self::GeneralEnclosed(value.clone())))
+            }
+            ComponentValue::Function(_) => Some(Self::GeneralEnclosed(value.clone())),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the condition in the three-valued logic, where None is unknown.
+    ///
+    /// <https://www.w3.org/TR/mediaqueries-4/#evaluating>
+    fn evaluate(&self, device: &Device) -> Option<bool> {
+        match self {
+            Self::Feature(feature) => feature.evaluate(device),
+            Self::Not(condition) => condition.evaluate(device).map(|result| !result),
+            Self::And(conditions) => {
+                let results: Vec<_> = conditions.iter().map(|c| c.evaluate(device)).collect();
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(true)
+                }
+            }
+            Self::Or(conditions) => {
+                let results: Vec<_> = conditions.iter().map(|c| c.evaluate(device)).collect();
+                if results.contains(&Some(true)) {
+                    Some(true)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(false)
+                }
+            }
+            Self::GeneralEnclosed(_) => None,
+        }
+    }
+}
+
+impl MediaFeature {
+    /// <https://www.w3.org/TR/mediaqueries-4/#typedef-media-feature>
+    ///
+    /// Parses the contents of the parentheses. Returns None if the feature is unknown or malformed.
+    fn parse(values: &[&ComponentValue]) -> Option<Self> {
+        let feature_type = |name: &str| {
+            MEDIA_FEATURES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, t)| *t)
+        };
+
+        // <mf-boolean>
+        if let [name] = values {
+            let name = ident(name)?;
+            feature_type(&name)?;
+            return Some(Self {
+                name,
+                comparisons: Vec::new(),
+            });
+        }
+
+        // <mf-plain>
+        if let [name, ComponentValue::PreservedToken(CssToken::Colon), value @ ..] = values {
+            let name = ident(name)?;
+            let (name, comparison) = match (name.strip_prefix("min-"), name.strip_prefix("max-")) {
+                (Some(name), _) => (String::from(name), Comparison::Ge),
+                (_, Some(name)) => (String::from(name), Comparison::Le),
+                _ => (name, Comparison::Eq),
+            };
+            let feature_type = feature_type(&name)?;
+            if comparison != Comparison::Eq && feature_type != FeatureType::Range {
+                return None;
+            }
+            let value = MediaFeatureValue::parse(value)?;
+            return Some(Self {
+                name,
+                comparisons: vec![(comparison, value)],
+            });
+        }
+
+        // <mf-range>
+        let mut operands: Vec<Vec<&ComponentValue>> = vec![Vec::new()];
+        let mut comparisons = Vec::new();
+        let mut iter = values.iter().peekable();
+        while let Some(value) = iter.next() {
+            let comparison = match value {
+                ComponentValue::PreservedToken(CssToken::Delim(c @ ('<' | '>'))) => {
+                    let or_equal = iter.next_if(|v| is_delim(v, '=')).is_some();
+                    match (c, or_equal) {
+                        ('<', false) => Comparison::Lt,
+                        ('<', true) => Comparison::Le,
+                        ('>', false) => Comparison::Gt,
+                        _ => Comparison::Ge,
+                    }
+                }
+                ComponentValue::PreservedToken(CssToken::Delim('=')) => Comparison::Eq,
+                _ => {
+                    operands.last_mut()?.push(value);
+                    continue;
+                }
+            };
+            comparisons.push(comparison);
+            operands.push(Vec::new());
+        }
+
+        let feature_name = |operand: &[&ComponentValue]| match operand {
+            [name] => ident(name).filter(|name| feature_type(name) == Some(FeatureType::Range)),
+            _ => None,
+        };
+        let value = |operand: &[&ComponentValue]| MediaFeatureValue::parse(operand);
+        let (name, comparisons) = match (operands.as_slice(), comparisons.as_slice()) {
+            // <mf-name> <mf-comparison> <mf-value>
+            ([a, b], [comparison]) if feature_name(a).is_some() => {
+                (feature_name(a)?, vec![(*comparison, value(b)?)])
+            }
+            // <mf-value> <mf-comparison> <mf-name>
+            ([a, b], [comparison]) => (feature_name(b)?, vec![(comparison.reverse(), value(a)?)]),
+            // <mf-value> <mf-lt> <mf-name> <mf-lt> <mf-value>, or the same with <mf-gt>
+            ([a, b, c], [first, second])
+                if (first.is_lt() && second.is_lt()) || (first.is_gt() && second.is_gt()) =>
+            {
+                (
+                    feature_name(b)?,
+                    vec![(first.reverse(), value(a)?), (*second, value(c)?)],
+                )
+            }
+            _ => return None,
+        };
+        Some(Self { name, comparisons })
+    }
+
+    /// <https://www.w3.org/TR/mediaqueries-4/#mf-dimensions>
+    /// <https://www.w3.org/TR/mediaqueries-4/#mf-display-quality>
+    /// <https://www.w3.org/TR/mediaqueries-5/#prefers-color-scheme>
+    ///
+    /// Returns None if a value is not valid for the feature.
+    fn evaluate(&self, device: &Device) -> Option<bool> {
+        // Relative lengths in media queries are relative to the initial values.
+        let context = ComputeContext::new(device.viewport_width, device.viewport_height);
+        let font_size = context.root_font_size;
+
+        let actual = match self.name.as_str() {
+            "width" => MediaFeatureValue::Length(Length::px(device.viewport_width)),
+            "height" => MediaFeatureValue::Length(Length::px(device.viewport_height)),
+            "aspect-ratio" => {
+                MediaFeatureValue::Ratio(device.viewport_width, device.viewport_height)
+            }
+            "resolution" => MediaFeatureValue::Resolution(device.resolution),
+            "orientation" if device.viewport_height >= device.viewport_width => {
+                MediaFeatureValue::Ident("portrait".into())
+            }
+            "orientation" => MediaFeatureValue::Ident("landscape".into()),
+            "prefers-color-scheme" => MediaFeatureValue::Ident(
+                match device.color_scheme {
+                    ColorScheme::Light => "light",
+                    ColorScheme::Dark => "dark",
+                }
+                .into(),
+            ),
+            _ => return None,
+        };
+
+        if self.comparisons.is_empty() {
+            // <https://www.w3.org/TR/mediaqueries-4/#mq-boolean-context>
+            return Some(match actual {
+                MediaFeatureValue::Length(length) => length.value != 0.0,
+                MediaFeatureValue::Ratio(numerator, _) => numerator != 0.0,
+                MediaFeatureValue::Resolution(resolution) => resolution != 0.0,
+                _ => true,
+            });
+        }
+
+        let mut result = true;
+        for (comparison, value) in &self.comparisons {
+            let ordering = match (&actual, value) {
+                (MediaFeatureValue::Length(actual), MediaFeatureValue::Length(value)) => actual
+                    .value
+                    .partial_cmp(&context.resolve_length(*value, font_size)),
+                // Unitless 0 is allowed as a length.
+                (MediaFeatureValue::Length(actual), MediaFeatureValue::Number(value))
+                    if *value == 0.0 =>
+                {
+                    actual.value.partial_cmp(value)
+                }
+                (MediaFeatureValue::Ratio(a, b), MediaFeatureValue::Ratio(c, d)) => {
+                    (a * d).partial_cmp(&(c * b))
+                }
+                (MediaFeatureValue::Ratio(a, b), MediaFeatureValue::Number(value)) => {
+                    (a / b).partial_cmp(value)
+                }
+                (MediaFeatureValue::Resolution(actual), MediaFeatureValue::Resolution(value)) => {
+                    actual.partial_cmp(value)
+                }
+                (MediaFeatureValue::Ident(actual), MediaFeatureValue::Ident(value)) => {
+                    if !["portrait", "landscape", "light", "dark"].contains(&value.as_str()) {
+                        return None;
+                    }
+                    Some(actual.cmp(value))
+                }
+                _ => return None,
+            };
+            result &= comparison.holds(ordering?);
+        }
+        Some(result)
+    }
+}
+
+impl Comparison {
+    fn is_lt(&self) -> bool {
+        matches!(self, Self::Lt | Self::Le)
+    }
+
+    fn is_gt(&self) -> bool {
+        matches!(self, Self::Gt | Self::Ge)
+    }
+
+    /// The comparison with the operands swapped, e.g. `a < b` is `b > a`.
+    fn reverse(&self) -> Self {
+        match self {
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Eq => Self::Eq,
+            Self::Ge => Self::Le,
+            Self::Gt => Self::Lt,
+        }
+    }
+
+    /// Whether `actual <comparison> value` holds, given the ordering of `actual` to `value`.
+    fn holds(&self, ordering: core::cmp::Ordering) -> bool {
+        use core::cmp::Ordering::*;
+        match self {
+            Self::Lt => ordering == Less,
+            Self::Le => ordering != Greater,
+            Self::Eq => ordering == Equal,
+            Self::Ge => ordering != Less,
+            Self::Gt => ordering == Greater,
+        }
+    }
+}
+
+impl MediaFeatureValue {
+    fn parse(values: &[&ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PreservedToken(CssToken::Number(Numeric {
+                value: numerator, ..
+            })), slash, ComponentValue::PreservedToken(CssToken::Number(Numeric {
+                value: denominator,
+                ..
+            }))] if is_delim(slash, '/') => Some(Self::Ratio(*numerator, *denominator)),
+            [ComponentValue::PreservedToken(CssToken::Number(Numeric { value: number, .. }))] => {
+                Some(Self::Number(*number))
+            }
+            [value @ ComponentValue::PreservedToken(CssToken::Dimension(
+                Numeric { value: number, .. },
+                unit,
+            ))] => {
+                // <https://www.w3.org/TR/css-values-4/#resolution>
+                let dppx = match unit.to_ascii_lowercase().as_str() {
+                    "dpi" => 1.0 / 96.0,
+                    "dpcm" => 2.54 / 96.0,
+                    "dppx" | "x" => 1.0,
+                    _ => return parse_length(value).map(Self::Length),
+                };
+                Some(Self::Resolution(number * dppx))
+            }
+            [value] => ident(value).map(Self::Ident),
+            _ => None,
+        }
+    }
+}
+
+fn non_whitespace(values: &[ComponentValue]) -> Vec<&ComponentValue> {
+    values
+        .iter()
+        .filter(|v| **v != ComponentValue::PreservedToken(CssToken::Whitespace))
+        .collect()
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-media-query-list>
+impl fmt::Display for MediaList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, query) in self.media_queries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{query}")?;
+        }
+        Ok(())
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-media-query>
+///
+/// `only` is dropped, and the media type `all` is omitted if there is a condition.
+impl fmt::Display for MediaQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "not ")?;
+        }
+        match (
+            &self.condition,
+            self.negated || self.media_type != MediaType::All,
+        ) {
+            (Some(condition), false) => write!(f, "{condition}"),
+            (Some(condition), true) => write!(f, "{} and {condition}", self.media_type),
+            (None, _) => write!(f, "{}", self.media_type),
+        }
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::Print => write!(f, "print"),
+            Self::Screen => write!(f, "screen"),
+            Self::Unknown(name) => write!(f, "{}", serialize_identifier(name)),
+        }
+    }
+}
+
+impl MediaCondition {
+    /// Serializes the condition as a `<media-in-parens>`.
+    fn fmt_in_parens(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Feature(_) | Self::GeneralEnclosed(_) => write!(f, "{self}"),
+            _ => write!(f, "({self})"),
+        }
+    }
+}
+
+impl fmt::Display for MediaCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (conditions, combinator) = match self {
+            Self::Feature(feature) => return write!(f, "{feature}"),
+            Self::GeneralEnclosed(value) => return write!(f, "{value}"),
+            Self::Not(condition) => {
+                write!(f, "not ")?;
+                return condition.fmt_in_parens(f);
+            }
+            Self::And(conditions) => (conditions, " and "),
+            Self::Or(conditions) => (conditions, " or "),
+        };
+        for (i, condition) in conditions.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{combinator}")?;
+            }
+            condition.fmt_in_parens(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Features compared with a single value are serialized in the plain syntax, e.g. `(min-width: 10px)`,
+/// and the others in the range syntax.
+impl fmt::Display for MediaFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = &self.name;
+        match self.comparisons.as_slice() {
+            [] => write!(f, "({name})"),
+            [(Comparison::Eq, value)] => write!(f, "({name}: {value})"),
+            [(Comparison::Ge, value)] => write!(f, "(min-{name}: {value})"),
+            [(Comparison::Le, value)] => write!(f, "(max-{name}: {value})"),
+            [(comparison, value)] => write!(f, "({name} {comparison} {value})"),
+            [(first, a), (second, b)] => {
+                write!(f, "({a} {} {name} {second} {b})", first.reverse())
+            }
+            _ => unreachable!("at most two comparisons"),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Eq => "=",
+            Self::Ge => ">=",
+            Self::Gt => ">",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl fmt::Display for MediaFeatureValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Length(length) => write!(f, "{length}"),
+            Self::Resolution(dppx) => write!(f, "{dppx}dppx"),
+            Self::Ratio(numerator, denominator) => write!(f, "{numerator} / {denominator}"),
+            Self::Ident(ident) => write!(f, "{}", serialize_identifier(ident)),
+        }
+    }
+}
+
+fn ident(value: &ComponentValue) -> Option<String> {
+    match value {
+        ComponentValue::PreservedToken(CssToken::Ident(ident)) => Some(ident.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+fn is_delim(value: &ComponentValue, delim: char) -> bool {
+    *value == ComponentValue::PreservedToken(CssToken::Delim(delim))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::parser::parse_media_query_list;
+
+    fn matches(media: &str, device: &Device) -> bool {
+        parse_media_query_list(media.into()).matches(device)
+    }
+
+    #[test]
+    fn test_media_types() {
+        let device = Device::new(800.0, 600.0);
+        let matches = |media: &str| matches(media, &device);
+        assert!(matches(""));
+        assert!(matches("screen"));
+        assert!(matches("ALL"));
+        assert!(matches("only screen"));
+        assert!(matches("print, screen"));
+        assert!(matches("not print"));
+        assert!(!matches("print"));
+        assert!(!matches("tv"));
+        assert!(!matches("not screen"));
+        assert!(!matches("screen print"));
+        assert!(!matches("only"));
+        assert!(!matches("screen and"));
+        assert_eq!(
+            parse_media_query_list("print, foo bar".into()).media_queries[1],
+            MediaQuery::NOT_ALL
+        );
+    }
+
+    #[test]
+    fn test_media_features() {
+        let device = Device::new(800.0, 600.0);
+        let matches = |media: &str| matches(media, &device);
+        assert!(matches("(width)"));
+        assert!(matches("(min-width: 800px)"));
+        assert!(!matches("(max-width: 799px)"));
+        assert!(matches(
+            "screen and (min-width: 50em) and (max-height: 37.5rem)"
+        ));
+        assert!(matches("(width: 800px)"));
+        assert!(matches("(min-width: 0)"));
+        assert!(matches("(max-width: 10in)"));
+        assert!(matches("(orientation: landscape)"));
+        assert!(!matches("(orientation: portrait)"));
+        assert!(matches("(prefers-color-scheme: light)"));
+        assert!(matches(
+            "(min-resolution: 96dpi) and (max-resolution: 1dppx)"
+        ));
+        assert!(matches("(min-aspect-ratio: 4 / 3)"));
+        assert!(!matches("(aspect-ratio: 16/9)"));
+        assert!(!matches("(min-orientation: portrait)"));
+        assert!(!matches("(orientation: foo)"));
+        assert!(!matches("(width: red)"));
+    }
+
+    #[test]
+    fn test_range_syntax() {
+        let device = Device::new(800.0, 600.0);
+        let matches = |media: &str| matches(media, &device);
+        assert!(matches("(width >= 800px)"));
+        assert!(!matches("(width > 800px)"));
+        assert!(matches("(800px = width)"));
+        assert!(matches("(700px < width <= 800px)"));
+        assert!(!matches("(700px < width < 800px)"));
+        assert!(matches("(900px > width > 700px)"));
+        assert!(!matches("(700px < width > 600px)"));
+        assert!(!matches("(orientation = landscape)"));
+        assert!(matches("(height < 100vw)"));
+    }
+
+    #[test]
+    fn test_media_conditions() {
+        let device = Device::new(400.0, 600.0);
+        let matches = |media: &str| matches(media, &device);
+        assert!(matches("not (min-width: 500px)"));
+        assert!(matches("(min-width: 500px) or (orientation: portrait)"));
+        assert!(!matches("(min-width: 500px) and (orientation: portrait)"));
+        assert!(matches(
+            "((min-width: 500px) or (max-width: 450px)) and (height)"
+        ));
+        assert!(!matches(
+            "(min-width: 500px) and (orientation: portrait) or (height)"
+        ));
+        assert!(!matches("screen and (width) or (height)"));
+        assert!(matches("not screen and (min-width: 500px)"));
+
+        // Unknown features and general-enclosed are unknown, which is false even after `not`.
+        assert!(!matches("(unknown)"));
+        assert!(!matches("not (unknown)"));
+        assert!(!matches("not (unknown: 1)"));
+        assert!(!matches("not foo(1)"));
+        assert!(matches("(unknown) or (width)"));
+        assert!(!matches("(unknown) and (width)"));
+        assert!(matches("not ((unknown) and (min-width: 500px))"));
+
+        let dark = Device {
+            color_scheme: ColorScheme::Dark,
+            ..device
+        };
+        assert!(parse_media_query_list("(prefers-color-scheme: dark)".into()).matches(&dark));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let serialize = |media: &str| parse_media_query_list(media.into()).to_string();
+        assert_eq!(serialize(""), "");
+        assert_eq!(serialize("SCREEN,  Print"), "screen, print");
+        // `only` has no effect, so it is not kept.
+        assert_eq!(serialize("only screen"), "screen");
+        assert_eq!(serialize("all and (color)"), "(color)");
+        assert_eq!(serialize("not all and (color)"), "not all and (color)");
+        assert_eq!(
+            serialize("screen and (MIN-WIDTH:100px) and (max-height: 2em)"),
+            "screen and (min-width: 100px) and (max-height: 2em)"
+        );
+        assert_eq!(
+            serialize("(width >= 600px) or (not (hover))"),
+            "(min-width: 600px) or (not (hover))"
+        );
+        assert_eq!(
+            serialize("(400px < width <= 700px)"),
+            "(400px < width <= 700px)"
+        );
+        assert_eq!(serialize("(600px > width)"), "(width < 600px)");
+        assert_eq!(
+            serialize("(min-aspect-ratio: 16/9)"),
+            "(min-aspect-ratio: 16 / 9)"
+        );
+        assert_eq!(serialize("(resolution: 2dppx)"), "(resolution: 2dppx)");
+        assert_eq!(
+            serialize("(foo: bar) and (color)"),
+            "(foo: bar) and (color)"
+        );
+        assert_eq!(serialize("speech"), "speech");
+        assert_eq!(serialize("screen print, tv"), "not all, tv");
+    }
+}
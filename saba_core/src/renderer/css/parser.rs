@@ -1,222 +1,351 @@
 use super::cssom;
-use super::token::{CssToken, CssTokenizer};
-use alloc::string::String;
+use super::token::{CssToken, CssTokenizer, Numeric};
+use super::value::serialize_identifier;
+use alloc::string::{String, ToString};
 use alloc::{vec, vec::Vec};
+use core::fmt;
 
 mod css_parser {
-    use crate::renderer::css::cssom::CssDeclaration;
-
     use super::super::cssom;
+    use super::super::custom_properties::{self, UnparsedValue};
+    use super::super::font_face::FontFaceDescriptors;
+    use super::super::media::MediaList;
+    use super::super::properties;
+    use super::super::selector;
+    use super::super::shorthands;
+    use super::super::supports::SupportsCondition;
     use super::super::token::{CssToken, CssTokenizer};
+    use super::super::value;
     use super::*;
     use alloc::string::String;
-    use alloc::{vec, vec::Vec};
+    use alloc::vec::Vec;
 
     /// <https://www.w3.org/TR/css-syntax-3/#parse-a-css-stylesheet>
     pub fn parse_css_stylesheet(source: String) -> cssom::CssStyleSheet {
         let tokenizer = CssTokenizer::new(source);
         let stylesheet = StyleSheetParser::new(tokenizer).parse_stylesheet();
 
-        let css_rules = stylesheet.rules.iter().map(parse_style_rule).collect();
+        let mut css_rules = Vec::new();
+        for rule in &stylesheet.rules {
+            match parse_rule(rule) {
+                // <https://www.w3.org/TR/css-cascade-4/#at-import>
+                // @import rules must precede all other rules.
+                Some(cssom::CssRule::Import(_))
+                    if !css_rules
+                        .iter()
+                        .all(|r| matches!(r, cssom::CssRule::Import(_))) => {}
+                Some(rule) => css_rules.push(rule),
+                None => {}
+            }
+        }
 
         cssom::CssStyleSheet { css_rules }
     }
 
-    /// <https://www.w3.org/TR/css-syntax-3/#parse-a-stylesheet>
-    fn parse_style_rule(rule: &QualifiedRule) -> cssom::CssStyleRule {
-        cssom::CssStyleRule {
-            selector: parse_selector_list(&rule.prelude),
-            declarations: parse_style_block_contents(&rule.block),
-        }
+    /// Parses the rules nested in a conditional group rule, e.g. @media.
+    fn parse_nested_rules(block: &SimpleBlock) -> Vec<cssom::CssRule> {
+        ComponentValueParser::new(block.value.clone())
+            .consume_list_of_rules(false)
+            .iter()
+            .filter_map(parse_rule)
+            .filter(|rule| !matches!(rule, cssom::CssRule::Import(_)))
+            .collect()
     }
 
-    /// <https://www.w3.org/TR/css-syntax-3/#parse-a-style-blocks-contents>
-    fn parse_style_block_contents(block: &SimpleBlock) -> cssom::CssStyleDeclaration {
-        cssom::CssStyleDeclaration {
-            declarations: DeclarationsParser::parse(block),
+    /// Interprets a rule. Returns None if the rule is invalid or not supported.
+    fn parse_rule(rule: &Rule) -> Option<cssom::CssRule> {
+        match rule {
+            Rule::Qualified(rule) => parse_style_rule(rule).map(cssom::CssRule::Style),
+            Rule::At(rule) => match rule.name.to_ascii_lowercase().as_str() {
+                "import" => parse_import_rule(rule).map(cssom::CssRule::Import),
+                "media" => parse_media_rule(rule).map(cssom::CssRule::Media),
+                "font-face" => parse_font_face_rule(rule).map(cssom::CssRule::FontFace),
+                "supports" => parse_supports_rule(rule).map(cssom::CssRule::Supports),
+                "keyframes" => parse_keyframes_rule(rule).map(cssom::CssRule::Keyframes),
+                _ => None,
+            },
         }
     }
 
-    /// <https://www.w3.org/TR/selectors-4/#typedef-selector-list>
-    fn parse_selector_list(prelude: &[ComponentValue]) -> cssom::SelectorList {
-        let mut selectors = Vec::new();
-
-        for v in prelude {
-            let ComponentValue::PreservedToken(v) = v;
-            use CssToken::*;
-            match v {
-                Hash(id) => selectors.push(cssom::SimpleSelector::IdSelector(id.clone())),
-                Ident(ty) => selectors.push(cssom::SimpleSelector::TypeSelector(ty.clone())),
-                Delim('.') => todo!(".class not implemented yet"),
-                _ => {}
-            }
-        }
-
-        cssom::SelectorList {
-            selectors: vec![cssom::ComplexSelector::CompoundSelector(
-                cssom::CompoundSelector(selectors),
-            )],
-        }
-    }
-
-    struct DeclarationsParser {
-        values: Vec<ComponentValue>,
-        pos: usize,
+    /// <https://www.w3.org/TR/css-syntax-3/#style-rules>
+    ///
+    /// Returns None if the selector list is invalid.
+    fn parse_style_rule(rule: &QualifiedRule) -> Option<cssom::CssStyleRule> {
+        Some(cssom::CssStyleRule {
+            selector: selector::parse_selector_list(&rule.prelude)?,
+            declarations: parse_style_block_contents(&rule.block),
+        })
     }
 
-    impl DeclarationsParser {
-        /// <https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-declarations>
-        pub fn parse(block: &SimpleBlock) -> Vec<cssom::CssDeclaration> {
-            let mut parser = Self {
-                values: block.value.clone(),
-                pos: 0,
-            };
-            let mut declarations = Vec::new();
-
-            loop {
-                match parser.consume_next_value() {
-                    None => {
-                        break;
-                    }
-                    Some(t) => {
-                        let ComponentValue::PreservedToken(t) = t;
-                        use CssToken::*;
-                        match t {
-                            Whitespace | SemiColon => {}
-                            Ident(_) => {
-                                let mut list = vec![t.clone()];
-                                loop {
-                                    match parser.peek_next_value() {
-                                        None => {
-                                            break;
-                                        }
-                                        Some(t) => {
-                                            let ComponentValue::PreservedToken(t) = t;
-                                            match t {
-                                                SemiColon => {
-                                                    break;
-                                                }
-                                                _ => {
-                                                    list.push(t.clone());
-                                                }
-                                            }
-                                        }
-                                    }
-                                    parser.consume_next_value();
-                                }
-                                if let Some(d) = DeclarationParser::parse(list) {
-                                    declarations.push(d);
-                                }
-                            }
-                            _ => unimplemented!(),
-                        }
-                    }
-                }
-            }
-
-            declarations
+    /// <https://www.w3.org/TR/css-cascade-4/#at-import>
+    fn parse_import_rule(rule: &AtRule) -> Option<cssom::CssImportRule> {
+        if rule.block.is_some() {
+            return None;
         }
-
-        fn consume_next_value(&mut self) -> Option<ComponentValue> {
-            if self.pos < self.values.len() {
-                let v = &self.values[self.pos];
-                self.pos += 1;
-                Some(v.clone())
-            } else {
-                None
+        let prelude = trim_whitespace(&rule.prelude);
+        let (url, media) = prelude.split_first()?;
+        let href = match url {
+            ComponentValue::PreservedToken(CssToken::Url(url))
+            | ComponentValue::PreservedToken(CssToken::String(url)) => url.clone(),
+            ComponentValue::Function(Function { name, value })
+                if name.eq_ignore_ascii_case("url") =>
+            {
+                match trim_whitespace(value) {
+                    [ComponentValue::PreservedToken(CssToken::String(url))] => url.clone(),
+                    _ => return None,
+                }
             }
-        }
+            _ => return None,
+        };
+        Some(cssom::CssImportRule {
+            href,
+            media: MediaList::parse(media),
+            style_sheet: None,
+        })
+    }
 
-        fn peek_next_value(&mut self) -> Option<&ComponentValue> {
-            if self.pos < self.values.len() {
-                Some(&self.values[self.pos])
-            } else {
-                None
-            }
-        }
+    /// <https://www.w3.org/TR/css-conditional-3/#at-media>
+    fn parse_media_rule(rule: &AtRule) -> Option<cssom::CssMediaRule> {
+        let block = rule.block.as_ref()?;
+        Some(cssom::CssMediaRule {
+            media: MediaList::parse(&rule.prelude),
+            css_rules: parse_nested_rules(block),
+        })
     }
 
-    struct DeclarationParser {
-        tokens: Vec<CssToken>,
-        pos: usize,
+    /// <https://www.w3.org/TR/css-conditional-3/#at-supports>
+    fn parse_supports_rule(rule: &AtRule) -> Option<cssom::CssSupportsRule> {
+        let block = rule.block.as_ref()?;
+        Some(cssom::CssSupportsRule {
+            condition: SupportsCondition::parse(&rule.prelude)?,
+            css_rules: parse_nested_rules(block),
+        })
     }
 
-    impl DeclarationParser {
-        pub fn parse(tokens: Vec<CssToken>) -> Option<CssDeclaration> {
-            let mut parser = DeclarationParser { tokens, pos: 0 };
-            parser.consume_declaration()
+    /// <https://www.w3.org/TR/css-fonts-4/#font-face-rule>
+    fn parse_font_face_rule(rule: &AtRule) -> Option<cssom::CssFontFaceRule> {
+        let block = rule.block.as_ref()?;
+        if !trim_whitespace(&rule.prelude).is_empty() {
+            return None;
         }
+        let declarations = ComponentValueParser::new(block.value.clone())
+            .consume_list_of_declarations()
+            .into_iter()
+            .filter_map(|d| match d {
+                DeclarationOrAtRule::Declaration(d) => Some(d),
+                DeclarationOrAtRule::AtRule(_) => None,
+            })
+            .collect::<Vec<_>>();
+        Some(cssom::CssFontFaceRule {
+            descriptors: FontFaceDescriptors::parse(&declarations),
+        })
+    }
 
-        fn consume_next_token(&mut self) -> Option<CssToken> {
-            if self.pos < self.tokens.len() {
-                let v = &self.tokens[self.pos];
-                self.pos += 1;
-                Some(v.clone())
-            } else {
-                None
+    /// <https://www.w3.org/TR/css-animations-1/#keyframes>
+    fn parse_keyframes_rule(rule: &AtRule) -> Option<cssom::CssKeyframesRule> {
+        let block = rule.block.as_ref()?;
+        let name = match trim_whitespace(&rule.prelude) {
+            [ComponentValue::PreservedToken(CssToken::Ident(name))]
+                if !["none", "initial", "inherit", "unset", "default"]
+                    .iter()
+                    .any(|keyword| name.eq_ignore_ascii_case(keyword)) =>
+            {
+                name.clone()
             }
-        }
+            [ComponentValue::PreservedToken(CssToken::String(name))] => name.clone(),
+            _ => return None,
+        };
 
-        fn peek_next_token(&mut self) -> Option<&CssToken> {
-            if self.pos < self.tokens.len() {
-                Some(&self.tokens[self.pos])
-            } else {
-                None
-            }
-        }
+        let css_rules = ComponentValueParser::new(block.value.clone())
+            .consume_list_of_rules(false)
+            .iter()
+            .filter_map(|rule| match rule {
+                Rule::Qualified(rule) => parse_keyframe_rule(rule),
+                Rule::At(_) => None,
+            })
+            .collect();
 
-        /// <https://www.w3.org/TR/css-syntax-3/#consume-a-declaration>
-        fn consume_declaration(&mut self) -> Option<cssom::CssDeclaration> {
-            let property_name = match self.consume_next_token() {
-                Some(CssToken::Ident(id)) => id,
-                // Note: This algorithm assumes that the next input token has already been checked to be an <ident-token>.
-                _ => unreachable!(),
-            };
+        Some(cssom::CssKeyframesRule { name, css_rules })
+    }
 
-            let mut declaration = cssom::CssDeclaration {
-                property_name,
-                value: Vec::new(),
+    /// <https://www.w3.org/TR/css-animations-1/#typedef-keyframe-block>
+    fn parse_keyframe_rule(rule: &QualifiedRule) -> Option<cssom::CssKeyframeRule> {
+        let mut keys = Vec::new();
+        for selector in rule
+            .prelude
+            .split(|v| *v == ComponentValue::PreservedToken(CssToken::Comma))
+        {
+            let key = match trim_whitespace(selector) {
+                [ComponentValue::PreservedToken(CssToken::Ident(ident))]
+                    if ident.eq_ignore_ascii_case("from") =>
+                {
+                    0.0
+                }
+                [ComponentValue::PreservedToken(CssToken::Ident(ident))]
+                    if ident.eq_ignore_ascii_case("to") =>
+                {
+                    100.0
+                }
+                [ComponentValue::PreservedToken(CssToken::Percentage(Numeric {
+                    value: p, ..
+                }))] if (0.0..=100.0).contains(p) => *p,
+                _ => return None,
             };
+            keys.push(key);
+        }
 
-            while matches!(self.peek_next_token(), Some(&CssToken::Whitespace),) {
-                self.consume_next_token();
-            }
+        // Declarations with !important in a keyframe rule are ignored.
+        let mut declarations = parse_style_block_contents(&rule.block);
+        declarations.declarations.retain(|d| !d.important);
 
-            assert_eq!(self.consume_next_token(), Some(CssToken::Colon),);
+        Some(cssom::CssKeyframeRule { keys, declarations })
+    }
 
-            while matches!(self.peek_next_token(), Some(&CssToken::Whitespace),) {
-                self.consume_next_token();
-            }
+    /// Parses the value of a `style` attribute.
+    ///
+    /// <https://drafts.csswg.org/css-style-attr/#syntax>
+    pub fn parse_style_attribute(source: String) -> cssom::CssStyleDeclaration {
+        let declarations = StyleSheetParser::new(CssTokenizer::new(source))
+            .parse_list_of_declarations()
+            .into_iter()
+            .flat_map(|d| match d {
+                DeclarationOrAtRule::Declaration(d) => parse_declaration(d),
+                DeclarationOrAtRule::AtRule(_) => Vec::new(),
+            })
+            .collect();
+        cssom::CssStyleDeclaration { declarations }
+    }
 
-            let mut declaration_value = Vec::new();
+    /// <https://www.w3.org/TR/css-syntax-3/#parse-a-style-blocks-contents>
+    fn parse_style_block_contents(block: &SimpleBlock) -> cssom::CssStyleDeclaration {
+        let declarations = ComponentValueParser::new(block.value.clone())
+            .consume_list_of_declarations()
+            .into_iter()
+            .flat_map(|d| match d {
+                DeclarationOrAtRule::Declaration(d) => parse_declaration(d),
+                DeclarationOrAtRule::AtRule(_) => Vec::new(),
+            })
+            .collect();
+        cssom::CssStyleDeclaration { declarations }
+    }
 
-            while let Some(t) = self.consume_next_token() {
-                declaration_value.push(t);
-            }
+    /// Returns the declarations of the longhands if the property is a shorthand.
+    /// Returns nothing if the property is not supported or the value does not match its grammar,
+    /// so that the declaration is dropped.
+    fn parse_declaration(declaration: Declaration) -> Vec<cssom::CssDeclaration> {
+        // <https://www.w3.org/TR/css-variables-1/#defining-variables>
+        // The name of a custom property is case-sensitive, and its value is not parsed
+        // except for the CSS-wide keywords.
+        if custom_properties::is_custom_property(&declaration.name) {
+            let value = match trim_whitespace(&declaration.value) {
+                [ComponentValue::PreservedToken(CssToken::Ident(keyword))]
+                    if properties::CSS_WIDE_KEYWORDS
+                        .iter()
+                        .any(|k| k.eq_ignore_ascii_case(keyword)) =>
+                {
+                    value::ComponentValue::Keyword(keyword.to_ascii_lowercase())
+                }
+                tokens => value::ComponentValue::Unparsed(UnparsedValue {
+                    tokens: tokens.to_vec(),
+                    shorthand: None,
+                }),
+            };
+            return vec![cssom::CssDeclaration {
+                property_name: declaration.name,
+                value: vec![value],
+                important: declaration.important,
+            }];
+        }
 
-            // TODO: handle !important
+        if custom_properties::contains_var(&declaration.value) {
+            let longhands =
+                custom_properties::parse_declaration(&declaration.name, &declaration.value);
+            return longhands
+                .into_iter()
+                .flatten()
+                .map(|(property_name, value)| cssom::CssDeclaration {
+                    property_name,
+                    value,
+                    important: declaration.important,
+                })
+                .collect();
+        }
 
-            while let Some(CssToken::Whitespace) = declaration_value.last() {
-                declaration_value.pop();
-            }
+        if shorthands::longhands(&declaration.name).is_some() {
+            let longhands = shorthands::expand(&declaration.name, &declaration.value);
+            return longhands
+                .into_iter()
+                .flatten()
+                .map(|(name, value)| cssom::CssDeclaration {
+                    property_name: name.into(),
+                    value,
+                    important: declaration.important,
+                })
+                .collect();
+        }
 
-            declaration.value = parse_value(declaration_value);
+        properties::parse_declaration_value(&declaration.name, &declaration.value)
+            .map(|value| cssom::CssDeclaration {
+                value,
+                property_name: declaration.name.to_ascii_lowercase(),
+                important: declaration.important,
+            })
+            .into_iter()
+            .collect()
+    }
 
-            Some(declaration)
-        }
+    /// <https://www.w3.org/TR/css-conditional-3/#dfn-support>
+    ///
+    /// A declaration is supported if it would not be dropped from a style rule.
+    pub fn supports_declaration(declaration: Declaration) -> bool {
+        !parse_declaration(declaration).is_empty()
     }
 
-    fn parse_value(declaration_value: Vec<CssToken>) -> Vec<super::super::value::ComponentValue> {
-        if declaration_value.len() == 1 {
-            if let CssToken::Ident(id) = &declaration_value[0] {
-                return vec![super::super::value::ComponentValue::Keyword(id.into())];
-            }
-        }
-        unimplemented!("not supported value: {declaration_value:#?}")
+    /// Parses the value of a property, e.g. for `CSSStyleDeclaration.setProperty()`.
+    ///
+    /// <https://www.w3.org/TR/cssom-1/#parse-a-css-value>
+    pub fn parse_property_value(
+        property: &str,
+        value: String,
+        important: bool,
+    ) -> Vec<cssom::CssDeclaration> {
+        let value =
+            StyleSheetParser::new(CssTokenizer::new(value)).parse_list_of_component_values();
+        parse_declaration(Declaration {
+            name: property.into(),
+            value,
+            important,
+        })
+    }
+
+    /// <https://www.w3.org/TR/mediaqueries-4/#typedef-media-query-list>
+    ///
+    /// Parses a media query list in a string, e.g. the `media` attribute of `<link>`.
+    pub fn parse_media_query_list(source: String) -> MediaList {
+        let values =
+            StyleSheetParser::new(CssTokenizer::new(source)).parse_list_of_component_values();
+        MediaList::parse(&values)
     }
 }
 
-pub use css_parser::parse_css_stylesheet;
+pub use css_parser::{
+    parse_css_stylesheet, parse_media_query_list, parse_property_value, parse_style_attribute,
+    supports_declaration,
+};
+
+pub(crate) fn trim_whitespace(values: &[ComponentValue]) -> &[ComponentValue] {
+    let is_whitespace =
+        |v: &ComponentValue| *v == ComponentValue::PreservedToken(CssToken::Whitespace);
+    let start = values
+        .iter()
+        .position(|v| !is_whitespace(v))
+        .unwrap_or(values.len());
+    let end = values
+        .iter()
+        .rposition(|v| !is_whitespace(v))
+        .map_or(start, |i| i + 1);
+    &values[start..end]
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StyleSheetParser {
@@ -244,87 +373,308 @@ impl StyleSheetParser {
 
     /// <https://www.w3.org/TR/css-syntax-3/#parse-a-stylesheet>
     pub fn parse_stylesheet(&mut self) -> StyleSheet {
-        let rules = self.consume_list_of_rules();
+        let values = self.parse_list_of_component_values();
+        let rules = ComponentValueParser::new(values).consume_list_of_rules(true);
         StyleSheet { rules }
     }
 
-    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-rules>
-    fn consume_list_of_rules(&mut self) -> Vec<QualifiedRule> {
-        let mut rules = Vec::new();
+    /// <https://www.w3.org/TR/css-syntax-3/#parse-a-list-of-declarations>
+    pub fn parse_list_of_declarations(&mut self) -> Vec<DeclarationOrAtRule> {
+        let values = self.parse_list_of_component_values();
+        ComponentValueParser::new(values).consume_list_of_declarations()
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#parse-a-list-of-component-values>
+    pub fn parse_list_of_component_values(&mut self) -> Vec<ComponentValue> {
+        let mut values = Vec::new();
+        while let Some(v) = self.consume_component_value() {
+            values.push(v);
+        }
+        values
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-component-value>
+    ///
+    /// Returns None at the end of the input.
+    fn consume_component_value(&mut self) -> Option<ComponentValue> {
+        let value = match self.consume_next_input_token()? {
+            t @ (CssToken::OpenCurly | CssToken::OpenSquare | CssToken::OpenParenthesis) => {
+                ComponentValue::SimpleBlock(self.consume_simple_block(t))
+            }
+            CssToken::Function(name) => ComponentValue::Function(self.consume_function(name)),
+            t => ComponentValue::PreservedToken(t),
+        };
+        Some(value)
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-simple-block>
+    fn consume_simple_block(&mut self, associated_token: CssToken) -> SimpleBlock {
+        let ending_token = match associated_token {
+            CssToken::OpenCurly => CssToken::CloseCurly,
+            CssToken::OpenSquare => CssToken::CloseSquare,
+            CssToken::OpenParenthesis => CssToken::CloseParenthesis,
+            _ => unreachable!("not a token which opens a block: {associated_token:?}"),
+        };
+        let mut block = SimpleBlock {
+            associated_token,
+            value: Vec::new(),
+        };
         loop {
             match self.consume_next_input_token() {
-                None => {
-                    break;
-                }
-                Some(CssToken::Whitespace) => {}
+                // Parse error.
+                None => return block,
+                Some(t) if t == ending_token => return block,
                 Some(t) => {
                     self.reconsume(t);
-                    rules.push(self.consume_qualified_rule());
+                    block.value.extend(self.consume_component_value());
                 }
             }
         }
-        rules
     }
 
-    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-qualified-rule>
-    fn consume_qualified_rule(&mut self) -> QualifiedRule {
-        let mut prelude = Vec::new();
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-function>
+    fn consume_function(&mut self, name: String) -> Function {
+        let mut function = Function {
+            name,
+            value: Vec::new(),
+        };
         loop {
             match self.consume_next_input_token() {
-                None => {
-                    unimplemented!();
+                // Parse error.
+                None => return function,
+                Some(CssToken::CloseParenthesis) => return function,
+                Some(t) => {
+                    self.reconsume(t);
+                    function.value.extend(self.consume_component_value());
+                }
+            }
+        }
+    }
+}
+
+/// Consumes rules and declarations from a list of component values.
+///
+/// The spec treats a list of component values as a token stream whose blocks and functions are already consumed,
+/// and so does this parser; [StyleSheetParser] consumes all the component values first.
+#[derive(Debug, Clone, PartialEq)]
+struct ComponentValueParser {
+    values: Vec<ComponentValue>,
+    pos: usize,
+}
+
+impl ComponentValueParser {
+    fn new(values: Vec<ComponentValue>) -> Self {
+        Self { values, pos: 0 }
+    }
+
+    fn consume_next_value(&mut self) -> Option<ComponentValue> {
+        let v = self.values.get(self.pos)?.clone();
+        self.pos += 1;
+        Some(v)
+    }
+
+    fn peek_next_value(&self) -> Option<&ComponentValue> {
+        self.values.get(self.pos)
+    }
+
+    fn reconsume(&mut self) {
+        assert!(self.pos > 0);
+        self.pos -= 1;
+    }
+
+    /// Consumes component values up to the next semicolon or the end.
+    fn consume_until_semicolon(&mut self) -> Vec<ComponentValue> {
+        let mut values = Vec::new();
+        while !matches!(
+            self.peek_next_value(),
+            None | Some(ComponentValue::PreservedToken(CssToken::SemiColon))
+        ) {
+            values.extend(self.consume_next_value());
+        }
+        values
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-rules>
+    fn consume_list_of_rules(&mut self, top_level: bool) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        loop {
+            match self.consume_next_value() {
+                None => return rules,
+                Some(ComponentValue::PreservedToken(CssToken::Whitespace)) => {}
+                Some(ComponentValue::PreservedToken(CssToken::Cdo | CssToken::Cdc))
+                    if top_level => {}
+                Some(ComponentValue::PreservedToken(CssToken::AtKeyword(name))) => {
+                    rules.push(Rule::At(self.consume_at_rule(name)));
                 }
-                Some(CssToken::OpenCurly) => {
-                    return QualifiedRule {
-                        prelude,
-                        block: self.consume_simple_block(CssToken::CloseCurly),
+                Some(_) => {
+                    self.reconsume();
+                    if let Some(rule) = self.consume_qualified_rule() {
+                        rules.push(Rule::Qualified(rule));
                     }
                 }
-                Some(t) => {
-                    self.reconsume(t);
-                    prelude.push(self.consume_component_value());
+            }
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-an-at-rule>
+    fn consume_at_rule(&mut self, name: String) -> AtRule {
+        let mut rule = AtRule {
+            name,
+            prelude: Vec::new(),
+            block: None,
+        };
+        loop {
+            match self.consume_next_value() {
+                // EOF is a parse error.
+                None | Some(ComponentValue::PreservedToken(CssToken::SemiColon)) => return rule,
+                Some(ComponentValue::SimpleBlock(block))
+                    if block.associated_token == CssToken::OpenCurly =>
+                {
+                    rule.block = Some(block);
+                    return rule;
                 }
+                Some(v) => rule.prelude.push(v),
             }
         }
     }
 
-    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-simple-block>
-    fn consume_simple_block(&mut self, ending_token: CssToken) -> SimpleBlock {
-        let mut block = SimpleBlock { value: Vec::new() };
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-qualified-rule>
+    ///
+    /// Returns None if the rule has no block.
+    fn consume_qualified_rule(&mut self) -> Option<QualifiedRule> {
+        let mut prelude = Vec::new();
         loop {
-            match self.consume_next_input_token() {
-                None => {
-                    unimplemented!();
+            match self.consume_next_value() {
+                // Parse error.
+                None => return None,
+                Some(ComponentValue::SimpleBlock(block))
+                    if block.associated_token == CssToken::OpenCurly =>
+                {
+                    return Some(QualifiedRule { prelude, block });
                 }
-                Some(t) if t == ending_token => return block,
-                Some(t) => {
-                    self.reconsume(t);
-                    block.value.push(self.consume_component_value());
+                Some(v) => prelude.push(v),
+            }
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-declarations>
+    fn consume_list_of_declarations(&mut self) -> Vec<DeclarationOrAtRule> {
+        let mut declarations = Vec::new();
+        loop {
+            match self.consume_next_value() {
+                None => return declarations,
+                Some(ComponentValue::PreservedToken(
+                    CssToken::Whitespace | CssToken::SemiColon,
+                )) => {}
+                Some(ComponentValue::PreservedToken(CssToken::AtKeyword(name))) => {
+                    declarations.push(DeclarationOrAtRule::AtRule(self.consume_at_rule(name)));
+                }
+                Some(v @ ComponentValue::PreservedToken(CssToken::Ident(_))) => {
+                    let mut list = vec![v];
+                    list.extend(self.consume_until_semicolon());
+                    if let Some(d) = consume_declaration(list) {
+                        declarations.push(DeclarationOrAtRule::Declaration(d));
+                    }
+                }
+                Some(_) => {
+                    // Parse error.
+                    self.consume_until_semicolon();
                 }
             }
         }
     }
+}
 
-    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-component-value>
-    fn consume_component_value(&mut self) -> ComponentValue {
-        match self.consume_next_input_token() {
-            None => unimplemented!(),
-            Some(t) => ComponentValue::PreservedToken(t),
+/// <https://www.w3.org/TR/css-syntax-3/#consume-a-declaration>
+///
+/// Returns None if the declaration is invalid.
+pub(crate) fn consume_declaration(values: Vec<ComponentValue>) -> Option<Declaration> {
+    let is_whitespace =
+        |v: &ComponentValue| *v == ComponentValue::PreservedToken(CssToken::Whitespace);
+    let mut values = values.into_iter().peekable();
+
+    let name = match values.next() {
+        Some(ComponentValue::PreservedToken(CssToken::Ident(name))) => name,
+        // Note: This algorithm assumes that the next input token has already been checked to be an <ident-token>.
+        _ => unreachable!(),
+    };
+
+    while values.next_if(is_whitespace).is_some() {}
+    // Parse error if not a colon.
+    values.next_if_eq(&ComponentValue::PreservedToken(CssToken::Colon))?;
+    while values.next_if(is_whitespace).is_some() {}
+
+    let mut value = values.collect::<Vec<_>>();
+    let mut important = false;
+
+    let mut non_whitespace = value
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, v)| !is_whitespace(v));
+    if let (
+        Some((_, ComponentValue::PreservedToken(CssToken::Ident(ident)))),
+        Some((bang_index, ComponentValue::PreservedToken(CssToken::Delim('!')))),
+    ) = (non_whitespace.next(), non_whitespace.next())
+    {
+        if ident.eq_ignore_ascii_case("important") {
+            value.truncate(bang_index);
+            important = true;
         }
     }
+
+    while value.last().is_some_and(is_whitespace) {
+        value.pop();
+    }
+
+    Some(Declaration {
+        name,
+        value,
+        important,
+    })
 }
 
 /// <https://www.w3.org/TR/css-syntax-3/#stylesheet-diagram>
 #[derive(Debug, Clone, PartialEq)]
 pub struct StyleSheet {
-    rules: Vec<QualifiedRule>,
+    pub rules: Vec<Rule>,
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#css-rule>
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    Qualified(QualifiedRule),
+    At(AtRule),
 }
 
 /// <https://www.w3.org/TR/css-syntax-3/#qualified-rule>
 #[derive(Debug, Clone, PartialEq)]
 pub struct QualifiedRule {
-    prelude: Vec<ComponentValue>,
-    block: SimpleBlock,
+    pub prelude: Vec<ComponentValue>,
+    pub block: SimpleBlock,
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#at-rule>
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: Vec<ComponentValue>,
+    pub block: Option<SimpleBlock>,
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#declaration>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub value: Vec<ComponentValue>,
+    pub important: bool,
+}
+
+/// An item of the result of <https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-declarations>.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeclarationOrAtRule {
+    Declaration(Declaration),
+    AtRule(AtRule),
 }
 
 /// <https://www.w3.org/TR/css-syntax-3/#component-value>
@@ -332,12 +682,51 @@ pub struct QualifiedRule {
 pub enum ComponentValue {
     /// <https://www.w3.org/TR/css-syntax-3/#preserved-tokens>
     PreservedToken(CssToken),
+    Function(Function),
+    SimpleBlock(SimpleBlock),
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#function>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub value: Vec<ComponentValue>,
 }
 
 /// <https://www.w3.org/TR/css-syntax-3/#simple-block>
 #[derive(Debug, Clone, PartialEq)]
 pub struct SimpleBlock {
-    value: Vec<ComponentValue>,
+    /// One of `{`, `[` and `(`.
+    pub associated_token: CssToken,
+    pub value: Vec<ComponentValue>,
+}
+
+impl fmt::Display for ComponentValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PreservedToken(token) => write!(f, "{token}"),
+            Self::Function(function) => {
+                write!(f, "{}(", serialize_identifier(&function.name))?;
+                function.value.iter().try_for_each(|v| write!(f, "{v}"))?;
+                write!(f, ")")
+            }
+            Self::SimpleBlock(block) => {
+                let end = match block.associated_token {
+                    CssToken::OpenCurly => CssToken::CloseCurly,
+                    CssToken::OpenSquare => CssToken::CloseSquare,
+                    _ => CssToken::CloseParenthesis,
+                };
+                write!(f, "{}", block.associated_token)?;
+                block.value.iter().try_for_each(|v| write!(f, "{v}"))?;
+                write!(f, "{end}")
+            }
+        }
+    }
+}
+
+/// Serializes a list of component values, e.g. the value of a custom property.
+pub fn serialize_component_values(values: &[ComponentValue]) -> String {
+    values.iter().map(|v| v.to_string()).collect()
 }
 
 #[cfg(test)]
@@ -351,11 +740,12 @@ mod tests {
         let parsed = StyleSheetParser::new(t).parse_stylesheet();
 
         let expected = StyleSheet {
-            rules: vec![QualifiedRule {
+            rules: vec![Rule::Qualified(QualifiedRule {
                 prelude: vec![ComponentValue::PreservedToken(CssToken::Ident(
                     "p".to_string(),
                 ))],
                 block: SimpleBlock {
+                    associated_token: CssToken::OpenCurly,
                     value: vec![
                         ComponentValue::PreservedToken(CssToken::Ident("color".to_string())),
                         ComponentValue::PreservedToken(CssToken::Colon),
@@ -363,7 +753,7 @@ mod tests {
                         ComponentValue::PreservedToken(CssToken::SemiColon),
                     ],
                 },
-            }],
+            })],
         };
 
         assert_eq!(expected, parsed);
@@ -377,7 +767,7 @@ mod tests {
         let expected = {
             use cssom::*;
             CssStyleSheet {
-                css_rules: vec![CssStyleRule {
+                css_rules: vec![CssRule::Style(CssStyleRule {
                     selector: SelectorList {
                         selectors: vec![ComplexSelector::CompoundSelector(CompoundSelector(vec![
                             SimpleSelector::TypeSelector("p".into()),
@@ -386,13 +776,210 @@ mod tests {
                     declarations: CssStyleDeclaration {
                         declarations: vec![CssDeclaration {
                             property_name: "color".into(),
-                            value: vec![super::super::value::ComponentValue::Keyword("red".into())],
+                            value: vec![super::super::value::ComponentValue::Color(
+                                super::super::value::Color::rgb(255, 0, 0),
+                            )],
+                            important: false,
                         }],
                     },
-                }],
+                })],
             }
         };
 
         assert_eq!(expected, parsed);
     }
+
+    #[test]
+    fn test_at_rules() {
+        let style = r#"
+            @charset "utf-8";
+            @import url("a.css") screen;
+            p { color: red }
+            @import "b.css";
+            @media screen and (min-width: 100px) { h1 { color: blue } @import "c.css"; }
+            @font-face { font-family: foo; src: url(foo.woff) }
+            @supports (display: block) { p { display: block } }
+            @keyframes fade { from { color: red } 50%, to { color: blue !important; display: none } }
+            @unknown foo { bar }
+        "#;
+        let parsed = parse_css_stylesheet(style.to_string());
+
+        use super::super::font_face::{FontFaceDescriptors, FontFaceSource};
+        use super::super::media::MediaList;
+        use super::super::supports::SupportsCondition;
+        use super::super::value::{Color, ComponentValue::*};
+        use cssom::*;
+        let declaration = |name: &str, value| CssDeclaration {
+            property_name: name.into(),
+            value: vec![value],
+            important: false,
+        };
+        let red = Color(Color::rgb(255, 0, 0));
+        let blue = Color(Color::rgb(0, 0, 255));
+        let style_rule = |ty: &str, declarations: Vec<CssDeclaration>| {
+            CssRule::Style(CssStyleRule {
+                selector: SelectorList {
+                    selectors: vec![ComplexSelector::CompoundSelector(CompoundSelector(vec![
+                        SimpleSelector::TypeSelector(ty.into()),
+                    ]))],
+                },
+                declarations: CssStyleDeclaration { declarations },
+            })
+        };
+        let tokens = |style: &str| {
+            StyleSheetParser::new(CssTokenizer::new(style.to_string()))
+                .parse_list_of_component_values()
+        };
+
+        let expected = vec![
+            CssRule::Import(CssImportRule {
+                href: "a.css".into(),
+                media: MediaList::parse(&tokens("screen")),
+                style_sheet: None,
+            }),
+            style_rule("p", vec![declaration("color", red.clone())]),
+            CssRule::Media(CssMediaRule {
+                media: MediaList::parse(&tokens("screen and (min-width: 100px)")),
+                css_rules: vec![style_rule("h1", vec![declaration("color", blue.clone())])],
+            }),
+            CssRule::FontFace(CssFontFaceRule {
+                descriptors: FontFaceDescriptors {
+                    family: Some("foo".into()),
+                    src: Some(vec![FontFaceSource::Url {
+                        url: "foo.woff".into(),
+                        format: None,
+                    }]),
+                    ..Default::default()
+                },
+            }),
+            CssRule::Supports(CssSupportsRule {
+                condition: SupportsCondition::parse(&tokens("(display: block)")).unwrap(),
+                css_rules: vec![style_rule(
+                    "p",
+                    vec![declaration("display", Keyword("block".into()))],
+                )],
+            }),
+            CssRule::Keyframes(CssKeyframesRule {
+                name: "fade".into(),
+                css_rules: vec![
+                    CssKeyframeRule {
+                        keys: vec![0.0],
+                        declarations: CssStyleDeclaration {
+                            declarations: vec![declaration("color", red.clone())],
+                        },
+                    },
+                    CssKeyframeRule {
+                        keys: vec![50.0, 100.0],
+                        declarations: CssStyleDeclaration {
+                            declarations: vec![declaration("display", Keyword("none".into()))],
+                        },
+                    },
+                ],
+            }),
+        ];
+        assert_eq!(expected, parsed.css_rules);
+    }
+
+    #[test]
+    fn test_invalid_selector_drops_rule() {
+        let parsed = parse_css_stylesheet("p:unknown { color: red } .a, #b { color: blue }".into());
+
+        use cssom::*;
+        let compound =
+            |selector| ComplexSelector::CompoundSelector(CompoundSelector(vec![selector]));
+        let expected = vec![CssRule::Style(CssStyleRule {
+            selector: SelectorList {
+                selectors: vec![
+                    compound(SimpleSelector::ClassSelector("a".into())),
+                    compound(SimpleSelector::IdSelector("b".into())),
+                ],
+            },
+            declarations: CssStyleDeclaration {
+                declarations: vec![CssDeclaration {
+                    property_name: "color".into(),
+                    value: vec![super::super::value::ComponentValue::Color(
+                        super::super::value::Color::rgb(0, 0, 255),
+                    )],
+                    important: false,
+                }],
+            },
+        })];
+        assert_eq!(expected, parsed.css_rules);
+    }
+
+    #[test]
+    fn test_declarations() {
+        let style =
+            "color : red ! IMPORTANT ; ; 12px: x; width; display: block; @page { } margin: {";
+        let parsed = StyleSheetParser::new(CssTokenizer::new(style.to_string()))
+            .parse_list_of_declarations();
+
+        let ident = |s: &str| ComponentValue::PreservedToken(CssToken::Ident(s.to_string()));
+        assert_eq!(
+            parsed,
+            vec![
+                DeclarationOrAtRule::Declaration(Declaration {
+                    name: "color".into(),
+                    value: vec![ident("red")],
+                    important: true,
+                }),
+                DeclarationOrAtRule::Declaration(Declaration {
+                    name: "display".into(),
+                    value: vec![ident("block")],
+                    important: false,
+                }),
+                DeclarationOrAtRule::AtRule(AtRule {
+                    name: "page".into(),
+                    prelude: vec![ComponentValue::PreservedToken(CssToken::Whitespace)],
+                    block: Some(SimpleBlock {
+                        associated_token: CssToken::OpenCurly,
+                        value: vec![ComponentValue::PreservedToken(CssToken::Whitespace)],
+                    }),
+                }),
+                DeclarationOrAtRule::Declaration(Declaration {
+                    name: "margin".into(),
+                    value: vec![ComponentValue::SimpleBlock(SimpleBlock {
+                        associated_token: CssToken::OpenCurly,
+                        value: vec![],
+                    })],
+                    important: false,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocks_and_functions() {
+        let parsed = StyleSheetParser::new(CssTokenizer::new("f(a [b] (c)".to_string()))
+            .parse_list_of_component_values();
+        let token = ComponentValue::PreservedToken;
+        assert_eq!(
+            parsed,
+            vec![ComponentValue::Function(Function {
+                name: "f".into(),
+                value: vec![
+                    token(CssToken::Ident("a".into())),
+                    token(CssToken::Whitespace),
+                    ComponentValue::SimpleBlock(SimpleBlock {
+                        associated_token: CssToken::OpenSquare,
+                        value: vec![token(CssToken::Ident("b".into()))],
+                    }),
+                    token(CssToken::Whitespace),
+                    ComponentValue::SimpleBlock(SimpleBlock {
+                        associated_token: CssToken::OpenParenthesis,
+                        value: vec![token(CssToken::Ident("c".into()))],
+                    }),
+                ],
+            })]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_rules() {
+        // Unclosed blocks are closed at the end of the input, and a rule without a block is dropped.
+        let parsed = parse_css_stylesheet("p { color: red; } h1 ".to_string());
+        assert_eq!(parsed.css_rules.len(), 1);
+        let parsed = parse_css_stylesheet("p { color: red".to_string());
+        assert_eq!(parsed.css_rules.len(), 1);
+    }
 }
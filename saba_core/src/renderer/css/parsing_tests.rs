@@ -0,0 +1,306 @@
+//! Runs the tokenizer and the parser against test files in the format of css-parsing-tests.
+//! https://github.com/SimonSapin/css-parsing-tests
+//!
+//! Each file is a JSON array alternating an input string and its expected result.
+//! Component values are represented as in the README of css-parsing-tests, e.g.
+//! `["ident", "red"]`, `["number", "+1.5", 1.5, "number"]` or `["()", ...]`.
+
+use crate::renderer::css::parser::ComponentValue;
+use crate::renderer::css::parser::StyleSheetParser;
+use crate::renderer::css::token::CssToken;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::css::token::HashType;
+use crate::renderer::css::token::NumberType;
+use crate::renderer::css::token::Numeric;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+use core::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+}
+
+impl Json {
+    fn str(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+
+    /// Compares numbers approximately, as the values of numeric tokens are computed
+    /// from their digits and may not be rounded as the JSON numbers are.
+    fn approx_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => {
+                (a - b).abs() <= 1e-6 * a.abs().max(b.abs()).max(1.0)
+            }
+            (Self::Array(a), Self::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// The representation of a numeric token in the test files is its source text,
+    /// which the tokenizer does not keep. Only whether it has a sign is compared.
+    fn normalize_repr(self) -> Self {
+        match self {
+            Self::Array(mut items) => {
+                let is_numeric = matches!(
+                    items.first(),
+                    Some(Self::String(kind)) if ["number", "percentage", "dimension"].contains(&kind.as_str())
+                );
+                if is_numeric {
+                    if let Some(Self::String(repr)) = items.get(1) {
+                        items[1] = Self::Bool(repr.starts_with(['+', '-']));
+                    }
+                }
+                Self::Array(items.into_iter().map(Self::normalize_repr).collect())
+            }
+            other => other,
+        }
+    }
+}
+
+/// Reads the subset of JSON used by the test files: strings, numbers, booleans and arrays.
+struct JsonReader<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> JsonReader<'a> {
+    fn read(input: &'a str) -> Json {
+        let mut reader = Self {
+            chars: input.chars().peekable(),
+        };
+        let value = reader.value();
+        reader.skip_whitespace();
+        assert_eq!(reader.chars.next(), None, "trailing input in JSON");
+        value
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+    }
+
+    fn value(&mut self) -> Json {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('"') => Json::String(self.string()),
+            Some('[') => self.array(),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            c => panic!("unexpected {c:?} in JSON"),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Json {
+        for expected in keyword.chars() {
+            assert_eq!(self.chars.next(), Some(expected));
+        }
+        value
+    }
+
+    fn number(&mut self) -> Json {
+        let mut text = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_digit() || ['-', '+', '.', 'e', 'E'].contains(c))
+        {
+            text.push(c);
+        }
+        Json::Number(text.parse().expect("invalid number in JSON"))
+    }
+
+    fn array(&mut self) -> Json {
+        assert_eq!(self.chars.next(), Some('['));
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&']').is_some() {
+            return Json::Array(items);
+        }
+        loop {
+            items.push(self.value());
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => {}
+                Some(']') => return Json::Array(items),
+                c => panic!("unexpected {c:?} in JSON array"),
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        assert_eq!(self.chars.next(), Some('"'));
+        let mut s = String::new();
+        loop {
+            match self.chars.next().expect("unterminated JSON string") {
+                '"' => return s,
+                '\\' => match self.chars.next().expect("unterminated JSON escape") {
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'f' => s.push('\u{c}'),
+                    'b' => s.push('\u{8}'),
+                    'u' => {
+                        let unit = self.code_unit();
+                        let c = if (0xd800..0xdc00).contains(&unit) {
+                            assert_eq!(self.chars.next(), Some('\\'));
+                            assert_eq!(self.chars.next(), Some('u'));
+                            let low = self.code_unit();
+                            char::from_u32(0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00))
+                        } else {
+                            char::from_u32(unit)
+                        };
+                        s.push(c.expect("invalid \\u escape in JSON"));
+                    }
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn code_unit(&mut self) -> u32 {
+        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+        u32::from_str_radix(&hex, 16).expect("invalid \\u escape in JSON")
+    }
+}
+
+fn numeric(kind: &str, n: &Numeric) -> Vec<Json> {
+    let type_ = match n.type_ {
+        NumberType::Integer => "integer",
+        NumberType::Number => "number",
+    };
+    vec![
+        Json::str(kind),
+        Json::Bool(n.has_sign),
+        Json::Number(n.value),
+        Json::str(type_),
+    ]
+}
+
+fn token_to_json(token: &CssToken) -> Json {
+    match token {
+        CssToken::Hash(name, type_) => {
+            let type_ = match type_ {
+                HashType::Id => "id",
+                HashType::Unrestricted => "unrestricted",
+            };
+            Json::Array(vec![Json::str("hash"), Json::str(name), Json::str(type_)])
+        }
+        CssToken::Number(n) => Json::Array(numeric("number", n)),
+        CssToken::Percentage(n) => Json::Array(numeric("percentage", n)),
+        CssToken::Dimension(n, unit) => {
+            let mut items = numeric("dimension", n);
+            items.push(Json::str(unit));
+            Json::Array(items)
+        }
+        CssToken::Ident(name) => Json::Array(vec![Json::str("ident"), Json::str(name)]),
+        CssToken::AtKeyword(name) => Json::Array(vec![Json::str("at-keyword"), Json::str(name)]),
+        CssToken::String(s) => Json::Array(vec![Json::str("string"), Json::str(s)]),
+        CssToken::Url(url) => Json::Array(vec![Json::str("url"), Json::str(url)]),
+        CssToken::BadString => Json::Array(vec![Json::str("error"), Json::str("bad-string")]),
+        CssToken::BadUrl => Json::Array(vec![Json::str("error"), Json::str("bad-url")]),
+        CssToken::CloseParenthesis | CssToken::CloseSquare | CssToken::CloseCurly => {
+            Json::Array(vec![Json::str("error"), Json::String(token.to_string())])
+        }
+        // A function token or an opening bracket is always consumed into a function or a block.
+        CssToken::Function(_)
+        | CssToken::OpenParenthesis
+        | CssToken::OpenSquare
+        | CssToken::OpenCurly => unreachable!("{token:?} is not a preserved token"),
+        CssToken::Delim(_)
+        | CssToken::Colon
+        | CssToken::SemiColon
+        | CssToken::Comma
+        | CssToken::Whitespace
+        | CssToken::Cdo
+        | CssToken::Cdc => Json::String(token.to_string()),
+    }
+}
+
+fn component_value_to_json(value: &ComponentValue) -> Json {
+    match value {
+        ComponentValue::PreservedToken(token) => token_to_json(token),
+        ComponentValue::Function(function) => {
+            let mut items = vec![Json::str("function"), Json::str(&function.name)];
+            items.extend(function.value.iter().map(component_value_to_json));
+            Json::Array(items)
+        }
+        ComponentValue::SimpleBlock(block) => {
+            let kind = match block.associated_token {
+                CssToken::OpenCurly => "{}",
+                CssToken::OpenSquare => "[]",
+                _ => "()",
+            };
+            let mut items = vec![Json::str(kind)];
+            items.extend(block.value.iter().map(component_value_to_json));
+            Json::Array(items)
+        }
+    }
+}
+
+fn parse_list_of_component_values(input: &str) -> Json {
+    let values = StyleSheetParser::new(CssTokenizer::new(input.to_string()))
+        .parse_list_of_component_values();
+    Json::Array(values.iter().map(component_value_to_json).collect())
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#parse-component-value>
+fn parse_one_component_value(input: &str) -> Json {
+    let values = StyleSheetParser::new(CssTokenizer::new(input.to_string()))
+        .parse_list_of_component_values();
+    let mut values = values
+        .iter()
+        .filter(|value| **value != ComponentValue::PreservedToken(CssToken::Whitespace));
+    match (values.next(), values.next()) {
+        (None, _) => Json::Array(vec![Json::str("error"), Json::str("empty")]),
+        (Some(value), None) => component_value_to_json(value),
+        (Some(_), Some(_)) => Json::Array(vec![Json::str("error"), Json::str("extra-input")]),
+    }
+}
+
+fn run(file: &str, parse: fn(&str) -> Json) {
+    let Json::Array(cases) = JsonReader::read(file) else {
+        panic!("a test file must be an array");
+    };
+    assert_eq!(cases.len() % 2, 0, "an input without an expected result");
+
+    let mut failures = Vec::new();
+    for case in cases.chunks(2) {
+        let Json::String(input) = &case[0] else {
+            panic!("an input must be a string: {:?}", case[0]);
+        };
+        let expected = case[1].clone().normalize_repr();
+        let actual = parse(input);
+        if !actual.approx_eq(&expected) {
+            failures.push(format!(
+                "input: {input:?}\nexpected: {expected:?}\nactual: {actual:?}"
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+#[test]
+fn test_component_value_list() {
+    run(
+        include_str!("parsing_tests/component_value_list.json"),
+        parse_list_of_component_values,
+    );
+}
+
+#[test]
+fn test_one_component_value() {
+    run(
+        include_str!("parsing_tests/one_component_value.json"),
+        parse_one_component_value,
+    );
+}
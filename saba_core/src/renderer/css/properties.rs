@@ -0,0 +1,742 @@
+//! <https://www.w3.org/TR/css-values-4/#value-defs>
+//!
+//! The grammars of the supported properties, which are validated at parse time
+//! so that invalid declarations are dropped.
+//!
+//! Servo: <https://github.com/servo/stylo/tree/main/style/properties/longhands>
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use super::calc::{parse_math_function, CalcType};
+use super::easing::parse_easing_function;
+use super::parser::{self, trim_whitespace};
+use super::token::CssToken;
+use super::value::{
+    parse_color, parse_keyword, parse_length, parse_length_percentage, parse_number, parse_time,
+    parse_url, ComponentValue, LengthPercentage,
+};
+
+/// <https://www.w3.org/TR/css-cascade-4/#defaulting-keywords>
+pub const CSS_WIDE_KEYWORDS: [&str; 3] = ["initial", "inherit", "unset"];
+
+/// The supported longhand properties.
+pub const LONGHANDS: &[&str] = &[
+    "display",
+    "color",
+    "background-color",
+    "background-image",
+    "background-repeat",
+    "background-attachment",
+    "background-position",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "font-variant",
+    "line-height",
+    "margin-top",
+    "margin-right",
+    "margin-bottom",
+    "margin-left",
+    "padding-top",
+    "padding-right",
+    "padding-bottom",
+    "padding-left",
+    "border-top-width",
+    "border-right-width",
+    "border-bottom-width",
+    "border-left-width",
+    "border-top-style",
+    "border-right-style",
+    "border-bottom-style",
+    "border-left-style",
+    "border-top-color",
+    "border-right-color",
+    "border-bottom-color",
+    "border-left-color",
+    "top",
+    "right",
+    "bottom",
+    "left",
+    "width",
+    "height",
+    "text-align",
+    "text-decoration-line",
+    "text-decoration-style",
+    "text-decoration-color",
+    "text-transform",
+    "white-space",
+    "word-break",
+    "line-break",
+    "overflow-wrap",
+    "list-style-type",
+    "list-style-position",
+    "list-style-image",
+    "flex-grow",
+    "flex-shrink",
+    "flex-basis",
+    "transition-property",
+    "transition-duration",
+    "transition-timing-function",
+    "transition-delay",
+    "animation-name",
+    "animation-duration",
+    "animation-timing-function",
+    "animation-delay",
+    "animation-iteration-count",
+    "animation-direction",
+    "animation-fill-mode",
+    "animation-play-state",
+];
+
+/// The properties whose values are comma-separated lists, e.g. one item for each animation.
+const LIST_PROPERTIES: [&str; 12] = [
+    "transition-property",
+    "transition-duration",
+    "transition-timing-function",
+    "transition-delay",
+    "animation-name",
+    "animation-duration",
+    "animation-timing-function",
+    "animation-delay",
+    "animation-iteration-count",
+    "animation-direction",
+    "animation-fill-mode",
+    "animation-play-state",
+];
+
+/// <https://www.w3.org/TR/css-fonts-4/#generic-font-families>
+const GENERIC_FONT_FAMILIES: [&str; 5] = ["serif", "sans-serif", "cursive", "fantasy", "monospace"];
+
+/// Parses the value of a declaration against the grammar of the property.
+/// Returns None if the property is not supported or the value does not match the grammar.
+pub fn parse_declaration_value(
+    name: &str,
+    value: &[parser::ComponentValue],
+) -> Option<Vec<ComponentValue>> {
+    let name = name.to_ascii_lowercase();
+    let value = trim_whitespace(value);
+    if !LONGHANDS.contains(&name.as_str()) {
+        return None;
+    }
+
+    if let [v] = value {
+        if let Some(keyword) = parse_keyword(v) {
+            if CSS_WIDE_KEYWORDS.contains(&keyword.as_str()) {
+                return Some(vec![ComponentValue::Keyword(keyword)]);
+            }
+        }
+    }
+
+    match name.as_str() {
+        "font-family" => return parse_font_family(value),
+        "text-decoration-line" => return parse_text_decoration_line(value),
+        "background-position" => return parse_background_position(value),
+        name if LIST_PROPERTIES.contains(&name) => return parse_list(name, value),
+        _ => {}
+    }
+
+    let [value] = value else {
+        return None;
+    };
+    let value = match name.as_str() {
+        "display" => keyword(
+            value,
+            &[
+                "block",
+                "inline",
+                "none",
+                "list-item",
+                "flow-root",
+                "inline-block",
+                "table",
+                "table-caption",
+                "table-row-group",
+                "table-header-group",
+                "table-footer-group",
+                "table-row",
+                "table-cell",
+                "table-column-group",
+                "table-column",
+            ],
+        )?,
+        "color"
+        | "background-color"
+        | "border-top-color"
+        | "border-right-color"
+        | "border-bottom-color"
+        | "border-left-color"
+        | "text-decoration-color" => color(value)?,
+        "background-image" | "list-style-image" => {
+            keyword(value, &["none"]).or_else(|| Some(ComponentValue::Url(parse_url(value)?)))?
+        }
+        "background-repeat" => keyword(
+            value,
+            &[
+                "repeat",
+                "repeat-x",
+                "repeat-y",
+                "no-repeat",
+                "space",
+                "round",
+            ],
+        )?,
+        "background-attachment" => keyword(value, &["scroll", "fixed", "local"])?,
+        "font-size" => keyword(
+            value,
+            &[
+                "xx-small",
+                "x-small",
+                "small",
+                "medium",
+                "large",
+                "x-large",
+                "xx-large",
+                "xxx-large",
+                "smaller",
+                "larger",
+            ],
+        )
+        .or_else(|| non_negative_length_percentage(value))?,
+        "font-weight" => {
+            keyword(value, &["normal", "bold", "bolder", "lighter"]).or_else(|| {
+                // <https://www.w3.org/TR/css-fonts-4/#font-weight-absolute-values>
+                number(value, 1.0..=1000.0)
+            })?
+        }
+        "font-style" => keyword(value, &["normal", "italic", "oblique"])?,
+        "font-variant" => keyword(value, &["normal", "small-caps"])?,
+        "line-height" => keyword(value, &["normal"])
+            .or_else(|| number(value, 0.0..=f64::INFINITY))
+            .or_else(|| non_negative_length_percentage(value))?,
+        "margin-top" | "margin-right" | "margin-bottom" | "margin-left" => {
+            keyword(value, &["auto"]).or_else(|| length_percentage(value))?
+        }
+        "padding-top" | "padding-right" | "padding-bottom" | "padding-left" => {
+            non_negative_length_percentage(value)?
+        }
+        "border-top-width" | "border-right-width" | "border-bottom-width" | "border-left-width" => {
+            // <https://www.w3.org/TR/css-backgrounds-3/#typedef-line-width>
+            keyword(value, &["thin", "medium", "thick"]).or_else(|| non_negative_length(value))?
+        }
+        "border-top-style" | "border-right-style" | "border-bottom-style" | "border-left-style" => {
+            keyword(
+                value,
+                &[
+                    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge",
+                    "inset", "outset",
+                ],
+            )?
+        }
+        "top" | "right" | "bottom" | "left" => {
+            keyword(value, &["auto"]).or_else(|| length_percentage(value))?
+        }
+        "width" | "height" => {
+            keyword(value, &["auto"]).or_else(|| non_negative_length_percentage(value))?
+        }
+        "text-align" => keyword(
+            value,
+            &["start", "end", "left", "right", "center", "justify"],
+        )?,
+        "text-decoration-style" => {
+            keyword(value, &["solid", "double", "dotted", "dashed", "wavy"])?
+        }
+        "list-style-type" => keyword(
+            value,
+            &[
+                "disc",
+                "circle",
+                "square",
+                "decimal",
+                "decimal-leading-zero",
+                "lower-roman",
+                "upper-roman",
+                "lower-alpha",
+                "upper-alpha",
+                "lower-latin",
+                "upper-latin",
+                "none",
+            ],
+        )?,
+        "list-style-position" => keyword(value, &["inside", "outside"])?,
+        "flex-grow" | "flex-shrink" => number(value, 0.0..=f64::INFINITY)?,
+        "flex-basis" => keyword(value, &["auto", "content"])
+            .or_else(|| non_negative_length_percentage(value))?,
+        "text-transform" => keyword(value, &["none", "capitalize", "uppercase", "lowercase"])?,
+        "word-break" => keyword(value, &["normal", "break-all", "keep-all", "break-word"])?,
+        "line-break" => keyword(value, &["auto", "loose", "normal", "strict", "anywhere"])?,
+        "overflow-wrap" => keyword(value, &["normal", "break-word", "anywhere"])?,
+        "white-space" => keyword(
+            value,
+            &[
+                "normal",
+                "pre",
+                "nowrap",
+                "pre-wrap",
+                "pre-line",
+                "break-spaces",
+            ],
+        )?,
+        _ => return None,
+    };
+    Some(vec![value])
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-css-value>
+pub fn serialize_value(name: &str, value: &[ComponentValue]) -> String {
+    // <https://www.w3.org/TR/css-variables-1/#variables-in-shorthands>
+    // A pending-substitution value of a longhand serializes as the empty string.
+    if let [ComponentValue::Unparsed(unparsed)] = value {
+        if unparsed.shorthand.is_some() {
+            return String::new();
+        }
+    }
+    let name = name.to_ascii_lowercase();
+    let separator = if name == "font-family" || LIST_PROPERTIES.contains(&name.as_str()) {
+        ", "
+    } else {
+        " "
+    };
+    value
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Parses one of the keywords.
+fn keyword(value: &parser::ComponentValue, keywords: &[&str]) -> Option<ComponentValue> {
+    let keyword = parse_keyword(value)?;
+    keywords
+        .contains(&keyword.as_str())
+        .then_some(ComponentValue::Keyword(keyword))
+}
+
+/// Parses a `<color>`, including `currentcolor`.
+fn color(value: &parser::ComponentValue) -> Option<ComponentValue> {
+    keyword(value, &["currentcolor"]).or_else(|| Some(ComponentValue::Color(parse_color(value)?)))
+}
+
+/// Parses a `<length [0,∞]>`.
+///
+/// Math functions are not checked at parse time, as they are clamped at computed-value time.
+fn non_negative_length(value: &parser::ComponentValue) -> Option<ComponentValue> {
+    match parse_length(value) {
+        Some(length) => (length.value >= 0.0).then_some(ComponentValue::Length(length)),
+        None => Some(ComponentValue::Calc(parse_math_function(
+            value,
+            CalcType::Length,
+        )?)),
+    }
+}
+
+/// Parses a `<length-percentage>`.
+fn length_percentage(value: &parser::ComponentValue) -> Option<ComponentValue> {
+    match parse_length_percentage(value) {
+        Some(value) => Some(value.into()),
+        None => Some(ComponentValue::Calc(parse_math_function(
+            value,
+            CalcType::LengthPercentage,
+        )?)),
+    }
+}
+
+/// Parses a `<length-percentage [0,∞]>`.
+fn non_negative_length_percentage(value: &parser::ComponentValue) -> Option<ComponentValue> {
+    let non_negative = match parse_length_percentage(value) {
+        Some(LengthPercentage::Length(length)) => length.value >= 0.0,
+        Some(LengthPercentage::Percentage(p)) => p >= 0.0,
+        None => true,
+    };
+    non_negative.then(|| length_percentage(value))?
+}
+
+/// Parses a `<number>` in the range.
+fn number(value: &parser::ComponentValue, range: RangeInclusive<f64>) -> Option<ComponentValue> {
+    match parse_number(value) {
+        Some(n) => range.contains(&n).then_some(ComponentValue::Number(n)),
+        None => Some(ComponentValue::Calc(parse_math_function(
+            value,
+            CalcType::Number,
+        )?)),
+    }
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-family-prop>
+///
+/// Generic families are parsed as keywords, and other family names as strings.
+fn parse_font_family(value: &[parser::ComponentValue]) -> Option<Vec<ComponentValue>> {
+    let mut families = Vec::new();
+    for family in value.split(|v| *v == parser::ComponentValue::PreservedToken(CssToken::Comma)) {
+        let family = match trim_whitespace(family) {
+            [parser::ComponentValue::PreservedToken(CssToken::String(name))] => {
+                ComponentValue::String(name.clone())
+            }
+            [v] if keyword(v, &GENERIC_FONT_FAMILIES).is_some() => {
+                keyword(v, &GENERIC_FONT_FAMILIES)?
+            }
+            // <https://www.w3.org/TR/css-fonts-4/#family-name-syntax>
+            // A sequence of identifiers is joined with single spaces.
+            idents => {
+                let mut names = Vec::new();
+                for v in idents {
+                    match v {
+                        parser::ComponentValue::PreservedToken(CssToken::Ident(ident)) => {
+                            names.push(ident.as_str())
+                        }
+                        parser::ComponentValue::PreservedToken(CssToken::Whitespace) => {}
+                        _ => return None,
+                    }
+                }
+                match names.as_slice() {
+                    [] => return None,
+                    [name]
+                        if CSS_WIDE_KEYWORDS.contains(&name.to_ascii_lowercase().as_str())
+                            || name.eq_ignore_ascii_case("default") =>
+                    {
+                        return None
+                    }
+                    _ => ComponentValue::String(names.join(" ")),
+                }
+            }
+        };
+        families.push(family);
+    }
+    Some(families)
+}
+
+/// <https://www.w3.org/TR/css-backgrounds-3/#background-position>
+///
+/// Only the one- and two-value syntaxes are supported.
+fn parse_background_position(value: &[parser::ComponentValue]) -> Option<Vec<ComponentValue>> {
+    let mut positions = Vec::new();
+    for v in value {
+        if *v == parser::ComponentValue::PreservedToken(CssToken::Whitespace) {
+            continue;
+        }
+        let position = keyword(v, &["left", "center", "right", "top", "bottom"])
+            .or_else(|| length_percentage(v))?;
+        positions.push(position);
+    }
+    let is = |v: &ComponentValue, keywords: &[&str]| matches!(v, ComponentValue::Keyword(k) if keywords.contains(&k.as_str()));
+    match positions.as_slice() {
+        [_] => Some(positions),
+        [x, y] => {
+            let horizontal = |v| is(v, &["left", "right"]);
+            let vertical = |v| is(v, &["top", "bottom"]);
+            // Two keywords can be in any order, e.g. `top left`.
+            let valid = if matches!(
+                (x, y),
+                (ComponentValue::Keyword(_), ComponentValue::Keyword(_))
+            ) {
+                !((horizontal(x) && horizontal(y)) || (vertical(x) && vertical(y)))
+            } else {
+                !vertical(x) && !horizontal(y)
+            };
+            valid.then_some(positions)
+        }
+        _ => None,
+    }
+}
+
+/// Parses a comma-separated list of a property in [LIST_PROPERTIES], with an item for each component.
+fn parse_list(name: &str, value: &[parser::ComponentValue]) -> Option<Vec<ComponentValue>> {
+    let mut items = Vec::new();
+    for item in value.split(|v| *v == parser::ComponentValue::PreservedToken(CssToken::Comma)) {
+        let [item] = trim_whitespace(item) else {
+            return None;
+        };
+        items.push(list_item(name, item)?);
+    }
+    // <https://www.w3.org/TR/css-transitions-1/#transition-property-property>
+    // `none` is valid only on its own.
+    let is_none = |v: &ComponentValue| matches!(v, ComponentValue::Keyword(k) if k == "none");
+    if name == "transition-property" && items.len() > 1 && items.iter().any(is_none) {
+        return None;
+    }
+    Some(items)
+}
+
+/// Parses an item of the list of a property in [LIST_PROPERTIES].
+///
+/// <https://www.w3.org/TR/css-transitions-1/#property-index>
+/// <https://www.w3.org/TR/css-animations-1/#property-index>
+fn list_item(name: &str, value: &parser::ComponentValue) -> Option<ComponentValue> {
+    match name {
+        "transition-property" => {
+            // Unsupported properties are kept, as they may be supported by other browsers.
+            let keyword = parse_keyword(value)?;
+            (!CSS_WIDE_KEYWORDS.contains(&keyword.as_str()) && keyword != "default")
+                .then_some(ComponentValue::Keyword(keyword))
+        }
+        "transition-duration" | "animation-duration" => {
+            let time = parse_time(value)?;
+            (time >= 0.0).then_some(ComponentValue::Time(time))
+        }
+        "transition-delay" | "animation-delay" => Some(ComponentValue::Time(parse_time(value)?)),
+        "transition-timing-function" | "animation-timing-function" => {
+            Some(ComponentValue::Easing(parse_easing_function(value)?))
+        }
+        "animation-name" => {
+            // <https://www.w3.org/TR/css-animations-1/#typedef-keyframes-name>
+            match value {
+                parser::ComponentValue::PreservedToken(CssToken::Ident(ident)) => {
+                    let keyword = ident.to_ascii_lowercase();
+                    if keyword == "none" {
+                        Some(ComponentValue::Keyword(keyword))
+                    } else if CSS_WIDE_KEYWORDS.contains(&keyword.as_str()) || keyword == "default"
+                    {
+                        None
+                    } else {
+                        Some(ComponentValue::CustomIdent(ident.clone()))
+                    }
+                }
+                parser::ComponentValue::PreservedToken(CssToken::String(name)) => {
+                    Some(ComponentValue::String(name.clone()))
+                }
+                _ => None,
+            }
+        }
+        "animation-iteration-count" => {
+            keyword(value, &["infinite"]).or_else(|| match parse_number(value) {
+                Some(n) if n >= 0.0 => Some(ComponentValue::Number(n)),
+                _ => None,
+            })
+        }
+        "animation-direction" => keyword(
+            value,
+            &["normal", "reverse", "alternate", "alternate-reverse"],
+        ),
+        "animation-fill-mode" => keyword(value, &["none", "forwards", "backwards", "both"]),
+        "animation-play-state" => keyword(value, &["running", "paused"]),
+        _ => None,
+    }
+}
+
+/// <https://www.w3.org/TR/css-text-decor-3/#text-decoration-line-property>
+fn parse_text_decoration_line(value: &[parser::ComponentValue]) -> Option<Vec<ComponentValue>> {
+    let values: Vec<_> = value
+        .iter()
+        .filter(|v| **v != parser::ComponentValue::PreservedToken(CssToken::Whitespace))
+        .collect();
+    if let [v] = values.as_slice() {
+        if let Some(none) = keyword(v, &["none"]) {
+            return Some(vec![none]);
+        }
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for v in values {
+        let line = parse_keyword(v)?;
+        if !["underline", "overline", "line-through"].contains(&line.as_str())
+            || lines.contains(&line)
+        {
+            return None;
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    Some(lines.into_iter().map(ComponentValue::Keyword).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::super::parser::StyleSheetParser;
+    use super::super::token::CssTokenizer;
+    use super::super::value::{Color, Length, LengthUnit};
+    use super::*;
+
+    fn parse(name: &str, value: &str) -> Option<Vec<ComponentValue>> {
+        let value = StyleSheetParser::new(CssTokenizer::new(value.to_string()))
+            .parse_list_of_component_values();
+        parse_declaration_value(name, &value)
+    }
+
+    #[test]
+    fn test_lengths() {
+        assert_eq!(
+            parse("margin-top", "1.5em"),
+            Some(vec![ComponentValue::Length(Length {
+                value: 1.5,
+                unit: LengthUnit::Em
+            })])
+        );
+        assert_eq!(
+            parse("margin-top", "-10PX"),
+            Some(vec![ComponentValue::Length(Length::px(-10.0))])
+        );
+        assert_eq!(
+            parse("width", "0"),
+            Some(vec![ComponentValue::Length(Length::px(0.0))])
+        );
+        assert_eq!(
+            parse("height", "50%"),
+            Some(vec![ComponentValue::Percentage(50.0)])
+        );
+        assert_eq!(
+            parse("font-size", "2rem"),
+            Some(vec![ComponentValue::Length(Length {
+                value: 2.0,
+                unit: LengthUnit::Rem
+            })])
+        );
+        // Negative values, unitless non-zero lengths and unknown units are invalid.
+        assert_eq!(parse("padding-left", "-1px"), None);
+        assert_eq!(parse("width", "10"), None);
+        assert_eq!(parse("width", "10foo"), None);
+        assert_eq!(parse("border-top-width", "10%"), None);
+    }
+
+    #[test]
+    fn test_colors() {
+        let color = |r, g, b, a| Some(vec![ComponentValue::Color(Color { r, g, b, a })]);
+        assert_eq!(parse("color", "RebeccaPurple"), color(102, 51, 153, 255));
+        assert_eq!(parse("color", "#f80"), color(255, 136, 0, 255));
+        assert_eq!(parse("color", "#ff880080"), color(255, 136, 0, 128));
+        assert_eq!(parse("color", "rgb(255, 0, 128)"), color(255, 0, 128, 255));
+        assert_eq!(
+            parse("color", "rgba(100%, 0%, 0%, 0.5)"),
+            color(255, 0, 0, 128)
+        );
+        assert_eq!(parse("color", "rgb(0 255 0 / 25%)"), color(0, 255, 0, 64));
+        assert_eq!(parse("color", "hsl(120, 100%, 25%)"), color(0, 128, 0, 255));
+        assert_eq!(
+            parse("color", "hsla(0.5turn 100% 50% / 1)"),
+            color(0, 255, 255, 255)
+        );
+        assert_eq!(
+            parse("border-left-color", "currentColor"),
+            Some(vec![ComponentValue::Keyword("currentcolor".into())])
+        );
+        assert_eq!(parse("color", "#ff"), None);
+        assert_eq!(parse("color", "rgb(1, 2)"), None);
+        assert_eq!(parse("color", "rgb(1, 2 3)"), None);
+        assert_eq!(parse("color", "foo"), None);
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(
+            parse("DISPLAY", " Block "),
+            Some(vec![ComponentValue::Keyword("block".into())])
+        );
+        assert_eq!(
+            parse("width", "inherit"),
+            Some(vec![ComponentValue::Keyword("inherit".into())])
+        );
+        assert_eq!(parse("display", "foo"), None);
+        assert_eq!(parse("display", "block inline"), None);
+        assert_eq!(parse("unknown", "block"), None);
+        assert_eq!(parse("unknown", "inherit"), None);
+        assert_eq!(
+            parse("text-decoration-line", "underline line-through"),
+            Some(vec![
+                ComponentValue::Keyword("underline".into()),
+                ComponentValue::Keyword("line-through".into()),
+            ])
+        );
+        assert_eq!(parse("text-decoration-line", "underline underline"), None);
+        assert_eq!(parse("text-decoration-line", "none underline"), None);
+        assert_eq!(
+            parse("font-weight", "550"),
+            Some(vec![ComponentValue::Number(550.0)])
+        );
+        assert_eq!(parse("font-weight", "1001"), None);
+        assert_eq!(
+            parse("overflow-wrap", "Anywhere"),
+            Some(vec![ComponentValue::Keyword("anywhere".into())])
+        );
+        assert_eq!(parse("overflow-wrap", "break-all"), None);
+    }
+
+    #[test]
+    fn test_math_functions() {
+        let serialize =
+            |name: &str, value: &str| parse(name, value).map(|value| serialize_value(name, &value));
+        assert_eq!(
+            serialize("width", "calc(100% - 2 * 1em)").as_deref(),
+            Some("calc(100% - 2em)")
+        );
+        // Negative values are clamped at computed-value time.
+        assert_eq!(
+            serialize("padding-top", "calc(1px - 2px)").as_deref(),
+            Some("calc(-1px)")
+        );
+        assert_eq!(
+            serialize("border-top-width", "max(1px, 1em)").as_deref(),
+            Some("max(1px, 1em)")
+        );
+        assert_eq!(serialize("border-top-width", "calc(10%)"), None);
+        assert_eq!(
+            serialize("flex-grow", "calc(1 + 1)").as_deref(),
+            Some("calc(2)")
+        );
+        assert_eq!(serialize("flex-grow", "calc(1px)"), None);
+        assert_eq!(
+            serialize("margin-top", "MIN(10%, 5px)").as_deref(),
+            Some("min(10%, 5px)")
+        );
+    }
+
+    #[test]
+    fn test_font_family() {
+        assert_eq!(
+            parse("font-family", r#"Times New  Roman, "Noto Sans", serif"#),
+            Some(vec![
+                ComponentValue::String("Times New Roman".into()),
+                ComponentValue::String("Noto Sans".into()),
+                ComponentValue::Keyword("serif".into()),
+            ])
+        );
+        assert_eq!(parse("font-family", "foo,"), None);
+        assert_eq!(parse("font-family", "foo, initial"), None);
+        assert_eq!(parse("font-family", "10px"), None);
+    }
+
+    #[test]
+    fn test_lists() {
+        let serialize =
+            |name: &str, value: &str| parse(name, value).map(|value| serialize_value(name, &value));
+        assert_eq!(
+            serialize("transition-property", "Opacity, all, color").as_deref(),
+            Some("opacity, all, color")
+        );
+        assert_eq!(serialize("transition-property", "none, color"), None);
+        assert_eq!(serialize("transition-property", "color, initial"), None);
+        assert_eq!(
+            serialize("transition-duration", "1s, 250ms").as_deref(),
+            Some("1s, 0.25s")
+        );
+        assert_eq!(serialize("transition-duration", "-1s"), None);
+        assert_eq!(serialize("transition-duration", "1s,"), None);
+        assert_eq!(
+            serialize("animation-delay", "-500ms").as_deref(),
+            Some("-0.5s")
+        );
+        assert_eq!(
+            serialize("animation-timing-function", "ease, steps(2, start)").as_deref(),
+            Some("ease, steps(2, jump-start)")
+        );
+        assert_eq!(
+            parse("animation-name", r#"Slide, none, "fade in""#),
+            Some(vec![
+                ComponentValue::CustomIdent("Slide".into()),
+                ComponentValue::Keyword("none".into()),
+                ComponentValue::String("fade in".into()),
+            ])
+        );
+        assert_eq!(
+            serialize("animation-iteration-count", "infinite, 2.5").as_deref(),
+            Some("infinite, 2.5")
+        );
+        assert_eq!(serialize("animation-iteration-count", "-1"), None);
+        assert_eq!(serialize("animation-direction", "alternate foo"), None);
+    }
+}
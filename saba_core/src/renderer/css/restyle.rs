@@ -0,0 +1,435 @@
+//! Incremental restyle: the styles computed for the nodes are cached, and invalidated by DOM mutations.
+//!
+//! Servo:
+//! - <https://github.com/servo/stylo/blob/main/style/data.rs>
+//! - <https://github.com/servo/stylo/tree/main/style/invalidation/element>
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::rc::{Rc, Weak};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ops::BitOr;
+
+use crate::renderer::dom::mutation_observer::{
+    MutationCallback, MutationObserver, MutationObserverInit, MutationRecord, MutationRecordType,
+};
+use crate::renderer::dom::node::{Node, NodeData, Window};
+use crate::renderer::layout::computed_style::{ComputeContext, ComputedStyle};
+
+use super::animation::ElementAnimations;
+use super::cssom::{
+    ComplexSelector, CompoundSelector, CssDeclaration, PseudoClass, SelectorList, SimpleSelector,
+};
+
+/// What the selectors in a stylist depend on, which decides the nodes a mutation restyles.
+///
+/// Servo: <https://github.com/servo/stylo/blob/main/style/invalidation/element/invalidation_map.rs>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvalidationMap {
+    classes: BTreeSet<String>,
+    ids: BTreeSet<String>,
+    /// The names of the attributes in attribute selectors, in lowercase.
+    attributes: BTreeSet<String>,
+    /// Whether any selector depends on the siblings or the children of an element,
+    /// i.e. sibling combinators and structural pseudo-classes such as `:first-child` and `:empty`.
+    structural: bool,
+    /// Whether any selector has `:has()`, which depends on the descendants of an element.
+    relative: bool,
+}
+
+impl InvalidationMap {
+    pub fn note_selector(&mut self, selector: &ComplexSelector) {
+        match selector {
+            ComplexSelector::CompoundSelector(compound) => self.note_compound(compound),
+            ComplexSelector::Combination(left, combinator, right) => {
+                use super::cssom::Combinator::*;
+                if matches!(combinator, NextSibling | SubsequentSibling) {
+                    self.structural = true;
+                }
+                self.note_selector(left);
+                self.note_compound(right);
+            }
+        }
+    }
+
+    fn note_selector_list(&mut self, list: &SelectorList) {
+        for selector in &list.selectors {
+            self.note_selector(selector);
+        }
+    }
+
+    fn note_compound(&mut self, compound: &CompoundSelector) {
+        for simple in &compound.0 {
+            match simple {
+                SimpleSelector::ClassSelector(class) => {
+                    self.classes.insert(class.clone());
+                }
+                SimpleSelector::IdSelector(id) => {
+                    self.ids.insert(id.clone());
+                }
+                SimpleSelector::AttributeSelector(attribute) => {
+                    self.attributes.insert(attribute.name.clone());
+                }
+                SimpleSelector::PseudoClass(pseudo_class) => self.note_pseudo_class(pseudo_class),
+                _ => {}
+            }
+        }
+    }
+
+    fn note_pseudo_class(&mut self, pseudo_class: &PseudoClass) {
+        use PseudoClass::*;
+        match pseudo_class {
+            Empty | FirstChild | LastChild | OnlyChild | FirstOfType | LastOfType | OnlyOfType
+            | NthOfType(_) | NthLastOfType(_) => self.structural = true,
+            NthChild(_, of) | NthLastChild(_, of) => {
+                self.structural = true;
+                if let Some(of) = of {
+                    self.note_selector_list(of);
+                }
+            }
+            Not(list) | Is(list) | Where(list) => self.note_selector_list(list),
+            Has(_) => self.relative = true,
+            // Whether an element is a link depends on `href`.
+            Link | Visited | AnyLink => {
+                self.attributes.insert("href".into());
+            }
+            Root | Hover | Active | Focus => {}
+        }
+    }
+
+    /// Whether changing the attribute from the old value to the new one may change
+    /// which selectors match.
+    fn depends_on_attribute(&self, name: &str, old: Option<&str>, new: Option<&str>) -> bool {
+        match name {
+            "class" => {
+                let old = old.unwrap_or("").split_ascii_whitespace();
+                let new = new.unwrap_or("").split_ascii_whitespace();
+                // A class in either value but not both.
+                old.clone()
+                    .filter(|c| !new.clone().any(|n| n == *c))
+                    .chain(new.clone().filter(|c| !old.clone().any(|o| o == *c)))
+                    .any(|c| self.classes.contains(c))
+            }
+            "id" => [old, new]
+                .into_iter()
+                .flatten()
+                .any(|id| self.ids.contains(id)),
+            _ => self.attributes.contains(name),
+        }
+    }
+}
+
+/// Which nodes need their selectors matched again.
+///
+/// Servo: <https://github.com/servo/stylo/blob/main/style/invalidation/element/restyle_hints.rs>
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestyleHint(u8);
+
+impl RestyleHint {
+    pub const RESTYLE_SELF: Self = Self(0x1);
+    pub const RESTYLE_DESCENDANTS: Self = Self(0x2);
+    /// The computed values need computing again without matching the selectors,
+    /// e.g. to sample the running animations at the current time.
+    pub const RECASCADE_SELF: Self = Self(0x4);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for RestyleHint {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// The style data of a node, cached between restyles.
+#[derive(Debug, Clone)]
+pub(crate) struct NodeStyleData {
+    /// Keeps the address of the node, which is the key in the cache, from being reused.
+    node: Weak<RefCell<Node>>,
+    /// The declarations matched for the node, which are empty for text.
+    /// None if they have not been matched since invalidated.
+    pub(crate) declarations: Option<Vec<CssDeclaration>>,
+    pub(crate) style: Option<ComputedStyle>,
+    pub(crate) hint: RestyleHint,
+    /// The transitions and animations running on the node.
+    pub(crate) animations: ElementAnimations,
+}
+
+/// The styles of the nodes of a document, which are matched and computed only if invalidated.
+#[derive(Debug, Clone, Default)]
+pub struct StyleCache {
+    /// Keyed by the address of the node.
+    entries: BTreeMap<usize, NodeStyleData>,
+    /// The context the styles were computed in. If it changes, all the styles are computed again.
+    pub(crate) compute_context: Option<ComputeContext>,
+    /// The number of nodes whose selectors were matched in the last restyle.
+    pub(crate) matched: usize,
+    /// The time the animations are sampled at, in milliseconds.
+    pub(crate) current_time: f64,
+}
+
+impl StyleCache {
+    pub(crate) fn entry(&mut self, node: &Rc<RefCell<Node>>) -> &mut NodeStyleData {
+        self.entries
+            .entry(Rc::as_ptr(node) as usize)
+            .or_insert_with(|| NodeStyleData {
+                node: Rc::downgrade(node),
+                declarations: None,
+                style: None,
+                hint: RestyleHint::default(),
+                animations: ElementAnimations::default(),
+            })
+    }
+
+    /// Advances the animations to the time, marking the nodes with running animations
+    /// to be restyled. Returns whether any node is marked.
+    pub fn update_animations(&mut self, now: f64) -> bool {
+        self.current_time = now;
+        let mut marked = false;
+        for data in self.entries.values_mut() {
+            if data.animations.is_active() {
+                data.hint = data.hint | RestyleHint::RECASCADE_SELF;
+                marked = true;
+            }
+        }
+        marked
+    }
+
+    /// Whether any node has transitions or animations running, so that another frame is needed.
+    pub fn is_animating(&self) -> bool {
+        self.entries
+            .values()
+            .any(|data| data.animations.is_active())
+    }
+
+    /// The hint the node is marked with.
+    pub fn hint(&self, node: &Rc<RefCell<Node>>) -> RestyleHint {
+        self.entries
+            .get(&(Rc::as_ptr(node) as usize))
+            .map_or(RestyleHint::default(), |data| data.hint)
+    }
+
+    /// The number of nodes whose selectors were matched in the last restyle.
+    pub fn matched_count(&self) -> usize {
+        self.matched
+    }
+
+    fn mark(&mut self, node: &Rc<RefCell<Node>>, hint: RestyleHint) {
+        let data = self.entry(node);
+        data.hint = data.hint | hint;
+    }
+
+    /// Drops the declarations of all the nodes, e.g. when the stylesheets change,
+    /// so that all the selectors are matched again.
+    ///
+    /// The running animations are kept, as they continue if the new styles still have them.
+    pub fn invalidate_all(&mut self) {
+        for data in self.entries.values_mut() {
+            data.declarations = None;
+            data.hint = RestyleHint::default();
+        }
+        self.compute_context = None;
+    }
+
+    /// Drops the data of the nodes which no longer exist.
+    pub(crate) fn prune(&mut self) {
+        self.entries.retain(|_, data| data.node.strong_count() > 0);
+    }
+
+    /// Observes the mutations of the document of the window which [`Self::invalidate`] takes.
+    pub fn observe(
+        window: &Rc<RefCell<Window>>,
+        callback: MutationCallback,
+    ) -> Rc<MutationObserver> {
+        let observer = MutationObserver::new(window, callback);
+        let document = window.borrow().document();
+        observer
+            .observe(
+                &document,
+                MutationObserverInit {
+                    child_list: true,
+                    attributes: Some(true),
+                    character_data: Some(true),
+                    subtree: true,
+                    attribute_old_value: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("the options are valid");
+        observer
+    }
+
+    /// Marks the nodes whose styles the mutations may change.
+    ///
+    /// Only the selectors are matched again for the marked nodes.
+    /// Whether the computed values of the descendants need updating is decided during the restyle.
+    pub fn invalidate(&mut self, records: &[MutationRecord], map: &InvalidationMap) {
+        if map.relative && !records.is_empty() {
+            // A mutation anywhere may change the ancestors matching `:has()`.
+            self.invalidate_all();
+            return;
+        }
+        let subtree = RestyleHint::RESTYLE_SELF | RestyleHint::RESTYLE_DESCENDANTS;
+        for record in records {
+            match record.type_ {
+                MutationRecordType::Attributes => {
+                    let name = record.attribute_name.as_deref().unwrap_or("");
+                    let new = match &record.target.borrow().data {
+                        NodeData::Element(e) => e.get_attribute(name).map(|v| v.to_string()),
+                        _ => None,
+                    };
+                    if name == "style" {
+                        // The descendants only inherit from it.
+                        self.mark(&record.target, RestyleHint::RESTYLE_SELF);
+                    } else if map.depends_on_attribute(
+                        name,
+                        record.old_value.as_deref(),
+                        new.as_deref(),
+                    ) {
+                        // The descendants and the siblings may match by combinators.
+                        self.mark(&record.target, subtree);
+                        let parent = record.target.borrow().parent.upgrade();
+                        if let (true, Some(parent)) = (map.structural, parent) {
+                            self.mark_children(&parent, subtree);
+                        }
+                    }
+                }
+                MutationRecordType::CharacterData => {
+                    // Only `:empty` depends on the text.
+                    let parent = record.target.borrow().parent.upgrade();
+                    if let (true, Some(parent)) = (map.structural, parent) {
+                        self.mark(&parent, RestyleHint::RESTYLE_SELF);
+                    }
+                }
+                MutationRecordType::ChildList => {
+                    // The inserted nodes may have been styled elsewhere in the tree.
+                    for node in &record.added_nodes {
+                        self.mark(node, subtree);
+                    }
+                    if map.structural {
+                        self.mark(&record.target, RestyleHint::RESTYLE_SELF);
+                        self.mark_children(&record.target, subtree);
+                    }
+                }
+            }
+        }
+    }
+
+    fn mark_children(&mut self, parent: &Rc<RefCell<Node>>, hint: RestyleHint) {
+        let children = parent.borrow().children().collect::<Vec<_>>();
+        for child in children {
+            self.mark(&child, hint);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::css::parser::parse_css_stylesheet;
+    use crate::renderer::dom::node::{ElementKind, Namespace};
+    use crate::renderer::dom::test_util::{element, parse};
+
+    use super::*;
+
+    fn invalidation_map(css: &str) -> InvalidationMap {
+        let mut map = InvalidationMap::default();
+        for rule in parse_css_stylesheet(css.into()).css_rules {
+            if let super::super::cssom::CssRule::Style(rule) = rule {
+                for selector in &rule.selector.selectors {
+                    map.note_selector(selector);
+                }
+            }
+        }
+        map
+    }
+
+    /// Parses the HTML with an observer of the whole document, styling every element once.
+    fn setup(html: &str) -> (Rc<RefCell<Window>>, Rc<MutationObserver>, StyleCache) {
+        let window = parse(html);
+        let observer = StyleCache::observe(&window, alloc::boxed::Box::new(|_, _| {}));
+        let mut cache = StyleCache::default();
+        for node in Node::descendants(window.borrow().document()) {
+            cache.entry(&node);
+        }
+        (window, observer, cache)
+    }
+
+    #[test]
+    fn test_attribute_dependencies() {
+        let map = invalidation_map(".a, #b, [data-x], :not(.c) a:any-link { color: red }");
+        assert!(map.depends_on_attribute("class", None, Some("x a")));
+        assert!(map.depends_on_attribute("class", Some("a c"), Some("a")));
+        assert!(!map.depends_on_attribute("class", Some("a b"), Some("b a")));
+        assert!(map.depends_on_attribute("id", Some("b"), None));
+        assert!(!map.depends_on_attribute("id", Some("c"), Some("d")));
+        assert!(map.depends_on_attribute("data-x", None, Some("")));
+        assert!(map.depends_on_attribute("href", None, Some("/")));
+        assert!(!map.depends_on_attribute("title", None, Some("t")));
+        assert!(!map.structural);
+        assert!(invalidation_map(":first-child {}").structural);
+        assert!(invalidation_map("a + b {}").structural);
+        assert!(invalidation_map(":has(a) {}").relative);
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let subtree = RestyleHint::RESTYLE_SELF | RestyleHint::RESTYLE_DESCENDANTS;
+        let (window, observer, mut cache) =
+            setup("<html><head></head><body><p>a</p><h1><a>b</a></h1></body></html>");
+        let (p, h1, a) = (
+            element(&window, ElementKind::P),
+            element(&window, ElementKind::H1),
+            element(&window, ElementKind::A),
+        );
+        let map = invalidation_map(".x a { color: red } .y { color: blue }");
+
+        Node::set_attribute(Rc::clone(&h1), "class", "x").unwrap();
+        Node::set_attribute(Rc::clone(&p), "class", "z").unwrap();
+        Node::set_attribute(Rc::clone(&a), "style", "color: green").unwrap();
+        cache.invalidate(&observer.take_records(), &map);
+        assert_eq!(cache.hint(&h1), subtree);
+        assert!(cache.hint(&p).is_empty());
+        assert_eq!(cache.hint(&a), RestyleHint::RESTYLE_SELF);
+
+        let (window, observer, mut cache) =
+            setup("<html><head></head><body><p>a</p><h1><a>b</a></h1></body></html>");
+        let (p, h1) = (
+            element(&window, ElementKind::P),
+            element(&window, ElementKind::H1),
+        );
+        let document = window.borrow().document();
+        let h2 = Node::create_element(document, "h2", Namespace::Html);
+        Node::append_child(Rc::clone(&h1), Rc::clone(&h2));
+        cache.invalidate(&observer.take_records(), &map);
+        assert_eq!(cache.hint(&h2), subtree);
+        assert!(cache.hint(&h1).is_empty());
+        assert!(cache.hint(&p).is_empty());
+
+        // Structural selectors restyle the siblings.
+        Node::set_attribute(Rc::clone(&p), "class", "y").unwrap();
+        Node::remove(Rc::clone(&h2));
+        cache.invalidate(
+            &observer.take_records(),
+            &invalidation_map("p ~ .y, :empty {}"),
+        );
+        assert_eq!(cache.hint(&p), subtree);
+        assert_eq!(cache.hint(&h1), subtree);
+
+        Node::set_attribute(Rc::clone(&p), "class", "x").unwrap();
+        cache.invalidate(&observer.take_records(), &invalidation_map(":has(.x) {}"));
+        assert!(cache.hint(&p).is_empty());
+        assert!(cache
+            .entries
+            .values()
+            .all(|data| data.declarations.is_none()));
+    }
+}
@@ -0,0 +1,808 @@
+//! <https://www.w3.org/TR/selectors-4/#grammar>
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use super::cssom::{
+    AnPlusB, AttributeMatcher, AttributeSelector, Combinator, ComplexSelector, CompoundSelector,
+    PseudoClass, PseudoElement, RelativeSelector, SelectorList, SimpleSelector,
+};
+use super::parser::{ComponentValue, Function, SimpleBlock};
+use super::token::{CssToken, HashType, Numeric};
+
+/// <https://www.w3.org/TR/selectors-4/#parse-a-selector>
+///
+/// Returns None if the selector list is invalid, in which case the style rule is dropped.
+pub fn parse_selector_list(values: &[ComponentValue]) -> Option<SelectorList> {
+    let selectors = split_by_comma(values)
+        .map(parse_complex_selector)
+        .collect::<Option<Vec<_>>>()?;
+    Some(SelectorList { selectors })
+}
+
+/// <https://www.w3.org/TR/selectors-4/#typedef-forgiving-selector-list>
+///
+/// Invalid selectors are dropped instead of making the whole list invalid.
+fn parse_forgiving_selector_list(values: &[ComponentValue]) -> SelectorList {
+    let selectors = split_by_comma(values)
+        .filter_map(parse_complex_selector)
+        .collect();
+    SelectorList { selectors }
+}
+
+/// <https://www.w3.org/TR/selectors-4/#typedef-relative-selector-list>
+fn parse_relative_selector_list(values: &[ComponentValue]) -> Option<Vec<RelativeSelector>> {
+    split_by_comma(values)
+        .map(|values| {
+            let mut parser = SelectorParser::new(values);
+            parser.skip_whitespace();
+            let combinator = parser
+                .consume_combinator()
+                .unwrap_or(Combinator::Descendant);
+            let selector = parser.consume_complex_selector()?;
+            Some(RelativeSelector {
+                combinator,
+                selector,
+            })
+        })
+        .collect()
+}
+
+fn parse_complex_selector(values: &[ComponentValue]) -> Option<ComplexSelector> {
+    SelectorParser::new(values).consume_complex_selector()
+}
+
+fn split_by_comma(values: &[ComponentValue]) -> impl Iterator<Item = &[ComponentValue]> {
+    values.split(|v| *v == ComponentValue::PreservedToken(CssToken::Comma))
+}
+
+struct SelectorParser<'a> {
+    values: &'a [ComponentValue],
+    pos: usize,
+}
+
+impl<'a> SelectorParser<'a> {
+    fn new(values: &'a [ComponentValue]) -> Self {
+        Self { values, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&'a ComponentValue> {
+        self.values.get(self.pos)
+    }
+
+    fn peek_token(&self) -> Option<&'a CssToken> {
+        match self.peek()? {
+            ComponentValue::PreservedToken(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    fn consume(&mut self) -> Option<&'a ComponentValue> {
+        let v = self.values.get(self.pos)?;
+        self.pos += 1;
+        Some(v)
+    }
+
+    /// Returns whether any whitespace was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek_token() == Some(&CssToken::Whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    /// <https://www.w3.org/TR/selectors-4/#typedef-complex-selector>
+    ///
+    /// Consumes all the remaining values.
+    fn consume_complex_selector(&mut self) -> Option<ComplexSelector> {
+        self.skip_whitespace();
+        let mut selector = ComplexSelector::CompoundSelector(self.consume_compound_selector()?);
+
+        loop {
+            let has_whitespace = self.skip_whitespace();
+            if self.peek().is_none() {
+                return Some(selector);
+            }
+            let combinator = match self.consume_combinator() {
+                Some(combinator) => combinator,
+                None if has_whitespace => Combinator::Descendant,
+                None => return None,
+            };
+            self.skip_whitespace();
+            let compound = self.consume_compound_selector()?;
+            selector = ComplexSelector::Combination(Box::new(selector), combinator, compound);
+        }
+    }
+
+    /// <https://www.w3.org/TR/selectors-4/#typedef-combinator>
+    ///
+    /// The descendant combinator, i.e. whitespace, is not consumed here.
+    fn consume_combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.peek_token()? {
+            CssToken::Delim('>') => Combinator::Child,
+            CssToken::Delim('+') => Combinator::NextSibling,
+            CssToken::Delim('~') => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.pos += 1;
+        self.skip_whitespace();
+        Some(combinator)
+    }
+
+    /// <https://www.w3.org/TR/selectors-4/#typedef-compound-selector>
+    ///
+    /// Returns None if the compound selector is empty or invalid.
+    fn consume_compound_selector(&mut self) -> Option<CompoundSelector> {
+        let mut selectors = Vec::new();
+
+        // <https://www.w3.org/TR/selectors-4/#typedef-type-selector>
+        // Namespace prefixes are not supported.
+        match self.peek_token() {
+            Some(CssToken::Ident(name)) => {
+                self.pos += 1;
+                selectors.push(SimpleSelector::TypeSelector(name.to_ascii_lowercase()));
+            }
+            Some(CssToken::Delim('*')) => {
+                self.pos += 1;
+                selectors.push(SimpleSelector::UniversalSelector);
+            }
+            _ => {}
+        }
+
+        let mut has_pseudo_element = false;
+        loop {
+            let selector = match self.peek() {
+                Some(ComponentValue::PreservedToken(CssToken::Hash(id, HashType::Id))) => {
+                    self.pos += 1;
+                    SimpleSelector::IdSelector(id.clone())
+                }
+                Some(ComponentValue::PreservedToken(CssToken::Delim('.'))) => {
+                    self.pos += 1;
+                    match self.consume()? {
+                        ComponentValue::PreservedToken(CssToken::Ident(class)) => {
+                            SimpleSelector::ClassSelector(class.clone())
+                        }
+                        _ => return None,
+                    }
+                }
+                Some(ComponentValue::SimpleBlock(block))
+                    if block.associated_token == CssToken::OpenSquare =>
+                {
+                    self.pos += 1;
+                    SimpleSelector::AttributeSelector(parse_attribute_selector(block)?)
+                }
+                Some(ComponentValue::PreservedToken(CssToken::Colon)) => {
+                    self.pos += 1;
+                    self.consume_pseudo()?
+                }
+                Some(ComponentValue::PreservedToken(CssToken::Hash(_, HashType::Unrestricted))) => {
+                    return None
+                }
+                _ => break,
+            };
+
+            // Only user action pseudo-classes may follow a pseudo-element, which we don't support.
+            if has_pseudo_element {
+                return None;
+            }
+            has_pseudo_element = matches!(selector, SimpleSelector::PseudoElement(_));
+            selectors.push(selector);
+        }
+
+        if selectors.is_empty() {
+            None
+        } else {
+            Some(CompoundSelector(selectors))
+        }
+    }
+
+    /// Consumes a pseudo-class or a pseudo-element after the first colon.
+    ///
+    /// <https://www.w3.org/TR/selectors-4/#typedef-pseudo-class-selector>
+    /// <https://www.w3.org/TR/selectors-4/#typedef-pseudo-element-selector>
+    fn consume_pseudo(&mut self) -> Option<SimpleSelector> {
+        match self.consume()? {
+            ComponentValue::PreservedToken(CssToken::Colon) => match self.consume()? {
+                ComponentValue::PreservedToken(CssToken::Ident(name)) => {
+                    parse_pseudo_element(name).map(SimpleSelector::PseudoElement)
+                }
+                _ => None,
+            },
+            ComponentValue::PreservedToken(CssToken::Ident(name)) => {
+                let name = name.to_ascii_lowercase();
+                // <https://www.w3.org/TR/selectors-4/#pseudo-element-syntax>
+                // The legacy single-colon syntax for CSS 2 pseudo-elements.
+                if let "before" | "after" | "first-line" | "first-letter" = name.as_str() {
+                    return parse_pseudo_element(&name).map(SimpleSelector::PseudoElement);
+                }
+                let pseudo_class = match name.as_str() {
+                    "root" => PseudoClass::Root,
+                    "empty" => PseudoClass::Empty,
+                    "first-child" => PseudoClass::FirstChild,
+                    "last-child" => PseudoClass::LastChild,
+                    "only-child" => PseudoClass::OnlyChild,
+                    "first-of-type" => PseudoClass::FirstOfType,
+                    "last-of-type" => PseudoClass::LastOfType,
+                    "only-of-type" => PseudoClass::OnlyOfType,
+                    "link" => PseudoClass::Link,
+                    "visited" => PseudoClass::Visited,
+                    "any-link" => PseudoClass::AnyLink,
+                    "hover" => PseudoClass::Hover,
+                    "active" => PseudoClass::Active,
+                    "focus" => PseudoClass::Focus,
+                    _ => return None,
+                };
+                Some(SimpleSelector::PseudoClass(pseudo_class))
+            }
+            ComponentValue::Function(function) => {
+                parse_functional_pseudo_class(function).map(SimpleSelector::PseudoClass)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/selectors-4/#typedef-attribute-selector>
+fn parse_attribute_selector(block: &SimpleBlock) -> Option<AttributeSelector> {
+    let mut parser = SelectorParser::new(&block.value);
+    parser.skip_whitespace();
+
+    let name = match parser.consume()? {
+        ComponentValue::PreservedToken(CssToken::Ident(name)) => name.to_ascii_lowercase(),
+        _ => return None,
+    };
+    parser.skip_whitespace();
+
+    let matcher = match parser.consume() {
+        None => {
+            return Some(AttributeSelector {
+                name,
+                matcher: None,
+                case_insensitive: false,
+            })
+        }
+        Some(ComponentValue::PreservedToken(CssToken::Delim(c))) => *c,
+        _ => return None,
+    };
+    if matcher != '=' {
+        match parser.consume()? {
+            ComponentValue::PreservedToken(CssToken::Delim('=')) => {}
+            _ => return None,
+        }
+    }
+    parser.skip_whitespace();
+
+    let value = match parser.consume()? {
+        ComponentValue::PreservedToken(CssToken::Ident(value) | CssToken::String(value)) => {
+            value.clone()
+        }
+        _ => return None,
+    };
+    let matcher = match matcher {
+        '=' => AttributeMatcher::Exact(value),
+        '~' => AttributeMatcher::Includes(value),
+        '|' => AttributeMatcher::DashMatch(value),
+        '^' => AttributeMatcher::Prefix(value),
+        '$' => AttributeMatcher::Suffix(value),
+        '*' => AttributeMatcher::Substring(value),
+        _ => return None,
+    };
+    parser.skip_whitespace();
+
+    // <https://www.w3.org/TR/selectors-4/#attribute-case>
+    let case_insensitive = match parser.consume() {
+        None => false,
+        Some(ComponentValue::PreservedToken(CssToken::Ident(modifier))) => {
+            match modifier.to_ascii_lowercase().as_str() {
+                "i" => true,
+                "s" => false,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return None;
+    }
+
+    Some(AttributeSelector {
+        name,
+        matcher: Some(matcher),
+        case_insensitive,
+    })
+}
+
+fn parse_pseudo_element(name: &str) -> Option<PseudoElement> {
+    match name.to_ascii_lowercase().as_str() {
+        "before" => Some(PseudoElement::Before),
+        "after" => Some(PseudoElement::After),
+        "first-line" => Some(PseudoElement::FirstLine),
+        "first-letter" => Some(PseudoElement::FirstLetter),
+        "marker" => Some(PseudoElement::Marker),
+        _ => None,
+    }
+}
+
+fn parse_functional_pseudo_class(function: &Function) -> Option<PseudoClass> {
+    let args = function.value.as_slice();
+    let pseudo_class = match function.name.to_ascii_lowercase().as_str() {
+        "not" => PseudoClass::Not(parse_selector_list(args)?),
+        "is" | "matches" => PseudoClass::Is(parse_forgiving_selector_list(args)),
+        "where" => PseudoClass::Where(parse_forgiving_selector_list(args)),
+        "has" => PseudoClass::Has(parse_relative_selector_list(args)?),
+        "nth-child" => {
+            let (an_plus_b, of) = parse_nth_child_arguments(args)?;
+            PseudoClass::NthChild(an_plus_b, of)
+        }
+        "nth-last-child" => {
+            let (an_plus_b, of) = parse_nth_child_arguments(args)?;
+            PseudoClass::NthLastChild(an_plus_b, of)
+        }
+        "nth-of-type" => PseudoClass::NthOfType(parse_an_plus_b(args)?),
+        "nth-last-of-type" => PseudoClass::NthLastOfType(parse_an_plus_b(args)?),
+        _ => return None,
+    };
+    Some(pseudo_class)
+}
+
+/// `<An+B> [of <complex-real-selector-list>]?`
+fn parse_nth_child_arguments(args: &[ComponentValue]) -> Option<(AnPlusB, Option<SelectorList>)> {
+    let of_index = args.iter().position(|v| {
+        matches!(v, ComponentValue::PreservedToken(CssToken::Ident(ident)) if ident.eq_ignore_ascii_case("of"))
+    });
+    match of_index {
+        Some(i) => Some((
+            parse_an_plus_b(&args[..i])?,
+            Some(parse_selector_list(&args[i + 1..])?),
+        )),
+        None => Some((parse_an_plus_b(args)?, None)),
+    }
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#anb-microsyntax>
+fn parse_an_plus_b(values: &[ComponentValue]) -> Option<AnPlusB> {
+    let mut parser = SelectorParser::new(values);
+    parser.skip_whitespace();
+
+    // <https://www.w3.org/TR/css-syntax-3/#anb-syntax>
+    let signed_integer = |n: &Numeric| n.has_sign.then(|| n.integer()).flatten();
+    let signless_integer = |n: &Numeric| (!n.has_sign).then(|| n.integer()).flatten();
+
+    // The coefficient of n, and the rest of the ident or the unit after the n.
+    let (a, rest): (i32, String) = match parser.consume()? {
+        ComponentValue::PreservedToken(CssToken::Ident(ident)) => {
+            let ident = ident.to_ascii_lowercase();
+            // `odd` and `even` are keywords only as a whole ident, not after an n.
+            let b = match ident.as_str() {
+                "odd" => Some(1),
+                "even" => Some(0),
+                _ => None,
+            };
+            if let Some(b) = b {
+                parser.skip_whitespace();
+                return match parser.peek() {
+                    None => Some(AnPlusB { a: 2, b }),
+                    Some(_) => None,
+                };
+            }
+            match ident.strip_prefix("-n") {
+                Some(rest) => (-1, rest.into()),
+                None => (1, ident.strip_prefix('n')?.into()),
+            }
+        }
+        ComponentValue::PreservedToken(CssToken::Delim('+')) => match parser.consume()? {
+            ComponentValue::PreservedToken(CssToken::Ident(ident)) => {
+                (1, ident.to_ascii_lowercase().strip_prefix('n')?.into())
+            }
+            _ => return None,
+        },
+        ComponentValue::PreservedToken(CssToken::Number(b)) => {
+            parser.skip_whitespace();
+            return match parser.peek() {
+                None => Some(AnPlusB {
+                    a: 0,
+                    b: b.integer()?,
+                }),
+                Some(_) => None,
+            };
+        }
+        ComponentValue::PreservedToken(CssToken::Dimension(a, unit)) => (
+            a.integer()?,
+            unit.to_ascii_lowercase().strip_prefix('n')?.to_string(),
+        ),
+        _ => return None,
+    };
+
+    let b = match rest.as_str() {
+        "" => {
+            parser.skip_whitespace();
+            match parser.consume() {
+                None => 0,
+                Some(ComponentValue::PreservedToken(CssToken::Number(b))) => signed_integer(b)?,
+                Some(ComponentValue::PreservedToken(CssToken::Delim(sign @ ('+' | '-')))) => {
+                    parser.skip_whitespace();
+                    let b = match parser.consume()? {
+                        ComponentValue::PreservedToken(CssToken::Number(b)) => signless_integer(b)?,
+                        _ => return None,
+                    };
+                    if *sign == '-' {
+                        -b
+                    } else {
+                        b
+                    }
+                }
+                _ => return None,
+            }
+        }
+        "-" => {
+            parser.skip_whitespace();
+            match parser.consume()? {
+                ComponentValue::PreservedToken(CssToken::Number(b)) => -signless_integer(b)?,
+                _ => return None,
+            }
+        }
+        rest => {
+            // e.g. `n-1`, which is tokenized as a single ident.
+            let digits = rest.strip_prefix('-')?;
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            -digits.parse::<i32>().ok()?
+        }
+    };
+
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return None;
+    }
+    Some(AnPlusB { a, b })
+}
+
+/// <https://www.w3.org/TR/selectors-4/#specificity-rules>
+///
+/// Specificities are compared lexicographically, i.e. in the order of (a, b, c).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Specificity {
+    /// The number of ID selectors.
+    pub a: u32,
+    /// The number of class selectors, attribute selectors and pseudo-classes.
+    pub b: u32,
+    /// The number of type selectors and pseudo-elements.
+    pub c: u32,
+}
+
+impl core::ops::Add for Specificity {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            a: self.a + rhs.a,
+            b: self.b + rhs.b,
+            c: self.c + rhs.c,
+        }
+    }
+}
+
+impl SelectorList {
+    /// The specificity of the most specific selector in the list, which is used for :is(), :not() and :has().
+    pub fn max_specificity(&self) -> Specificity {
+        self.selectors
+            .iter()
+            .map(ComplexSelector::specificity)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl ComplexSelector {
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Self::CompoundSelector(compound) => compound.specificity(),
+            Self::Combination(left, _, right) => left.specificity() + right.specificity(),
+        }
+    }
+}
+
+impl CompoundSelector {
+    pub fn specificity(&self) -> Specificity {
+        self.0
+            .iter()
+            .map(SimpleSelector::specificity)
+            .fold(Specificity::default(), |acc, s| acc + s)
+    }
+}
+
+impl SimpleSelector {
+    pub fn specificity(&self) -> Specificity {
+        let (a, b, c) = (
+            Specificity { a: 1, b: 0, c: 0 },
+            Specificity { a: 0, b: 1, c: 0 },
+            Specificity { a: 0, b: 0, c: 1 },
+        );
+        match self {
+            Self::IdSelector(_) => a,
+            Self::ClassSelector(_) | Self::AttributeSelector(_) => b,
+            Self::TypeSelector(_) | Self::PseudoElement(_) => c,
+            Self::UniversalSelector => Specificity::default(),
+            Self::PseudoClass(pseudo_class) => match pseudo_class {
+                PseudoClass::Is(list) | PseudoClass::Not(list) => list.max_specificity(),
+                PseudoClass::Where(_) => Specificity::default(),
+                PseudoClass::Has(selectors) => selectors
+                    .iter()
+                    .map(|s| s.selector.specificity())
+                    .max()
+                    .unwrap_or_default(),
+                PseudoClass::NthChild(_, Some(list)) | PseudoClass::NthLastChild(_, Some(list)) => {
+                    b + list.max_specificity()
+                }
+                _ => b,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::renderer::css::parser::StyleSheetParser;
+    use crate::renderer::css::token::CssTokenizer;
+
+    use super::*;
+
+    fn parse(selector: &str) -> Option<SelectorList> {
+        let values = StyleSheetParser::new(CssTokenizer::new(selector.to_string()))
+            .parse_list_of_component_values();
+        parse_selector_list(&values)
+    }
+
+    fn compound(selectors: Vec<SimpleSelector>) -> CompoundSelector {
+        CompoundSelector(selectors)
+    }
+
+    fn ty(name: &str) -> SimpleSelector {
+        SimpleSelector::TypeSelector(name.into())
+    }
+
+    #[test]
+    fn test_combinators() {
+        use ComplexSelector::*;
+        assert_eq!(
+            parse("DIV p > a.link#top + *  ~ h1, h2"),
+            Some(SelectorList {
+                selectors: vec![
+                    Combination(
+                        Box::new(Combination(
+                            Box::new(Combination(
+                                Box::new(Combination(
+                                    Box::new(CompoundSelector(compound(vec![ty("div")]))),
+                                    Combinator::Descendant,
+                                    compound(vec![ty("p")]),
+                                )),
+                                Combinator::Child,
+                                compound(vec![
+                                    ty("a"),
+                                    SimpleSelector::ClassSelector("link".into()),
+                                    SimpleSelector::IdSelector("top".into()),
+                                ]),
+                            )),
+                            Combinator::NextSibling,
+                            compound(vec![SimpleSelector::UniversalSelector]),
+                        )),
+                        Combinator::SubsequentSibling,
+                        compound(vec![ty("h1")]),
+                    ),
+                    CompoundSelector(compound(vec![ty("h2")])),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_attribute_selectors() {
+        let attribute = |selector: &str| match parse(selector) {
+            Some(SelectorList { selectors }) => match selectors.as_slice() {
+                [ComplexSelector::CompoundSelector(CompoundSelector(simple))] => {
+                    match simple.as_slice() {
+                        [SimpleSelector::AttributeSelector(a)] => Some(a.clone()),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            None => None,
+        };
+        let selector = |name: &str, matcher, case_insensitive| {
+            Some(AttributeSelector {
+                name: name.into(),
+                matcher,
+                case_insensitive,
+            })
+        };
+
+        assert_eq!(attribute("[HREF]"), selector("href", None, false));
+        assert_eq!(
+            attribute("[ lang |= en ]"),
+            selector(
+                "lang",
+                Some(AttributeMatcher::DashMatch("en".into())),
+                false
+            )
+        );
+        assert_eq!(
+            attribute("[class~=\"a b\" i]"),
+            selector(
+                "class",
+                Some(AttributeMatcher::Includes("a b".into())),
+                true
+            )
+        );
+        assert_eq!(
+            attribute("[a^=x]"),
+            selector("a", Some(AttributeMatcher::Prefix("x".into())), false)
+        );
+        assert_eq!(
+            attribute("[a$=x s]"),
+            selector("a", Some(AttributeMatcher::Suffix("x".into())), false)
+        );
+        assert_eq!(
+            attribute("[a*=x]"),
+            selector("a", Some(AttributeMatcher::Substring("x".into())), false)
+        );
+        assert_eq!(
+            attribute("[a=x]"),
+            selector("a", Some(AttributeMatcher::Exact("x".into())), false)
+        );
+        assert_eq!(attribute("[a=x y]"), None);
+        assert_eq!(attribute("[a==x]"), None);
+        assert_eq!(attribute("[1]"), None);
+    }
+
+    #[test]
+    fn test_an_plus_b() {
+        let an_plus_b = |s: &str| {
+            let values = StyleSheetParser::new(CssTokenizer::new(s.to_string()))
+                .parse_list_of_component_values();
+            parse_an_plus_b(&values).map(|AnPlusB { a, b }| (a, b))
+        };
+        assert_eq!(an_plus_b("odd"), Some((2, 1)));
+        assert_eq!(an_plus_b(" EVEN "), Some((2, 0)));
+        assert_eq!(an_plus_b("5"), Some((0, 5)));
+        assert_eq!(an_plus_b("-5"), Some((0, -5)));
+        assert_eq!(an_plus_b("n"), Some((1, 0)));
+        assert_eq!(an_plus_b("+n"), Some((1, 0)));
+        assert_eq!(an_plus_b("-n+3"), Some((-1, 3)));
+        assert_eq!(an_plus_b("2n+1"), Some((2, 1)));
+        assert_eq!(an_plus_b("2n + 1"), Some((2, 1)));
+        assert_eq!(an_plus_b("2n- 1"), Some((2, -1)));
+        assert_eq!(an_plus_b("2n-1"), Some((2, -1)));
+        assert_eq!(an_plus_b("-2n - 10"), Some((-2, -10)));
+        assert_eq!(an_plus_b("n-1"), Some((1, -1)));
+        assert_eq!(an_plus_b("-n-1"), Some((-1, -1)));
+        assert_eq!(an_plus_b("1.5n"), None);
+        assert_eq!(an_plus_b("1.0n"), None);
+        assert_eq!(an_plus_b("5.0"), None);
+        // The integer after the n must be signed, but not after a sign.
+        assert_eq!(an_plus_b("2n 1"), None);
+        assert_eq!(an_plus_b("2n +1"), Some((2, 1)));
+        assert_eq!(an_plus_b("2n + +1"), None);
+        assert_eq!(an_plus_b("2n- -1"), None);
+        assert_eq!(an_plus_b("n- 1"), Some((1, -1)));
+        assert_eq!(an_plus_b("2n+"), None);
+        assert_eq!(an_plus_b("2m"), None);
+        assert_eq!(an_plus_b("n-a"), None);
+        assert_eq!(an_plus_b("nodd"), None);
+        assert_eq!(an_plus_b("-neven"), None);
+        assert_eq!(an_plus_b("+nodd"), None);
+        assert_eq!(an_plus_b("2nodd"), None);
+        assert_eq!(an_plus_b("odd 1"), None);
+        assert_eq!(an_plus_b(""), None);
+    }
+
+    #[test]
+    fn test_pseudo_classes() {
+        let pseudo_class =
+            |p| ComplexSelector::CompoundSelector(compound(vec![SimpleSelector::PseudoClass(p)]));
+        let list = |selectors| SelectorList { selectors };
+
+        assert_eq!(
+            parse("li:nth-child(2n+1 of .a):FIRST-CHILD::before"),
+            Some(list(vec![ComplexSelector::CompoundSelector(compound(
+                vec![
+                    ty("li"),
+                    SimpleSelector::PseudoClass(PseudoClass::NthChild(
+                        AnPlusB { a: 2, b: 1 },
+                        Some(list(vec![ComplexSelector::CompoundSelector(compound(
+                            vec![SimpleSelector::ClassSelector("a".into())]
+                        ))]))
+                    )),
+                    SimpleSelector::PseudoClass(PseudoClass::FirstChild),
+                    SimpleSelector::PseudoElement(PseudoElement::Before),
+                ]
+            ))]))
+        );
+
+        // :is() and :where() take forgiving selector lists.
+        assert_eq!(
+            parse(":is(p, !), :where(!)"),
+            Some(list(vec![
+                pseudo_class(PseudoClass::Is(list(vec![
+                    ComplexSelector::CompoundSelector(compound(vec![ty("p")]))
+                ]))),
+                pseudo_class(PseudoClass::Where(list(vec![]))),
+            ]))
+        );
+
+        assert_eq!(
+            parse(":has(> img, + p)"),
+            Some(list(vec![pseudo_class(PseudoClass::Has(vec![
+                RelativeSelector {
+                    combinator: Combinator::Child,
+                    selector: ComplexSelector::CompoundSelector(compound(vec![ty("img")])),
+                },
+                RelativeSelector {
+                    combinator: Combinator::NextSibling,
+                    selector: ComplexSelector::CompoundSelector(compound(vec![ty("p")])),
+                },
+            ]))]))
+        );
+
+        assert_eq!(
+            parse(":not(p, .a)"),
+            Some(list(vec![pseudo_class(PseudoClass::Not(list(vec![
+                ComplexSelector::CompoundSelector(compound(vec![ty("p")])),
+                ComplexSelector::CompoundSelector(compound(vec![SimpleSelector::ClassSelector(
+                    "a".into()
+                )])),
+            ])))]))
+        );
+    }
+
+    #[test]
+    fn test_specificity() {
+        let specificity = |selector: &str| {
+            let Specificity { a, b, c } = parse(selector).unwrap().max_specificity();
+            (a, b, c)
+        };
+        assert_eq!(specificity("*"), (0, 0, 0));
+        assert_eq!(specificity("li"), (0, 0, 1));
+        assert_eq!(specificity("ul li::before"), (0, 0, 3));
+        assert_eq!(specificity("ul ol+li"), (0, 0, 3));
+        assert_eq!(specificity("h1 + *[rel=up]"), (0, 1, 1));
+        assert_eq!(specificity("ul ol li.red"), (0, 1, 3));
+        assert_eq!(specificity("li.red.level"), (0, 2, 1));
+        assert_eq!(specificity("#x34y"), (1, 0, 0));
+        assert_eq!(specificity("#s12:not(FOO)"), (1, 0, 1));
+        assert_eq!(specificity(".foo :is(.bar, #baz)"), (1, 1, 0));
+        assert_eq!(specificity(":where(#a) p"), (0, 0, 1));
+        assert_eq!(specificity(":nth-child(2n of .a, #b)"), (1, 1, 0));
+        assert_eq!(specificity("p:has(> a.b)"), (0, 1, 2));
+        assert_eq!(specificity("p, #a"), (1, 0, 0));
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        for selector in [
+            "",
+            "p,",
+            ", p",
+            "p >",
+            "> p",
+            "p > > a",
+            ".",
+            ". a",
+            "#1a",
+            ":unknown",
+            "::before.a",
+            ":not(!)",
+            ":nth-child(x)",
+            "p !",
+            "a:hover::unknown",
+        ] {
+            assert_eq!(parse(selector), None, "{selector}");
+        }
+    }
+}
@@ -0,0 +1,860 @@
+//! <https://www.w3.org/TR/css-cascade-4/#shorthand>
+//!
+//! Shorthand properties are expanded into their longhands at parse time,
+//! and the longhands are serialized back into shorthands for the CSSOM.
+//!
+//! Servo: <https://github.com/servo/stylo/tree/main/style/properties/shorthands>
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::easing::EasingFunction;
+use super::parser::{self, trim_whitespace};
+use super::properties::{parse_declaration_value, serialize_value, CSS_WIDE_KEYWORDS};
+use super::token::CssToken;
+use super::value::{parse_keyword, Color, ComponentValue};
+
+/// The supported shorthands.
+pub const SHORTHANDS: [&str; 18] = [
+    "margin",
+    "padding",
+    "inset",
+    "border",
+    "border-width",
+    "border-style",
+    "border-color",
+    "border-top",
+    "border-right",
+    "border-bottom",
+    "border-left",
+    "background",
+    "font",
+    "list-style",
+    "text-decoration",
+    "flex",
+    "transition",
+    "animation",
+];
+
+/// Returns the longhands of the property in the canonical order, or None if it is not a shorthand.
+pub fn longhands(name: &str) -> Option<&'static [&'static str]> {
+    let longhands: &'static [&'static str] = match name.to_ascii_lowercase().as_str() {
+        "margin" => &["margin-top", "margin-right", "margin-bottom", "margin-left"],
+        "padding" => &[
+            "padding-top",
+            "padding-right",
+            "padding-bottom",
+            "padding-left",
+        ],
+        "inset" => &["top", "right", "bottom", "left"],
+        "border" => &[
+            "border-top-width",
+            "border-right-width",
+            "border-bottom-width",
+            "border-left-width",
+            "border-top-style",
+            "border-right-style",
+            "border-bottom-style",
+            "border-left-style",
+            "border-top-color",
+            "border-right-color",
+            "border-bottom-color",
+            "border-left-color",
+        ],
+        "border-width" => &[
+            "border-top-width",
+            "border-right-width",
+            "border-bottom-width",
+            "border-left-width",
+        ],
+        "border-style" => &[
+            "border-top-style",
+            "border-right-style",
+            "border-bottom-style",
+            "border-left-style",
+        ],
+        "border-color" => &[
+            "border-top-color",
+            "border-right-color",
+            "border-bottom-color",
+            "border-left-color",
+        ],
+        "border-top" => &["border-top-width", "border-top-style", "border-top-color"],
+        "border-right" => &[
+            "border-right-width",
+            "border-right-style",
+            "border-right-color",
+        ],
+        "border-bottom" => &[
+            "border-bottom-width",
+            "border-bottom-style",
+            "border-bottom-color",
+        ],
+        "border-left" => &[
+            "border-left-width",
+            "border-left-style",
+            "border-left-color",
+        ],
+        "background" => &[
+            "background-color",
+            "background-image",
+            "background-repeat",
+            "background-attachment",
+            "background-position",
+        ],
+        "font" => &[
+            "font-style",
+            "font-variant",
+            "font-weight",
+            "font-size",
+            "line-height",
+            "font-family",
+        ],
+        "list-style" => &["list-style-type", "list-style-position", "list-style-image"],
+        "text-decoration" => &[
+            "text-decoration-line",
+            "text-decoration-style",
+            "text-decoration-color",
+        ],
+        "flex" => &["flex-grow", "flex-shrink", "flex-basis"],
+        "transition" => &[
+            "transition-property",
+            "transition-duration",
+            "transition-timing-function",
+            "transition-delay",
+        ],
+        // The name is the last, as it is serialized after the others.
+        "animation" => &[
+            "animation-duration",
+            "animation-timing-function",
+            "animation-delay",
+            "animation-iteration-count",
+            "animation-direction",
+            "animation-fill-mode",
+            "animation-play-state",
+            "animation-name",
+        ],
+        _ => return None,
+    };
+    Some(longhands)
+}
+
+/// The initial values of the longhands which can be omitted in a shorthand.
+fn initial_value(longhand: &str) -> Vec<ComponentValue> {
+    let keyword = |k: &str| vec![ComponentValue::Keyword(k.to_string())];
+    match longhand {
+        "background-color" => vec![ComponentValue::Color(Color::TRANSPARENT)],
+        "background-position" => vec![
+            ComponentValue::Percentage(0.0),
+            ComponentValue::Percentage(0.0),
+        ],
+        "background-repeat" => keyword("repeat"),
+        "background-attachment" => keyword("scroll"),
+        "list-style-type" => keyword("disc"),
+        "list-style-position" => keyword("outside"),
+        "text-decoration-style" => keyword("solid"),
+        "font-style" | "font-variant" | "font-weight" | "line-height" => keyword("normal"),
+        "transition-property" => keyword("all"),
+        "transition-duration" | "transition-delay" | "animation-duration" | "animation-delay" => {
+            vec![ComponentValue::Time(0.0)]
+        }
+        "transition-timing-function" | "animation-timing-function" => {
+            vec![ComponentValue::Easing(EasingFunction::Ease)]
+        }
+        "animation-iteration-count" => vec![ComponentValue::Number(1.0)],
+        "animation-direction" => keyword("normal"),
+        "animation-play-state" => keyword("running"),
+        l if l.ends_with("-width") => keyword("medium"),
+        l if l.ends_with("-color") => keyword("currentcolor"),
+        _ => keyword("none"),
+    }
+}
+
+/// Parses the value of a longhand inside a shorthand, where CSS-wide keywords are not allowed.
+fn longhand(name: &str, value: &[parser::ComponentValue]) -> Option<Vec<ComponentValue>> {
+    if let [v] = value {
+        if parse_keyword(v).is_some_and(|k| CSS_WIDE_KEYWORDS.contains(&k.as_str())) {
+            return None;
+        }
+    }
+    parse_declaration_value(name, value)
+}
+
+/// Expands the value of a shorthand into the values of its longhands.
+/// Returns None if the property is not a shorthand or the value does not match its grammar.
+pub fn expand(
+    name: &str,
+    value: &[parser::ComponentValue],
+) -> Option<Vec<(&'static str, Vec<ComponentValue>)>> {
+    let name = name.to_ascii_lowercase();
+    let names = longhands(&name)?;
+    let value = trim_whitespace(value);
+
+    // CSS-wide keywords set all the longhands.
+    if let [v] = value {
+        if let Some(keyword) = parse_keyword(v) {
+            if CSS_WIDE_KEYWORDS.contains(&keyword.as_str()) {
+                let value = vec![ComponentValue::Keyword(keyword)];
+                return Some(names.iter().map(|l| (*l, value.clone())).collect());
+            }
+        }
+    }
+
+    let items: Vec<parser::ComponentValue> = value
+        .iter()
+        .filter(|v| **v != parser::ComponentValue::PreservedToken(CssToken::Whitespace))
+        .cloned()
+        .collect();
+    let values = match name.as_str() {
+        "margin" | "padding" | "inset" | "border-width" | "border-style" | "border-color" => {
+            expand_sides(names, &items)?
+        }
+        "border" => {
+            // All the sides are set to the same values.
+            let side = expand_any_order(longhands("border-top")?, &items)?;
+            side.into_iter().flat_map(|v| vec![v; 4]).collect()
+        }
+        "font" => expand_font(&items)?,
+        "flex" => expand_flex(&items)?,
+        "transition" | "animation" => expand_list(&name, names, &items)?,
+        _ => expand_any_order(names, &items)?,
+    };
+    Some(names.iter().copied().zip(values).collect())
+}
+
+/// Expands `<top> <right>? <bottom>? <left>?`.
+fn expand_sides(
+    longhands: &[&str],
+    items: &[parser::ComponentValue],
+) -> Option<Vec<Vec<ComponentValue>>> {
+    if items.is_empty() || items.len() > 4 {
+        return None;
+    }
+    let mut values = Vec::new();
+    for (longhand_name, item) in longhands.iter().zip(items) {
+        values.push(longhand(longhand_name, core::slice::from_ref(item))?);
+    }
+    // The right defaults to the top, the bottom to the top, and the left to the right.
+    for i in values.len()..4 {
+        let value = values[if i == 3 { 1 } else { 0 }].clone();
+        values.push(value);
+    }
+    Some(values)
+}
+
+/// Expands `a || b || c ...`, where each longhand appears at most once in any order.
+/// Omitted longhands are set to their initial values.
+fn expand_any_order(
+    longhands: &[&str],
+    items: &[parser::ComponentValue],
+) -> Option<Vec<Vec<ComponentValue>>> {
+    /// Assigns the items to the longhands, backtracking if the rest cannot be assigned,
+    /// e.g. `none` in `list-style: none disc` is not the type but the image.
+    fn assign(
+        longhands: &[&str],
+        items: &[parser::ComponentValue],
+        values: &mut [Option<Vec<ComponentValue>>],
+    ) -> bool {
+        if items.is_empty() {
+            return true;
+        }
+        for (i, name) in longhands.iter().enumerate() {
+            if values[i].is_some() {
+                continue;
+            }
+            // Some longhands take multiple items, e.g. `underline overline` or `left top`.
+            for n in (1..=items.len().min(3)).rev() {
+                if let Some(value) = longhand(name, &items[..n]) {
+                    values[i] = Some(value);
+                    if assign(longhands, &items[n..], values) {
+                        return true;
+                    }
+                    values[i] = None;
+                }
+            }
+        }
+        false
+    }
+
+    if items.is_empty() {
+        return None;
+    }
+    let mut values = vec![None; longhands.len()];
+    if !assign(longhands, items, &mut values) {
+        return None;
+    }
+    Some(
+        longhands
+            .iter()
+            .zip(values)
+            .map(|(name, value)| value.unwrap_or_else(|| initial_value(name)))
+            .collect(),
+    )
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-prop>
+///
+/// `[ <font-style> || <font-variant-css2> || <font-weight> ]? <font-size> [ / <line-height> ]? <font-family>`
+fn expand_font(items: &[parser::ComponentValue]) -> Option<Vec<Vec<ComponentValue>>> {
+    let normal = initial_value("font-style");
+    let mut values = [None, None, None];
+    let mut i = 0;
+    while i < items.len() && i < 3 {
+        let item = core::slice::from_ref(&items[i]);
+        // `normal` is valid for any of them, which are `normal` by default.
+        if longhand("font-style", item) == Some(normal.clone()) {
+            i += 1;
+            continue;
+        }
+        let Some((j, value)) = ["font-style", "font-variant", "font-weight"]
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| values[*j].is_none())
+            .find_map(|(j, name)| Some((j, longhand(name, item)?)))
+        else {
+            break;
+        };
+        values[j] = Some(value);
+        i += 1;
+    }
+    let [style, variant, weight] = values.map(|v| v.unwrap_or_else(|| normal.clone()));
+
+    let size = longhand("font-size", core::slice::from_ref(items.get(i)?))?;
+    i += 1;
+    let mut line_height = normal;
+    if items.get(i)
+        == Some(&parser::ComponentValue::PreservedToken(CssToken::Delim(
+            '/',
+        )))
+    {
+        line_height = longhand("line-height", core::slice::from_ref(items.get(i + 1)?))?;
+        i += 2;
+    }
+    let family = longhand("font-family", &items[i..])?;
+
+    Some(vec![style, variant, weight, size, line_height, family])
+}
+
+/// <https://www.w3.org/TR/css-flexbox-1/#flex-property>
+///
+/// `none | [ <flex-grow> <flex-shrink>? || <flex-basis> ]`
+fn expand_flex(items: &[parser::ComponentValue]) -> Option<Vec<Vec<ComponentValue>>> {
+    let number = |n| vec![ComponentValue::Number(n)];
+    if let [item] = items {
+        if parse_keyword(item).is_some_and(|k| k == "none") {
+            return Some(vec![
+                number(0.0),
+                number(0.0),
+                vec![ComponentValue::Keyword("auto".into())],
+            ]);
+        }
+    }
+    if items.is_empty() || items.len() > 3 {
+        return None;
+    }
+
+    let (mut grow, mut shrink, mut basis) = (None, None, None);
+    let mut after_grow = false;
+    for item in items {
+        let item = core::slice::from_ref(item);
+        // A unitless zero is a flex factor, unless both factors are already specified.
+        if grow.is_none() {
+            if let Some(value) = longhand("flex-grow", item) {
+                grow = Some(value);
+                after_grow = true;
+                continue;
+            }
+        } else if after_grow && shrink.is_none() {
+            if let Some(value) = longhand("flex-shrink", item) {
+                shrink = Some(value);
+                after_grow = false;
+                continue;
+            }
+        }
+        after_grow = false;
+        if basis.is_some() {
+            return None;
+        }
+        basis = Some(longhand("flex-basis", item)?);
+    }
+    // Omitted values are not the initial values, but `1 1 0%`.
+    Some(vec![
+        grow.unwrap_or_else(|| number(1.0)),
+        shrink.unwrap_or_else(|| number(1.0)),
+        basis.unwrap_or_else(|| vec![ComponentValue::Percentage(0.0)]),
+    ])
+}
+
+/// The longhand of a list shorthand which takes any identifier, e.g. the name of an animation.
+///
+/// It is parsed after the others, so that keywords such as `ease` are taken by the others.
+fn identifier_longhand(name: &str) -> &'static str {
+    if name == "transition" {
+        "transition-property"
+    } else {
+        "animation-name"
+    }
+}
+
+/// Parses an item of the list of a longhand of a list shorthand.
+fn list_item(name: &str, item: &parser::ComponentValue) -> Option<ComponentValue> {
+    longhand(name, core::slice::from_ref(item))?.pop()
+}
+
+/// <https://www.w3.org/TR/css-transitions-1/#transition-shorthand-property>
+/// <https://www.w3.org/TR/css-animations-1/#animation>
+///
+/// Expands a comma-separated list of `a || b || c ...`, where each element sets an item
+/// of the list of each longhand. Omitted items are set to their initial values.
+fn expand_list(
+    name: &str,
+    longhands: &[&str],
+    items: &[parser::ComponentValue],
+) -> Option<Vec<Vec<ComponentValue>>> {
+    let identifier = identifier_longhand(name);
+    let mut order: Vec<usize> = (0..longhands.len())
+        .filter(|i| longhands[*i] != identifier)
+        .collect();
+    order.extend((0..longhands.len()).filter(|i| longhands[*i] == identifier));
+
+    let mut lists = vec![Vec::new(); longhands.len()];
+    let elements: Vec<_> = items
+        .split(|v| *v == parser::ComponentValue::PreservedToken(CssToken::Comma))
+        .collect();
+    for element in &elements {
+        if element.is_empty() {
+            return None;
+        }
+        let mut values = vec![None; longhands.len()];
+        for item in *element {
+            let (i, value) = order
+                .iter()
+                .filter(|i| values[**i].is_none())
+                .find_map(|i| Some((*i, list_item(longhands[*i], item)?)))?;
+            values[i] = Some(value);
+        }
+        for ((list, name), value) in lists.iter_mut().zip(longhands).zip(values) {
+            list.push(value.unwrap_or_else(|| initial_value(name).remove(0)));
+        }
+    }
+    // `transition: none` is valid only on its own.
+    let is_none = |v: &ComponentValue| matches!(v, ComponentValue::Keyword(k) if k == "none");
+    if name == "transition" && elements.len() > 1 && lists[0].iter().any(is_none) {
+        return None;
+    }
+    Some(lists)
+}
+
+/// Serializes the lists of the longhands of a list shorthand, which must have the same length.
+fn serialize_list(name: &str, longhands: &[&str], values: &[&[ComponentValue]]) -> Option<String> {
+    let len = values[0].len();
+    if values.iter().any(|v| v.len() != len) {
+        return None;
+    }
+    let identifier = longhands
+        .iter()
+        .position(|l| *l == identifier_longhand(name))?;
+    let position = |suffix: &str| longhands.iter().position(|l| l.ends_with(suffix));
+    let (duration, delay) = (position("-duration")?, position("-delay")?);
+
+    let mut elements = Vec::new();
+    for i in 0..len {
+        let mut included: Vec<bool> = longhands
+            .iter()
+            .zip(values)
+            .map(|(name, value)| value[i] != initial_value(name)[0])
+            .collect();
+        // The first time is the duration, so the delay needs the duration before it.
+        if included[delay] {
+            included[duration] = true;
+        }
+        if !included.contains(&true) {
+            included[identifier] = true;
+        }
+        // The identifier must not be taken by other longhands when parsed back, which happens if
+        // it precedes them, or they are omitted. It does not matter if both are the initial values,
+        // e.g. `animation: none`.
+        if included[identifier] {
+            let value = &values[identifier][i];
+            if let ComponentValue::Keyword(ident) | ComponentValue::CustomIdent(ident) = value {
+                let token = parser::ComponentValue::PreservedToken(CssToken::Ident(ident.clone()));
+                let is_initial =
+                    |j: usize, v: &ComponentValue| *v == initial_value(longhands[j])[0];
+                let ambiguous = (0..longhands.len()).any(|j| {
+                    j != identifier
+                        && (identifier == 0 || !included[j])
+                        && list_item(longhands[j], &token)
+                            .is_some_and(|v| !is_initial(j, &v) || !is_initial(identifier, value))
+                });
+                if ambiguous {
+                    return None;
+                }
+            }
+        }
+        elements.push(join(
+            (0..longhands.len())
+                .filter(|j| included[*j])
+                .map(|j| values[j][i].to_string()),
+        ));
+    }
+    Some(elements.join(", "))
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-css-value>
+///
+/// Serializes the values of the longhands, in the order of [longhands], as the value of the shorthand.
+/// Returns None if the shorthand cannot represent the values.
+pub fn serialize(name: &str, values: &[&[ComponentValue]]) -> Option<String> {
+    let name = name.to_ascii_lowercase();
+    let names = longhands(&name)?;
+    if values.len() != names.len() {
+        return None;
+    }
+
+    // A CSS-wide keyword can be serialized only if all the longhands have it.
+    let is_css_wide_keyword = |value: &[ComponentValue]| matches!(value, [ComponentValue::Keyword(k)] if CSS_WIDE_KEYWORDS.contains(&k.as_str()));
+    if values.iter().any(|v| is_css_wide_keyword(v)) {
+        return values
+            .iter()
+            .all(|v| *v == values[0])
+            .then(|| serialize_value(&name, values[0]));
+    }
+
+    let serialized: Vec<String> = names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| serialize_value(name, value))
+        .collect();
+    match name.as_str() {
+        "margin" | "padding" | "inset" | "border-width" | "border-style" | "border-color" => {
+            let [top, right, bottom, left] = serialized.as_slice() else {
+                return None;
+            };
+            let mut sides = vec![top, right, bottom, left];
+            if left == right {
+                sides.pop();
+                if bottom == top {
+                    sides.pop();
+                    if right == top {
+                        sides.pop();
+                    }
+                }
+            }
+            Some(join(sides.into_iter().cloned()))
+        }
+        "border" => {
+            // All the sides must have the same values.
+            if values
+                .chunks(4)
+                .any(|side| side.iter().any(|v| *v != side[0]))
+            {
+                return None;
+            }
+            serialize("border-top", &[values[0], values[4], values[8]])
+        }
+        "font" => {
+            let normal = initial_value("font-style");
+            let mut components: Vec<String> = (0..3)
+                .filter(|i| values[*i] != normal.as_slice())
+                .map(|i| serialized[i].clone())
+                .collect();
+            if values[4] == normal.as_slice() {
+                components.push(serialized[3].clone());
+            } else {
+                components.push(format!("{}/{}", serialized[3], serialized[4]));
+            }
+            components.push(serialized[5].clone());
+            Some(join(components.into_iter()))
+        }
+        "flex" => Some(join(serialized.into_iter())),
+        "transition" | "animation" => serialize_list(&name, names, values),
+        _ => {
+            // The longhands with the initial values are omitted, but at least one is kept.
+            let components: Vec<String> = names
+                .iter()
+                .zip(values)
+                .zip(&serialized)
+                .filter(|((name, value), _)| **value != initial_value(name).as_slice())
+                .map(|(_, s)| s.clone())
+                .collect();
+            if components.is_empty() {
+                // The line for text-decoration, the type for list-style, and the image or the style otherwise.
+                let kept = match name.as_str() {
+                    "list-style" | "text-decoration" => 0,
+                    _ => 1,
+                };
+                return Some(serialized[kept].clone());
+            }
+            Some(join(components.into_iter()))
+        }
+    }
+}
+
+fn join(components: impl Iterator<Item = String>) -> String {
+    components.collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::StyleSheetParser;
+    use super::super::token::CssTokenizer;
+    use super::super::value::Length;
+    use super::*;
+
+    fn component_values(value: &str) -> Vec<parser::ComponentValue> {
+        StyleSheetParser::new(CssTokenizer::new(value.to_string())).parse_list_of_component_values()
+    }
+
+    fn expand_str(name: &str, value: &str) -> Option<Vec<(&'static str, String)>> {
+        Some(
+            expand(name, &component_values(value))?
+                .into_iter()
+                .map(|(name, value)| (name, serialize_value(name, &value)))
+                .collect(),
+        )
+    }
+
+    fn round_trip(name: &str, value: &str) -> Option<String> {
+        let values: Vec<_> = expand(name, &component_values(value))?
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect();
+        let values: Vec<&[ComponentValue]> = values.iter().map(|v| v.as_slice()).collect();
+        serialize(name, &values)
+    }
+
+    #[test]
+    fn test_expand_sides() {
+        assert_eq!(
+            expand_str("margin", "1px 2em"),
+            Some(vec![
+                ("margin-top", "1px".into()),
+                ("margin-right", "2em".into()),
+                ("margin-bottom", "1px".into()),
+                ("margin-left", "2em".into()),
+            ])
+        );
+        assert_eq!(expand_str("padding", ""), None);
+        assert_eq!(expand_str("padding", "1px -2px"), None);
+        assert_eq!(expand_str("margin", "1px 2px 3px 4px 5px"), None);
+        assert_eq!(
+            expand_str("inset", "INHERIT"),
+            Some(vec![
+                ("top", "inherit".into()),
+                ("right", "inherit".into()),
+                ("bottom", "inherit".into()),
+                ("left", "inherit".into()),
+            ])
+        );
+        assert_eq!(expand_str("margin", "1px inherit"), None);
+    }
+
+    #[test]
+    fn test_expand_any_order() {
+        let border = expand("border", &component_values("red 2px")).unwrap();
+        assert_eq!(border.len(), 12);
+        assert_eq!(
+            border[3],
+            (
+                "border-left-width",
+                vec![ComponentValue::Length(Length::px(2.0))]
+            )
+        );
+        assert_eq!(
+            border[7],
+            (
+                "border-left-style",
+                vec![ComponentValue::Keyword("none".into())]
+            )
+        );
+        assert_eq!(
+            border[8],
+            (
+                "border-top-color",
+                vec![ComponentValue::Color(Color::rgb(255, 0, 0))]
+            )
+        );
+
+        assert_eq!(
+            expand_str("list-style", "none disc"),
+            Some(vec![
+                ("list-style-type", "disc".into()),
+                ("list-style-position", "outside".into()),
+                ("list-style-image", "none".into()),
+            ])
+        );
+        assert_eq!(
+            expand_str("text-decoration", "red underline overline"),
+            Some(vec![
+                ("text-decoration-line", "underline overline".into()),
+                ("text-decoration-style", "solid".into()),
+                ("text-decoration-color", "rgb(255, 0, 0)".into()),
+            ])
+        );
+        assert_eq!(
+            expand_str("background", r#"url("a.png") no-repeat #00f right 10%"#),
+            Some(vec![
+                ("background-color", "rgb(0, 0, 255)".into()),
+                ("background-image", r#"url("a.png")"#.into()),
+                ("background-repeat", "no-repeat".into()),
+                ("background-attachment", "scroll".into()),
+                ("background-position", "right 10%".into()),
+            ])
+        );
+        assert_eq!(expand_str("border-top", "solid solid"), None);
+    }
+
+    #[test]
+    fn test_expand_font_and_flex() {
+        assert_eq!(
+            expand_str("font", "italic bold 12px/1.5 Foo Bar, serif"),
+            Some(vec![
+                ("font-style", "italic".into()),
+                ("font-variant", "normal".into()),
+                ("font-weight", "bold".into()),
+                ("font-size", "12px".into()),
+                ("line-height", "1.5".into()),
+                ("font-family", r#""Foo Bar", serif"#.into()),
+            ])
+        );
+        assert_eq!(expand_str("font", "12px"), None);
+        assert_eq!(expand_str("font", "bold serif"), None);
+
+        let flex = |value| {
+            expand_str("flex", value).map(|v| v.into_iter().map(|(_, v)| v).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            flex("none"),
+            Some(vec!["0".into(), "0".into(), "auto".into()])
+        );
+        assert_eq!(flex("2"), Some(vec!["2".into(), "1".into(), "0%".into()]));
+        assert_eq!(
+            flex("1 30px"),
+            Some(vec!["1".into(), "1".into(), "30px".into()])
+        );
+        assert_eq!(
+            flex("2 3 0"),
+            Some(vec!["2".into(), "3".into(), "0px".into()])
+        );
+        assert_eq!(
+            flex("auto"),
+            Some(vec!["1".into(), "1".into(), "auto".into()])
+        );
+        assert_eq!(flex("1 2 3"), None);
+    }
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(
+            round_trip("margin", "1px 2px 1px 2px"),
+            Some("1px 2px".into())
+        );
+        assert_eq!(round_trip("margin", "1px 1px 1px"), Some("1px".into()));
+        assert_eq!(
+            round_trip("padding", "1px 2px 3px"),
+            Some("1px 2px 3px".into())
+        );
+        assert_eq!(
+            round_trip("border", "2px red"),
+            Some("2px rgb(255, 0, 0)".into())
+        );
+        assert_eq!(round_trip("border-left", "none"), Some("none".into()));
+        assert_eq!(
+            round_trip("background", "red"),
+            Some("rgb(255, 0, 0)".into())
+        );
+        assert_eq!(round_trip("background", "transparent"), Some("none".into()));
+        assert_eq!(
+            round_trip("font", "normal 16px serif"),
+            Some("16px serif".into())
+        );
+        assert_eq!(
+            round_trip("font", "small-caps 1em/2 monospace"),
+            Some("small-caps 1em/2 monospace".into())
+        );
+        assert_eq!(round_trip("list-style", "none"), Some("none".into()));
+        assert_eq!(round_trip("flex", "auto"), Some("1 1 auto".into()));
+        assert_eq!(round_trip("inset", "unset"), Some("unset".into()));
+
+        let solid = [ComponentValue::Keyword("solid".into())];
+        let dashed = [ComponentValue::Keyword("dashed".into())];
+        let inherit = [ComponentValue::Keyword("inherit".into())];
+        assert_eq!(
+            serialize("border-style", &[&solid, &dashed, &solid, &dashed]),
+            Some("solid dashed".into())
+        );
+        assert_eq!(
+            serialize("border-style", &[&solid, &inherit, &solid, &solid]),
+            None
+        );
+        let mut border: Vec<&[ComponentValue]> = vec![&inherit; 12];
+        assert_eq!(serialize("border", &border), Some("inherit".into()));
+        border[5] = &solid;
+        assert_eq!(serialize("border", &border), None);
+    }
+
+    #[test]
+    fn test_transition_and_animation() {
+        assert_eq!(
+            expand_str("transition", "opacity 1s linear 2s, color 500ms"),
+            Some(vec![
+                ("transition-property", "opacity, color".into()),
+                ("transition-duration", "1s, 0.5s".into()),
+                ("transition-timing-function", "linear, ease".into()),
+                ("transition-delay", "2s, 0s".into()),
+            ])
+        );
+        assert_eq!(expand_str("transition", "none, color"), None);
+        assert_eq!(expand_str("transition", "1s 2s 3s"), None);
+        assert_eq!(expand_str("transition", "color,"), None);
+        assert_eq!(
+            expand_str("animation", "ease 3s infinite slide both, 1s"),
+            Some(vec![
+                ("animation-duration", "3s, 1s".into()),
+                ("animation-timing-function", "ease, ease".into()),
+                ("animation-delay", "0s, 0s".into()),
+                ("animation-iteration-count", "infinite, 1".into()),
+                ("animation-direction", "normal, normal".into()),
+                ("animation-fill-mode", "both, none".into()),
+                ("animation-play-state", "running, running".into()),
+                ("animation-name", "slide, none".into()),
+            ])
+        );
+        // The keywords of the other longhands are taken by them first.
+        assert_eq!(
+            expand_str("animation", "ease-in ease-out")
+                .unwrap()
+                .into_iter()
+                .filter(|(name, _)| name.ends_with("-name") || name.ends_with("-function"))
+                .collect::<Vec<_>>(),
+            vec![
+                ("animation-timing-function", "ease-in".into()),
+                ("animation-name", "ease-out".into()),
+            ]
+        );
+
+        assert_eq!(
+            round_trip("transition", "color 0s ease 1s, all"),
+            Some("color 0s 1s, all".into())
+        );
+        assert_eq!(round_trip("transition", "none"), Some("none".into()));
+        assert_eq!(
+            round_trip("animation", "slide 2s reverse"),
+            Some("2s reverse slide".into())
+        );
+        assert_eq!(round_trip("animation", "0s"), Some("none".into()));
+        assert_eq!(
+            round_trip("animation", "1s both both").as_deref(),
+            Some("1s both both")
+        );
+        // The name would be parsed as the timing function, which is omitted as the initial value.
+        assert_eq!(round_trip("animation", "ease ease"), None);
+        let one = [ComponentValue::Time(1.0)];
+        let two = [ComponentValue::Time(1.0), ComponentValue::Time(2.0)];
+        let all = [ComponentValue::Keyword("all".into())];
+        let ease = [ComponentValue::Easing(EasingFunction::Ease)];
+        assert_eq!(serialize("transition", &[&all, &two, &ease, &one]), None);
+    }
+}
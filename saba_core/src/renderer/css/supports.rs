@@ -0,0 +1,213 @@
+//! <https://www.w3.org/TR/css-conditional-3/#at-supports>
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::parser::{
+    consume_declaration, serialize_component_values, supports_declaration, trim_whitespace,
+    ComponentValue, Declaration,
+};
+use super::token::CssToken;
+use super::value::serialize_identifier;
+
+/// <https://www.w3.org/TR/css-conditional-3/#typedef-supports-condition>
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupportsCondition {
+    Not(Box<SupportsCondition>),
+    And(Vec<SupportsCondition>),
+    Or(Vec<SupportsCondition>),
+    /// <https://www.w3.org/TR/css-conditional-3/#typedef-supports-decl>
+    ///
+    /// Whether the property and the value are supported is determined when it is parsed.
+    Declaration {
+        declaration: Declaration,
+        supported: bool,
+    },
+    /// <https://www.w3.org/TR/css-conditional-3/#typedef-general-enclosed>
+    ///
+    /// A parenthesized or functional notation not understood, which evaluates to false.
+    /// It is kept as it is for serialization.
+    GeneralEnclosed(ComponentValue),
+}
+
+impl SupportsCondition {
+    /// Returns None if the values are not a `<supports-condition>`, in which case the rule is invalid.
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let values: Vec<_> = values
+            .iter()
+            .filter(|v| **v != ComponentValue::PreservedToken(CssToken::Whitespace))
+            .collect();
+        let (first, rest) = values.split_first()?;
+        if ident_is(first, "not") {
+            return match rest {
+                [value] => Some(Self::Not(Box::new(Self::parse_in_parens(value)?))),
+                _ => None,
+            };
+        }
+
+        let mut conditions = vec![Self::parse_in_parens(first)?];
+        let Some(combinator) = rest.first() else {
+            return Some(conditions.remove(0));
+        };
+        let is_and = ident_is(combinator, "and");
+        if !is_and && !ident_is(combinator, "or") {
+            return None;
+        }
+        for pair in rest.chunks(2) {
+            match pair {
+                [keyword, value] if ident_is(keyword, if is_and { "and" } else { "or" }) => {
+                    conditions.push(Self::parse_in_parens(value)?)
+                }
+                _ => return None,
+            }
+        }
+        Some(if is_and {
+            Self::And(conditions)
+        } else {
+            Self::Or(conditions)
+        })
+    }
+
+    /// <https://www.w3.org/TR/css-conditional-3/#typedef-supports-in-parens>
+    fn parse_in_parens(value: &ComponentValue) -> Option<Self> {
+        match value {
+            ComponentValue::SimpleBlock(block)
+                if block.associated_token == CssToken::OpenParenthesis =>
+            {
+                Self::parse(&block.value)
+                    .or_else(|| Self::parse_declaration(&block.value))
+                    .or_else(|| Some(Self::GeneralEnclosed(value.clone())))
+            }
+            ComponentValue::Function(_) => Some(Self::GeneralEnclosed(value.clone())),
+            _ => None,
+        }
+    }
+
+    fn parse_declaration(values: &[ComponentValue]) -> Option<Self> {
+        let values = trim_whitespace(values);
+        if !matches!(
+            values.first(),
+            Some(ComponentValue::PreservedToken(CssToken::Ident(_)))
+        ) {
+            return None;
+        }
+        let declaration = consume_declaration(values.to_vec())?;
+        Some(Self::Declaration {
+            supported: supports_declaration(declaration.clone()),
+            declaration,
+        })
+    }
+
+    /// <https://www.w3.org/TR/css-conditional-3/#support-definition>
+    pub fn matches(&self) -> bool {
+        match self {
+            Self::Not(condition) => !condition.matches(),
+            Self::And(conditions) => conditions.iter().all(Self::matches),
+            Self::Or(conditions) => conditions.iter().any(Self::matches),
+            Self::Declaration { supported, .. } => *supported,
+            Self::GeneralEnclosed(_) => false,
+        }
+    }
+
+    fn fmt_in_parens(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Declaration { .. } | Self::GeneralEnclosed(_) => write!(f, "{self}"),
+            _ => write!(f, "({self})"),
+        }
+    }
+}
+
+impl fmt::Display for SupportsCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (conditions, combinator) = match self {
+            Self::Declaration { declaration, .. } => {
+                let name = serialize_identifier(&declaration.name);
+                let value = serialize_component_values(&declaration.value);
+                let important = if declaration.important {
+                    " !important"
+                } else {
+                    ""
+                };
+                return write!(f, "({name}: {value}{important})");
+            }
+            Self::GeneralEnclosed(value) => return write!(f, "{value}"),
+            Self::Not(condition) => {
+                write!(f, "not ")?;
+                return condition.fmt_in_parens(f);
+            }
+            Self::And(conditions) => (conditions, " and "),
+            Self::Or(conditions) => (conditions, " or "),
+        };
+        for (i, condition) in conditions.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{combinator}")?;
+            }
+            condition.fmt_in_parens(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn ident_is(value: &ComponentValue, keyword: &str) -> bool {
+    matches!(value, ComponentValue::PreservedToken(CssToken::Ident(ident)) if ident.eq_ignore_ascii_case(keyword))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::super::parser::StyleSheetParser;
+    use super::super::token::CssTokenizer;
+    use super::*;
+
+    fn parse(condition: &str) -> Option<SupportsCondition> {
+        let values = StyleSheetParser::new(CssTokenizer::new(condition.to_string()))
+            .parse_list_of_component_values();
+        SupportsCondition::parse(&values)
+    }
+
+    fn matches(condition: &str) -> bool {
+        parse(condition).unwrap().matches()
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("(display: block)"));
+        assert!(matches("(DISPLAY: Block !important)"));
+        assert!(!matches("(display: grid)"));
+        assert!(!matches("(foo: block)"));
+        assert!(matches("not (display: grid)"));
+        assert!(matches("(display: block) and (color: red)"));
+        assert!(!matches("(display: block) and (display: grid)"));
+        assert!(matches("(display: grid) or (color: red)"));
+        assert!(matches(
+            "((display: grid) or (color: red)) and (not (display: grid))"
+        ));
+        assert!(matches("(--foo: anything)"));
+        // <general-enclosed> is false.
+        assert!(!matches("(display block)"));
+        assert!(!matches("selector(p)"));
+        assert!(matches("not (display block)"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("display: block"), None);
+        assert_eq!(parse("(color: red) and (color: red) or (color: red)"), None);
+        assert_eq!(parse("not (color: red) and (color: red)"), None);
+        assert_eq!(parse("(color: red) (color: red)"), None);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let serialize = |condition: &str| parse(condition).map(|c| c.to_string());
+        assert_eq!(
+            serialize("(display:block)and ( not (color :red))").as_deref(),
+            Some("(display: block) and (not (color: red))")
+        );
+        assert_eq!(serialize("(a b) or f(x)").as_deref(), Some("(a b) or f(x)"));
+    }
+}
@@ -1,19 +1,32 @@
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::fmt;
+
+use super::value::{serialize_identifier, serialize_string};
 
 /// <https://www.w3.org/TR/css-syntax-3/#token-diagrams>
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssToken {
     /// <https://www.w3.org/TR/css-syntax-3/#hash-token-diagram>
-    Hash(String),
+    Hash(String, HashType),
     /// <https://www.w3.org/TR/css-syntax-3/#delim-token-diagram>
     Delim(char),
     /// <https://www.w3.org/TR/css-syntax-3/#number-token-diagram>
-    Number(f64),
+    Number(Numeric),
+    /// <https://www.w3.org/TR/css-syntax-3/#percentage-token-diagram>
+    Percentage(Numeric),
+    /// <https://www.w3.org/TR/css-syntax-3/#dimension-token-diagram>
+    Dimension(Numeric, String),
     /// <https://www.w3.org/TR/css-syntax-3/#colon-token-diagram>
     Colon,
     /// <https://www.w3.org/TR/css-syntax-3/#semicolon-token-diagram>
     SemiColon,
+    /// <https://www.w3.org/TR/css-syntax-3/#comma-token-diagram>
+    Comma,
+    /// <https://www.w3.org/TR/css-syntax-3/#open-square-token-diagram>
+    OpenSquare,
+    /// <https://www.w3.org/TR/css-syntax-3/#close-square-token-diagram>
+    CloseSquare,
     /// <https://www.w3.org/TR/css-syntax-3/#open-paren-token-diagram>
     OpenParenthesis,
     /// <https://www.w3.org/TR/css-syntax-3/#close-paren-token-diagram>
@@ -24,15 +37,110 @@ pub enum CssToken {
     CloseCurly,
     /// <https://www.w3.org/TR/css-syntax-3/#ident-token-diagram>
     Ident(String),
+    /// <https://www.w3.org/TR/css-syntax-3/#function-token-diagram>
+    Function(String),
     /// <https://www.w3.org/TR/css-syntax-3/#string-token-diagram>
     String(String),
+    /// <https://www.w3.org/TR/css-syntax-3/#typedef-bad-string-token>
+    BadString,
+    /// <https://www.w3.org/TR/css-syntax-3/#url-token-diagram>
+    Url(String),
+    /// <https://www.w3.org/TR/css-syntax-3/#typedef-bad-url-token>
+    BadUrl,
     /// <https://www.w3.org/TR/css-syntax-3/#at-keyword-token-diagram>
     AtKeyword(String),
     /// <https://www.w3.org/TR/css-syntax-3/#whitespace-token-diagram>
     Whitespace,
+    /// <https://www.w3.org/TR/css-syntax-3/#CDO-token-diagram>
+    Cdo,
+    /// <https://www.w3.org/TR/css-syntax-3/#CDC-token-diagram>
+    Cdc,
+}
+
+/// The type flag of a hash token.
+///
+/// <https://www.w3.org/TR/css-syntax-3/#typedef-hash-token>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashType {
+    /// The value would be a valid identifier, e.g. `#foo`.
+    Id,
+    /// e.g. `#123`.
+    Unrestricted,
+}
+
+/// The numeric value of a number, percentage or dimension token.
+///
+/// <https://www.w3.org/TR/css-syntax-3/#typedef-number-token>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Numeric {
+    pub value: f64,
+    pub type_: NumberType,
+    /// Whether the number is written with a leading `+` or `-`,
+    /// which some grammars depend on, e.g. the `An+B` microsyntax.
+    pub has_sign: bool,
+}
+
+impl Numeric {
+    /// The value if the type flag is "integer", for the grammars accepting only `<integer>`.
+    pub fn integer(&self) -> Option<i32> {
+        match self.type_ {
+            NumberType::Integer => Some(self.value as i32),
+            NumberType::Number => None,
+        }
+    }
+}
+
+/// The type flag of a numeric token.
+///
+/// <https://www.w3.org/TR/css-syntax-3/#typedef-number-token>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberType {
+    /// Written without a fraction or an exponent, e.g. `-12`.
+    Integer,
+    /// e.g. `1.0` or `1e3`.
+    Number,
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#serialization>
+///
+/// The tokens are serialized as they are, so that a token stream can be reparsed,
+/// e.g. the value of a custom property.
+/// Comments separating two tokens which would be merged are not inserted.
+impl fmt::Display for CssToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hash(name, HashType::Id) => write!(f, "#{}", serialize_identifier(name)),
+            Self::Hash(name, HashType::Unrestricted) => write!(f, "#{name}"),
+            Self::Delim(c) => write!(f, "{c}"),
+            Self::Number(n) => write!(f, "{}", n.value),
+            Self::Percentage(n) => write!(f, "{}%", n.value),
+            Self::Dimension(n, unit) => write!(f, "{}{unit}", n.value),
+            Self::Colon => write!(f, ":"),
+            Self::SemiColon => write!(f, ";"),
+            Self::Comma => write!(f, ","),
+            Self::OpenSquare => write!(f, "["),
+            Self::CloseSquare => write!(f, "]"),
+            Self::OpenParenthesis => write!(f, "("),
+            Self::CloseParenthesis => write!(f, ")"),
+            Self::OpenCurly => write!(f, "{{"),
+            Self::CloseCurly => write!(f, "}}"),
+            Self::Ident(name) => write!(f, "{}", serialize_identifier(name)),
+            Self::Function(name) => write!(f, "{}(", serialize_identifier(name)),
+            Self::String(s) => write!(f, "{}", serialize_string(s)),
+            Self::BadString | Self::BadUrl => Ok(()),
+            Self::Url(url) => write!(f, "url({})", serialize_string(url)),
+            Self::AtKeyword(name) => write!(f, "@{}", serialize_identifier(name)),
+            Self::Whitespace => write!(f, " "),
+            Self::Cdo => write!(f, "<!--"),
+            Self::Cdc => write!(f, "-->"),
+        }
+    }
 }
 
 /// <https://www.w3.org/TR/css-syntax-3/#tokenization>
+///
+/// Parse errors are not reported; the tokenizer recovers from them as the spec says,
+/// so any input can be tokenized.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CssTokenizer {
     input: Vec<char>,
@@ -43,12 +151,30 @@ impl CssTokenizer {
     /// Creates a new tokenizer for the given CSS input
     pub fn new(input: String) -> Self {
         Self {
-            input: input.chars().collect(),
+            input: preprocess(&input),
             pos: 0,
         }
     }
 }
 
+/// <https://www.w3.org/TR/css-syntax-3/#input-preprocessing>
+fn preprocess(input: &str) -> Vec<char> {
+    let mut result = Vec::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                result.push('\n');
+            }
+            '\u{c}' => result.push('\n'),
+            '\0' => result.push(char::REPLACEMENT_CHARACTER),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
 impl Iterator for CssTokenizer {
     type Item = CssToken;
 
@@ -61,51 +187,120 @@ impl CssTokenizer {
     /// <https://www.w3.org/TR/css-syntax-3/#consume-token>
     fn consume_token(&mut self) -> Option<CssToken> {
         self.consume_comments();
-        match self.consume_input() {
-            Some(c) if c.is_ascii_whitespace() => {
+        let c = self.consume_input()?;
+        let token = match c {
+            c if is_whitespace(c) => {
                 self.consume_whitespaces();
-                Some(CssToken::Whitespace)
+                CssToken::Whitespace
+            }
+            '"' | '\'' => self.consume_string_token(c),
+            '#' => {
+                if self.peek(0).is_some_and(is_ident_code_point)
+                    || is_valid_escape(self.peek(0), self.peek(1))
+                {
+                    let type_ = if self.would_start_ident_sequence(0) {
+                        HashType::Id
+                    } else {
+                        HashType::Unrestricted
+                    };
+                    CssToken::Hash(self.consume_ident_sequence(), type_)
+                } else {
+                    CssToken::Delim('#')
+                }
+            }
+            '(' => CssToken::OpenParenthesis,
+            ')' => CssToken::CloseParenthesis,
+            '+' | '.' => {
+                if self.would_start_number(-1) {
+                    self.reconsume_input();
+                    self.consume_numeric_token()
+                } else {
+                    CssToken::Delim(c)
+                }
+            }
+            ',' => CssToken::Comma,
+            '-' => {
+                if self.would_start_number(-1) {
+                    self.reconsume_input();
+                    self.consume_numeric_token()
+                } else if self.peek(0) == Some('-') && self.peek(1) == Some('>') {
+                    self.pos += 2;
+                    CssToken::Cdc
+                } else if self.would_start_ident_sequence(-1) {
+                    self.reconsume_input();
+                    self.consume_ident_like_token()
+                } else {
+                    CssToken::Delim('-')
+                }
             }
-            Some('"') => Some(self.consume_string_token()),
-            Some(':') => Some(CssToken::Colon),
-            Some(';') => Some(CssToken::SemiColon),
-            Some('{') => Some(CssToken::OpenCurly),
-            Some('}') => Some(CssToken::CloseCurly),
-            Some('.') => {
-                // TODO: If the input stream starts with a number, reconsume the current input code point, consume a numeric token, and return it.
-                Some(CssToken::Delim('.'))
+            ':' => CssToken::Colon,
+            ';' => CssToken::SemiColon,
+            '<' => {
+                if self.peek(0) == Some('!')
+                    && self.peek(1) == Some('-')
+                    && self.peek(2) == Some('-')
+                {
+                    self.pos += 3;
+                    CssToken::Cdo
+                } else {
+                    CssToken::Delim('<')
+                }
             }
-            Some('#') => {
-                // TODO: Otherwise, return a <delim-token> with its value set to the current input code point.
-                let ident = self.consume_ident_sequence();
-                Some(CssToken::Hash(ident))
+            '@' => {
+                if self.would_start_ident_sequence(0) {
+                    CssToken::AtKeyword(self.consume_ident_sequence())
+                } else {
+                    CssToken::Delim('@')
+                }
             }
-            Some(c) if c.is_ascii_digit() => {
+            '[' => CssToken::OpenSquare,
+            ']' => CssToken::CloseSquare,
+            '{' => CssToken::OpenCurly,
+            '}' => CssToken::CloseCurly,
+            '\\' => {
+                if is_valid_escape(Some('\\'), self.peek(0)) {
+                    self.reconsume_input();
+                    self.consume_ident_like_token()
+                } else {
+                    // Parse error.
+                    CssToken::Delim('\\')
+                }
+            }
+            c if c.is_ascii_digit() => {
                 self.reconsume_input();
-                Some(self.consume_numeric_token())
+                self.consume_numeric_token()
             }
-            Some(c) if is_ident_start_code_point(c) => {
+            c if is_ident_start_code_point(c) => {
                 self.reconsume_input();
-                Some(self.consume_ident_like_token())
+                self.consume_ident_like_token()
             }
-            None => None,
-            _ => unimplemented!(),
-        }
+            c => CssToken::Delim(c),
+        };
+        Some(token)
     }
 
     /// <https://www.w3.org/TR/css-syntax-3/#consume-comments>
     fn consume_comments(&mut self) {
-        // TODO: implement
+        while self.peek(0) == Some('/') && self.peek(1) == Some('*') {
+            self.pos += 2;
+            loop {
+                match self.consume_input() {
+                    Some('*') if self.peek(0) == Some('/') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    // Parse error.
+                    None => return,
+                    Some(_) => {}
+                }
+            }
+        }
     }
 
     fn consume_input(&mut self) -> Option<char> {
-        if self.pos < self.input.len() {
-            let c = self.input[self.pos];
-            self.pos += 1;
-            Some(c)
-        } else {
-            None
-        }
+        let c = self.peek(0)?;
+        self.pos += 1;
+        Some(c)
     }
 
     fn reconsume_input(&mut self) {
@@ -113,56 +308,151 @@ impl CssTokenizer {
         self.pos -= 1;
     }
 
+    /// Returns the code point `n` code points after the next input code point.
+    fn peek(&self, n: usize) -> Option<char> {
+        self.input.get(self.pos + n).copied()
+    }
+
+    /// Returns the code points starting `offset` code points after the next input code point.
+    /// An offset of -1 means the current input code point.
+    fn three_code_points(&self, offset: isize) -> [Option<char>; 3] {
+        let at = |i: isize| {
+            let index = self.pos as isize + offset + i;
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| self.input.get(index).copied())
+        };
+        [at(0), at(1), at(2)]
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#check-if-three-code-points-would-start-an-ident-sequence>
+    fn would_start_ident_sequence(&self, offset: isize) -> bool {
+        match self.three_code_points(offset) {
+            [Some('-'), second, third] => {
+                second.is_some_and(|c| is_ident_start_code_point(c) || c == '-')
+                    || is_valid_escape(second, third)
+            }
+            [Some(c), _, _] if is_ident_start_code_point(c) => true,
+            [first @ Some('\\'), second, _] => is_valid_escape(first, second),
+            _ => false,
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#check-if-three-code-points-would-start-a-number>
+    fn would_start_number(&self, offset: isize) -> bool {
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+        match self.three_code_points(offset) {
+            [Some('+' | '-'), second, third] => {
+                is_digit(second) || (second == Some('.') && is_digit(third))
+            }
+            [Some('.'), second, _] => is_digit(second),
+            [first, _, _] => is_digit(first),
+        }
+    }
+
     /// <https://www.w3.org/TR/css-syntax-3/#consume-an-ident-like-token>
     fn consume_ident_like_token(&mut self) -> CssToken {
         let string = self.consume_ident_sequence();
-        // TODO: handle url, function
-        CssToken::Ident(string)
+        if string.eq_ignore_ascii_case("url") && self.peek(0) == Some('(') {
+            self.pos += 1;
+            while self.peek(0).is_some_and(is_whitespace) && self.peek(1).is_some_and(is_whitespace)
+            {
+                self.pos += 1;
+            }
+            let is_quote = |c: Option<char>| matches!(c, Some('"' | '\''));
+            if is_quote(self.peek(0))
+                || (self.peek(0).is_some_and(is_whitespace) && is_quote(self.peek(1)))
+            {
+                CssToken::Function(string)
+            } else {
+                self.consume_url_token()
+            }
+        } else if self.peek(0) == Some('(') {
+            self.pos += 1;
+            CssToken::Function(string)
+        } else {
+            CssToken::Ident(string)
+        }
     }
 
     /// <https://www.w3.org/TR/css-syntax-3/#consume-name>
     fn consume_ident_sequence(&mut self) -> String {
         let mut result = String::new();
         loop {
-            match self.consume_input() {
+            match self.peek(0) {
                 Some(c) if is_ident_code_point(c) => {
+                    self.pos += 1;
                     result.push(c);
                 }
-                // TODO: handle escape
-                _ => {
-                    self.reconsume_input();
-                    return result;
+                Some('\\') if is_valid_escape(Some('\\'), self.peek(1)) => {
+                    self.pos += 1;
+                    result.push(self.consume_escaped_code_point());
                 }
+                _ => return result,
             }
         }
     }
 
-    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-token> (step 2)
-    fn consume_whitespaces(&mut self) {
-        loop {
-            match self.consume_input() {
-                Some(c) if c.is_ascii_whitespace() => {}
-                _ => {
-                    self.reconsume_input();
-                    break;
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-an-escaped-code-point>
+    ///
+    /// Assumes that the U+005C REVERSE SOLIDUS (\) has already been consumed.
+    fn consume_escaped_code_point(&mut self) -> char {
+        match self.consume_input() {
+            Some(c) if c.is_ascii_hexdigit() => {
+                let mut hex = String::from(c);
+                while hex.len() < 6 {
+                    match self.peek(0) {
+                        Some(c) if c.is_ascii_hexdigit() => {
+                            self.pos += 1;
+                            hex.push(c);
+                        }
+                        _ => break,
+                    }
+                }
+                if self.peek(0).is_some_and(is_whitespace) {
+                    self.pos += 1;
                 }
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|&n| n != 0)
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER)
             }
+            // Parse error.
+            None => char::REPLACEMENT_CHARACTER,
+            Some(c) => c,
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-token> (step 2)
+    fn consume_whitespaces(&mut self) {
+        while self.peek(0).is_some_and(is_whitespace) {
+            self.pos += 1;
         }
     }
 
     /// <https://www.w3.org/TR/css-syntax-3/#consume-a-string-token>
-    fn consume_string_token(&mut self) -> CssToken {
-        let ending_code_point = self.current_input();
+    fn consume_string_token(&mut self, ending_code_point: char) -> CssToken {
         let mut string = String::new();
         loop {
             match self.consume_input() {
                 Some(c) if c == ending_code_point => {
                     return CssToken::String(string);
                 }
+                // Parse error.
                 None => {
                     return CssToken::String(string);
                 }
-                // TODO: handle escape
+                Some('\n') => {
+                    // Parse error.
+                    self.reconsume_input();
+                    return CssToken::BadString;
+                }
+                Some('\\') => match self.peek(0) {
+                    None => {}
+                    Some('\n') => self.pos += 1,
+                    Some(_) => string.push(self.consume_escaped_code_point()),
+                },
                 Some(c) => {
                     string.push(c);
                 }
@@ -170,39 +460,132 @@ impl CssTokenizer {
         }
     }
 
-    fn current_input(&self) -> char {
-        assert!(self.pos > 0);
-        self.input[self.pos - 1]
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-a-url-token>
+    fn consume_url_token(&mut self) -> CssToken {
+        let mut url = String::new();
+        self.consume_whitespaces();
+        loop {
+            match self.consume_input() {
+                Some(')') => return CssToken::Url(url),
+                // Parse error.
+                None => return CssToken::Url(url),
+                Some(c) if is_whitespace(c) => {
+                    self.consume_whitespaces();
+                    match self.peek(0) {
+                        Some(')') => {
+                            self.pos += 1;
+                            return CssToken::Url(url);
+                        }
+                        // Parse error.
+                        None => return CssToken::Url(url),
+                        Some(_) => {
+                            self.consume_remnants_of_bad_url();
+                            return CssToken::BadUrl;
+                        }
+                    }
+                }
+                Some(c) if matches!(c, '"' | '\'' | '(') || is_non_printable_code_point(c) => {
+                    // Parse error.
+                    self.consume_remnants_of_bad_url();
+                    return CssToken::BadUrl;
+                }
+                Some('\\') => {
+                    if is_valid_escape(Some('\\'), self.peek(0)) {
+                        url.push(self.consume_escaped_code_point());
+                    } else {
+                        // Parse error.
+                        self.consume_remnants_of_bad_url();
+                        return CssToken::BadUrl;
+                    }
+                }
+                Some(c) => url.push(c),
+            }
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-syntax-3/#consume-the-remnants-of-a-bad-url>
+    fn consume_remnants_of_bad_url(&mut self) {
+        loop {
+            match self.consume_input() {
+                Some(')') | None => return,
+                Some('\\') if is_valid_escape(Some('\\'), self.peek(0)) => {
+                    self.consume_escaped_code_point();
+                }
+                Some(_) => {}
+            }
+        }
     }
 
     /// <https://www.w3.org/TR/css-syntax-3/#consume-a-numeric-token>
     fn consume_numeric_token(&mut self) -> CssToken {
         let number = self.consume_number();
-        // TODO: handle dimension
-        CssToken::Number(number)
+        if self.would_start_ident_sequence(0) {
+            CssToken::Dimension(number, self.consume_ident_sequence())
+        } else if self.peek(0) == Some('%') {
+            self.pos += 1;
+            CssToken::Percentage(number)
+        } else {
+            CssToken::Number(number)
+        }
     }
 
     /// <https://www.w3.org/TR/css-syntax-3/#consume-a-number>
-    fn consume_number(&mut self) -> f64 {
+    fn consume_number(&mut self) -> Numeric {
         let mut repr = String::new();
-        loop {
-            match self.consume_input() {
-                Some(c) if c.is_ascii_digit() => {
-                    repr.push(c);
-                }
+        let mut type_ = NumberType::Integer;
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
 
-                _ => {
-                    self.reconsume_input();
-                    break;
+        let mut has_sign = false;
+        if let Some(c @ ('+' | '-')) = self.peek(0) {
+            self.pos += 1;
+            repr.push(c);
+            has_sign = true;
+        }
+        self.consume_digits(&mut repr);
+
+        if self.peek(0) == Some('.') && is_digit(self.peek(1)) {
+            self.pos += 1;
+            repr.push('.');
+            self.consume_digits(&mut repr);
+            type_ = NumberType::Number;
+        }
+
+        if let Some(e @ ('e' | 'E')) = self.peek(0) {
+            let sign = self.peek(1).filter(|c| matches!(c, '+' | '-'));
+            let digit_offset = if sign.is_some() { 2 } else { 1 };
+            if is_digit(self.peek(digit_offset)) {
+                self.pos += digit_offset;
+                repr.push(e);
+                if let Some(sign) = sign {
+                    repr.push(sign);
                 }
-            };
+                self.consume_digits(&mut repr);
+                type_ = NumberType::Number;
+            }
+        }
+
+        Numeric {
+            // The representation is always a valid number here.
+            value: repr.parse().unwrap_or(0.0),
+            type_,
+            has_sign,
+        }
+    }
+
+    fn consume_digits(&mut self, repr: &mut String) {
+        while let Some(c) = self.peek(0).filter(char::is_ascii_digit) {
+            self.pos += 1;
+            repr.push(c);
         }
-        // TODO: handle fraction
-        repr.parse().unwrap()
     }
 }
 
-/// <https://www.w3.org/TR/css-syntax-3/#check-if-three-code-points-would-start-an-identifier>
+/// <https://www.w3.org/TR/css-syntax-3/#whitespace>
+fn is_whitespace(c: char) -> bool {
+    matches!(c, '\n' | '\t' | ' ')
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#ident-start-code-point>
 fn is_ident_start_code_point(c: char) -> bool {
     c.is_ascii_alphabetic() || !c.is_ascii() || c == '_'
 }
@@ -212,6 +595,16 @@ fn is_ident_code_point(c: char) -> bool {
     is_ident_start_code_point(c) || c.is_ascii_digit() || c == '-'
 }
 
+/// <https://www.w3.org/TR/css-syntax-3/#non-printable-code-point>
+fn is_non_printable_code_point(c: char) -> bool {
+    matches!(c, '\0'..='\u{8}' | '\u{b}' | '\u{e}'..='\u{1f}' | '\u{7f}')
+}
+
+/// <https://www.w3.org/TR/css-syntax-3/#check-if-two-code-points-are-a-valid-escape>
+fn is_valid_escape(first: Option<char>, second: Option<char>) -> bool {
+    first == Some('\\') && second != Some('\n')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,7 +656,7 @@ mod tests {
             CssToken::Ident("font-size".to_string()),
             CssToken::Colon,
             CssToken::Whitespace,
-            CssToken::Number(40.0),
+            CssToken::Number(integer(40.0)),
             CssToken::SemiColon,
             CssToken::Whitespace,
             CssToken::Ident("color".to_string()),
@@ -279,4 +672,183 @@ mod tests {
         }
         assert_eq!(None, t.next());
     }
+
+    fn tokenize(style: &str) -> Vec<CssToken> {
+        CssTokenizer::new(style.to_string()).collect()
+    }
+
+    fn integer(value: f64) -> Numeric {
+        Numeric {
+            value,
+            type_: NumberType::Integer,
+            has_sign: false,
+        }
+    }
+
+    fn number(value: f64) -> Numeric {
+        Numeric {
+            value,
+            type_: NumberType::Number,
+            has_sign: false,
+        }
+    }
+
+    fn signed(numeric: Numeric) -> Numeric {
+        Numeric {
+            has_sign: true,
+            ..numeric
+        }
+    }
+
+    #[test]
+    fn test_comments_and_escapes() {
+        use CssToken::*;
+        assert_eq!(
+            tokenize(
+                r#"/* comment */a/**/b \31 23 "a\"b\
+c" /* unterminated"#
+            ),
+            [
+                Ident("a".to_string()),
+                Ident("b".to_string()),
+                Whitespace,
+                Ident("123".to_string()),
+                Whitespace,
+                String("a\"bc".to_string()),
+                Whitespace,
+            ]
+        );
+        assert_eq!(
+            tokenize(r"\0 \110000 \D800 \"),
+            [Ident("\u{fffd}\u{fffd}\u{fffd}\u{fffd}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        use CssToken::*;
+        assert_eq!(
+            tokenize("12 +.5 -3.25e2 1e-1 10% 2.5em -1px 4e 1-2 .a"),
+            [
+                Number(integer(12.0)),
+                Whitespace,
+                Number(signed(number(0.5))),
+                Whitespace,
+                Number(signed(number(-325.0))),
+                Whitespace,
+                Number(number(0.1)),
+                Whitespace,
+                Percentage(integer(10.0)),
+                Whitespace,
+                Dimension(number(2.5), "em".to_string()),
+                Whitespace,
+                Dimension(signed(integer(-1.0)), "px".to_string()),
+                Whitespace,
+                Dimension(integer(4.0), "e".to_string()),
+                Whitespace,
+                Number(integer(1.0)),
+                Number(signed(integer(-2.0))),
+                Whitespace,
+                Delim('.'),
+                Ident("a".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_functions_and_urls() {
+        use CssToken::*;
+        assert_eq!(
+            tokenize(r#"rgb(1,2) url( a.png ) URL("b.png") url(c d) url(e"f) url("#),
+            [
+                Function("rgb".to_string()),
+                Number(integer(1.0)),
+                Comma,
+                Number(integer(2.0)),
+                CloseParenthesis,
+                Whitespace,
+                Url("a.png".to_string()),
+                Whitespace,
+                Function("URL".to_string()),
+                String("b.png".to_string()),
+                CloseParenthesis,
+                Whitespace,
+                BadUrl,
+                Whitespace,
+                BadUrl,
+                Whitespace,
+                Url("".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_tokens() {
+        use CssToken::*;
+        assert_eq!(
+            tokenize("<!-- #id #1x # @media @ [a] -- --x --> 'c' ! \"a\nb"),
+            [
+                Cdo,
+                Whitespace,
+                Hash("id".to_string(), HashType::Id),
+                Whitespace,
+                Hash("1x".to_string(), HashType::Unrestricted),
+                Whitespace,
+                Delim('#'),
+                Whitespace,
+                AtKeyword("media".to_string()),
+                Whitespace,
+                Delim('@'),
+                Whitespace,
+                OpenSquare,
+                Ident("a".to_string()),
+                CloseSquare,
+                Whitespace,
+                Ident("--".to_string()),
+                Whitespace,
+                Ident("--x".to_string()),
+                Whitespace,
+                Cdc,
+                Whitespace,
+                String("c".to_string()),
+                Whitespace,
+                Delim('!'),
+                Whitespace,
+                BadString,
+                Whitespace,
+                Ident("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_panic() {
+        let inputs = [
+            "",
+            "\\",
+            "#",
+            "-",
+            "+",
+            ".",
+            "@",
+            "<!",
+            "url(",
+            "url(\\",
+            "url(a\\",
+            "\"\\",
+            "'",
+            "/*",
+            "/",
+            "1e",
+            "1e+",
+            "1.",
+            "--\\",
+            "\r\n\u{c}\0",
+            "}}}{{{",
+            "a(b[c{",
+        ];
+        for input in inputs {
+            tokenize(input);
+        }
+    }
 }
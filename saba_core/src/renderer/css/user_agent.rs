@@ -0,0 +1,30 @@
+//! <https://www.w3.org/TR/css-cascade-4/#cascade-origin-ua>
+
+use alloc::string::ToString;
+
+use super::cssom::CssStyleSheet;
+use super::parser::parse_css_stylesheet;
+
+const USER_AGENT_STYLESHEET: &str = include_str!("user_agent.css");
+
+/// Parses the user-agent stylesheet.
+///
+/// This is expected to be called once when the browser starts, and the result shared among pages.
+pub fn user_agent_stylesheet() -> CssStyleSheet {
+    parse_css_stylesheet(USER_AGENT_STYLESHEET.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let stylesheet = user_agent_stylesheet();
+        // Every rule has a valid selector.
+        assert_eq!(
+            stylesheet.css_rules.len(),
+            USER_AGENT_STYLESHEET.matches('{').count()
+        );
+    }
+}
@@ -1,11 +1,688 @@
+use alloc::format;
 use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::calc::CalcNode;
+use super::custom_properties::UnparsedValue;
+use super::easing::EasingFunction;
+use super::parser::{self, trim_whitespace};
+use super::token::{CssToken, Numeric};
 
 /// <https://www.w3.org/TR/css-values-4/#component-types>
 ///
 /// This type represents the syntax of CSS values,
 /// defined in <https://www.w3.org/TR/css-values-4/>.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ComponentValue {
     /// <https://www.w3.org/TR/css-values-4/#keywords>
+    ///
+    /// The keyword is in lowercase, as CSS keywords are case-insensitive.
     Keyword(String),
+    /// <https://www.w3.org/TR/css-values-4/#lengths>
+    Length(Length),
+    /// <https://www.w3.org/TR/css-values-4/#percentages>
+    Percentage(f64),
+    /// <https://www.w3.org/TR/css-values-4/#numbers>
+    Number(f64),
+    /// <https://www.w3.org/TR/css-color-4/#color-type>
+    Color(Color),
+    /// <https://www.w3.org/TR/css-values-4/#urls>
+    Url(String),
+    /// <https://www.w3.org/TR/css-values-4/#strings>
+    String(String),
+    /// <https://www.w3.org/TR/css-values-4/#custom-idents>
+    ///
+    /// A user-defined identifier, e.g. the name of `@keyframes`, which is case-sensitive.
+    CustomIdent(String),
+    /// <https://www.w3.org/TR/css-values-4/#time>, in seconds.
+    Time(f64),
+    /// <https://www.w3.org/TR/css-easing-1/#typedef-easing-function>
+    Easing(EasingFunction),
+    /// A value containing `var()`, which is parsed after the substitution.
+    ///
+    /// <https://www.w3.org/TR/css-variables-1/#using-variables>
+    ///
+    /// It is also the value of a custom property, which is never parsed.
+    Unparsed(UnparsedValue),
+    /// <https://www.w3.org/TR/css-values-4/#math>
+    ///
+    /// A math function, which is simplified but not evaluated.
+    Calc(CalcNode),
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-css-component-value>
+impl fmt::Display for ComponentValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keyword(keyword) => write!(f, "{keyword}"),
+            Self::Length(length) => write!(f, "{length}"),
+            Self::Percentage(p) => write!(f, "{p}%"),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Color(color) => write!(f, "{color}"),
+            Self::Url(url) => write!(f, "url({})", serialize_string(url)),
+            Self::String(s) => write!(f, "{}", serialize_string(s)),
+            Self::CustomIdent(ident) => write!(f, "{}", serialize_identifier(ident)),
+            Self::Time(s) => write!(f, "{s}s"),
+            Self::Easing(easing) => write!(f, "{easing}"),
+            Self::Calc(calc) => write!(f, "{calc}"),
+            Self::Unparsed(value) => {
+                write!(f, "{}", parser::serialize_component_values(&value.tokens))
+            }
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-an-identifier>
+pub fn serialize_identifier(s: &str) -> String {
+    let mut serialized = String::new();
+    let chars: Vec<char> = s.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        let escape_code_point = matches!(c, '\u{1}'..='\u{1f}' | '\u{7f}')
+            || (i == 0 && c.is_ascii_digit())
+            || (i == 1 && c.is_ascii_digit() && chars[0] == '-');
+        match c {
+            '\0' => serialized.push('\u{FFFD}'),
+            c if escape_code_point => serialized.push_str(&format!("\\{:x} ", c as u32)),
+            '-' if chars.len() == 1 => serialized.push_str("\\-"),
+            c if c >= '\u{80}' || c == '-' || c == '_' || c.is_ascii_alphanumeric() => {
+                serialized.push(c)
+            }
+            c => {
+                serialized.push('\\');
+                serialized.push(c);
+            }
+        }
+    }
+    serialized
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-string>
+pub fn serialize_string(s: &str) -> String {
+    let mut serialized = String::from('"');
+    for c in s.chars() {
+        match c {
+            '\0' => serialized.push('\u{FFFD}'),
+            '\u{1}'..='\u{1f}' | '\u{7f}' => serialized.push_str(&format!("\\{:x} ", c as u32)),
+            '"' | '\\' => {
+                serialized.push('\\');
+                serialized.push(c);
+            }
+            c => serialized.push(c),
+        }
+    }
+    serialized.push('"');
+    serialized
+}
+
+/// <https://www.w3.org/TR/css-values-4/#lengths>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    pub value: f64,
+    pub unit: LengthUnit,
+}
+
+impl Length {
+    pub const fn px(value: f64) -> Self {
+        Self {
+            value,
+            unit: LengthUnit::Px,
+        }
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// <https://www.w3.org/TR/css-values-4/#absolute-lengths>
+    Px,
+    Cm,
+    Mm,
+    Q,
+    In,
+    Pt,
+    Pc,
+    /// <https://www.w3.org/TR/css-values-4/#font-relative-lengths>
+    Em,
+    Rem,
+    Ch,
+    /// <https://www.w3.org/TR/css-values-4/#viewport-relative-lengths>
+    Vw,
+    Vh,
+}
+
+impl LengthUnit {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Px => "px",
+            Self::Cm => "cm",
+            Self::Mm => "mm",
+            Self::Q => "q",
+            Self::In => "in",
+            Self::Pt => "pt",
+            Self::Pc => "pc",
+            Self::Em => "em",
+            Self::Rem => "rem",
+            Self::Ch => "ch",
+            Self::Vw => "vw",
+            Self::Vh => "vh",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let unit = match name.to_ascii_lowercase().as_str() {
+            "px" => Self::Px,
+            "cm" => Self::Cm,
+            "mm" => Self::Mm,
+            "q" => Self::Q,
+            "in" => Self::In,
+            "pt" => Self::Pt,
+            "pc" => Self::Pc,
+            "em" => Self::Em,
+            "rem" => Self::Rem,
+            "ch" => Self::Ch,
+            "vw" => Self::Vw,
+            "vh" => Self::Vh,
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+/// <https://www.w3.org/TR/css-values-4/#typedef-length-percentage>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthPercentage {
+    Length(Length),
+    Percentage(f64),
+}
+
+impl From<LengthPercentage> for ComponentValue {
+    fn from(value: LengthPercentage) -> Self {
+        match value {
+            LengthPercentage::Length(length) => Self::Length(length),
+            LengthPercentage::Percentage(p) => Self::Percentage(p),
+        }
+    }
+}
+
+/// Parses a `<length>`. A unitless zero is also a length.
+pub fn parse_length(value: &parser::ComponentValue) -> Option<Length> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Dimension(
+            Numeric { value, .. },
+            unit,
+        )) => Some(Length {
+            value: *value,
+            unit: LengthUnit::from_name(unit)?,
+        }),
+        parser::ComponentValue::PreservedToken(CssToken::Number(Numeric { value: n, .. }))
+            if *n == 0.0 =>
+        {
+            Some(Length::px(0.0))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a `<length-percentage>`.
+pub fn parse_length_percentage(value: &parser::ComponentValue) -> Option<LengthPercentage> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Percentage(Numeric {
+            value: p, ..
+        })) => Some(LengthPercentage::Percentage(*p)),
+        value => parse_length(value).map(LengthPercentage::Length),
+    }
+}
+
+/// Parses a `<number>`.
+pub fn parse_number(value: &parser::ComponentValue) -> Option<f64> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Number(Numeric { value: n, .. })) => {
+            Some(*n)
+        }
+        _ => None,
+    }
+}
+
+/// Parses an `<integer>`, which is a number token whose type flag is "integer", e.g. not `1.0`.
+pub fn parse_integer(value: &parser::ComponentValue) -> Option<i32> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Number(n)) => n.integer(),
+        _ => None,
+    }
+}
+
+/// Parses a `<time>` in seconds.
+pub fn parse_time(value: &parser::ComponentValue) -> Option<f64> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Dimension(
+            Numeric { value, .. },
+            unit,
+        )) => match unit.to_ascii_lowercase().as_str() {
+            "s" => Some(*value),
+            "ms" => Some(*value / 1000.0),
+            _ => None,
+        },
+        _ => None,
+    }
 }
+
+/// Parses a keyword, which is returned in lowercase.
+pub fn parse_keyword(value: &parser::ComponentValue) -> Option<String> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Ident(ident)) => {
+            Some(ident.to_ascii_lowercase())
+        }
+        _ => None,
+    }
+}
+
+/// Parses a `<url>`.
+pub fn parse_url(value: &parser::ComponentValue) -> Option<String> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Url(url)) => Some(url.clone()),
+        parser::ComponentValue::Function(parser::Function { name, value })
+            if name.eq_ignore_ascii_case("url") =>
+        {
+            match trim_whitespace(value) {
+                [parser::ComponentValue::PreservedToken(CssToken::String(url))] => {
+                    Some(url.clone())
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// <https://www.w3.org/TR/css-color-4/#color-type>
+///
+/// An sRGB color with an alpha channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const TRANSPARENT: Self = Self {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 0,
+    };
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    /// <https://www.w3.org/TR/css-color-4/#named-colors>
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Some(Self::TRANSPARENT);
+        }
+        let rgb = NAMED_COLORS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, rgb)| *rgb)?;
+        Some(Self::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+    }
+
+    /// <https://www.w3.org/TR/css-color-4/#hex-notation>
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+        let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let color = match hex.len() {
+            3 | 4 => Self {
+                r: digit(0)? * 17,
+                g: digit(1)? * 17,
+                b: digit(2)? * 17,
+                a: if hex.len() == 4 { digit(3)? * 17 } else { 255 },
+            },
+            6 | 8 => Self {
+                r: pair(0)?,
+                g: pair(2)?,
+                b: pair(4)?,
+                a: if hex.len() == 8 { pair(6)? } else { 255 },
+            },
+            _ => return None,
+        };
+        Some(color)
+    }
+}
+
+/// <https://www.w3.org/TR/cssom-1/#serialize-a-css-component-value>
+///
+/// Colors are serialized in the `rgb()` or `rgba()` notation.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.a == 255 {
+            return write!(f, "rgb({}, {}, {})", self.r, self.g, self.b);
+        }
+        // The alpha is rounded to two decimal places if that preserves the value, or three otherwise.
+        let round = |digits: f64| (self.a as f64 / 255.0 * digits + 0.5) as u64 as f64 / digits;
+        let alpha = if to_u8(round(100.0) * 255.0) == self.a {
+            round(100.0)
+        } else {
+            round(1000.0)
+        };
+        write!(f, "rgba({}, {}, {}, {})", self.r, self.g, self.b, alpha)
+    }
+}
+
+/// Parses a `<color>` other than `currentcolor`, which is a keyword resolved at computed-value time.
+pub fn parse_color(value: &parser::ComponentValue) -> Option<Color> {
+    match value {
+        parser::ComponentValue::PreservedToken(CssToken::Ident(name)) => Color::from_name(name),
+        parser::ComponentValue::PreservedToken(CssToken::Hash(hex, _)) => Color::from_hex(hex),
+        parser::ComponentValue::Function(function) => {
+            match function.name.to_ascii_lowercase().as_str() {
+                "rgb" | "rgba" => parse_rgb_function(&function.value),
+                "hsl" | "hsla" => parse_hsl_function(&function.value),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Splits the arguments of a color function into the channels and the alpha,
+/// in either the legacy comma-separated syntax or the modern space-separated syntax.
+fn color_function_arguments(
+    args: &[parser::ComponentValue],
+) -> Option<(
+    Vec<&parser::ComponentValue>,
+    Option<&parser::ComponentValue>,
+)> {
+    let is_comma =
+        |v: &parser::ComponentValue| *v == parser::ComponentValue::PreservedToken(CssToken::Comma);
+    let mut channels = Vec::new();
+    let mut alpha = None;
+    if args.iter().any(is_comma) {
+        for arg in args.split(is_comma) {
+            match non_whitespace(arg).as_slice() {
+                [value] => channels.push(*value),
+                _ => return None,
+            }
+        }
+        if channels.len() == 4 {
+            alpha = channels.pop();
+        }
+    } else {
+        let values = non_whitespace(args);
+        let mut parts =
+            values.split(|v| **v == parser::ComponentValue::PreservedToken(CssToken::Delim('/')));
+        channels = parts.next()?.to_vec();
+        if let Some(part) = parts.next() {
+            match part {
+                [value] => alpha = Some(*value),
+                _ => return None,
+            }
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+    }
+    if channels.len() != 3 {
+        return None;
+    }
+    Some((channels, alpha))
+}
+
+fn non_whitespace(values: &[parser::ComponentValue]) -> Vec<&parser::ComponentValue> {
+    values
+        .iter()
+        .filter(|v| **v != parser::ComponentValue::PreservedToken(CssToken::Whitespace))
+        .collect()
+}
+
+/// <https://www.w3.org/TR/css-color-4/#typedef-alpha-value>
+fn parse_alpha(value: Option<&parser::ComponentValue>) -> Option<u8> {
+    let alpha = match value {
+        None => 1.0,
+        Some(parser::ComponentValue::PreservedToken(CssToken::Number(Numeric {
+            value: n,
+            ..
+        }))) => *n,
+        Some(parser::ComponentValue::PreservedToken(CssToken::Percentage(Numeric {
+            value: p,
+            ..
+        }))) => *p / 100.0,
+        _ => return None,
+    };
+    Some(to_u8(alpha * 255.0))
+}
+
+fn to_u8(value: f64) -> u8 {
+    // Rounds half up. `as` saturates out-of-range values.
+    (value + 0.5) as u8
+}
+
+/// <https://www.w3.org/TR/css-color-4/#rgb-functions>
+fn parse_rgb_function(args: &[parser::ComponentValue]) -> Option<Color> {
+    let (channels, alpha) = color_function_arguments(args)?;
+    let channel = |value: &parser::ComponentValue| match value {
+        parser::ComponentValue::PreservedToken(CssToken::Number(Numeric { value: n, .. })) => {
+            Some(to_u8(*n))
+        }
+        parser::ComponentValue::PreservedToken(CssToken::Percentage(Numeric {
+            value: p, ..
+        })) => Some(to_u8(*p * 255.0 / 100.0)),
+        _ => None,
+    };
+    Some(Color {
+        r: channel(channels[0])?,
+        g: channel(channels[1])?,
+        b: channel(channels[2])?,
+        a: parse_alpha(alpha)?,
+    })
+}
+
+/// <https://www.w3.org/TR/css-color-4/#the-hsl-notation>
+fn parse_hsl_function(args: &[parser::ComponentValue]) -> Option<Color> {
+    let (channels, alpha) = color_function_arguments(args)?;
+
+    // <https://www.w3.org/TR/css-values-4/#angles>
+    let hue = match channels[0] {
+        parser::ComponentValue::PreservedToken(CssToken::Number(Numeric { value: n, .. })) => *n,
+        parser::ComponentValue::PreservedToken(CssToken::Dimension(
+            Numeric { value: n, .. },
+            unit,
+        )) => match unit.to_ascii_lowercase().as_str() {
+            "deg" => *n,
+            "grad" => *n * 360.0 / 400.0,
+            "rad" => *n * 180.0 / core::f64::consts::PI,
+            "turn" => *n * 360.0,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let percentage = |value: &parser::ComponentValue| match value {
+        parser::ComponentValue::PreservedToken(
+            CssToken::Percentage(Numeric { value: p, .. })
+            | CssToken::Number(Numeric { value: p, .. }),
+        ) => Some(p.clamp(0.0, 100.0) / 100.0),
+        _ => None,
+    };
+    let saturation = percentage(channels[1])?;
+    let lightness = percentage(channels[2])?;
+
+    // <https://www.w3.org/TR/css-color-4/#hsl-to-rgb>
+    let hue = hue - 360.0 * (hue / 360.0) as i64 as f64;
+    let hue = if hue < 0.0 { hue + 360.0 } else { hue };
+    let f = |n: f64| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    Some(Color {
+        r: to_u8(f(0.0) * 255.0),
+        g: to_u8(f(8.0) * 255.0),
+        b: to_u8(f(4.0) * 255.0),
+        a: parse_alpha(alpha)?,
+    })
+}
+
+/// <https://www.w3.org/TR/css-color-4/#named-colors>
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
@@ -1 +1,9 @@
+pub mod character_data;
+pub mod event;
+pub mod mutation_observer;
 pub mod node;
+pub mod range;
+pub mod selection;
+#[cfg(test)]
+pub mod test_util;
+pub mod traversal;
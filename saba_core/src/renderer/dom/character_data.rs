@@ -0,0 +1,296 @@
+//! <https://dom.spec.whatwg.org/#interface-characterdata>
+//!
+//! Offsets and counts are in UTF-16 code units, as in the DOM spec.
+//! Rust strings cannot hold lone surrogates, so an operation that splits a surrogate pair
+//! leaves U+FFFD in place of each half, which keeps the length in code units unchanged.
+
+use core::cell::RefCell;
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{DomException, Error};
+
+use super::mutation_observer::{queue_mutation_record, MutationRecord, MutationRecordType};
+use super::node::{Node, NodeData};
+
+impl Node {
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn character_data(&self) -> &str {
+        match &self.data {
+            NodeData::Text(text) => text,
+            _ => panic!("not a text node"),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-data>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn set_data(node: Rc<RefCell<Node>>, data: String) {
+        let length = node.borrow().length();
+        Self::replace_data(node, 0, length, &data).expect("offset 0 is always in range");
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-cd-substring>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn substring_data(&self, offset: usize, count: usize) -> Result<String, Error> {
+        let data = self.character_data().encode_utf16().collect::<Vec<_>>();
+        if offset > data.len() {
+            return Err(Error::Dom(DomException::IndexSizeError));
+        }
+        let end = offset.saturating_add(count).min(data.len());
+        Ok(String::from_utf16_lossy(&data[offset..end]))
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-appenddata>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn append_data(node: Rc<RefCell<Node>>, data: &str) {
+        let length = node.borrow().length();
+        Self::replace_data(node, length, 0, data).expect("the end of data is always in range");
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-insertdata>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn insert_data(node: Rc<RefCell<Node>>, offset: usize, data: &str) -> Result<(), Error> {
+        Self::replace_data(node, offset, 0, data)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-characterdata-deletedata>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn delete_data(node: Rc<RefCell<Node>>, offset: usize, count: usize) -> Result<(), Error> {
+        Self::replace_data(node, offset, count, "")
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-cd-replace>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn replace_data(
+        node: Rc<RefCell<Node>>,
+        offset: usize,
+        count: usize,
+        data: &str,
+    ) -> Result<(), Error> {
+        let (old_value, count) = {
+            let mut node_ref = node.borrow_mut();
+            let NodeData::Text(text) = &mut node_ref.data else {
+                panic!("not a text node");
+            };
+
+            let mut units = text.encode_utf16().collect::<Vec<_>>();
+            let length = units.len();
+            if offset > length {
+                return Err(Error::Dom(DomException::IndexSizeError));
+            }
+            let count = count.min(length - offset);
+
+            units.splice(offset..offset + count, data.encode_utf16());
+            (
+                core::mem::replace(text, String::from_utf16_lossy(&units)),
+                count,
+            )
+        };
+
+        let data_length = data.encode_utf16().count();
+        for range in node.borrow().live_ranges() {
+            range
+                .borrow_mut()
+                .replaced_data(&node, offset, count, data_length);
+        }
+
+        queue_mutation_record(MutationRecord {
+            type_: MutationRecordType::CharacterData,
+            target: Rc::clone(&node),
+            added_nodes: Vec::new(),
+            removed_nodes: Vec::new(),
+            previous_sibling: None,
+            next_sibling: None,
+            attribute_name: None,
+            old_value: Some(old_value),
+        });
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-text-split>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn split_text(node: Rc<RefCell<Node>>, offset: usize) -> Result<Rc<RefCell<Node>>, Error> {
+        let length = node.borrow().length();
+        if offset > length {
+            return Err(Error::Dom(DomException::IndexSizeError));
+        }
+        let count = length - offset;
+        let new_data = node.borrow().substring_data(offset, count)?;
+
+        let document = node.borrow().node_document();
+        let new_node = Node::create_text_node(document, new_data);
+
+        let parent = node.borrow().parent.upgrade();
+        if let Some(parent) = parent {
+            let next_sibling = node.borrow().next_sibling.clone();
+            Node::insert_before(Rc::clone(&parent), Rc::clone(&new_node), next_sibling)?;
+
+            for range in node.borrow().live_ranges() {
+                range
+                    .borrow_mut()
+                    .split_text(&node, &new_node, &parent, offset);
+            }
+        }
+
+        Self::replace_data(node, offset, count, "")?;
+        Ok(new_node)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-text-wholetext>
+    ///
+    /// Panics if the node is not a text node.
+    pub fn whole_text(node: Rc<RefCell<Node>>) -> String {
+        let is_text = |node: &Rc<RefCell<Node>>| matches!(node.borrow().data, NodeData::Text(_));
+
+        // https://dom.spec.whatwg.org/#contiguous-text-nodes
+        let mut first = Rc::clone(&node);
+        loop {
+            let previous = first.borrow().previous_sibling.upgrade();
+            match previous {
+                Some(previous) if is_text(&previous) => first = previous,
+                _ => break,
+            }
+        }
+
+        let mut whole_text = String::new();
+        let mut current = Some(first);
+        while let Some(text) = current.filter(is_text) {
+            whole_text += text.borrow().character_data();
+            current = text.borrow().next_sibling.clone();
+        }
+        whole_text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::renderer::dom::mutation_observer::{MutationObserver, MutationObserverInit};
+    use crate::renderer::dom::node::{ElementKind, Window};
+    use crate::renderer::dom::test_util::{element, parse};
+
+    use super::*;
+
+    fn text_node(window: &Rc<RefCell<Window>>, data: &str) -> Rc<RefCell<Node>> {
+        Node::create_text_node(window.borrow().document(), data.into())
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        let window = Window::new();
+        // "あ" is one code unit and "🍣" is two.
+        let text = text_node(&window, "あ🍣い");
+        assert_eq!(text.borrow().length(), 4);
+        assert_eq!(text.borrow().substring_data(1, 2), Ok("🍣".into()));
+
+        Node::insert_data(Rc::clone(&text), 3, "x").unwrap();
+        assert_eq!(text.borrow().character_data(), "あ🍣xい");
+
+        Node::delete_data(Rc::clone(&text), 1, 2).unwrap();
+        assert_eq!(text.borrow().character_data(), "あxい");
+
+        Node::replace_data(Rc::clone(&text), 1, 100, "🍣").unwrap();
+        assert_eq!(text.borrow().character_data(), "あ🍣");
+
+        Node::append_data(Rc::clone(&text), "う");
+        assert_eq!(text.borrow().character_data(), "あ🍣う");
+
+        // Splitting a surrogate pair.
+        Node::delete_data(Rc::clone(&text), 1, 1).unwrap();
+        assert_eq!(text.borrow().character_data(), "あ\u{fffd}う");
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let window = Window::new();
+        let text = text_node(&window, "abc");
+        let index_size_error = Error::Dom(DomException::IndexSizeError);
+
+        assert_eq!(
+            text.borrow().substring_data(4, 0).unwrap_err(),
+            index_size_error
+        );
+        assert_eq!(
+            Node::insert_data(Rc::clone(&text), 4, "x").unwrap_err(),
+            index_size_error
+        );
+        assert_eq!(
+            Node::delete_data(Rc::clone(&text), 4, 1).unwrap_err(),
+            index_size_error
+        );
+        assert_eq!(
+            Node::split_text(Rc::clone(&text), 4).unwrap_err(),
+            index_size_error
+        );
+        assert_eq!(text.borrow().character_data(), "abc");
+    }
+
+    #[test]
+    fn test_split_text() {
+        let window = parse("<html><head></head><body><p>hello world</p></body></html>");
+        let p = element(&window, ElementKind::P);
+        let text = p.borrow().first_child.clone().unwrap();
+
+        let new_node = Node::split_text(Rc::clone(&text), 6).unwrap();
+        assert_eq!(text.borrow().character_data(), "hello ");
+        assert_eq!(new_node.borrow().character_data(), "world");
+        assert!(Rc::ptr_eq(
+            &text.borrow().next_sibling.clone().unwrap(),
+            &new_node
+        ));
+        assert!(Rc::ptr_eq(&p.borrow().last_child().unwrap(), &new_node));
+
+        assert_eq!(Node::whole_text(Rc::clone(&text)), "hello world");
+        assert_eq!(Node::whole_text(new_node), "hello world");
+        assert_eq!(p.borrow().inner_html(), "hello world");
+    }
+
+    #[test]
+    fn test_split_text_without_parent() {
+        let window = Window::new();
+        let text = text_node(&window, "abc");
+        let new_node = Node::split_text(Rc::clone(&text), 1).unwrap();
+        assert_eq!(text.borrow().character_data(), "a");
+        assert_eq!(new_node.borrow().character_data(), "bc");
+        assert!(new_node.borrow().parent.upgrade().is_none());
+        assert_eq!(Node::whole_text(text), "a");
+    }
+
+    #[test]
+    fn test_character_data_records() {
+        let window = Window::new();
+        let text = text_node(&window, "abc");
+        let observer = MutationObserver::new(&window, Box::new(|_, _| {}));
+        observer
+            .observe(
+                &text,
+                MutationObserverInit {
+                    character_data: Some(true),
+                    character_data_old_value: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        Node::append_data(Rc::clone(&text), "d");
+        Node::delete_data(Rc::clone(&text), 0, 1).unwrap();
+
+        let old_values = observer
+            .take_records()
+            .into_iter()
+            .map(|record| record.old_value.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(old_values, ["abc", "abcd"]);
+    }
+}
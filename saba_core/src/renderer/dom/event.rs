@@ -0,0 +1,540 @@
+//! <https://dom.spec.whatwg.org/#events>
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Debug;
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{DomException, Error};
+use crate::url::Url;
+
+use super::node::{Element, ElementKind, Node, NodeData};
+
+/// <https://dom.spec.whatwg.org/#interface-event>
+#[derive(Debug, Clone)]
+pub struct Event {
+    type_: String,
+    target: Option<Rc<RefCell<Node>>>,
+    current_target: Option<Rc<RefCell<Node>>>,
+    event_phase: EventPhase,
+    bubbles: bool,
+    cancelable: bool,
+    /// <https://dom.spec.whatwg.org/#stop-propagation-flag>
+    stop_propagation_flag: bool,
+    /// <https://dom.spec.whatwg.org/#stop-immediate-propagation-flag>
+    stop_immediate_propagation_flag: bool,
+    /// <https://dom.spec.whatwg.org/#canceled-flag>
+    canceled_flag: bool,
+    /// <https://dom.spec.whatwg.org/#in-passive-listener-flag>
+    in_passive_listener_flag: bool,
+    /// <https://dom.spec.whatwg.org/#dispatch-flag>
+    dispatch_flag: bool,
+}
+
+/// <https://dom.spec.whatwg.org/#dictdef-eventinit>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventInit {
+    pub bubbles: bool,
+    pub cancelable: bool,
+}
+
+/// <https://dom.spec.whatwg.org/#dom-event-eventphase>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPhase {
+    None,
+    CapturingPhase,
+    AtTarget,
+    BubblingPhase,
+}
+
+impl Event {
+    /// <https://dom.spec.whatwg.org/#dom-event-event>
+    pub fn new(type_: &str, init: EventInit) -> Self {
+        Self {
+            type_: type_.into(),
+            target: None,
+            current_target: None,
+            event_phase: EventPhase::None,
+            bubbles: init.bubbles,
+            cancelable: init.cancelable,
+            stop_propagation_flag: false,
+            stop_immediate_propagation_flag: false,
+            canceled_flag: false,
+            in_passive_listener_flag: false,
+            dispatch_flag: false,
+        }
+    }
+
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    pub fn target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.target.clone()
+    }
+
+    pub fn current_target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.current_target.clone()
+    }
+
+    pub fn event_phase(&self) -> EventPhase {
+        self.event_phase
+    }
+
+    pub fn bubbles(&self) -> bool {
+        self.bubbles
+    }
+
+    pub fn cancelable(&self) -> bool {
+        self.cancelable
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-event-stoppropagation>
+    pub fn stop_propagation(&mut self) {
+        self.stop_propagation_flag = true;
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-event-stopimmediatepropagation>
+    pub fn stop_immediate_propagation(&mut self) {
+        self.stop_propagation_flag = true;
+        self.stop_immediate_propagation_flag = true;
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-event-preventdefault>
+    pub fn prevent_default(&mut self) {
+        // https://dom.spec.whatwg.org/#set-the-canceled-flag
+        if self.cancelable && !self.in_passive_listener_flag {
+            self.canceled_flag = true;
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-event-defaultprevented>
+    pub fn default_prevented(&self) -> bool {
+        self.canceled_flag
+    }
+}
+
+/// The callback of an event listener.
+///
+/// Listeners are compared by the identity of this `Rc` on removal,
+/// in the same way as JS functions are compared by reference.
+pub type EventListenerCallback = Rc<dyn Fn(&mut Event)>;
+
+/// <https://dom.spec.whatwg.org/#concept-event-listener>
+#[derive(Clone)]
+pub struct EventListener {
+    type_: String,
+    callback: EventListenerCallback,
+    capture: bool,
+    passive: bool,
+    once: bool,
+    /// Shared with the clones taken during dispatch, so that removing a listener takes effect immediately.
+    removed: Rc<Cell<bool>>,
+}
+
+impl Debug for EventListener {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventListener")
+            .field("type_", &self.type_)
+            .field("capture", &self.capture)
+            .field("passive", &self.passive)
+            .field("once", &self.once)
+            .field("removed", &self.removed.get())
+            .finish()
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#dictdef-addeventlisteneroptions>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddEventListenerOptions {
+    pub capture: bool,
+    pub passive: bool,
+    pub once: bool,
+}
+
+impl Node {
+    /// <https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener>
+    pub fn add_event_listener(
+        &mut self,
+        type_: &str,
+        callback: EventListenerCallback,
+        options: AddEventListenerOptions,
+    ) {
+        // https://dom.spec.whatwg.org/#add-an-event-listener
+        let exists = self.event_listener_list.iter().any(|listener| {
+            listener.type_ == type_
+                && Rc::ptr_eq(&listener.callback, &callback)
+                && listener.capture == options.capture
+        });
+        if exists {
+            return;
+        }
+        self.event_listener_list.push(EventListener {
+            type_: type_.into(),
+            callback,
+            capture: options.capture,
+            passive: options.passive,
+            once: options.once,
+            removed: Rc::new(Cell::new(false)),
+        });
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-eventtarget-removeeventlistener>
+    pub fn remove_event_listener(
+        &mut self,
+        type_: &str,
+        callback: &EventListenerCallback,
+        capture: bool,
+    ) {
+        // https://dom.spec.whatwg.org/#remove-an-event-listener
+        self.event_listener_list.retain(|listener| {
+            let matched = listener.type_ == type_
+                && Rc::ptr_eq(&listener.callback, callback)
+                && listener.capture == capture;
+            if matched {
+                listener.removed.set(true);
+            }
+            !matched
+        });
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-eventtarget-dispatchevent>
+    ///
+    /// Returns false if the event was canceled.
+    pub fn dispatch_event(target: Rc<RefCell<Node>>, event: &mut Event) -> Result<bool, Error> {
+        if event.dispatch_flag {
+            return Err(Error::Dom(DomException::InvalidStateError));
+        }
+        Ok(dispatch(&target, event))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/links.html#the-a-element:activation-behaviour>
+    fn has_activation_behavior(&self) -> bool {
+        matches!(
+            &self.data,
+            NodeData::Element(element @ Element { kind: ElementKind::A, .. })
+                if element.get_attribute("href").is_some()
+        )
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#concept-event-dispatch>
+///
+/// Shadow trees, related targets and touch targets are not supported.
+fn dispatch(target: &Rc<RefCell<Node>>, event: &mut Event) -> bool {
+    event.dispatch_flag = true;
+    event.target = Some(Rc::clone(target));
+
+    // <https://html.spec.whatwg.org/multipage/webappapis.html#fire-a-click-event> would create a MouseEvent,
+    // but we don't distinguish event interfaces yet.
+    let is_activation_event = event.type_ == "click";
+    let path = Node::inclusive_ancestors(Rc::clone(target)).collect::<Vec<_>>();
+    let activation_target = if is_activation_event {
+        // The parents are considered only if the event bubbles.
+        let candidates = if event.bubbles { &path[..] } else { &path[..1] };
+        candidates
+            .iter()
+            .find(|node| node.borrow().has_activation_behavior())
+            .cloned()
+    } else {
+        None
+    };
+
+    for node in path.iter().rev() {
+        event.event_phase = if Rc::ptr_eq(node, target) {
+            EventPhase::AtTarget
+        } else {
+            EventPhase::CapturingPhase
+        };
+        invoke(node, event, Phase::Capturing);
+    }
+    for node in path.iter() {
+        if Rc::ptr_eq(node, target) {
+            event.event_phase = EventPhase::AtTarget;
+        } else {
+            if !event.bubbles {
+                continue;
+            }
+            event.event_phase = EventPhase::BubblingPhase;
+        }
+        invoke(node, event, Phase::Bubbling);
+    }
+
+    event.event_phase = EventPhase::None;
+    event.current_target = None;
+    event.dispatch_flag = false;
+    event.stop_propagation_flag = false;
+    event.stop_immediate_propagation_flag = false;
+
+    if let Some(activation_target) = activation_target {
+        if !event.canceled_flag {
+            run_activation_behavior(&activation_target);
+        }
+    }
+
+    !event.canceled_flag
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Capturing,
+    Bubbling,
+}
+
+/// <https://dom.spec.whatwg.org/#concept-event-listener-invoke>
+fn invoke(node: &Rc<RefCell<Node>>, event: &mut Event, phase: Phase) {
+    if event.stop_propagation_flag {
+        return;
+    }
+    event.current_target = Some(Rc::clone(node));
+
+    // Listeners added during the dispatch must not run, so the list is cloned beforehand.
+    // The node must not be borrowed while running listeners since they may mutate the node.
+    let listeners = node.borrow().event_listener_list.clone();
+
+    // https://dom.spec.whatwg.org/#concept-event-listener-inner-invoke
+    for listener in listeners {
+        if listener.removed.get() || listener.type_ != event.type_ {
+            continue;
+        }
+        if (phase == Phase::Capturing && !listener.capture)
+            || (phase == Phase::Bubbling && listener.capture)
+        {
+            continue;
+        }
+        if listener.once {
+            node.borrow_mut().remove_event_listener(
+                &listener.type_,
+                &listener.callback,
+                listener.capture,
+            );
+        }
+        if listener.passive {
+            event.in_passive_listener_flag = true;
+        }
+        (listener.callback)(event);
+        event.in_passive_listener_flag = false;
+        if event.stop_immediate_propagation_flag {
+            return;
+        }
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/links.html#the-a-element:activation-behaviour>
+fn run_activation_behavior(element: &Rc<RefCell<Node>>) {
+    let href = match &element.borrow().data {
+        NodeData::Element(element) => element.get_attribute("href").map(String::from),
+        _ => None,
+    };
+    let window = element.borrow().window.upgrade();
+    if let (Some(href), Some(window)) = (href, window) {
+        // https://html.spec.whatwg.org/multipage/links.html#following-hyperlinks-2
+        // The URL is parsed relative to the URL of the document, and not followed if it fails.
+        let document_url = window.borrow().url();
+        let url = match document_url {
+            Some(document_url) => document_url.join(&href),
+            None => Url::new(href).parse(),
+        };
+        if let Ok(url) = url {
+            window.borrow_mut().request_navigation(url);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::{format, vec::Vec};
+
+    use crate::renderer::dom::test_util::{element, parse};
+
+    use super::*;
+
+    fn recorder(
+        log: &Rc<RefCell<Vec<String>>>,
+        name: &'static str,
+        f: impl Fn(&mut Event) + 'static,
+    ) -> EventListenerCallback {
+        let log = Rc::clone(log);
+        Rc::new(move |event: &mut Event| {
+            log.borrow_mut()
+                .push(format!("{name}:{:?}", event.event_phase()));
+            f(event);
+        })
+    }
+
+    #[test]
+    fn test_capture_target_bubble() {
+        let window = parse("<html><head></head><body><p><a>link</a></p></body></html>");
+        let document = window.borrow().document();
+        let p = element(&window, ElementKind::P);
+        let a = element(&window, ElementKind::A);
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let capture = AddEventListenerOptions {
+            capture: true,
+            ..Default::default()
+        };
+        document.borrow_mut().add_event_listener(
+            "click",
+            recorder(&log, "document", |_| {}),
+            Default::default(),
+        );
+        document.borrow_mut().add_event_listener(
+            "click",
+            recorder(&log, "document", |_| {}),
+            capture.clone(),
+        );
+        p.borrow_mut()
+            .add_event_listener("click", recorder(&log, "p", |_| {}), Default::default());
+        a.borrow_mut()
+            .add_event_listener("click", recorder(&log, "a", |_| {}), Default::default());
+        a.borrow_mut()
+            .add_event_listener("click", recorder(&log, "a", |_| {}), capture);
+
+        let mut event = Event::new(
+            "click",
+            EventInit {
+                bubbles: true,
+                cancelable: true,
+            },
+        );
+        assert_eq!(Ok(true), Node::dispatch_event(Rc::clone(&a), &mut event));
+        assert_eq!(
+            vec![
+                "document:CapturingPhase",
+                "a:AtTarget",
+                "a:AtTarget",
+                "p:BubblingPhase",
+                "document:BubblingPhase",
+            ],
+            *log.borrow()
+        );
+        assert!(Rc::ptr_eq(&a, &event.target().unwrap()));
+        assert_eq!(EventPhase::None, event.event_phase());
+        assert!(event.current_target().is_none());
+    }
+
+    #[test]
+    fn test_stop_propagation_and_non_bubbling() {
+        let window = parse("<html><head></head><body><p><a>link</a></p></body></html>");
+        let p = element(&window, ElementKind::P);
+        let a = element(&window, ElementKind::A);
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        a.borrow_mut().add_event_listener(
+            "click",
+            recorder(&log, "a1", |e| e.stop_immediate_propagation()),
+            Default::default(),
+        );
+        a.borrow_mut().add_event_listener(
+            "click",
+            recorder(&log, "a2", |_| {}),
+            Default::default(),
+        );
+        p.borrow_mut()
+            .add_event_listener("click", recorder(&log, "p", |_| {}), Default::default());
+        p.borrow_mut()
+            .add_event_listener("focus", recorder(&log, "p", |_| {}), Default::default());
+
+        let mut event = Event::new(
+            "click",
+            EventInit {
+                bubbles: true,
+                ..Default::default()
+            },
+        );
+        Node::dispatch_event(Rc::clone(&a), &mut event).unwrap();
+        assert_eq!(vec!["a1:AtTarget"], *log.borrow());
+
+        log.borrow_mut().clear();
+        let mut event = Event::new("focus", Default::default());
+        Node::dispatch_event(Rc::clone(&a), &mut event).unwrap();
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_remove_and_once() {
+        let window = parse("<html><head></head><body><p>text</p></body></html>");
+        let p = element(&window, ElementKind::P);
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let removed = recorder(&log, "removed", |_| {});
+        p.borrow_mut()
+            .add_event_listener("test", Rc::clone(&removed), Default::default());
+        p.borrow_mut().add_event_listener(
+            "test",
+            recorder(&log, "once", |_| {}),
+            AddEventListenerOptions {
+                once: true,
+                ..Default::default()
+            },
+        );
+        p.borrow_mut()
+            .remove_event_listener("test", &removed, false);
+
+        for _ in 0..2 {
+            let mut event = Event::new("test", Default::default());
+            Node::dispatch_event(Rc::clone(&p), &mut event).unwrap();
+        }
+        assert_eq!(vec!["once:AtTarget"], *log.borrow());
+    }
+
+    #[test]
+    fn test_link_activation() {
+        let window = parse(
+            r#"<html><head></head><body><p><a href="../next">link</a> <a>no href</a></p></body></html>"#,
+        );
+        window.borrow_mut().set_url(Some(
+            Url::new("http://example.com/dir/index.html".into())
+                .parse()
+                .unwrap(),
+        ));
+        let a = element(&window, ElementKind::A);
+        let text = a.borrow().first_child.clone().unwrap();
+
+        // A click on the text inside <a href> follows the link, resolved against the document URL.
+        let mut event = Event::new(
+            "click",
+            EventInit {
+                bubbles: true,
+                cancelable: true,
+            },
+        );
+        Node::dispatch_event(Rc::clone(&text), &mut event).unwrap();
+        let url = window.borrow_mut().take_navigation_request().unwrap();
+        assert_eq!(
+            ("example.com".to_string(), "next".to_string()),
+            (url.host(), url.path())
+        );
+
+        // A click which doesn't bubble activates only the target itself.
+        let mut event = Event::new("click", Default::default());
+        Node::dispatch_event(Rc::clone(&text), &mut event).unwrap();
+        assert_eq!(None, window.borrow_mut().take_navigation_request());
+        let mut event = Event::new("click", Default::default());
+        Node::dispatch_event(Rc::clone(&a), &mut event).unwrap();
+        assert!(window.borrow_mut().take_navigation_request().is_some());
+
+        // preventDefault() cancels the activation behavior.
+        a.borrow_mut().add_event_listener(
+            "click",
+            Rc::new(|e: &mut Event| e.prevent_default()),
+            Default::default(),
+        );
+        let mut event = Event::new(
+            "click",
+            EventInit {
+                bubbles: true,
+                cancelable: true,
+            },
+        );
+        assert_eq!(
+            Ok(false),
+            Node::dispatch_event(Rc::clone(&text), &mut event)
+        );
+        assert_eq!(None, window.borrow_mut().take_navigation_request());
+    }
+}
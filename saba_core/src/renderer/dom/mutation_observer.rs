@@ -0,0 +1,478 @@
+//! <https://dom.spec.whatwg.org/#mutation-observers>
+
+use core::cell::RefCell;
+use core::fmt::Debug;
+
+use alloc::boxed::Box;
+use alloc::rc::{Rc, Weak};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{DomException, Error};
+
+use super::node::{Node, Window};
+
+/// <https://dom.spec.whatwg.org/#callbackdef-mutationcallback>
+pub type MutationCallback = Box<dyn FnMut(Vec<MutationRecord>, &Rc<MutationObserver>)>;
+
+/// <https://dom.spec.whatwg.org/#interface-mutationobserver>
+pub struct MutationObserver {
+    window: Weak<RefCell<Window>>,
+    callback: RefCell<MutationCallback>,
+    /// <https://dom.spec.whatwg.org/#mutationobserver-record-queue>
+    record_queue: RefCell<Vec<MutationRecord>>,
+    /// The spec keeps a "registered observer list" on each node, and a "node list" on each observer.
+    /// We only keep the latter, together with the options, so that nodes don't need to own observers.
+    registrations: RefCell<Vec<RegisteredObserver>>,
+}
+
+/// <https://dom.spec.whatwg.org/#registered-observer>
+#[derive(Debug, Clone)]
+struct RegisteredObserver {
+    node: Weak<RefCell<Node>>,
+    options: MutationObserverInit,
+    /// <https://dom.spec.whatwg.org/#transient-registered-observer>
+    transient: bool,
+}
+
+/// <https://dom.spec.whatwg.org/#dictdef-mutationobserverinit>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MutationObserverInit {
+    pub child_list: bool,
+    pub attributes: Option<bool>,
+    pub character_data: Option<bool>,
+    pub subtree: bool,
+    pub attribute_old_value: Option<bool>,
+    pub character_data_old_value: Option<bool>,
+    pub attribute_filter: Option<Vec<String>>,
+}
+
+/// <https://dom.spec.whatwg.org/#interface-mutationrecord>
+#[derive(Debug, Clone)]
+pub struct MutationRecord {
+    pub type_: MutationRecordType,
+    pub target: Rc<RefCell<Node>>,
+    pub added_nodes: Vec<Rc<RefCell<Node>>>,
+    pub removed_nodes: Vec<Rc<RefCell<Node>>>,
+    pub previous_sibling: Option<Rc<RefCell<Node>>>,
+    pub next_sibling: Option<Rc<RefCell<Node>>>,
+    pub attribute_name: Option<String>,
+    pub old_value: Option<String>,
+}
+
+/// <https://dom.spec.whatwg.org/#dom-mutationrecord-type>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationRecordType {
+    Attributes,
+    CharacterData,
+    ChildList,
+}
+
+impl Debug for MutationObserver {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MutationObserver")
+            .field("record_queue", &self.record_queue.borrow().len())
+            .field("registrations", &self.registrations.borrow().len())
+            .finish()
+    }
+}
+
+impl MutationObserver {
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-mutationobserver>
+    ///
+    /// The observer observes as long as the caller holds it, since the window refers to it weakly.
+    pub fn new(window: &Rc<RefCell<Window>>, callback: MutationCallback) -> Rc<Self> {
+        Rc::new(Self {
+            window: Rc::downgrade(window),
+            callback: RefCell::new(callback),
+            record_queue: RefCell::new(Vec::new()),
+            registrations: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-observe>
+    pub fn observe(
+        self: &Rc<Self>,
+        target: &Rc<RefCell<Node>>,
+        options: MutationObserverInit,
+    ) -> Result<(), Error> {
+        let mut options = options;
+        if options.attributes.is_none()
+            && (options.attribute_old_value.is_some() || options.attribute_filter.is_some())
+        {
+            options.attributes = Some(true);
+        }
+        if options.character_data.is_none() && options.character_data_old_value.is_some() {
+            options.character_data = Some(true);
+        }
+        if !options.child_list
+            && options.attributes != Some(true)
+            && options.character_data != Some(true)
+        {
+            return Err(Error::Dom(DomException::TypeError(
+                "one of childList, attributes or characterData must be true".into(),
+            )));
+        }
+        if options.attribute_old_value == Some(true) && options.attributes == Some(false) {
+            return Err(Error::Dom(DomException::TypeError(
+                "attributeOldValue requires attributes".into(),
+            )));
+        }
+        if options.attribute_filter.is_some() && options.attributes == Some(false) {
+            return Err(Error::Dom(DomException::TypeError(
+                "attributeFilter requires attributes".into(),
+            )));
+        }
+        if options.character_data_old_value == Some(true) && options.character_data == Some(false) {
+            return Err(Error::Dom(DomException::TypeError(
+                "characterDataOldValue requires characterData".into(),
+            )));
+        }
+
+        let mut registrations = self.registrations.borrow_mut();
+        let target_weak = Rc::downgrade(target);
+        if let Some(registered) = registrations
+            .iter_mut()
+            .find(|r| !r.transient && Weak::ptr_eq(&r.node, &target_weak))
+        {
+            // The spec only drops the transient observers whose source is this registration,
+            // but we don't track the source, so all of them are dropped.
+            registered.options = options;
+            registrations.retain(|r| !r.transient);
+        } else {
+            registrations.push(RegisteredObserver {
+                node: target_weak,
+                options,
+                transient: false,
+            });
+            drop(registrations);
+            if let Some(window) = self.window.upgrade() {
+                window.borrow_mut().register_mutation_observer(self);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-disconnect>
+    pub fn disconnect(&self) {
+        self.registrations.borrow_mut().clear();
+        self.record_queue.borrow_mut().clear();
+        if let Some(window) = self.window.upgrade() {
+            window.borrow_mut().unregister_mutation_observer(self);
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-mutationobserver-takerecords>
+    pub fn take_records(&self) -> Vec<MutationRecord> {
+        core::mem::take(&mut self.record_queue.borrow_mut())
+    }
+
+    /// Yields the options of the registrations on `ancestors`, which are the inclusive ancestors of a target,
+    /// together with whether the registration is on the target itself.
+    fn interested_options<'a>(
+        registrations: &'a [RegisteredObserver],
+        ancestors: &[Rc<RefCell<Node>>],
+    ) -> impl Iterator<Item = (bool, &'a MutationObserverInit)> + 'a {
+        let ancestors = ancestors.iter().map(Rc::downgrade).collect::<Vec<_>>();
+        registrations.iter().filter_map(move |r| {
+            ancestors
+                .iter()
+                .position(|a| Weak::ptr_eq(a, &r.node))
+                .map(|i| (i == 0, &r.options))
+        })
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#queue-a-mutation-record>
+pub(crate) fn queue_mutation_record(record: MutationRecord) {
+    let Some(window) = record.target.borrow().window.upgrade() else {
+        return;
+    };
+    let observers = window.borrow().mutation_observers();
+    if observers.is_empty() {
+        return;
+    }
+
+    let nodes = Node::inclusive_ancestors(Rc::clone(&record.target)).collect::<Vec<_>>();
+    let mut interested_observers: Vec<(Rc<MutationObserver>, Option<String>)> = Vec::new();
+    for observer in observers {
+        let registrations = observer.registrations.borrow();
+        for (is_target, options) in MutationObserver::interested_options(&registrations, &nodes) {
+            let excluded = (!is_target && !options.subtree)
+                || (record.type_ == MutationRecordType::Attributes
+                    && options.attributes != Some(true))
+                || (record.type_ == MutationRecordType::Attributes
+                    && options.attribute_filter.as_ref().is_some_and(|filter| {
+                        !filter
+                            .iter()
+                            .any(|name| Some(name) == record.attribute_name.as_ref())
+                    }))
+                || (record.type_ == MutationRecordType::CharacterData
+                    && options.character_data != Some(true))
+                || (record.type_ == MutationRecordType::ChildList && !options.child_list);
+            if excluded {
+                continue;
+            }
+
+            let entry = match interested_observers
+                .iter_mut()
+                .find(|(o, _)| Rc::ptr_eq(o, &observer))
+            {
+                Some(entry) => entry,
+                None => {
+                    interested_observers.push((Rc::clone(&observer), None));
+                    interested_observers.last_mut().unwrap()
+                }
+            };
+            if (record.type_ == MutationRecordType::Attributes
+                && options.attribute_old_value == Some(true))
+                || (record.type_ == MutationRecordType::CharacterData
+                    && options.character_data_old_value == Some(true))
+            {
+                entry.1 = record.old_value.clone();
+            }
+        }
+    }
+
+    for (observer, mapped_old_value) in interested_observers {
+        let mut record = record.clone();
+        record.old_value = mapped_old_value;
+        observer.record_queue.borrow_mut().push(record);
+        window
+            .borrow_mut()
+            .append_pending_mutation_observer(observer);
+    }
+
+    Window::queue_mutation_observer_microtask(&window);
+}
+
+/// <https://dom.spec.whatwg.org/#queue-a-tree-mutation-record>
+pub(crate) fn queue_tree_mutation_record(
+    target: &Rc<RefCell<Node>>,
+    added_nodes: Vec<Rc<RefCell<Node>>>,
+    removed_nodes: Vec<Rc<RefCell<Node>>>,
+    previous_sibling: Option<Rc<RefCell<Node>>>,
+    next_sibling: Option<Rc<RefCell<Node>>>,
+) {
+    assert!(!added_nodes.is_empty() || !removed_nodes.is_empty());
+    queue_mutation_record(MutationRecord {
+        type_: MutationRecordType::ChildList,
+        target: Rc::clone(target),
+        added_nodes,
+        removed_nodes,
+        previous_sibling,
+        next_sibling,
+        attribute_name: None,
+        old_value: None,
+    });
+}
+
+/// Appends transient registered observers to `node` that is being removed from `parent`.
+///
+/// <https://dom.spec.whatwg.org/#concept-node-remove> (step 13)
+pub(crate) fn add_transient_registered_observers(
+    parent: &Rc<RefCell<Node>>,
+    node: &Rc<RefCell<Node>>,
+) {
+    let Some(window) = node.borrow().window.upgrade() else {
+        return;
+    };
+    let observers = window.borrow().mutation_observers();
+    let ancestors = Node::inclusive_ancestors(Rc::clone(parent)).collect::<Vec<_>>();
+    for observer in observers {
+        let transient =
+            MutationObserver::interested_options(&observer.registrations.borrow(), &ancestors)
+                .filter(|(_, options)| options.subtree)
+                .map(|(_, options)| RegisteredObserver {
+                    node: Rc::downgrade(node),
+                    options: options.clone(),
+                    transient: true,
+                })
+                .collect::<Vec<_>>();
+        observer.registrations.borrow_mut().extend(transient);
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#notify-mutation-observers>
+pub(crate) fn notify_mutation_observers(window: &Rc<RefCell<Window>>) {
+    let notify_set = window.borrow_mut().take_pending_mutation_observers();
+    for observer in notify_set {
+        let records = observer.take_records();
+        observer
+            .registrations
+            .borrow_mut()
+            .retain(|registered| !registered.transient);
+        if !records.is_empty() {
+            (observer.callback.borrow_mut())(records, &observer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::renderer::dom::node::{ElementKind, Namespace, NodeData};
+    use crate::renderer::dom::test_util::{element, parse};
+
+    use super::*;
+
+    /// Returns the observer, which observes while held, and the records it receives.
+    fn observe(
+        window: &Rc<RefCell<Window>>,
+        target: &Rc<RefCell<Node>>,
+        options: MutationObserverInit,
+    ) -> (Rc<MutationObserver>, Rc<RefCell<Vec<MutationRecord>>>) {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let observer = MutationObserver::new(window, {
+            let received = Rc::clone(&received);
+            Box::new(move |records, _| received.borrow_mut().extend(records))
+        });
+        observer.observe(target, options).unwrap();
+        (observer, received)
+    }
+
+    #[test]
+    fn test_child_list() {
+        let window = parse("<html><head></head><body><p>text</p></body></html>");
+        let document = window.borrow().document();
+        let body = element(&window, ElementKind::Body);
+        let p = element(&window, ElementKind::P);
+
+        let (_observer, received) = observe(
+            &window,
+            &body,
+            MutationObserverInit {
+                child_list: true,
+                ..Default::default()
+            },
+        );
+
+        let a = Node::create_element(Rc::clone(&document), "a", Namespace::Html);
+        Node::append_child(Rc::clone(&body), Rc::clone(&a));
+        Node::remove_child(Rc::clone(&body), Rc::clone(&p)).unwrap();
+
+        // Records are delivered at the microtask checkpoint, not synchronously.
+        assert!(received.borrow().is_empty());
+        Window::perform_microtask_checkpoint(&window);
+
+        let received = received.borrow();
+        assert_eq!(2, received.len());
+        assert_eq!(MutationRecordType::ChildList, received[0].type_);
+        assert!(Rc::ptr_eq(&a, &received[0].added_nodes[0]));
+        assert!(Rc::ptr_eq(
+            &p,
+            received[0].previous_sibling.as_ref().unwrap()
+        ));
+        assert!(Rc::ptr_eq(&p, &received[1].removed_nodes[0]));
+        assert!(Rc::ptr_eq(&a, received[1].next_sibling.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn test_subtree_attributes_and_character_data() {
+        let window = parse("<html><head></head><body><p>text</p></body></html>");
+        let document = window.borrow().document();
+        let p = element(&window, ElementKind::P);
+        let text = p.borrow().first_child.clone().unwrap();
+
+        let (_observer, received) = observe(
+            &window,
+            &document,
+            MutationObserverInit {
+                subtree: true,
+                attribute_old_value: Some(true),
+                character_data_old_value: Some(true),
+                attribute_filter: Some(vec!["id".into()]),
+                ..Default::default()
+            },
+        );
+
+        Node::set_attribute(Rc::clone(&p), "class", "ignored").unwrap();
+        Node::set_attribute(Rc::clone(&p), "id", "first").unwrap();
+        Node::set_attribute(Rc::clone(&p), "id", "second").unwrap();
+        Node::set_data(Rc::clone(&text), "new text".into());
+        Window::perform_microtask_checkpoint(&window);
+
+        let received = received.borrow();
+        let summary = received
+            .iter()
+            .map(|r| (r.type_, r.attribute_name.clone(), r.old_value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (MutationRecordType::Attributes, Some("id".into()), None),
+                (
+                    MutationRecordType::Attributes,
+                    Some("id".into()),
+                    Some("first".into())
+                ),
+                (MutationRecordType::CharacterData, None, Some("text".into())),
+            ],
+            summary
+        );
+        assert_eq!(&NodeData::Text("new text".into()), text.borrow().data());
+    }
+
+    #[test]
+    fn test_transient_observer() {
+        let window = parse("<html><head></head><body><p><a>text</a></p></body></html>");
+        let body = element(&window, ElementKind::Body);
+        let p = element(&window, ElementKind::P);
+        let a = element(&window, ElementKind::A);
+
+        let (_observer, received) = observe(
+            &window,
+            &body,
+            MutationObserverInit {
+                attributes: Some(true),
+                subtree: true,
+                ..Default::default()
+            },
+        );
+
+        // Mutations in a removed subtree are still observed until the next checkpoint.
+        Node::remove(Rc::clone(&p));
+        Node::set_attribute(Rc::clone(&a), "href", "/").unwrap();
+        Window::perform_microtask_checkpoint(&window);
+        assert_eq!(1, received.borrow().len());
+
+        Node::set_attribute(a, "href", "/next").unwrap();
+        Window::perform_microtask_checkpoint(&window);
+        assert_eq!(1, received.borrow().len());
+    }
+
+    #[test]
+    fn test_dropped_observer() {
+        let window = parse("<html><head></head><body></body></html>");
+        let body = element(&window, ElementKind::Body);
+        let received = Rc::new(RefCell::new(Vec::new()));
+        // The callback captures the window, as the callbacks of scripts do.
+        let observer = MutationObserver::new(&window, {
+            let window = Rc::clone(&window);
+            let received = Rc::clone(&received);
+            Box::new(move |records, _| {
+                let _ = &window;
+                received.borrow_mut().extend(records);
+            })
+        });
+        observer
+            .observe(
+                &body,
+                MutationObserverInit {
+                    attributes: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // The window doesn't keep the observer alive, so dropping it frees the observer.
+        let weak = Rc::downgrade(&observer);
+        drop(observer);
+        assert!(weak.upgrade().is_none());
+
+        Node::set_attribute(body, "id", "x").unwrap();
+        Window::perform_microtask_checkpoint(&window);
+        assert!(received.borrow().is_empty());
+    }
+}
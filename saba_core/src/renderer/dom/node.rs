@@ -1,13 +1,26 @@
-use core::{cell::RefCell, fmt::Display};
+use core::{cell::RefCell, fmt::Display, mem};
 
 use alloc::{
+    collections::VecDeque,
     format,
     rc::{Rc, Weak},
-    string::{String, ToString},
+    string::String,
+    vec,
     vec::Vec,
 };
 
+use crate::error::{DomException, Error};
 use crate::renderer::html::{self, attribute::Attribute};
+use crate::url::Url;
+
+use super::event::EventListener;
+use super::mutation_observer::{
+    self, add_transient_registered_observers, queue_mutation_record, queue_tree_mutation_record,
+    MutationObserver, MutationRecord, MutationRecordType,
+};
+use super::range::RangeState;
+use super::selection::Selection;
+use super::traversal::NodeIteratorState;
 
 #[derive(Debug, Clone)]
 pub struct Node {
@@ -18,6 +31,8 @@ pub struct Node {
     pub last_child: Weak<RefCell<Node>>,
     pub previous_sibling: Weak<RefCell<Node>>,
     pub next_sibling: Option<Rc<RefCell<Node>>>,
+    /// <https://dom.spec.whatwg.org/#eventtarget-event-listener-list>
+    pub(crate) event_listener_list: Vec<EventListener>,
 }
 
 // sababook did a custom implementation of PartialEq for Node, but I'm not sure why it's necessary.
@@ -31,6 +46,35 @@ pub enum NodeData {
 #[derive(Debug, Clone)]
 pub struct Window {
     document: Rc<RefCell<Node>>,
+    /// Mutation observers that observe any node of the document.
+    /// They are held weakly, since their callbacks usually capture the window,
+    /// so an observer stops observing once its owner drops it.
+    mutation_observers: Vec<Weak<MutationObserver>>,
+    /// <https://dom.spec.whatwg.org/#mutation-observer-list>
+    pending_mutation_observers: Vec<Rc<MutationObserver>>,
+    /// <https://dom.spec.whatwg.org/#mutation-observer-microtask-queued>
+    mutation_observer_microtask_queued: bool,
+    /// <https://html.spec.whatwg.org/multipage/webappapis.html#microtask-queue>
+    microtask_queue: VecDeque<Microtask>,
+    /// <https://html.spec.whatwg.org/multipage/webappapis.html#performing-a-microtask-checkpoint>
+    performing_microtask_checkpoint: bool,
+    /// <https://dom.spec.whatwg.org/#document-nodeiterator-collection>
+    node_iterators: Vec<Weak<RefCell<NodeIteratorState>>>,
+    /// <https://dom.spec.whatwg.org/#concept-live-range>
+    ranges: Vec<Weak<RefCell<RangeState>>>,
+    /// <https://w3c.github.io/selection-api/#dfn-selection>
+    selection: Rc<RefCell<Selection>>,
+    /// <https://dom.spec.whatwg.org/#concept-document-url>
+    url: Option<Url>,
+    /// The URL requested by following a hyperlink, which is to be navigated by the page.
+    navigation_request: Option<Url>,
+}
+
+/// <https://html.spec.whatwg.org/multipage/webappapis.html#microtask>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Microtask {
+    /// <https://dom.spec.whatwg.org/#notify-mutation-observers>
+    NotifyMutationObservers,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,12 +98,21 @@ impl Element {
     pub fn tag_name(&self) -> &ElementKind {
         &self.kind
     }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-getattribute>
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.value.as_str())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ElementKind {
     Html,
     Head,
+    Link,
     Style,
     Body,
     P,
@@ -81,6 +134,7 @@ impl ElementKind {
             Namespace::Html => match local_name {
                 "html" => Some(Self::Html),
                 "head" => Some(Self::Head),
+                "link" => Some(Self::Link),
                 "style" => Some(Self::Style),
                 "body" => Some(Self::Body),
                 "p" => Some(Self::P),
@@ -105,6 +159,7 @@ impl ElementKind {
         match self {
             Self::Html => Namespace::Html,
             Self::Head => Namespace::Html,
+            Self::Link => Namespace::Html,
             Self::Style => Namespace::Html,
             Self::Body => Namespace::Html,
             Self::P => Namespace::Html,
@@ -136,6 +191,7 @@ impl TryFrom<&str> for ElementKind {
         match value {
             "html" => Ok(Self::Html),
             "head" => Ok(Self::Head),
+            "link" => Ok(Self::Link),
             "style" => Ok(Self::Style),
             "body" => Ok(Self::Body),
             "p" => Ok(Self::P),
@@ -154,6 +210,7 @@ impl Display for ElementKind {
         match self {
             Self::Html => write!(f, "html"),
             Self::Head => write!(f, "head"),
+            Self::Link => write!(f, "link"),
             Self::Style => write!(f, "style"),
             Self::Body => write!(f, "body"),
             Self::P => write!(f, "p"),
@@ -184,13 +241,141 @@ impl Window {
             last_child: Weak::new(),
             previous_sibling: Weak::new(),
             next_sibling: None,
+            event_listener_list: Vec::new(),
         }));
         let window = Rc::new(RefCell::new(Self {
             document: Rc::clone(&document),
+            mutation_observers: Vec::new(),
+            pending_mutation_observers: Vec::new(),
+            mutation_observer_microtask_queued: false,
+            microtask_queue: VecDeque::new(),
+            performing_microtask_checkpoint: false,
+            node_iterators: Vec::new(),
+            ranges: Vec::new(),
+            selection: Rc::new(RefCell::new(Selection::new(Rc::downgrade(&document)))),
+            url: None,
+            navigation_request: None,
         }));
         document.borrow_mut().window = Rc::downgrade(&window);
         window
     }
+
+    pub(crate) fn register_mutation_observer(&mut self, observer: &Rc<MutationObserver>) {
+        self.mutation_observers.retain(|o| o.strong_count() > 0);
+        let observer = Rc::downgrade(observer);
+        if !self
+            .mutation_observers
+            .iter()
+            .any(|o| Weak::ptr_eq(o, &observer))
+        {
+            self.mutation_observers.push(observer);
+        }
+    }
+
+    pub(crate) fn unregister_mutation_observer(&mut self, observer: &MutationObserver) {
+        self.mutation_observers
+            .retain(|o| o.strong_count() > 0 && !core::ptr::eq(o.as_ptr(), observer));
+    }
+
+    /// The observers still alive.
+    pub(crate) fn mutation_observers(&self) -> Vec<Rc<MutationObserver>> {
+        self.mutation_observers
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    pub(crate) fn append_pending_mutation_observer(&mut self, observer: Rc<MutationObserver>) {
+        if !self
+            .pending_mutation_observers
+            .iter()
+            .any(|o| Rc::ptr_eq(o, &observer))
+        {
+            self.pending_mutation_observers.push(observer);
+        }
+    }
+
+    pub(crate) fn take_pending_mutation_observers(&mut self) -> Vec<Rc<MutationObserver>> {
+        self.mutation_observer_microtask_queued = false;
+        mem::take(&mut self.pending_mutation_observers)
+    }
+
+    /// <https://dom.spec.whatwg.org/#queue-a-mutation-observer-compound-microtask>
+    pub(crate) fn queue_mutation_observer_microtask(window: &Rc<RefCell<Self>>) {
+        let mut window = window.borrow_mut();
+        if window.mutation_observer_microtask_queued {
+            return;
+        }
+        window.mutation_observer_microtask_queued = true;
+        window
+            .microtask_queue
+            .push_back(Microtask::NotifyMutationObservers);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/webappapis.html#perform-a-microtask-checkpoint>
+    pub fn perform_microtask_checkpoint(window: &Rc<RefCell<Self>>) {
+        if window.borrow().performing_microtask_checkpoint {
+            return;
+        }
+        window.borrow_mut().performing_microtask_checkpoint = true;
+
+        // Microtasks may queue other microtasks, so the window must not be borrowed while running them.
+        loop {
+            let microtask = window.borrow_mut().microtask_queue.pop_front();
+            match microtask {
+                Some(Microtask::NotifyMutationObservers) => {
+                    mutation_observer::notify_mutation_observers(window)
+                }
+                None => break,
+            }
+        }
+
+        window.borrow_mut().performing_microtask_checkpoint = false;
+    }
+
+    pub(crate) fn register_node_iterator(&mut self, iterator: &Rc<RefCell<NodeIteratorState>>) {
+        self.node_iterators.retain(|i| i.strong_count() > 0);
+        self.node_iterators.push(Rc::downgrade(iterator));
+    }
+
+    fn node_iterators(&self) -> Vec<Rc<RefCell<NodeIteratorState>>> {
+        self.node_iterators
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    pub(crate) fn register_range(&mut self, range: &Rc<RefCell<RangeState>>) {
+        self.ranges.retain(|r| r.strong_count() > 0);
+        self.ranges.push(Rc::downgrade(range));
+    }
+
+    fn live_ranges(&self) -> Vec<Rc<RefCell<RangeState>>> {
+        self.ranges.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-document-getselection>
+    pub fn get_selection(&self) -> Rc<RefCell<Selection>> {
+        Rc::clone(&self.selection)
+    }
+
+    /// The URL of the document, against which the URLs in it are resolved.
+    pub fn url(&self) -> Option<Url> {
+        self.url.clone()
+    }
+
+    pub fn set_url(&mut self, url: Option<Url>) {
+        self.url = url;
+    }
+
+    pub(crate) fn request_navigation(&mut self, url: Url) {
+        self.navigation_request = Some(url);
+    }
+
+    /// Takes the URL requested by following a hyperlink, if any.
+    pub fn take_navigation_request(&mut self) -> Option<Url> {
+        self.navigation_request.take()
+    }
 }
 
 impl Node {
@@ -198,6 +383,55 @@ impl Node {
         &self.data
     }
 
+    pub const ELEMENT_NODE: u16 = 1;
+    pub const TEXT_NODE: u16 = 3;
+    pub const DOCUMENT_NODE: u16 = 9;
+
+    /// <https://dom.spec.whatwg.org/#dom-node-nodetype>
+    pub fn node_type(&self) -> u16 {
+        match self.data {
+            NodeData::Element(_) => Self::ELEMENT_NODE,
+            NodeData::Text(_) => Self::TEXT_NODE,
+            NodeData::Document => Self::DOCUMENT_NODE,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-length>
+    ///
+    /// The length of text nodes is in UTF-16 code units.
+    pub fn length(&self) -> usize {
+        match &self.data {
+            NodeData::Text(text) => text.encode_utf16().count(),
+            _ => self.children().count(),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-index>
+    pub fn index(&self) -> usize {
+        let mut index = 0;
+        let mut sibling = self.previous_sibling.upgrade();
+        while let Some(node) = sibling {
+            index += 1;
+            sibling = node.borrow().previous_sibling.upgrade();
+        }
+        index
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-root>
+    pub fn root(node: Rc<RefCell<Node>>) -> Rc<RefCell<Node>> {
+        Self::inclusive_ancestors(Rc::clone(&node))
+            .last()
+            .unwrap_or(node)
+    }
+
+    /// The live ranges of the node document.
+    pub(crate) fn live_ranges(&self) -> Vec<Rc<RefCell<RangeState>>> {
+        self.window
+            .upgrade()
+            .map(|window| window.borrow().live_ranges())
+            .unwrap_or_default()
+    }
+
     pub fn node_document(&self) -> Rc<RefCell<Node>> {
         self.window
             .upgrade()
@@ -226,6 +460,7 @@ impl Node {
             last_child: Weak::new(),
             previous_sibling: Weak::new(),
             next_sibling: None,
+            event_listener_list: Vec::new(),
         };
         Rc::new(RefCell::new(element))
     }
@@ -239,11 +474,42 @@ impl Node {
             last_child: Weak::new(),
             previous_sibling: Weak::new(),
             next_sibling: None,
+            event_listener_list: Vec::new(),
         }));
 
         text_node
     }
 
+    /// <https://dom.spec.whatwg.org/#concept-node-clone>
+    ///
+    /// Event listeners are not copied, as in the spec.
+    /// Panics if the node is a document.
+    pub fn clone_node(node: Rc<RefCell<Node>>, deep: bool) -> Rc<RefCell<Node>> {
+        let copy = {
+            let node = node.borrow();
+            if let NodeData::Document = node.data {
+                panic!("cloning a document is not supported");
+            }
+            Rc::new(RefCell::new(Node {
+                data: node.data.clone(),
+                window: node.window.clone(),
+                parent: Weak::new(),
+                first_child: None,
+                last_child: Weak::new(),
+                previous_sibling: Weak::new(),
+                next_sibling: None,
+                event_listener_list: Vec::new(),
+            }))
+        };
+
+        if deep {
+            for child in node.borrow().children() {
+                Self::append_child(Rc::clone(&copy), Self::clone_node(child, true));
+            }
+        }
+        copy
+    }
+
     pub fn extend_element_attributes(&mut self, attributes: Vec<Attribute>) {
         match &mut self.data {
             NodeData::Element(element) => {
@@ -262,58 +528,348 @@ impl Node {
         }
     }
 
-    /// append the node as a child of self, after the last child
+    /// <https://dom.spec.whatwg.org/#dom-node-appendchild>
+    ///
+    /// Panics if the node cannot be a child of the parent.
     pub fn append_child(parent: Rc<RefCell<Node>>, node: Rc<RefCell<Node>>) {
-        // TODO: refer to the specificaton
+        Self::pre_insert(&parent, &node, None).expect("failed to append a child");
+    }
 
-        let mut parent_ref = parent.borrow_mut();
-        let mut node_ref = node.borrow_mut();
+    /// <https://dom.spec.whatwg.org/#dom-node-insertbefore>
+    pub fn insert_before(
+        parent: Rc<RefCell<Node>>,
+        node: Rc<RefCell<Node>>,
+        child: Option<Rc<RefCell<Node>>>,
+    ) -> Result<Rc<RefCell<Node>>, Error> {
+        Self::pre_insert(&parent, &node, child)
+    }
 
-        assert!(Weak::ptr_eq(&parent_ref.window, &node_ref.window));
+    /// <https://dom.spec.whatwg.org/#dom-node-removechild>
+    pub fn remove_child(
+        parent: Rc<RefCell<Node>>,
+        child: Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, Error> {
+        // https://dom.spec.whatwg.org/#concept-node-pre-remove
+        if !child
+            .borrow()
+            .parent
+            .upgrade()
+            .is_some_and(|p| Rc::ptr_eq(&p, &parent))
+        {
+            return Err(Error::Dom(DomException::NotFoundError));
+        }
+        Self::remove_node(&child, false);
+        Ok(child)
+    }
 
-        // assert that this node is has no parent or siblings
-        assert!(node_ref.parent.upgrade().is_none());
-        assert!(node_ref.previous_sibling.upgrade().is_none());
-        assert!(node_ref.next_sibling.is_none());
+    /// <https://dom.spec.whatwg.org/#dom-node-replacechild>
+    pub fn replace_child(
+        parent: Rc<RefCell<Node>>,
+        node: Rc<RefCell<Node>>,
+        child: Rc<RefCell<Node>>,
+    ) -> Result<Rc<RefCell<Node>>, Error> {
+        // https://dom.spec.whatwg.org/#concept-node-replace
+        Self::ensure_pre_insertion_validity(&parent, &node, Some(&child), Some(&child))?;
 
-        if let Some(last_child) = Weak::clone(&parent_ref.last_child).upgrade() {
-            parent_ref.last_child = Rc::downgrade(&node);
+        let mut reference_child = child.borrow().next_sibling.clone();
+        if reference_child
+            .as_ref()
+            .is_some_and(|r| Rc::ptr_eq(r, &node))
+        {
+            reference_child = node.borrow().next_sibling.clone();
+        }
+        let previous_sibling = child.borrow().previous_sibling.upgrade();
+        let removed_nodes = if child.borrow().parent.upgrade().is_some() {
+            Self::remove_node(&child, true);
+            vec![Rc::clone(&child)]
+        } else {
+            Vec::new()
+        };
+        Self::insert(&parent, &node, reference_child.clone(), true);
+        queue_tree_mutation_record(
+            &parent,
+            vec![node],
+            removed_nodes,
+            previous_sibling,
+            reference_child,
+        );
 
-            last_child.borrow_mut().next_sibling = Some(Rc::clone(&node));
+        Ok(child)
+    }
 
-            node_ref.parent = Rc::downgrade(&parent);
-            node_ref.previous_sibling = Rc::downgrade(&last_child);
-        } else {
-            // make the node the first child
-            assert!(parent_ref.first_child.is_none());
-            parent_ref.first_child = Some(Rc::clone(&node));
-            parent_ref.last_child = Rc::downgrade(&node);
+    /// <https://dom.spec.whatwg.org/#dom-childnode-remove>
+    pub fn remove(node: Rc<RefCell<Node>>) {
+        if node.borrow().parent.upgrade().is_some() {
+            Self::remove_node(&node, false);
+        }
+    }
 
-            node_ref.parent = Rc::downgrade(&parent);
+    /// <https://dom.spec.whatwg.org/#concept-node-ensure-pre-insertion-validity>
+    ///
+    /// `replaced` is the child to be replaced, which is ignored on checking the element count of a document.
+    fn ensure_pre_insertion_validity(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: Option<&Rc<RefCell<Node>>>,
+        replaced: Option<&Rc<RefCell<Node>>>,
+    ) -> Result<(), Error> {
+        if let NodeData::Text(_) = parent.borrow().data {
+            return Err(Error::Dom(DomException::HierarchyRequestError));
+        }
+        if Self::inclusive_ancestors(Rc::clone(parent)).any(|a| Rc::ptr_eq(&a, node)) {
+            return Err(Error::Dom(DomException::HierarchyRequestError));
+        }
+        if let Some(child) = child {
+            if !child
+                .borrow()
+                .parent
+                .upgrade()
+                .is_some_and(|p| Rc::ptr_eq(&p, parent))
+            {
+                return Err(Error::Dom(DomException::NotFoundError));
+            }
         }
+        match (&parent.borrow().data, &node.borrow().data) {
+            (_, NodeData::Document) => Err(Error::Dom(DomException::HierarchyRequestError)),
+            (NodeData::Document, NodeData::Text(_)) => {
+                Err(Error::Dom(DomException::HierarchyRequestError))
+            }
+            (NodeData::Document, NodeData::Element(_))
+                if parent.borrow().children().any(|c| {
+                    !replaced.is_some_and(|r| Rc::ptr_eq(r, &c))
+                        && matches!(c.borrow().data, NodeData::Element(_))
+                }) =>
+            {
+                Err(Error::Dom(DomException::HierarchyRequestError))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-pre-insert>
+    fn pre_insert(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: Option<Rc<RefCell<Node>>>,
+    ) -> Result<Rc<RefCell<Node>>, Error> {
+        Self::ensure_pre_insertion_validity(parent, node, child.as_ref(), None)?;
+
+        let reference_child = match child {
+            Some(child) if Rc::ptr_eq(&child, node) => node.borrow().next_sibling.clone(),
+            child => child,
+        };
+        Self::insert(parent, node, reference_child, false);
+
+        Ok(Rc::clone(node))
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-insert>
+    fn insert(
+        parent: &Rc<RefCell<Node>>,
+        node: &Rc<RefCell<Node>>,
+        child: Option<Rc<RefCell<Node>>>,
+        suppress_observers: bool,
+    ) {
+        if let Some(child) = &child {
+            let index = child.borrow().index();
+            for range in parent.borrow().live_ranges() {
+                range.borrow_mut().inserted(parent, index, 1);
+            }
+        }
+
+        // https://dom.spec.whatwg.org/#concept-node-adopt
+        // We don't move nodes across documents, so adopting is just removing it from the old parent.
+        assert!(Weak::ptr_eq(&parent.borrow().window, &node.borrow().window));
+        if node.borrow().parent.upgrade().is_some() {
+            Self::remove_node(node, false);
+        }
+
+        let previous_sibling = match &child {
+            Some(child) => child.borrow().previous_sibling.upgrade(),
+            None => parent.borrow().last_child(),
+        };
+
+        {
+            let mut node_ref = node.borrow_mut();
+            node_ref.parent = Rc::downgrade(parent);
+            node_ref.previous_sibling = previous_sibling
+                .as_ref()
+                .map(Rc::downgrade)
+                .unwrap_or_default();
+            node_ref.next_sibling = child.clone();
+        }
+        match &previous_sibling {
+            Some(previous_sibling) => {
+                previous_sibling.borrow_mut().next_sibling = Some(Rc::clone(node))
+            }
+            None => parent.borrow_mut().first_child = Some(Rc::clone(node)),
+        }
+        match &child {
+            Some(child) => child.borrow_mut().previous_sibling = Rc::downgrade(node),
+            None => parent.borrow_mut().last_child = Rc::downgrade(node),
+        }
+
+        if !suppress_observers {
+            queue_tree_mutation_record(
+                parent,
+                vec![Rc::clone(node)],
+                Vec::new(),
+                previous_sibling,
+                child,
+            );
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-node-remove>
+    fn remove_node(node: &Rc<RefCell<Node>>, suppress_observers: bool) {
+        let parent = node
+            .borrow()
+            .parent
+            .upgrade()
+            .expect("the node to be removed must have a parent");
+
+        let index = node.borrow().index();
+        for range in node.borrow().live_ranges() {
+            range.borrow_mut().pre_remove(node, &parent, index);
+        }
+
+        let window = node.borrow().window.upgrade();
+        if let Some(window) = window {
+            let node_iterators = window.borrow().node_iterators();
+            for iterator in node_iterators {
+                iterator.borrow_mut().pre_remove(node);
+            }
+        }
+
+        let old_previous_sibling = node.borrow().previous_sibling.upgrade();
+        let old_next_sibling = node.borrow().next_sibling.clone();
+
+        match &old_previous_sibling {
+            Some(previous_sibling) => {
+                previous_sibling.borrow_mut().next_sibling = old_next_sibling.clone()
+            }
+            None => parent.borrow_mut().first_child = old_next_sibling.clone(),
+        }
+        let old_previous_sibling_weak = old_previous_sibling
+            .as_ref()
+            .map(Rc::downgrade)
+            .unwrap_or_default();
+        match &old_next_sibling {
+            Some(next_sibling) => {
+                next_sibling.borrow_mut().previous_sibling = old_previous_sibling_weak
+            }
+            None => parent.borrow_mut().last_child = old_previous_sibling_weak,
+        }
+        {
+            let mut node_ref = node.borrow_mut();
+            node_ref.parent = Weak::new();
+            node_ref.previous_sibling = Weak::new();
+            node_ref.next_sibling = None;
+        }
+
+        add_transient_registered_observers(&parent, node);
+
+        if !suppress_observers {
+            queue_tree_mutation_record(
+                &parent,
+                Vec::new(),
+                vec![Rc::clone(node)],
+                old_previous_sibling,
+                old_next_sibling,
+            );
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-setattribute>
+    ///
+    /// Panics if the node is not an element.
+    pub fn set_attribute(node: Rc<RefCell<Node>>, name: &str, value: &str) -> Result<(), Error> {
+        // https://dom.spec.whatwg.org/#valid-attribute-local-name
+        if name.is_empty()
+            || name
+                .chars()
+                .any(|c| c.is_ascii_whitespace() || ['\0', '/', '=', '>'].contains(&c))
+        {
+            return Err(Error::Dom(DomException::InvalidCharacterError));
+        }
+        // All of our elements are HTML elements in an HTML document.
+        let name = name.to_ascii_lowercase();
+
+        let old_value = match &mut node.borrow_mut().data {
+            NodeData::Element(element) => {
+                match element.attributes.iter_mut().find(|attr| attr.name == name) {
+                    Some(attr) => Some(mem::replace(&mut attr.value, value.into())),
+                    None => {
+                        element
+                            .attributes
+                            .push(Attribute::new(name.clone(), value.into()));
+                        None
+                    }
+                }
+            }
+            _ => panic!("not an element"),
+        };
+
+        Self::queue_attribute_mutation_record(&node, name, old_value);
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-element-removeattribute>
+    ///
+    /// Panics if the node is not an element.
+    pub fn remove_attribute(node: Rc<RefCell<Node>>, name: &str) {
+        let name = name.to_ascii_lowercase();
+
+        let removed = match &mut node.borrow_mut().data {
+            NodeData::Element(element) => element
+                .attributes
+                .iter()
+                .position(|attr| attr.name == name)
+                .map(|index| element.attributes.remove(index)),
+            _ => panic!("not an element"),
+        };
+
+        if let Some(removed) = removed {
+            Self::queue_attribute_mutation_record(&node, name, Some(removed.value));
+        }
+    }
+
+    fn queue_attribute_mutation_record(
+        node: &Rc<RefCell<Node>>,
+        name: String,
+        old_value: Option<String>,
+    ) {
+        queue_mutation_record(MutationRecord {
+            type_: MutationRecordType::Attributes,
+            target: Rc::clone(node),
+            added_nodes: Vec::new(),
+            removed_nodes: Vec::new(),
+            previous_sibling: None,
+            next_sibling: None,
+            attribute_name: Some(name),
+            old_value,
+        });
     }
 
     pub fn get_element_by_tag_name(
         node: Rc<RefCell<Self>>,
         tag: ElementKind,
     ) -> Option<Rc<RefCell<Self>>> {
-        if let NodeData::Element(Element { kind, .. }) = &node.borrow().data {
-            if kind == &tag {
-                return Some(node.clone());
-            }
-        }
-        node.borrow()
-            .children()
-            .find_map(|node| Self::get_element_by_tag_name(node, tag.clone()))
+        Self::preorder(node).find(|node| {
+            matches!(&node.borrow().data, NodeData::Element(Element { kind, .. }) if kind == &tag)
+        })
     }
 
+    /// <https://dom.spec.whatwg.org/#dom-node-textcontent>
     pub fn text_content(&self) -> String {
         let mut content = String::new();
         if let NodeData::Text(s) = &self.data {
             content += s;
         }
-        for node in self.children() {
-            content += &node.borrow().text_content()
+        for node in self.children().flat_map(Self::preorder) {
+            if let NodeData::Text(s) = &node.borrow().data {
+                content += s;
+            }
         }
 
         content
@@ -350,6 +906,120 @@ impl Iterator for NodeChildrenIterator {
     }
 }
 
+/// An edge of a node visited during a depth-first traversal.
+///
+/// `Start` is visited in tree order (preorder), and `End` after all the descendants are visited (postorder).
+#[derive(Debug, Clone)]
+pub enum NodeEdge {
+    Start(Rc<RefCell<Node>>),
+    End(Rc<RefCell<Node>>),
+}
+
+/// Depth-first traversal of the inclusive descendants of `root`, yielding the both edges of each node.
+pub struct TraverseIterator {
+    root: Rc<RefCell<Node>>,
+    next: Option<NodeEdge>,
+}
+
+impl Iterator for TraverseIterator {
+    type Item = NodeEdge;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        self.next = match &current {
+            NodeEdge::Start(node) => match node.borrow().first_child.clone() {
+                Some(child) => Some(NodeEdge::Start(child)),
+                None => Some(NodeEdge::End(Rc::clone(node))),
+            },
+            NodeEdge::End(node) if Rc::ptr_eq(node, &self.root) => None,
+            NodeEdge::End(node) => match node.borrow().next_sibling.clone() {
+                Some(sibling) => Some(NodeEdge::Start(sibling)),
+                None => node.borrow().parent.upgrade().map(NodeEdge::End),
+            },
+        };
+        Some(current)
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#concept-tree-order>
+pub struct PreorderIterator(TraverseIterator);
+
+impl Iterator for PreorderIterator {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.find_map(|edge| match edge {
+            NodeEdge::Start(node) => Some(node),
+            NodeEdge::End(_) => None,
+        })
+    }
+}
+
+pub struct PostorderIterator(TraverseIterator);
+
+impl Iterator for PostorderIterator {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.find_map(|edge| match edge {
+            NodeEdge::Start(_) => None,
+            NodeEdge::End(node) => Some(node),
+        })
+    }
+}
+
+pub struct AncestorsIterator {
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+impl Iterator for AncestorsIterator {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        self.next = current.borrow().parent.upgrade();
+        Some(current)
+    }
+}
+
+impl Node {
+    pub fn traverse(root: Rc<RefCell<Node>>) -> TraverseIterator {
+        TraverseIterator {
+            root: Rc::clone(&root),
+            next: Some(NodeEdge::Start(root)),
+        }
+    }
+
+    /// The inclusive descendants of `root` in tree order.
+    pub fn preorder(root: Rc<RefCell<Node>>) -> PreorderIterator {
+        PreorderIterator(Self::traverse(root))
+    }
+
+    /// The inclusive descendants of `root`, each visited after its descendants.
+    pub fn postorder(root: Rc<RefCell<Node>>) -> PostorderIterator {
+        PostorderIterator(Self::traverse(root))
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-descendant>
+    pub fn descendants(node: Rc<RefCell<Node>>) -> PreorderIterator {
+        let mut iter = Self::preorder(node);
+        iter.next();
+        iter
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-ancestor>
+    pub fn ancestors(node: Rc<RefCell<Node>>) -> AncestorsIterator {
+        AncestorsIterator {
+            next: node.borrow().parent.upgrade(),
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-tree-inclusive-ancestor>
+    pub fn inclusive_ancestors(node: Rc<RefCell<Node>>) -> AncestorsIterator {
+        AncestorsIterator { next: Some(node) }
+    }
+}
+
 impl Node {
     pub fn assert_tree_structure(node: Rc<RefCell<Node>>) {
         Self::assert_tree_structure_rec(node, None);
@@ -394,20 +1064,20 @@ impl Node {
 impl Node {
     pub fn build_ascii_tree(node: Rc<RefCell<Node>>) -> String {
         let mut buf = String::new();
-        Self::build_ascii_tree_rec(node, &mut buf, 0);
+        let mut depth = 0;
 
-        buf
-    }
-
-    fn build_ascii_tree_rec(node: Rc<RefCell<Node>>, writer: &mut String, depth: usize) {
-        writer.push_str(&"   ".repeat(depth));
-        writer.push_str("|- ");
-
-        let node = node.borrow();
-        writer.push_str(&format!("{:?}\n", node.data));
-
-        for child in node.children() {
-            Self::build_ascii_tree_rec(child, writer, depth + 1);
+        for edge in Self::traverse(node) {
+            match edge {
+                NodeEdge::Start(node) => {
+                    buf.push_str(&"   ".repeat(depth));
+                    buf.push_str("|- ");
+                    buf.push_str(&format!("{:?}\n", node.borrow().data));
+                    depth += 1;
+                }
+                NodeEdge::End(_) => depth -= 1,
+            }
         }
+
+        buf
     }
 }
@@ -0,0 +1,849 @@
+//! <https://dom.spec.whatwg.org/#ranges>
+
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::fmt::Display;
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{DomException, Error};
+
+use super::node::{Node, NodeData};
+
+/// The children of a DocumentFragment, which is not supported as a node yet.
+pub type DocumentFragment = Vec<Rc<RefCell<Node>>>;
+
+/// <https://dom.spec.whatwg.org/#concept-range-bp>
+#[derive(Debug, Clone)]
+pub struct BoundaryPoint {
+    pub node: Rc<RefCell<Node>>,
+    /// In UTF-16 code units for text nodes, and in children otherwise.
+    pub offset: usize,
+}
+
+impl BoundaryPoint {
+    pub fn new(node: Rc<RefCell<Node>>, offset: usize) -> Self {
+        Self { node, offset }
+    }
+
+    fn is(&self, node: &Rc<RefCell<Node>>) -> bool {
+        Rc::ptr_eq(&self.node, node)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-bp-position>
+    ///
+    /// The boundary points must have the same root.
+    pub(crate) fn position(&self, other: &BoundaryPoint) -> Ordering {
+        if self.is(&other.node) {
+            return self.offset.cmp(&other.offset);
+        }
+
+        if compare_tree_order(&self.node, &other.node) == Ordering::Greater {
+            return other.position(self).reverse();
+        }
+
+        if is_inclusive_ancestor(&self.node, &other.node) {
+            let mut child = Rc::clone(&other.node);
+            loop {
+                let parent = child.borrow().parent.upgrade().expect("not a descendant");
+                if Rc::ptr_eq(&parent, &self.node) {
+                    break;
+                }
+                child = parent;
+            }
+            if child.borrow().index() < self.offset {
+                return Ordering::Greater;
+            }
+        }
+
+        Ordering::Less
+    }
+}
+
+/// The children of the common ancestor container to be cloned or extracted.
+struct ChildrenToProcess {
+    first_partially_contained_child: Option<Rc<RefCell<Node>>>,
+    last_partially_contained_child: Option<Rc<RefCell<Node>>>,
+    contained_children: Vec<Rc<RefCell<Node>>>,
+}
+
+/// The boundary points of a range.
+///
+/// Registered to the window when created by [Range::new] so that it is updated on DOM mutations,
+/// i.e. it is a live range.
+#[derive(Debug, Clone)]
+pub(crate) struct RangeState {
+    start: BoundaryPoint,
+    end: BoundaryPoint,
+}
+
+impl RangeState {
+    /// <https://dom.spec.whatwg.org/#concept-range-root>
+    fn root(&self) -> Rc<RefCell<Node>> {
+        Node::root(Rc::clone(&self.start.node))
+    }
+
+    fn collapsed(&self) -> bool {
+        self.start.position(&self.end) == Ordering::Equal
+    }
+
+    /// <https://dom.spec.whatwg.org/#contained>
+    fn contains(&self, node: &Rc<RefCell<Node>>) -> bool {
+        if !Rc::ptr_eq(&Node::root(Rc::clone(node)), &self.root()) {
+            return false;
+        }
+        let length = node.borrow().length();
+        BoundaryPoint::new(Rc::clone(node), 0).position(&self.start) == Ordering::Greater
+            && BoundaryPoint::new(Rc::clone(node), length).position(&self.end) == Ordering::Less
+    }
+
+    /// <https://dom.spec.whatwg.org/#partially-contained>
+    fn partially_contains(&self, node: &Rc<RefCell<Node>>) -> bool {
+        is_inclusive_ancestor(node, &self.start.node) != is_inclusive_ancestor(node, &self.end.node)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-commonancestorcontainer>
+    fn common_ancestor_container(&self) -> Rc<RefCell<Node>> {
+        let mut container = Rc::clone(&self.start.node);
+        while !is_inclusive_ancestor(&container, &self.end.node) {
+            let parent = container
+                .borrow()
+                .parent
+                .upgrade()
+                .expect("ranges have a root");
+            container = parent;
+        }
+        container
+    }
+
+    /// Steps 5-9 of both <https://dom.spec.whatwg.org/#concept-range-clone>
+    /// and <https://dom.spec.whatwg.org/#concept-range-extract>.
+    fn children_to_process(&self) -> ChildrenToProcess {
+        let common_ancestor = self.common_ancestor_container();
+        let children = common_ancestor.borrow().children().collect::<Vec<_>>();
+
+        let first_partially_contained_child =
+            if is_inclusive_ancestor(&self.start.node, &self.end.node) {
+                None
+            } else {
+                children
+                    .iter()
+                    .find(|child| self.partially_contains(child))
+                    .cloned()
+            };
+        let last_partially_contained_child =
+            if is_inclusive_ancestor(&self.end.node, &self.start.node) {
+                None
+            } else {
+                children
+                    .iter()
+                    .rev()
+                    .find(|child| self.partially_contains(child))
+                    .cloned()
+            };
+        let contained_children = children
+            .into_iter()
+            .filter(|child| self.contains(child))
+            .collect();
+
+        ChildrenToProcess {
+            first_partially_contained_child,
+            last_partially_contained_child,
+            contained_children,
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-clone>
+    fn clone_contents(&self) -> Result<DocumentFragment, Error> {
+        let mut fragment = Vec::new();
+        if self.collapsed() {
+            return Ok(fragment);
+        }
+
+        let BoundaryPoint {
+            node: start_node,
+            offset: start_offset,
+        } = &self.start;
+        let BoundaryPoint {
+            node: end_node,
+            offset: end_offset,
+        } = &self.end;
+
+        if Rc::ptr_eq(start_node, end_node) && is_text(start_node) {
+            let data = start_node
+                .borrow()
+                .substring_data(*start_offset, end_offset - start_offset)?;
+            fragment.push(clone_text(start_node, data));
+            return Ok(fragment);
+        }
+
+        let ChildrenToProcess {
+            first_partially_contained_child,
+            last_partially_contained_child,
+            contained_children,
+        } = self.children_to_process();
+
+        if let Some(child) = first_partially_contained_child {
+            if is_text(&child) {
+                let length = start_node.borrow().length();
+                let data = start_node
+                    .borrow()
+                    .substring_data(*start_offset, length - start_offset)?;
+                fragment.push(clone_text(&child, data));
+            } else {
+                let clone = Node::clone_node(Rc::clone(&child), false);
+                let length = child.borrow().length();
+                let subrange = RangeState {
+                    start: self.start.clone(),
+                    end: BoundaryPoint::new(child, length),
+                };
+                for node in subrange.clone_contents()? {
+                    Node::append_child(Rc::clone(&clone), node);
+                }
+                fragment.push(clone);
+            }
+        }
+
+        for child in contained_children {
+            fragment.push(Node::clone_node(child, true));
+        }
+
+        if let Some(child) = last_partially_contained_child {
+            if is_text(&child) {
+                let data = end_node.borrow().substring_data(0, *end_offset)?;
+                fragment.push(clone_text(&child, data));
+            } else {
+                let clone = Node::clone_node(Rc::clone(&child), false);
+                let subrange = RangeState {
+                    start: BoundaryPoint::new(child, 0),
+                    end: self.end.clone(),
+                };
+                for node in subrange.clone_contents()? {
+                    Node::append_child(Rc::clone(&clone), node);
+                }
+                fragment.push(clone);
+            }
+        }
+
+        Ok(fragment)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-extract>
+    ///
+    /// Returns the extracted nodes and the boundary point to which the range should be collapsed.
+    fn extract_contents(&self) -> Result<(DocumentFragment, BoundaryPoint), Error> {
+        let mut fragment = Vec::new();
+        if self.collapsed() {
+            return Ok((fragment, self.start.clone()));
+        }
+
+        let BoundaryPoint {
+            node: start_node,
+            offset: start_offset,
+        } = &self.start;
+        let BoundaryPoint {
+            node: end_node,
+            offset: end_offset,
+        } = &self.end;
+
+        if Rc::ptr_eq(start_node, end_node) && is_text(start_node) {
+            let count = end_offset - start_offset;
+            let data = start_node.borrow().substring_data(*start_offset, count)?;
+            fragment.push(clone_text(start_node, data));
+            Node::replace_data(Rc::clone(start_node), *start_offset, count, "")?;
+            return Ok((fragment, self.start.clone()));
+        }
+
+        let ChildrenToProcess {
+            first_partially_contained_child,
+            last_partially_contained_child,
+            contained_children,
+        } = self.children_to_process();
+
+        let new_boundary_point = if is_inclusive_ancestor(start_node, end_node) {
+            self.start.clone()
+        } else {
+            let mut reference_node = Rc::clone(start_node);
+            loop {
+                let parent = reference_node.borrow().parent.upgrade();
+                match parent {
+                    Some(parent) if !is_inclusive_ancestor(&parent, end_node) => {
+                        reference_node = parent
+                    }
+                    _ => break,
+                }
+            }
+            let parent = reference_node
+                .borrow()
+                .parent
+                .upgrade()
+                .expect("the common ancestor is an ancestor of the reference node");
+            let index = reference_node.borrow().index();
+            BoundaryPoint::new(parent, index + 1)
+        };
+
+        if let Some(child) = first_partially_contained_child {
+            if is_text(&child) {
+                let count = start_node.borrow().length() - start_offset;
+                let data = start_node.borrow().substring_data(*start_offset, count)?;
+                fragment.push(clone_text(&child, data));
+                Node::replace_data(Rc::clone(start_node), *start_offset, count, "")?;
+            } else {
+                let clone = Node::clone_node(Rc::clone(&child), false);
+                let length = child.borrow().length();
+                let subrange = RangeState {
+                    start: self.start.clone(),
+                    end: BoundaryPoint::new(child, length),
+                };
+                for node in subrange.extract_contents()?.0 {
+                    Node::append_child(Rc::clone(&clone), node);
+                }
+                fragment.push(clone);
+            }
+        }
+
+        for child in contained_children {
+            Node::remove(Rc::clone(&child));
+            fragment.push(child);
+        }
+
+        if let Some(child) = last_partially_contained_child {
+            if is_text(&child) {
+                let data = end_node.borrow().substring_data(0, *end_offset)?;
+                fragment.push(clone_text(&child, data));
+                Node::replace_data(Rc::clone(end_node), 0, *end_offset, "")?;
+            } else {
+                let clone = Node::clone_node(Rc::clone(&child), false);
+                let subrange = RangeState {
+                    start: BoundaryPoint::new(child, 0),
+                    end: self.end.clone(),
+                };
+                for node in subrange.extract_contents()?.0 {
+                    Node::append_child(Rc::clone(&clone), node);
+                }
+                fragment.push(clone);
+            }
+        }
+
+        Ok((fragment, new_boundary_point))
+    }
+
+    /// The live range update steps of <https://dom.spec.whatwg.org/#concept-node-insert>.
+    pub(crate) fn inserted(&mut self, parent: &Rc<RefCell<Node>>, index: usize, count: usize) {
+        for bp in [&mut self.start, &mut self.end] {
+            if bp.is(parent) && bp.offset > index {
+                bp.offset += count;
+            }
+        }
+    }
+
+    /// The live range update steps of <https://dom.spec.whatwg.org/#concept-node-remove>.
+    ///
+    /// `index` is the index of the node to be removed.
+    pub(crate) fn pre_remove(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        parent: &Rc<RefCell<Node>>,
+        index: usize,
+    ) {
+        for bp in [&mut self.start, &mut self.end] {
+            if is_inclusive_ancestor(node, &bp.node) {
+                *bp = BoundaryPoint::new(Rc::clone(parent), index);
+            } else if bp.is(parent) && bp.offset > index {
+                bp.offset -= 1;
+            }
+        }
+    }
+
+    /// The live range update steps of <https://dom.spec.whatwg.org/#concept-cd-replace>.
+    ///
+    /// `data_length` is the length of the inserted data in UTF-16 code units.
+    pub(crate) fn replaced_data(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        offset: usize,
+        count: usize,
+        data_length: usize,
+    ) {
+        for bp in [&mut self.start, &mut self.end] {
+            if !bp.is(node) {
+                continue;
+            }
+            if bp.offset > offset && bp.offset <= offset + count {
+                bp.offset = offset;
+            } else if bp.offset > offset + count {
+                bp.offset = bp.offset + data_length - count;
+            }
+        }
+    }
+
+    /// The live range update steps of <https://dom.spec.whatwg.org/#concept-text-split>,
+    /// run after the new node is inserted into the parent.
+    pub(crate) fn split_text(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        new_node: &Rc<RefCell<Node>>,
+        parent: &Rc<RefCell<Node>>,
+        offset: usize,
+    ) {
+        let index = node.borrow().index();
+        for bp in [&mut self.start, &mut self.end] {
+            if bp.is(node) && bp.offset > offset {
+                *bp = BoundaryPoint::new(Rc::clone(new_node), bp.offset - offset);
+            } else if bp.is(parent) && bp.offset == index + 1 {
+                bp.offset += 1;
+            }
+        }
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#interface-range>
+///
+/// Clones share the same boundary points, as JS references to a Range object do.
+#[derive(Debug, Clone)]
+pub struct Range {
+    state: Rc<RefCell<RangeState>>,
+}
+
+impl PartialEq for Range {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl Range {
+    /// <https://dom.spec.whatwg.org/#dom-document-createrange>
+    pub fn new(document: Rc<RefCell<Node>>) -> Self {
+        let state = Rc::new(RefCell::new(RangeState {
+            start: BoundaryPoint::new(Rc::clone(&document), 0),
+            end: BoundaryPoint::new(Rc::clone(&document), 0),
+        }));
+        if let Some(window) = document.borrow().window.upgrade() {
+            window.borrow_mut().register_range(&state);
+        }
+        Self { state }
+    }
+
+    pub fn start(&self) -> BoundaryPoint {
+        self.state.borrow().start.clone()
+    }
+
+    pub fn end(&self) -> BoundaryPoint {
+        self.state.borrow().end.clone()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-startcontainer>
+    pub fn start_container(&self) -> Rc<RefCell<Node>> {
+        Rc::clone(&self.state.borrow().start.node)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-startoffset>
+    pub fn start_offset(&self) -> usize {
+        self.state.borrow().start.offset
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-endcontainer>
+    pub fn end_container(&self) -> Rc<RefCell<Node>> {
+        Rc::clone(&self.state.borrow().end.node)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-endoffset>
+    pub fn end_offset(&self) -> usize {
+        self.state.borrow().end.offset
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-collapsed>
+    pub fn collapsed(&self) -> bool {
+        self.state.borrow().collapsed()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-commonancestorcontainer>
+    pub fn common_ancestor_container(&self) -> Rc<RefCell<Node>> {
+        self.state.borrow().common_ancestor_container()
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-bp-set>
+    fn set_boundary_point(
+        &self,
+        node: Rc<RefCell<Node>>,
+        offset: usize,
+        is_start: bool,
+    ) -> Result<(), Error> {
+        if offset > node.borrow().length() {
+            return Err(Error::Dom(DomException::IndexSizeError));
+        }
+        let bp = BoundaryPoint::new(node, offset);
+
+        let mut state = self.state.borrow_mut();
+        let same_root = Rc::ptr_eq(&state.root(), &Node::root(Rc::clone(&bp.node)));
+        if is_start {
+            if !same_root || bp.position(&state.end) == Ordering::Greater {
+                state.end = bp.clone();
+            }
+            state.start = bp;
+        } else {
+            if !same_root || bp.position(&state.start) == Ordering::Less {
+                state.start = bp.clone();
+            }
+            state.end = bp;
+        }
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setstart>
+    pub fn set_start(&self, node: Rc<RefCell<Node>>, offset: usize) -> Result<(), Error> {
+        self.set_boundary_point(node, offset, true)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setend>
+    pub fn set_end(&self, node: Rc<RefCell<Node>>, offset: usize) -> Result<(), Error> {
+        self.set_boundary_point(node, offset, false)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setstartbefore>
+    pub fn set_start_before(&self, node: Rc<RefCell<Node>>) -> Result<(), Error> {
+        let (parent, index) = parent_and_index(&node)?;
+        self.set_start(parent, index)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setstartafter>
+    pub fn set_start_after(&self, node: Rc<RefCell<Node>>) -> Result<(), Error> {
+        let (parent, index) = parent_and_index(&node)?;
+        self.set_start(parent, index + 1)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setendbefore>
+    pub fn set_end_before(&self, node: Rc<RefCell<Node>>) -> Result<(), Error> {
+        let (parent, index) = parent_and_index(&node)?;
+        self.set_end(parent, index)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-setendafter>
+    pub fn set_end_after(&self, node: Rc<RefCell<Node>>) -> Result<(), Error> {
+        let (parent, index) = parent_and_index(&node)?;
+        self.set_end(parent, index + 1)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-collapse>
+    pub fn collapse(&self, to_start: bool) {
+        let mut state = self.state.borrow_mut();
+        if to_start {
+            state.end = state.start.clone();
+        } else {
+            state.start = state.end.clone();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-range-select>
+    pub fn select_node(&self, node: Rc<RefCell<Node>>) -> Result<(), Error> {
+        let (parent, index) = parent_and_index(&node)?;
+        let mut state = self.state.borrow_mut();
+        state.start = BoundaryPoint::new(Rc::clone(&parent), index);
+        state.end = BoundaryPoint::new(parent, index + 1);
+        Ok(())
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-selectnodecontents>
+    pub fn select_node_contents(&self, node: Rc<RefCell<Node>>) {
+        let length = node.borrow().length();
+        let mut state = self.state.borrow_mut();
+        state.start = BoundaryPoint::new(Rc::clone(&node), 0);
+        state.end = BoundaryPoint::new(node, length);
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-ispointinrange>
+    pub fn is_point_in_range(&self, node: Rc<RefCell<Node>>, offset: usize) -> Result<bool, Error> {
+        let state = self.state.borrow();
+        if !Rc::ptr_eq(&Node::root(Rc::clone(&node)), &state.root()) {
+            return Ok(false);
+        }
+        if offset > node.borrow().length() {
+            return Err(Error::Dom(DomException::IndexSizeError));
+        }
+        let bp = BoundaryPoint::new(node, offset);
+        Ok(bp.position(&state.start) != Ordering::Less
+            && bp.position(&state.end) != Ordering::Greater)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-clonecontents>
+    pub fn clone_contents(&self) -> Result<DocumentFragment, Error> {
+        let state = self.state.borrow().clone();
+        state.clone_contents()
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-range-extractcontents>
+    pub fn extract_contents(&self) -> Result<DocumentFragment, Error> {
+        // The live range is updated during the extraction, so work on a snapshot of the original boundary points.
+        let state = self.state.borrow().clone();
+        let (fragment, new_boundary_point) = state.extract_contents()?;
+
+        let mut state = self.state.borrow_mut();
+        state.start = new_boundary_point.clone();
+        state.end = new_boundary_point;
+        Ok(fragment)
+    }
+}
+
+impl Display for Range {
+    /// <https://dom.spec.whatwg.org/#dom-range-stringifier>
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let state = self.state.borrow();
+        let BoundaryPoint {
+            node: start_node,
+            offset: start_offset,
+        } = &state.start;
+        let BoundaryPoint {
+            node: end_node,
+            offset: end_offset,
+        } = &state.end;
+
+        let substring = |node: &Rc<RefCell<Node>>, offset: usize, count: usize| {
+            node.borrow()
+                .substring_data(offset, count)
+                .expect("boundary points are within the node")
+        };
+
+        if Rc::ptr_eq(start_node, end_node) && is_text(start_node) {
+            return f.write_str(&substring(
+                start_node,
+                *start_offset,
+                end_offset - start_offset,
+            ));
+        }
+
+        let mut s = String::new();
+        if is_text(start_node) {
+            s += &substring(start_node, *start_offset, usize::MAX);
+        }
+        for node in Node::preorder(state.common_ancestor_container()) {
+            if is_text(&node) && state.contains(&node) {
+                s += node.borrow().character_data();
+            }
+        }
+        if is_text(end_node) {
+            s += &substring(end_node, 0, *end_offset);
+        }
+        f.write_str(&s)
+    }
+}
+
+fn is_text(node: &Rc<RefCell<Node>>) -> bool {
+    matches!(node.borrow().data, NodeData::Text(_))
+}
+
+fn clone_text(node: &Rc<RefCell<Node>>, data: String) -> Rc<RefCell<Node>> {
+    let document = node.borrow().node_document();
+    Node::create_text_node(document, data)
+}
+
+fn is_inclusive_ancestor(ancestor: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>) -> bool {
+    Node::inclusive_ancestors(Rc::clone(node)).any(|n| Rc::ptr_eq(&n, ancestor))
+}
+
+fn parent_and_index(node: &Rc<RefCell<Node>>) -> Result<(Rc<RefCell<Node>>, usize), Error> {
+    let node = node.borrow();
+    let parent = node
+        .parent
+        .upgrade()
+        .ok_or(Error::Dom(DomException::InvalidNodeTypeError))?;
+    Ok((parent, node.index()))
+}
+
+/// Compares two nodes of the same tree in tree order.
+fn compare_tree_order(a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>) -> Ordering {
+    let mut a_path = Node::inclusive_ancestors(Rc::clone(a)).collect::<Vec<_>>();
+    let mut b_path = Node::inclusive_ancestors(Rc::clone(b)).collect::<Vec<_>>();
+    a_path.reverse();
+    b_path.reverse();
+
+    match a_path
+        .iter()
+        .zip(b_path.iter())
+        .find(|(a, b)| !Rc::ptr_eq(a, b))
+    {
+        Some((a, b)) => a.borrow().index().cmp(&b.borrow().index()),
+        // One is an inclusive ancestor of the other, which precedes its descendants.
+        None => a_path.len().cmp(&b_path.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::dom::test_util::{element, parse};
+
+    use super::*;
+
+    fn serialize(nodes: &[Rc<RefCell<Node>>]) -> String {
+        nodes
+            .iter()
+            .map(|node| match &node.borrow().data {
+                NodeData::Text(text) => text.clone(),
+                NodeData::Element(element) => {
+                    let kind = element.kind.to_string();
+                    alloc::format!("<{kind}>{}</{kind}>", node.borrow().inner_html())
+                }
+                NodeData::Document => unreachable!(),
+            })
+            .collect()
+    }
+
+    const HTML: &str =
+        "<html><head></head><body><p>hello <a>big</a> world</p><h1>title</h1></body></html>";
+
+    #[test]
+    fn test_set_start_end() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let body = element(&window, ElementKind::Body);
+        let p = element(&window, ElementKind::P);
+        let hello = p.borrow().first_child.clone().unwrap();
+        let h1 = element(&window, ElementKind::H1);
+        let title = h1.borrow().first_child.clone().unwrap();
+        let range = Range::new(Rc::clone(&document));
+        assert!(range.collapsed());
+
+        range.set_start(Rc::clone(&hello), 2).unwrap();
+        // The end is moved to the start since it was before the start.
+        assert!(Rc::ptr_eq(&range.end_container(), &hello));
+        assert_eq!(range.end_offset(), 2);
+
+        range.set_end(Rc::clone(&title), 3).unwrap();
+        assert!(!range.collapsed());
+        assert!(Rc::ptr_eq(&range.common_ancestor_container(), &body));
+
+        assert_eq!(
+            range.set_end(Rc::clone(&title), 6),
+            Err(Error::Dom(DomException::IndexSizeError))
+        );
+
+        range.set_end_before(Rc::clone(&hello)).unwrap();
+        assert!(range.collapsed());
+        assert!(Rc::ptr_eq(&range.start_container(), &p));
+        assert_eq!(range.start_offset(), 0);
+    }
+
+    #[test]
+    fn test_to_string() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let p = element(&window, ElementKind::P);
+        let hello = p.borrow().first_child.clone().unwrap();
+        let h1 = element(&window, ElementKind::H1);
+        let title = h1.borrow().first_child.clone().unwrap();
+        let range = Range::new(Rc::clone(&document));
+        range.set_start(Rc::clone(&hello), 2).unwrap();
+        range.set_end(Rc::clone(&title), 3).unwrap();
+        assert_eq!(range.to_string(), "llo big worldtit");
+
+        range.set_end(Rc::clone(&hello), 4).unwrap();
+        assert_eq!(range.to_string(), "ll");
+
+        range.select_node_contents(Rc::clone(&p));
+        assert_eq!(range.to_string(), "hello big world");
+    }
+
+    #[test]
+    fn test_clone_contents() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let body = element(&window, ElementKind::Body);
+        let p = element(&window, ElementKind::P);
+        let hello = p.borrow().first_child.clone().unwrap();
+        let h1 = element(&window, ElementKind::H1);
+        let title = h1.borrow().first_child.clone().unwrap();
+        let range = Range::new(Rc::clone(&document));
+        range.set_start(Rc::clone(&hello), 2).unwrap();
+        range.set_end(Rc::clone(&title), 3).unwrap();
+
+        let contents = range.clone_contents().unwrap();
+        assert_eq!(
+            serialize(&contents),
+            "<p>llo <a>big</a> world</p><h1>tit</h1>"
+        );
+        // The document is not modified.
+        assert_eq!(
+            body.borrow().inner_html(),
+            "<p>hello <a>big</a> world</p><h1>title</h1>"
+        );
+    }
+
+    #[test]
+    fn test_extract_contents() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let body = element(&window, ElementKind::Body);
+        let p = element(&window, ElementKind::P);
+        let hello = p.borrow().first_child.clone().unwrap();
+        let h1 = element(&window, ElementKind::H1);
+        let title = h1.borrow().first_child.clone().unwrap();
+        let range = Range::new(Rc::clone(&document));
+        range.set_start(Rc::clone(&hello), 2).unwrap();
+        range.set_end(Rc::clone(&title), 3).unwrap();
+
+        let contents = range.extract_contents().unwrap();
+        assert_eq!(
+            serialize(&contents),
+            "<p>llo <a>big</a> world</p><h1>tit</h1>"
+        );
+        assert_eq!(body.borrow().inner_html(), "<p>he</p><h1>le</h1>");
+
+        assert!(range.collapsed());
+        assert!(Rc::ptr_eq(&range.start_container(), &body));
+        assert_eq!(range.start_offset(), 1);
+    }
+
+    #[test]
+    fn test_extract_contents_in_text() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let p = element(&window, ElementKind::P);
+        let world = p.borrow().last_child().unwrap();
+        let range = Range::new(Rc::clone(&document));
+        range.set_start(Rc::clone(&world), 1).unwrap();
+        range.set_end(Rc::clone(&world), 4).unwrap();
+
+        assert_eq!(serialize(&range.extract_contents().unwrap()), "wor");
+        assert_eq!(p.borrow().inner_html(), "hello <a>big</a> ld");
+        assert!(Rc::ptr_eq(&range.start_container(), &world));
+        assert_eq!(range.start_offset(), 1);
+    }
+
+    #[test]
+    fn test_live_range() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let p = element(&window, ElementKind::P);
+        let hello = p.borrow().first_child.clone().unwrap();
+        let world = p.borrow().last_child().unwrap();
+        let range = Range::new(Rc::clone(&document));
+        range.set_start(Rc::clone(&p), 2).unwrap();
+        range.set_end(Rc::clone(&world), 3).unwrap();
+
+        // Inserting a node before the start shifts the offset.
+        let text = Node::create_text_node(Rc::clone(&document), "!".into());
+        Node::insert_before(Rc::clone(&p), Rc::clone(&text), Some(Rc::clone(&hello))).unwrap();
+        assert_eq!(range.start_offset(), 3);
+
+        // Editing the text before the end shifts the offset.
+        Node::insert_data(Rc::clone(&world), 0, "big").unwrap();
+        assert_eq!(range.end_offset(), 6);
+        Node::delete_data(Rc::clone(&world), 2, 3).unwrap();
+        assert_eq!(range.end_offset(), 3);
+
+        // Splitting the text moves the end to the new node.
+        let new_node = Node::split_text(Rc::clone(&world), 1).unwrap();
+        assert!(Rc::ptr_eq(&range.end_container(), &new_node));
+        assert_eq!(range.end_offset(), 2);
+
+        // Removing the container moves the boundary point to the parent.
+        Node::remove(Rc::clone(&new_node));
+        assert!(Rc::ptr_eq(&range.end_container(), &p));
+        assert_eq!(range.end_offset(), 4);
+        Node::remove(Rc::clone(&text));
+        assert_eq!(range.start_offset(), 2);
+        assert_eq!(range.end_offset(), 3);
+    }
+}
@@ -0,0 +1,348 @@
+//! <https://w3c.github.io/selection-api/>
+
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::fmt::Display;
+
+use alloc::rc::{Rc, Weak};
+
+use crate::error::{DomException, Error};
+
+use super::node::Node;
+use super::range::{BoundaryPoint, Range};
+
+/// <https://w3c.github.io/selection-api/#dfn-direction>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionDirection {
+    Forwards,
+    Backwards,
+    Directionless,
+}
+
+/// <https://w3c.github.io/selection-api/#selection-interface>
+///
+/// Each document has one selection, which is obtained by [super::node::Window::get_selection].
+#[derive(Debug, Clone)]
+pub struct Selection {
+    document: Weak<RefCell<Node>>,
+    range: Option<Range>,
+    direction: SelectionDirection,
+}
+
+impl Selection {
+    pub(crate) fn new(document: Weak<RefCell<Node>>) -> Self {
+        Self {
+            document,
+            range: None,
+            direction: SelectionDirection::Directionless,
+        }
+    }
+
+    pub fn direction(&self) -> SelectionDirection {
+        self.direction
+    }
+
+    /// <https://w3c.github.io/selection-api/#dfn-anchor>
+    fn anchor(&self) -> Option<BoundaryPoint> {
+        let range = self.range.as_ref()?;
+        Some(match self.direction {
+            SelectionDirection::Backwards => range.end(),
+            _ => range.start(),
+        })
+    }
+
+    /// <https://w3c.github.io/selection-api/#dfn-focus>
+    fn focus(&self) -> Option<BoundaryPoint> {
+        let range = self.range.as_ref()?;
+        Some(match self.direction {
+            SelectionDirection::Backwards => range.start(),
+            _ => range.end(),
+        })
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-anchornode>
+    pub fn anchor_node(&self) -> Option<Rc<RefCell<Node>>> {
+        self.anchor().map(|bp| bp.node)
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-anchoroffset>
+    pub fn anchor_offset(&self) -> usize {
+        self.anchor().map_or(0, |bp| bp.offset)
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-focusnode>
+    pub fn focus_node(&self) -> Option<Rc<RefCell<Node>>> {
+        self.focus().map(|bp| bp.node)
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-focusoffset>
+    pub fn focus_offset(&self) -> usize {
+        self.focus().map_or(0, |bp| bp.offset)
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-iscollapsed>
+    pub fn is_collapsed(&self) -> bool {
+        self.range.as_ref().map_or(true, Range::collapsed)
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-rangecount>
+    pub fn range_count(&self) -> usize {
+        if self.range.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-getrangeat>
+    pub fn get_range_at(&self, index: usize) -> Result<Range, Error> {
+        match &self.range {
+            Some(range) if index == 0 => Ok(range.clone()),
+            _ => Err(Error::Dom(DomException::IndexSizeError)),
+        }
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-addrange>
+    pub fn add_range(&mut self, range: Range) {
+        if !self.is_in_document(&range.start_container()) || self.range.is_some() {
+            return;
+        }
+        self.range = Some(range);
+        self.direction = SelectionDirection::Directionless;
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-removerange>
+    pub fn remove_range(&mut self, range: &Range) -> Result<(), Error> {
+        if self.range.as_ref() != Some(range) {
+            return Err(Error::Dom(DomException::NotFoundError));
+        }
+        self.remove_all_ranges();
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-removeallranges>
+    pub fn remove_all_ranges(&mut self) {
+        self.range = None;
+        self.direction = SelectionDirection::Directionless;
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-collapse>
+    pub fn collapse(
+        &mut self,
+        node: Option<Rc<RefCell<Node>>>,
+        offset: usize,
+    ) -> Result<(), Error> {
+        let Some(node) = node else {
+            self.remove_all_ranges();
+            return Ok(());
+        };
+        if offset > node.borrow().length() {
+            return Err(Error::Dom(DomException::IndexSizeError));
+        }
+        if !self.is_in_document(&node) {
+            return Ok(());
+        }
+
+        let range = self.new_range()?;
+        range.set_start(Rc::clone(&node), offset)?;
+        range.set_end(node, offset)?;
+        self.range = Some(range);
+        self.direction = SelectionDirection::Directionless;
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-collapsetostart>
+    pub fn collapse_to_start(&mut self) -> Result<(), Error> {
+        let start = self
+            .range
+            .as_ref()
+            .ok_or(Error::Dom(DomException::InvalidStateError))?
+            .start();
+        self.collapse(Some(start.node), start.offset)
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-collapsetoend>
+    pub fn collapse_to_end(&mut self) -> Result<(), Error> {
+        let end = self
+            .range
+            .as_ref()
+            .ok_or(Error::Dom(DomException::InvalidStateError))?
+            .end();
+        self.collapse(Some(end.node), end.offset)
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-extend>
+    pub fn extend(&mut self, node: Rc<RefCell<Node>>, offset: usize) -> Result<(), Error> {
+        if !self.is_in_document(&node) {
+            return Ok(());
+        }
+        let (Some(range), Some(old_anchor)) = (&self.range, self.anchor()) else {
+            return Err(Error::Dom(DomException::InvalidStateError));
+        };
+        let old_root = Node::root(range.start_container());
+        let new_focus = BoundaryPoint::new(node, offset);
+
+        let new_range = self.new_range()?;
+        let direction = if !Rc::ptr_eq(&old_root, &Node::root(Rc::clone(&new_focus.node))) {
+            new_range.set_start(Rc::clone(&new_focus.node), new_focus.offset)?;
+            new_range.set_end(new_focus.node, new_focus.offset)?;
+            SelectionDirection::Forwards
+        } else if old_anchor.position(&new_focus) != Ordering::Greater {
+            new_range.set_start(old_anchor.node, old_anchor.offset)?;
+            new_range.set_end(new_focus.node, new_focus.offset)?;
+            SelectionDirection::Forwards
+        } else {
+            new_range.set_start(new_focus.node, new_focus.offset)?;
+            new_range.set_end(old_anchor.node, old_anchor.offset)?;
+            SelectionDirection::Backwards
+        };
+        self.range = Some(new_range);
+        self.direction = direction;
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-setbaseandextent>
+    pub fn set_base_and_extent(
+        &mut self,
+        anchor_node: Rc<RefCell<Node>>,
+        anchor_offset: usize,
+        focus_node: Rc<RefCell<Node>>,
+        focus_offset: usize,
+    ) -> Result<(), Error> {
+        if anchor_offset > anchor_node.borrow().length()
+            || focus_offset > focus_node.borrow().length()
+        {
+            return Err(Error::Dom(DomException::IndexSizeError));
+        }
+        if !self.is_in_document(&anchor_node) || !self.is_in_document(&focus_node) {
+            return Ok(());
+        }
+        let anchor = BoundaryPoint::new(anchor_node, anchor_offset);
+        let focus = BoundaryPoint::new(focus_node, focus_offset);
+
+        let new_range = self.new_range()?;
+        let direction = if anchor.position(&focus) == Ordering::Greater {
+            new_range.set_start(focus.node, focus.offset)?;
+            new_range.set_end(anchor.node, anchor.offset)?;
+            SelectionDirection::Backwards
+        } else {
+            new_range.set_start(anchor.node, anchor.offset)?;
+            new_range.set_end(focus.node, focus.offset)?;
+            SelectionDirection::Forwards
+        };
+        self.range = Some(new_range);
+        self.direction = direction;
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/selection-api/#dom-selection-selectallchildren>
+    pub fn select_all_children(&mut self, node: Rc<RefCell<Node>>) -> Result<(), Error> {
+        if !self.is_in_document(&node) {
+            return Ok(());
+        }
+        let child_count = node.borrow().children().count();
+
+        let new_range = self.new_range()?;
+        new_range.set_start(Rc::clone(&node), 0)?;
+        new_range.set_end(node, child_count)?;
+        self.range = Some(new_range);
+        self.direction = SelectionDirection::Forwards;
+        Ok(())
+    }
+
+    fn document(&self) -> Result<Rc<RefCell<Node>>, Error> {
+        self.document
+            .upgrade()
+            .ok_or(Error::Other("the document has been dropped".into()))
+    }
+
+    fn new_range(&self) -> Result<Range, Error> {
+        Ok(Range::new(self.document()?))
+    }
+
+    /// Whether the document associated with the selection is the root of the node.
+    fn is_in_document(&self, node: &Rc<RefCell<Node>>) -> bool {
+        self.document
+            .upgrade()
+            .is_some_and(|document| Rc::ptr_eq(&Node::root(Rc::clone(node)), &document))
+    }
+}
+
+impl Display for Selection {
+    /// <https://w3c.github.io/selection-api/#dom-selection-stringifier>
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.range {
+            Some(range) => range.fmt(f),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::dom::test_util::{element, parse};
+
+    use super::*;
+
+    #[test]
+    fn test_extend() {
+        let window = parse("<html><head></head><body><p>hello</p><h1>world</h1></body></html>");
+        let p = element(&window, ElementKind::P);
+        let h1 = element(&window, ElementKind::H1);
+        let hello = p.borrow().first_child.clone().unwrap();
+        let world = h1.borrow().first_child.clone().unwrap();
+
+        let selection = window.borrow().get_selection();
+        let mut selection = selection.borrow_mut();
+        assert_eq!(selection.range_count(), 0);
+        assert_eq!(
+            selection.extend(Rc::clone(&hello), 0),
+            Err(Error::Dom(DomException::InvalidStateError))
+        );
+
+        selection.collapse(Some(Rc::clone(&world)), 2).unwrap();
+        assert!(selection.is_collapsed());
+
+        // Extending backwards keeps the anchor.
+        selection.extend(Rc::clone(&hello), 3).unwrap();
+        assert_eq!(selection.direction(), SelectionDirection::Backwards);
+        assert!(Rc::ptr_eq(&selection.anchor_node().unwrap(), &world));
+        assert_eq!(selection.anchor_offset(), 2);
+        assert!(Rc::ptr_eq(&selection.focus_node().unwrap(), &hello));
+        assert_eq!(selection.focus_offset(), 3);
+        assert_eq!(selection.to_string(), "lowo");
+
+        let range = selection.get_range_at(0).unwrap();
+        assert!(Rc::ptr_eq(&range.start_container(), &hello));
+        assert_eq!(
+            selection.get_range_at(1),
+            Err(Error::Dom(DomException::IndexSizeError))
+        );
+
+        // The selected range is live.
+        Node::remove(Rc::clone(&h1));
+        assert_eq!(selection.to_string(), "lo");
+
+        selection.remove_range(&range).unwrap();
+        assert_eq!(selection.range_count(), 0);
+        assert_eq!(selection.to_string(), "");
+    }
+
+    #[test]
+    fn test_select_all_children() {
+        let window = parse("<html><head></head><body><p>hello</p><h1>world</h1></body></html>");
+        let document = window.borrow().document();
+        let body = element(&window, ElementKind::Body);
+
+        let selection = window.borrow().get_selection();
+        let mut selection = selection.borrow_mut();
+        selection.select_all_children(body).unwrap();
+        assert_eq!(selection.to_string(), "helloworld");
+
+        // Nodes outside the document are ignored.
+        let text = Node::create_text_node(document, "detached".into());
+        selection.collapse(Some(text), 0).unwrap();
+        assert_eq!(selection.to_string(), "helloworld");
+    }
+}
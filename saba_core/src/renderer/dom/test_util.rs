@@ -0,0 +1,19 @@
+//! Helpers shared by the tests of the DOM modules.
+
+use alloc::rc::Rc;
+use alloc::string::ToString;
+use core::cell::RefCell;
+
+use super::node::{ElementKind, Node, Window};
+use crate::renderer::html::{parser::HtmlParser, token::HtmlTokenizer};
+
+/// Parses the HTML into a window holding its document.
+pub fn parse(html: &str) -> Rc<RefCell<Window>> {
+    HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree()
+}
+
+/// Returns the first element of the kind in the document of the window.
+pub fn element(window: &Rc<RefCell<Window>>, kind: ElementKind) -> Rc<RefCell<Node>> {
+    let document = window.borrow().document();
+    Node::get_element_by_tag_name(document, kind).unwrap()
+}
@@ -0,0 +1,617 @@
+//! <https://dom.spec.whatwg.org/#traversal>
+
+use core::cell::RefCell;
+
+use alloc::rc::Rc;
+
+use super::node::Node;
+
+/// <https://dom.spec.whatwg.org/#interface-nodefilter>
+///
+/// The callback part of NodeFilter. The constants for `whatToShow` live on [NodeFilter].
+pub type NodeFilterCallback = Rc<dyn Fn(&Rc<RefCell<Node>>) -> FilterResult>;
+
+/// <https://dom.spec.whatwg.org/#interface-nodefilter>
+pub struct NodeFilter;
+
+impl NodeFilter {
+    pub const SHOW_ALL: u32 = 0xFFFF_FFFF;
+    pub const SHOW_ELEMENT: u32 = 0x1;
+    pub const SHOW_ATTRIBUTE: u32 = 0x2;
+    pub const SHOW_TEXT: u32 = 0x4;
+    pub const SHOW_CDATA_SECTION: u32 = 0x8;
+    pub const SHOW_PROCESSING_INSTRUCTION: u32 = 0x40;
+    pub const SHOW_COMMENT: u32 = 0x80;
+    pub const SHOW_DOCUMENT: u32 = 0x100;
+    pub const SHOW_DOCUMENT_TYPE: u32 = 0x200;
+    pub const SHOW_DOCUMENT_FRAGMENT: u32 = 0x400;
+}
+
+/// <https://dom.spec.whatwg.org/#dom-nodefilter-filter_accept>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterResult {
+    Accept,
+    Reject,
+    Skip,
+}
+
+/// <https://dom.spec.whatwg.org/#concept-node-filter>
+///
+/// The spec throws an "InvalidStateError" when the filter is re-entered.
+/// Our filters are Rust closures which cannot reach the traverser, so the active flag is omitted.
+fn filter_node(
+    node: &Rc<RefCell<Node>>,
+    what_to_show: u32,
+    filter: &Option<NodeFilterCallback>,
+) -> FilterResult {
+    let n = node.borrow().node_type() - 1;
+    if what_to_show & (1 << n) == 0 {
+        return FilterResult::Skip;
+    }
+    match filter {
+        Some(filter) => filter(node),
+        None => FilterResult::Accept,
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#interface-treewalker>
+pub struct TreeWalker {
+    root: Rc<RefCell<Node>>,
+    what_to_show: u32,
+    filter: Option<NodeFilterCallback>,
+    current: Rc<RefCell<Node>>,
+}
+
+impl TreeWalker {
+    /// <https://dom.spec.whatwg.org/#dom-document-createtreewalker>
+    pub fn new(
+        root: Rc<RefCell<Node>>,
+        what_to_show: u32,
+        filter: Option<NodeFilterCallback>,
+    ) -> Self {
+        Self {
+            current: Rc::clone(&root),
+            root,
+            what_to_show,
+            filter,
+        }
+    }
+
+    pub fn root(&self) -> Rc<RefCell<Node>> {
+        Rc::clone(&self.root)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-currentnode>
+    pub fn current_node(&self) -> Rc<RefCell<Node>> {
+        Rc::clone(&self.current)
+    }
+
+    pub fn set_current_node(&mut self, node: Rc<RefCell<Node>>) {
+        self.current = node;
+    }
+
+    fn filter(&self, node: &Rc<RefCell<Node>>) -> FilterResult {
+        filter_node(node, self.what_to_show, &self.filter)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-parentnode>
+    pub fn parent_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let mut node = Rc::clone(&self.current);
+        while !Rc::ptr_eq(&node, &self.root) {
+            let parent = node.borrow().parent.upgrade();
+            match parent {
+                None => break,
+                Some(parent) => {
+                    node = parent;
+                    if self.filter(&node) == FilterResult::Accept {
+                        self.current = Rc::clone(&node);
+                        return Some(node);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-firstchild>
+    pub fn first_child(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_children(ChildType::First)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-lastchild>
+    pub fn last_child(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_children(ChildType::Last)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-previoussibling>
+    pub fn previous_sibling(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_siblings(SiblingType::Previous)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-nextsibling>
+    pub fn next_sibling(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse_siblings(SiblingType::Next)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-previousnode>
+    pub fn previous_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let mut node = Rc::clone(&self.current);
+        while !Rc::ptr_eq(&node, &self.root) {
+            let mut sibling = node.borrow().previous_sibling.upgrade();
+            while let Some(s) = sibling {
+                node = s;
+                let mut result = self.filter(&node);
+                while result != FilterResult::Reject {
+                    let last_child = node.borrow().last_child();
+                    match last_child {
+                        Some(child) => {
+                            node = child;
+                            result = self.filter(&node);
+                        }
+                        None => break,
+                    }
+                }
+                if result == FilterResult::Accept {
+                    self.current = Rc::clone(&node);
+                    return Some(node);
+                }
+                sibling = node.borrow().previous_sibling.upgrade();
+            }
+
+            if Rc::ptr_eq(&node, &self.root) {
+                return None;
+            }
+            let parent = node.borrow().parent.upgrade();
+            match parent {
+                None => return None,
+                Some(parent) => node = parent,
+            }
+            if self.filter(&node) == FilterResult::Accept {
+                self.current = Rc::clone(&node);
+                return Some(node);
+            }
+        }
+        None
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-treewalker-nextnode>
+    pub fn next_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let mut node = Rc::clone(&self.current);
+        let mut result = FilterResult::Accept;
+        loop {
+            while result != FilterResult::Reject {
+                let first_child = node.borrow().first_child.clone();
+                match first_child {
+                    Some(child) => {
+                        node = child;
+                        result = self.filter(&node);
+                        if result == FilterResult::Accept {
+                            self.current = Rc::clone(&node);
+                            return Some(node);
+                        }
+                    }
+                    None => break,
+                }
+            }
+
+            // Find the next node in tree order that is not a descendant of `node`, staying within root.
+            let mut sibling = None;
+            let mut temporary = Some(Rc::clone(&node));
+            while let Some(t) = temporary {
+                if Rc::ptr_eq(&t, &self.root) {
+                    return None;
+                }
+                sibling = t.borrow().next_sibling.clone();
+                if sibling.is_some() {
+                    break;
+                }
+                temporary = t.borrow().parent.upgrade();
+            }
+            node = sibling?;
+            result = self.filter(&node);
+            if result == FilterResult::Accept {
+                self.current = Rc::clone(&node);
+                return Some(node);
+            }
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-traverse-children>
+    fn traverse_children(&mut self, ty: ChildType) -> Option<Rc<RefCell<Node>>> {
+        let first = |node: &Rc<RefCell<Node>>| match ty {
+            ChildType::First => node.borrow().first_child.clone(),
+            ChildType::Last => node.borrow().last_child(),
+        };
+        let next = |node: &Rc<RefCell<Node>>| match ty {
+            ChildType::First => node.borrow().next_sibling.clone(),
+            ChildType::Last => node.borrow().previous_sibling.upgrade(),
+        };
+
+        let mut node = first(&self.current)?;
+        loop {
+            match self.filter(&node) {
+                FilterResult::Accept => {
+                    self.current = Rc::clone(&node);
+                    return Some(node);
+                }
+                FilterResult::Skip => {
+                    if let Some(child) = first(&node) {
+                        node = child;
+                        continue;
+                    }
+                }
+                FilterResult::Reject => {}
+            }
+
+            loop {
+                if let Some(sibling) = next(&node) {
+                    node = sibling;
+                    break;
+                }
+                let parent = node.borrow().parent.upgrade()?;
+                if Rc::ptr_eq(&parent, &self.root) || Rc::ptr_eq(&parent, &self.current) {
+                    return None;
+                }
+                node = parent;
+            }
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-traverse-siblings>
+    fn traverse_siblings(&mut self, ty: SiblingType) -> Option<Rc<RefCell<Node>>> {
+        let sibling_of = |node: &Rc<RefCell<Node>>| match ty {
+            SiblingType::Next => node.borrow().next_sibling.clone(),
+            SiblingType::Previous => node.borrow().previous_sibling.upgrade(),
+        };
+        let child_of = |node: &Rc<RefCell<Node>>| match ty {
+            SiblingType::Next => node.borrow().first_child.clone(),
+            SiblingType::Previous => node.borrow().last_child(),
+        };
+
+        let mut node = Rc::clone(&self.current);
+        if Rc::ptr_eq(&node, &self.root) {
+            return None;
+        }
+        loop {
+            let mut sibling = sibling_of(&node);
+            while let Some(s) = sibling {
+                node = s;
+                let result = self.filter(&node);
+                if result == FilterResult::Accept {
+                    self.current = Rc::clone(&node);
+                    return Some(node);
+                }
+                sibling = child_of(&node);
+                if result == FilterResult::Reject || sibling.is_none() {
+                    sibling = sibling_of(&node);
+                }
+            }
+            let parent = node.borrow().parent.upgrade()?;
+            node = parent;
+            if Rc::ptr_eq(&node, &self.root) {
+                return None;
+            }
+            if self.filter(&node) == FilterResult::Accept {
+                return None;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ChildType {
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SiblingType {
+    Next,
+    Previous,
+}
+
+/// <https://dom.spec.whatwg.org/#interface-nodeiterator>
+pub struct NodeIterator {
+    state: Rc<RefCell<NodeIteratorState>>,
+    what_to_show: u32,
+    filter: Option<NodeFilterCallback>,
+}
+
+/// The part of a NodeIterator updated when nodes are removed from the document.
+#[derive(Debug)]
+pub(crate) struct NodeIteratorState {
+    root: Rc<RefCell<Node>>,
+    reference: Rc<RefCell<Node>>,
+    pointer_before_reference: bool,
+}
+
+impl NodeIterator {
+    /// <https://dom.spec.whatwg.org/#dom-document-createnodeiterator>
+    pub fn new(
+        root: Rc<RefCell<Node>>,
+        what_to_show: u32,
+        filter: Option<NodeFilterCallback>,
+    ) -> Self {
+        let state = Rc::new(RefCell::new(NodeIteratorState {
+            reference: Rc::clone(&root),
+            root: Rc::clone(&root),
+            pointer_before_reference: true,
+        }));
+        if let Some(window) = root.borrow().window.upgrade() {
+            window.borrow_mut().register_node_iterator(&state);
+        }
+        Self {
+            state,
+            what_to_show,
+            filter,
+        }
+    }
+
+    pub fn root(&self) -> Rc<RefCell<Node>> {
+        Rc::clone(&self.state.borrow().root)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-nodeiterator-referencenode>
+    pub fn reference_node(&self) -> Rc<RefCell<Node>> {
+        Rc::clone(&self.state.borrow().reference)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-nodeiterator-pointerbeforereferencenode>
+    pub fn pointer_before_reference_node(&self) -> bool {
+        self.state.borrow().pointer_before_reference
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-nodeiterator-nextnode>
+    pub fn next_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse(Direction::Next)
+    }
+
+    /// <https://dom.spec.whatwg.org/#dom-nodeiterator-previousnode>
+    pub fn previous_node(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.traverse(Direction::Previous)
+    }
+
+    /// <https://dom.spec.whatwg.org/#concept-nodeiterator-traverse>
+    fn traverse(&mut self, direction: Direction) -> Option<Rc<RefCell<Node>>> {
+        let root = self.root();
+        let mut node = self.reference_node();
+        let mut before_node = self.pointer_before_reference_node();
+        loop {
+            match direction {
+                Direction::Next => {
+                    if before_node {
+                        before_node = false;
+                    } else {
+                        node = following(&node, &root)?;
+                    }
+                }
+                Direction::Previous => {
+                    if before_node {
+                        node = preceding(&node, &root)?;
+                    } else {
+                        before_node = true;
+                    }
+                }
+            }
+            if filter_node(&node, self.what_to_show, &self.filter) == FilterResult::Accept {
+                break;
+            }
+        }
+        let mut state = self.state.borrow_mut();
+        state.reference = Rc::clone(&node);
+        state.pointer_before_reference = before_node;
+        Some(node)
+    }
+}
+
+impl NodeIteratorState {
+    /// <https://dom.spec.whatwg.org/#nodeiterator-pre-removing-steps>
+    pub(crate) fn pre_remove(&mut self, to_be_removed: &Rc<RefCell<Node>>) {
+        let reference_is_inclusive_descendant =
+            Node::inclusive_ancestors(Rc::clone(&self.reference))
+                .any(|ancestor| Rc::ptr_eq(&ancestor, to_be_removed));
+        if !reference_is_inclusive_descendant || Rc::ptr_eq(to_be_removed, &self.root) {
+            return;
+        }
+
+        if self.pointer_before_reference {
+            // The first following node that is not an inclusive descendant of to_be_removed
+            let mut next = Some(Rc::clone(to_be_removed));
+            while let Some(n) = next {
+                if Rc::ptr_eq(&n, &self.root) {
+                    break;
+                }
+                let sibling = n.borrow().next_sibling.clone();
+                if let Some(sibling) = sibling {
+                    self.reference = sibling;
+                    return;
+                }
+                next = n.borrow().parent.upgrade();
+            }
+            self.pointer_before_reference = false;
+        }
+
+        // Set reference to the inclusive descendant of to_be_removed's previous sibling that appears last in tree order,
+        // or to_be_removed's parent if there is no previous sibling.
+        let previous_sibling = to_be_removed.borrow().previous_sibling.upgrade();
+        self.reference = match previous_sibling {
+            Some(mut node) => {
+                loop {
+                    let last_child = node.borrow().last_child();
+                    match last_child {
+                        Some(child) => node = child,
+                        None => break,
+                    }
+                }
+                node
+            }
+            None => to_be_removed
+                .borrow()
+                .parent
+                .upgrade()
+                .expect("the removed node must have a parent"),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Next,
+    Previous,
+}
+
+/// <https://dom.spec.whatwg.org/#concept-tree-following>
+///
+/// Returns the node following `node` in tree order, without leaving `root`.
+pub(crate) fn following(
+    node: &Rc<RefCell<Node>>,
+    root: &Rc<RefCell<Node>>,
+) -> Option<Rc<RefCell<Node>>> {
+    if let Some(child) = node.borrow().first_child.clone() {
+        return Some(child);
+    }
+    let mut node = Rc::clone(node);
+    loop {
+        if Rc::ptr_eq(&node, root) {
+            return None;
+        }
+        if let Some(sibling) = node.borrow().next_sibling.clone() {
+            return Some(sibling);
+        }
+        let parent = node.borrow().parent.upgrade()?;
+        node = parent;
+    }
+}
+
+/// <https://dom.spec.whatwg.org/#concept-tree-preceding>
+///
+/// Returns the node preceding `node` in tree order, without leaving `root`.
+pub(crate) fn preceding(
+    node: &Rc<RefCell<Node>>,
+    root: &Rc<RefCell<Node>>,
+) -> Option<Rc<RefCell<Node>>> {
+    if Rc::ptr_eq(node, root) {
+        return None;
+    }
+    let previous_sibling = node.borrow().previous_sibling.upgrade();
+    match previous_sibling {
+        Some(mut node) => {
+            loop {
+                let last_child = node.borrow().last_child();
+                match last_child {
+                    Some(child) => node = child,
+                    None => break,
+                }
+            }
+            Some(node)
+        }
+        None => node.borrow().parent.upgrade(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    use crate::renderer::dom::node::{ElementKind, NodeData};
+    use crate::renderer::dom::test_util::{element, parse};
+
+    use super::*;
+
+    fn describe(node: &Rc<RefCell<Node>>) -> String {
+        match &node.borrow().data {
+            NodeData::Document => "#document".to_string(),
+            NodeData::Element(e) => e.kind.to_string(),
+            NodeData::Text(t) => t.clone(),
+        }
+    }
+
+    const HTML: &str = "<html><head></head><body><p>t1<a>t2</a></p><h1>t3</h1>t4</body></html>";
+
+    #[test]
+    fn test_tree_walker_next_node() {
+        let window = parse(HTML);
+        let mut walker =
+            TreeWalker::new(window.borrow().document(), NodeFilter::SHOW_ELEMENT, None);
+        let mut nodes = Vec::new();
+        while let Some(node) = walker.next_node() {
+            nodes.push(describe(&node));
+        }
+        assert_eq!(vec!["html", "head", "body", "p", "a", "h1"], nodes);
+
+        let mut nodes = Vec::new();
+        while let Some(node) = walker.previous_node() {
+            nodes.push(describe(&node));
+        }
+        assert_eq!(vec!["a", "p", "body", "head", "html"], nodes);
+    }
+
+    #[test]
+    fn test_tree_walker_previous_node_to_root() {
+        let window = parse(HTML);
+        let body = element(&window, ElementKind::Body);
+        let mut walker = TreeWalker::new(Rc::clone(&body), NodeFilter::SHOW_ELEMENT, None);
+        assert_eq!("p", describe(&walker.first_child().unwrap()));
+
+        // The root itself is returned when accepted, but nothing beyond it.
+        assert!(Rc::ptr_eq(&body, &walker.previous_node().unwrap()));
+        assert!(walker.previous_node().is_none());
+        assert!(Rc::ptr_eq(&body, &walker.current_node()));
+    }
+
+    #[test]
+    fn test_tree_walker_skip_and_reject() {
+        let window = parse(HTML);
+        let filter: NodeFilterCallback = Rc::new(|node| match &node.borrow().data {
+            NodeData::Element(e) if e.kind.to_string() == "p" => FilterResult::Skip,
+            NodeData::Element(e) if e.kind.to_string() == "h1" => FilterResult::Reject,
+            _ => FilterResult::Accept,
+        });
+        let body = element(&window, ElementKind::Body);
+        let mut walker = TreeWalker::new(body, NodeFilter::SHOW_ALL, Some(filter));
+
+        // Children of the skipped <p> are visible as children of <body>, while <h1> is pruned entirely.
+        let mut children = Vec::new();
+        let mut child = walker.first_child();
+        while let Some(node) = child {
+            children.push(describe(&node));
+            child = walker.next_sibling();
+        }
+        assert_eq!(vec!["t1", "a", "t4"], children);
+
+        assert_eq!("body", describe(&walker.parent_node().unwrap()));
+        assert!(walker.parent_node().is_none());
+    }
+
+    #[test]
+    fn test_node_iterator() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let mut iterator = NodeIterator::new(document, NodeFilter::SHOW_TEXT, None);
+        let mut nodes = Vec::new();
+        while let Some(node) = iterator.next_node() {
+            nodes.push(describe(&node));
+        }
+        assert_eq!(vec!["t1", "t2", "t3", "t4"], nodes);
+        assert!(!iterator.pointer_before_reference_node());
+
+        assert_eq!("t4", describe(&iterator.previous_node().unwrap()));
+        assert_eq!("t3", describe(&iterator.previous_node().unwrap()));
+        assert!(iterator.pointer_before_reference_node());
+    }
+
+    #[test]
+    fn test_node_iterator_pre_removing_steps() {
+        let window = parse(HTML);
+        let document = window.borrow().document();
+        let mut iterator = NodeIterator::new(document, NodeFilter::SHOW_TEXT, None);
+        assert_eq!("t1", describe(&iterator.next_node().unwrap()));
+        assert_eq!("t2", describe(&iterator.next_node().unwrap()));
+
+        // Removing <p> containing the reference node moves the reference to the preceding node.
+        Node::remove(element(&window, ElementKind::P));
+        assert_eq!("body", describe(&iterator.reference_node()));
+        assert_eq!("t3", describe(&iterator.next_node().unwrap()));
+    }
+}
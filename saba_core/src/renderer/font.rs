@@ -0,0 +1,9 @@
+//! <https://www.w3.org/TR/css-fonts-4/>
+//!
+//! Servo: https://github.com/servo/servo/tree/main/components/fonts
+
+pub mod bdf;
+pub mod face;
+pub mod loader;
+pub mod matching;
+pub mod truetype;
@@ -0,0 +1,280 @@
+//! Bitmap fonts in the Glyph Bitmap Distribution Format (BDF).
+//!
+//! <https://adobe-type-tools.github.io/font-tech-notes/pdfs/5005.BDF_Spec.pdf>
+//!
+//! The encodings of the glyphs are assumed to be Unicode code points, i.e. ISO10646-1.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::renderer::layout::computed_style::FontStyle;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitmapFont {
+    /// `FAMILY_NAME` in the properties.
+    pub family: Option<String>,
+    /// Derived from `WEIGHT_NAME` in the properties.
+    pub weight: u16,
+    /// Derived from `SLANT` in the properties.
+    pub style: FontStyle,
+    /// The size the glyphs are designed for, in px.
+    pub pixel_size: u32,
+    /// The distance from the baseline to the top of the font, in px at the pixel size.
+    pub ascent: i32,
+    /// The distance from the baseline to the bottom of the font, in px at the pixel size.
+    pub descent: i32,
+    /// The glyph drawn for the characters without glyphs.
+    default_char: Option<char>,
+    glyphs: BTreeMap<char, BitmapGlyph>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitmapGlyph {
+    /// The horizontal distance to the origin of the next glyph, in px.
+    pub advance: i32,
+    /// The size of the bitmap.
+    pub width: u32,
+    pub height: u32,
+    /// The offset of the bottom-left corner of the bitmap from the origin on the baseline,
+    /// where y goes up.
+    pub x_offset: i32,
+    pub y_offset: i32,
+    /// The rows from the top, each of which is padded to a multiple of 8 bits.
+    pub bitmap: Vec<Vec<u8>>,
+}
+
+impl BitmapGlyph {
+    /// Returns true if the pixel at the position from the top-left corner of the bitmap is set.
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        self.bitmap
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize / 8))
+            .is_some_and(|byte| byte & (0x80 >> (x % 8)) != 0)
+    }
+}
+
+impl BitmapFont {
+    /// Parses a font in BDF. Returns None if it is malformed.
+    pub fn parse(source: &str) -> Option<Self> {
+        let mut lines = source.lines().map(str::trim).filter(|l| !l.is_empty());
+        if !lines.next()?.starts_with("STARTFONT") {
+            return None;
+        }
+
+        let mut font = Self {
+            family: None,
+            weight: 400,
+            style: FontStyle::Normal,
+            pixel_size: 0,
+            ascent: 0,
+            descent: 0,
+            default_char: None,
+            glyphs: BTreeMap::new(),
+        };
+        // The global values, which the properties and the glyphs can override.
+        let mut bounding_box = None;
+        let mut default_advance = None;
+        let (mut ascent, mut descent) = (None, None);
+
+        while let Some(line) = lines.next() {
+            let (keyword, args) = line.split_once(' ').unwrap_or((line, ""));
+            let numbers = || {
+                args.split_ascii_whitespace()
+                    .map(|n| n.parse::<i32>().ok())
+                    .collect::<Option<Vec<_>>>()
+            };
+            match keyword {
+                "SIZE" => font.pixel_size = *numbers()?.first()? as u32,
+                "FONTBOUNDINGBOX" => bounding_box = Some(numbers()?),
+                "DWIDTH" => default_advance = numbers()?.first().copied(),
+                "PIXEL_SIZE" => font.pixel_size = *numbers()?.first()? as u32,
+                "FONT_ASCENT" => ascent = numbers()?.first().copied(),
+                "FONT_DESCENT" => descent = numbers()?.first().copied(),
+                "DEFAULT_CHAR" => {
+                    font.default_char = numbers()?.first().and_then(|c| char::from_u32(*c as u32))
+                }
+                "FAMILY_NAME" => font.family = Some(unquote(args)),
+                "WEIGHT_NAME" => font.weight = weight_from_name(&unquote(args)),
+                "SLANT" => {
+                    font.style = match unquote(args).as_str() {
+                        "I" | "RI" => FontStyle::Italic,
+                        "O" | "RO" => FontStyle::Oblique,
+                        _ => FontStyle::Normal,
+                    }
+                }
+                "STARTCHAR" => {
+                    let (encoding, glyph) = parse_glyph(&mut lines, default_advance)?;
+                    if let Some(c) = encoding {
+                        font.glyphs.insert(c, glyph);
+                    }
+                }
+                "ENDFONT" => break,
+                _ => {}
+            }
+        }
+
+        // The bounding box is `width height x-offset y-offset`.
+        let bounding_box = bounding_box.unwrap_or_default();
+        let box_height = bounding_box.get(1).copied().unwrap_or(0);
+        let box_y_offset = bounding_box.get(3).copied().unwrap_or(0);
+        font.ascent = ascent.unwrap_or(box_height + box_y_offset);
+        font.descent = descent.unwrap_or(-box_y_offset);
+        if font.pixel_size == 0 {
+            font.pixel_size = (font.ascent + font.descent).max(1) as u32;
+        }
+        Some(font)
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&BitmapGlyph> {
+        self.glyphs.get(&c)
+    }
+
+    /// The glyph drawn for the characters without glyphs, if any.
+    pub fn default_glyph(&self) -> Option<&BitmapGlyph> {
+        self.default_char.and_then(|c| self.glyph(c))
+    }
+}
+
+/// Parses the lines from `STARTCHAR` to `ENDCHAR`.
+/// Returns the character the glyph is encoded for, which is None for unencoded glyphs.
+fn parse_glyph<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    default_advance: Option<i32>,
+) -> Option<(Option<char>, BitmapGlyph)> {
+    let mut encoding = None;
+    let mut glyph = BitmapGlyph {
+        advance: 0,
+        width: 0,
+        height: 0,
+        x_offset: 0,
+        y_offset: 0,
+        bitmap: Vec::new(),
+    };
+    let mut advance = default_advance;
+    loop {
+        let line = lines.next()?;
+        let (keyword, args) = line.split_once(' ').unwrap_or((line, ""));
+        let numbers = args
+            .split_ascii_whitespace()
+            .map(|n| n.parse::<i32>().ok())
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        match (keyword, numbers.as_slice()) {
+            // -1 means the glyph is not encoded.
+            ("ENCODING", [code, ..]) => {
+                encoding = u32::try_from(*code).ok().and_then(char::from_u32)
+            }
+            ("DWIDTH", [x, ..]) => advance = Some(*x),
+            ("BBX", [width, height, x_offset, y_offset]) => {
+                glyph.width = u32::try_from(*width).ok()?;
+                glyph.height = u32::try_from(*height).ok()?;
+                glyph.x_offset = *x_offset;
+                glyph.y_offset = *y_offset;
+            }
+            ("BITMAP", _) => {
+                for _ in 0..glyph.height {
+                    glyph.bitmap.push(parse_hex(lines.next()?)?);
+                }
+            }
+            ("ENDCHAR", _) => break,
+            _ => {}
+        }
+    }
+    glyph.advance = advance.unwrap_or(glyph.width as i32);
+    Some((encoding, glyph))
+}
+
+fn parse_hex(row: &str) -> Option<Vec<u8>> {
+    let row = row.as_bytes();
+    row.chunks(2)
+        .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Removes the quotes around a string property.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .replace("\"\"", "\"")
+        .to_string()
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-weight-numeric-values>
+fn weight_from_name(name: &str) -> u16 {
+    match name.to_ascii_lowercase().replace(['-', ' '], "").as_str() {
+        "thin" | "hairline" => 100,
+        "extralight" | "ultralight" => 200,
+        "light" => 300,
+        "medium" => 500,
+        "semibold" | "demibold" => 600,
+        "bold" => 700,
+        "extrabold" | "ultrabold" => 800,
+        "black" | "heavy" => 900,
+        _ => 400,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "STARTFONT 2.1
+FONT -sample-Sample Mono-Bold-I-Normal--8-80-75-75-C-40-ISO10646-1
+SIZE 8 75 75
+FONTBOUNDINGBOX 4 8 0 -2
+STARTPROPERTIES 4
+FAMILY_NAME \"Sample Mono\"
+WEIGHT_NAME \"Bold\"
+SLANT \"I\"
+DEFAULT_CHAR 63
+ENDPROPERTIES
+CHARS 2
+STARTCHAR A
+ENCODING 65
+SWIDTH 500 0
+DWIDTH 4 0
+BBX 3 5 0 0
+BITMAP
+40
+A0
+E0
+A0
+A0
+ENDCHAR
+STARTCHAR question
+ENCODING 63
+DWIDTH 5 0
+BBX 4 2 0 3
+BITMAP
+F0
+10
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn test_parse() {
+        let font = BitmapFont::parse(SAMPLE).unwrap();
+        assert_eq!(font.family.as_deref(), Some("Sample Mono"));
+        assert_eq!(font.weight, 700);
+        assert_eq!(font.style, FontStyle::Italic);
+        assert_eq!(font.pixel_size, 8);
+        assert_eq!((font.ascent, font.descent), (6, 2));
+
+        let a = font.glyph('A').unwrap();
+        assert_eq!(a.advance, 4);
+        assert_eq!((a.width, a.height, a.x_offset, a.y_offset), (3, 5, 0, 0));
+        assert!(a.pixel(1, 0));
+        assert!(!a.pixel(0, 0));
+        assert!(a.pixel(0, 2) && a.pixel(1, 2) && a.pixel(2, 2));
+        assert!(!a.pixel(3, 2));
+        assert!(font.glyph('B').is_none());
+        assert_eq!(font.default_glyph().unwrap().advance, 5);
+
+        assert_eq!(BitmapFont::parse("STARTCHAR A"), None);
+    }
+}
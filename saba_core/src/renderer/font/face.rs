@@ -0,0 +1,180 @@
+//! <https://www.w3.org/TR/css-fonts-4/#font-face-rule>
+//!
+//! A font face is a font in a family with a style and a range of weights,
+//! either installed in the browser or loaded by an `@font-face` rule.
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::ops::RangeInclusive;
+
+use crate::renderer::layout::computed_style::FontStyle;
+
+use super::bdf::BitmapFont;
+use super::truetype::TrueTypeFont;
+
+/// The family of the font the browser always has.
+pub const FIXED_FONT_FAMILY: &str = "Saba Fixed";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontData {
+    /// A fixed-width font whose glyphs are drawn by the UI, e.g. the 8x16 font of the OS.
+    Fixed(FixedFont),
+    Bitmap(BitmapFont),
+    TrueType(TrueTypeFont),
+}
+
+/// A fixed-width font, where every glyph occupies a cell of the same size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedFont {
+    /// The size of the cells in px, whose height is the size the font is designed for.
+    pub cell_width: u32,
+    pub cell_height: u32,
+    /// The distance from the top of the cells to the baseline, in px.
+    pub ascent: u32,
+    /// The characters the font has the glyphs for.
+    pub coverage: RangeInclusive<char>,
+}
+
+impl FixedFont {
+    /// The 8x16 font the UI draws text with, covering the printable ASCII characters.
+    pub const UI: Self = Self {
+        cell_width: 8,
+        cell_height: 16,
+        ascent: 12,
+        coverage: ' '..='~',
+    };
+}
+
+/// The metrics of a font at a font size, in px.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// The distance from the baseline to the top of the font.
+    pub ascent: f64,
+    /// The distance from the baseline to the bottom of the font.
+    pub descent: f64,
+    /// The recommended gap between the lines.
+    pub line_gap: f64,
+}
+
+impl FontData {
+    /// Parses a font in TrueType, OpenType or BDF. Returns None if the format is not supported.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if TrueTypeFont::is_supported(data) {
+            return TrueTypeFont::parse(data.to_vec()).map(Self::TrueType);
+        }
+        BitmapFont::parse(core::str::from_utf8(data).ok()?).map(Self::Bitmap)
+    }
+
+    /// The family name the font declares, if any.
+    pub fn family(&self) -> Option<&str> {
+        match self {
+            Self::Fixed(_) => Some(FIXED_FONT_FAMILY),
+            Self::Bitmap(font) => font.family.as_deref(),
+            Self::TrueType(font) => font.family.as_deref(),
+        }
+    }
+
+    /// The weight the font declares.
+    pub fn weight(&self) -> u16 {
+        match self {
+            Self::Fixed(_) => 400,
+            Self::Bitmap(font) => font.weight,
+            Self::TrueType(font) => font.weight,
+        }
+    }
+
+    /// The style the font declares.
+    pub fn style(&self) -> FontStyle {
+        match self {
+            Self::Fixed(_) => FontStyle::Normal,
+            Self::Bitmap(font) => font.style,
+            Self::TrueType(font) => font.style,
+        }
+    }
+
+    pub fn has_glyph(&self, c: char) -> bool {
+        match self {
+            Self::Fixed(font) => font.coverage.contains(&c),
+            Self::Bitmap(font) => font.glyph(c).is_some(),
+            Self::TrueType(font) => font.glyph_index(c).is_some(),
+        }
+    }
+
+    /// Returns the advance of the glyph of the character in px,
+    /// or that of the missing glyph if the font lacks it.
+    pub fn advance(&self, c: char, font_size: f64) -> f64 {
+        match self {
+            Self::Fixed(font) => font.cell_width as f64 * font_size / font.cell_height as f64,
+            Self::Bitmap(font) => {
+                let advance = font
+                    .glyph(c)
+                    .or_else(|| font.default_glyph())
+                    .map_or(font.pixel_size as i32 / 2, |glyph| glyph.advance);
+                advance as f64 * font_size / font.pixel_size as f64
+            }
+            Self::TrueType(font) => {
+                let advance = font.advance(font.glyph_index(c).unwrap_or(0));
+                advance as f64 * font_size / font.units_per_em as f64
+            }
+        }
+    }
+
+    pub fn metrics(&self, font_size: f64) -> FontMetrics {
+        let (ascent, descent, line_gap, units) = match self {
+            Self::Fixed(font) => (
+                font.ascent as f64,
+                (font.cell_height - font.ascent) as f64,
+                0.0,
+                font.cell_height as f64,
+            ),
+            Self::Bitmap(font) => (
+                font.ascent as f64,
+                font.descent as f64,
+                0.0,
+                font.pixel_size as f64,
+            ),
+            Self::TrueType(font) => (
+                font.ascender as f64,
+                -font.descender as f64,
+                font.line_gap as f64,
+                font.units_per_em as f64,
+            ),
+        };
+        let scale = font_size / units;
+        FontMetrics {
+            ascent: ascent * scale,
+            descent: descent * scale,
+            line_gap: line_gap.max(0.0) * scale,
+        }
+    }
+}
+
+/// A font in a family, which is matched against the font properties of elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFace {
+    pub family: String,
+    /// The range of the weights the font supports, which is a single weight for static fonts.
+    pub weight: (u16, u16),
+    pub style: FontStyle,
+    pub data: Rc<FontData>,
+}
+
+impl FontFace {
+    /// Creates a face with the family, the weight and the style the font declares.
+    /// Returns None if the font has no family name.
+    pub fn new(data: FontData) -> Option<Self> {
+        let family = data.family()?.into();
+        Some(Self::with_family(family, data))
+    }
+
+    /// Creates a face in the family, with the weight and the style the font declares.
+    pub fn with_family(family: String, data: FontData) -> Self {
+        let weight = data.weight();
+        Self {
+            family,
+            weight: (weight, weight),
+            style: data.style(),
+            data: Rc::new(data),
+        }
+    }
+}
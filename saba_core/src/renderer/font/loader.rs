@@ -0,0 +1,222 @@
+//! Loads the fonts of the `@font-face` rules in the stylesheets of a document.
+//!
+//! <https://www.w3.org/TR/css-fonts-4/#font-face-loading>
+//!
+//! Servo: <https://github.com/servo/servo/blob/main/components/fonts/font_context.rs>
+
+use alloc::rc::Rc;
+
+use crate::http::Fetcher;
+use crate::renderer::css::cssom::{CssFontFaceRule, CssRule, CssStyleSheet, StyleSheetList};
+use crate::renderer::css::font_face::FontFaceSource;
+use crate::url::Url;
+
+use super::face::{FontData, FontFace};
+use super::matching::FontRegistry;
+
+/// The formats in `format()` of `src` the browser can load. Sources without `format()` are tried.
+const SUPPORTED_FORMATS: [&str; 2] = ["truetype", "opentype"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct FontLoader<'a> {
+    /// The URL of the document, against which the URLs in the embedded stylesheets are resolved.
+    base_url: Option<&'a Url>,
+    /// Without it, only `local()` fonts are loaded.
+    fetcher: Option<&'a dyn Fetcher>,
+}
+
+impl<'a> FontLoader<'a> {
+    pub fn new(base_url: Option<&'a Url>, fetcher: Option<&'a dyn Fetcher>) -> Self {
+        Self { base_url, fetcher }
+    }
+
+    /// Returns the registry of the fonts loaded from the enabled stylesheets, in addition to
+    /// the installed fonts.
+    ///
+    /// `@font-face` rules in conditional group rules, e.g. `@media`, are not loaded.
+    pub fn load(&self, style_sheets: &StyleSheetList, installed: Rc<FontRegistry>) -> FontRegistry {
+        let mut registry = FontRegistry::with_fallback(installed);
+        for style_sheet in style_sheets.enabled() {
+            let location = style_sheet.location.as_ref().or(self.base_url);
+            self.load_style_sheet(&style_sheet.css_style_sheet, location, &mut registry);
+        }
+        registry
+    }
+
+    fn load_style_sheet(
+        &self,
+        css_style_sheet: &CssStyleSheet,
+        location: Option<&Url>,
+        registry: &mut FontRegistry,
+    ) {
+        for rule in &css_style_sheet.css_rules {
+            match rule {
+                CssRule::Import(rule) => {
+                    if let Some(imported) = &rule.style_sheet {
+                        let url = self.resolve(location, &rule.href);
+                        self.load_style_sheet(imported, url.as_ref(), registry);
+                    }
+                }
+                CssRule::FontFace(rule) => {
+                    if let Some(face) = self.load_font_face(rule, location, registry) {
+                        registry.add(face);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-fonts-4/#font-face-src-loading>
+    ///
+    /// Loads the first source which succeeds. Returns None if the rule lacks `font-family` or `src`,
+    /// or no source can be loaded.
+    fn load_font_face(
+        &self,
+        rule: &CssFontFaceRule,
+        location: Option<&Url>,
+        registry: &FontRegistry,
+    ) -> Option<FontFace> {
+        let descriptors = &rule.descriptors;
+        let family = descriptors.family.clone()?;
+        let data = descriptors
+            .src
+            .as_ref()?
+            .iter()
+            .find_map(|source| match source {
+                FontFaceSource::Local(name) => {
+                    registry.local(name).map(|face| face.data.as_ref().clone())
+                }
+                FontFaceSource::Url { url, format } => {
+                    if format
+                        .as_ref()
+                        .is_some_and(|format| !SUPPORTED_FORMATS.contains(&format.as_str()))
+                    {
+                        return None;
+                    }
+                    self.fetch(&self.resolve(location, url)?)
+                }
+            })?;
+
+        let mut face = FontFace::with_family(family, data);
+        // `auto` takes the weight and the style of the font.
+        if let Some(Some(weight)) = descriptors.weight {
+            face.weight = weight;
+        }
+        if let Some(Some(style)) = descriptors.style {
+            face.style = style;
+        }
+        Some(face)
+    }
+
+    fn resolve(&self, base_url: Option<&Url>, href: &str) -> Option<Url> {
+        match base_url {
+            Some(base_url) => base_url.join(href).ok(),
+            None => Url::new(href.into()).parse().ok(),
+        }
+    }
+
+    /// Fetches and parses a font.
+    /// Returns None if the request fails, the response is not successful, or the font is malformed.
+    fn fetch(&self, url: &Url) -> Option<FontData> {
+        let response = self.fetcher?.fetch(url).ok()?;
+        if !(200..300).contains(&response.status_code()) {
+            return None;
+        }
+        FontData::parse(response.body_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    use crate::http::test_util::TestFetcher;
+    use crate::renderer::css::loader::StyleSheetLoader;
+    use crate::renderer::dom::test_util;
+    use crate::renderer::layout::computed_style::{ComputedStyle, FontStyle};
+
+    use super::*;
+
+    /// A bitmap font in the family with a glyph of the character, which is `advance` wide.
+    fn bdf(family: &str, c: char, advance: i32) -> String {
+        format!(
+            "STARTFONT 2.1\nSIZE 16 75 75\nFONTBOUNDINGBOX 8 16 0 -4\nSTARTPROPERTIES 1\n\
+             FAMILY_NAME \"{family}\"\nENDPROPERTIES\nSTARTCHAR x\nENCODING {}\n\
+             DWIDTH {advance} 0\nBBX 0 0 0 0\nBITMAP\nENDCHAR\nENDFONT\n",
+            c as u32
+        )
+    }
+
+    #[test]
+    fn test_load() {
+        let fetcher = TestFetcher::new(vec![
+            ("example.com:80/fonts/a.bdf", bdf("A", 'a', 10)),
+            ("example.com:80/css/b.bdf", bdf("B", 'b', 11)),
+            ("example.com:80/css/c.ttf", bdf("C", 'c', 12)),
+            ("example.com:80/css/e.bdf", bdf("E", 'e', 13)),
+        ]);
+        let base_url = Url::new("http://example.com/index.html".into())
+            .parse()
+            .unwrap();
+        let mut installed = FontRegistry::default();
+        installed.add(
+            FontFace::new(FontData::parse(bdf("Installed", 'i', 14).as_bytes()).unwrap()).unwrap(),
+        );
+
+        let html = r#"<html><head><style>
+            @import "css/imported.css";
+            @font-face { font-family: Web; src: url(missing.bdf), url(fonts/a.bdf); font-weight: 100 300 }
+            @font-face { font-family: Web; src: local(Installed); font-style: italic }
+            @font-face { font-family: NoSource; src: url(missing.bdf) }
+            @media print { @font-face { font-family: Print; src: url(fonts/a.bdf) } }
+        </style></head></html>"#;
+        let fetcher_css = TestFetcher::new(vec![(
+            "example.com:80/css/imported.css",
+            "@font-face { font-family: B; src: url(c.ttf) format('woff2'), url(b.bdf) } \
+             @font-face { src: url(e.bdf) }"
+                .to_string(),
+        )]);
+        let window = test_util::parse(html);
+        let document = window.borrow().document();
+        let style_sheets =
+            StyleSheetLoader::new(Some(&base_url), Some(&fetcher_css)).load(document);
+
+        let registry = FontLoader::new(Some(&base_url), Some(&fetcher))
+            .load(&style_sheets, Rc::new(installed));
+        let face = |family: &str, weight, style| {
+            registry
+                .match_family(family, weight, style)
+                .map(|face| (face.weight, face.style, face.data.advance('x', 16.0)))
+        };
+        // The advance of the missing glyph is half of the size.
+        assert_eq!(
+            face("Web", 400, FontStyle::Normal),
+            Some(((100, 300), FontStyle::Normal, 8.0))
+        );
+        assert_eq!(
+            face("web", 400, FontStyle::Italic),
+            Some(((400, 400), FontStyle::Italic, 8.0))
+        );
+        assert!(registry
+            .match_family("NoSource", 400, FontStyle::Normal)
+            .is_none());
+        assert!(registry
+            .match_family("Print", 400, FontStyle::Normal)
+            .is_none());
+        assert!(registry.match_family("E", 400, FontStyle::Normal).is_none());
+
+        let style = ComputedStyle {
+            font_family: vec!["B".into(), "Web".into()],
+            ..Default::default()
+        };
+        assert_eq!(registry.measure(&style, "ab"), 10.0 + 11.0);
+        let italic = ComputedStyle {
+            font_style: FontStyle::Italic,
+            ..style
+        };
+        assert_eq!(registry.font_for_char(&italic, 'i').family, "Web");
+    }
+}
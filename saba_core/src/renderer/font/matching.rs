@@ -0,0 +1,343 @@
+//! <https://www.w3.org/TR/css-fonts-4/#font-matching-algorithm>
+//!
+//! Servo: <https://github.com/servo/servo/blob/main/components/fonts/font_context.rs>
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::renderer::layout::computed_style::{ComputedStyle, FontStyle};
+
+use super::face::{FixedFont, FontData, FontFace, FIXED_FONT_FAMILY};
+
+/// <https://www.w3.org/TR/css-fonts-4/#generic-font-families>
+pub const GENERIC_FAMILIES: [&str; 5] = ["serif", "sans-serif", "cursive", "fantasy", "monospace"];
+
+/// The fonts available to a document, which are matched against the font properties.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontRegistry {
+    faces: Vec<FontFace>,
+    /// The families the generic families are mapped to.
+    generic_families: BTreeMap<String, String>,
+    /// The fonts installed in the browser, for a document with web fonts.
+    ///
+    /// The families of the faces in this registry shadow those in the fallback.
+    fallback: Option<Rc<FontRegistry>>,
+}
+
+/// The registry of the browser, which has the fixed font of the UI
+/// as the family of all the generic families.
+impl Default for FontRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            faces: Vec::new(),
+            generic_families: BTreeMap::new(),
+            fallback: None,
+        };
+        registry.add(FontFace::with_family(
+            FIXED_FONT_FAMILY.into(),
+            FontData::Fixed(FixedFont::UI),
+        ));
+        for generic in GENERIC_FAMILIES {
+            registry.set_generic_family(generic, FIXED_FONT_FAMILY);
+        }
+        registry
+    }
+}
+
+impl FontRegistry {
+    /// Creates a registry for the web fonts of a document, falling back to the installed fonts.
+    pub fn with_fallback(fallback: Rc<FontRegistry>) -> Self {
+        Self {
+            faces: Vec::new(),
+            generic_families: BTreeMap::new(),
+            fallback: Some(fallback),
+        }
+    }
+
+    pub fn add(&mut self, face: FontFace) {
+        self.faces.push(face);
+    }
+
+    /// Maps a generic family, e.g. `sans-serif`, to a family in the registry.
+    pub fn set_generic_family(&mut self, generic: &str, family: &str) {
+        self.generic_families
+            .insert(generic.to_ascii_lowercase(), family.into());
+    }
+
+    /// Returns the faces of the family, which is ASCII case-insensitive.
+    fn family(&self, family: &str) -> Vec<&FontFace> {
+        let faces = self
+            .faces
+            .iter()
+            .filter(|face| face.family.eq_ignore_ascii_case(family))
+            .collect::<Vec<_>>();
+        match &self.fallback {
+            Some(fallback) if faces.is_empty() => fallback.family(family),
+            _ => faces,
+        }
+    }
+
+    fn generic_family(&self, generic: &str) -> Option<&str> {
+        match self.generic_families.get(&generic.to_ascii_lowercase()) {
+            Some(family) => Some(family),
+            None => self.fallback.as_ref()?.generic_family(generic),
+        }
+    }
+
+    /// Returns the installed face whose family is the name, for `local()` in `@font-face`.
+    pub fn local(&self, name: &str) -> Option<&FontFace> {
+        match &self.fallback {
+            Some(fallback) => fallback.local(name),
+            None => self.family(name).into_iter().next(),
+        }
+    }
+
+    /// <https://www.w3.org/TR/css-fonts-4/#font-style-matching>
+    ///
+    /// Returns the face in the family which best matches the style and the weight.
+    /// A generic family is matched if no family has the name.
+    pub fn match_family(&self, family: &str, weight: u16, style: FontStyle) -> Option<&FontFace> {
+        let mut faces = self.family(family);
+        if faces.is_empty() {
+            if let Some(generic) = self.generic_family(family) {
+                faces = self.family(generic);
+            }
+        }
+
+        // The style is narrowed before the weight.
+        let style_order = match style {
+            FontStyle::Italic => [FontStyle::Italic, FontStyle::Oblique, FontStyle::Normal],
+            FontStyle::Oblique => [FontStyle::Oblique, FontStyle::Italic, FontStyle::Normal],
+            FontStyle::Normal => [FontStyle::Normal, FontStyle::Oblique, FontStyle::Italic],
+        };
+        let style = style_order
+            .into_iter()
+            .find(|style| faces.iter().any(|face| face.style == *style))?;
+        faces
+            .into_iter()
+            .filter(|face| face.style == style)
+            .min_by_key(|face| weight_distance(face.weight, weight))
+    }
+
+    /// <https://www.w3.org/TR/css-fonts-4/#first-available-font>
+    ///
+    /// Returns the face of the first family in `font-family` which is available,
+    /// or the face of the default family. The metrics of a line are taken from it.
+    pub fn primary_font(&self, style: &ComputedStyle) -> &FontFace {
+        style
+            .font_family
+            .iter()
+            .find_map(|family| self.match_family(family, style.font_weight, style.font_style))
+            .unwrap_or_else(|| self.default_font(style))
+    }
+
+    /// <https://www.w3.org/TR/css-fonts-4/#cluster-matching>
+    ///
+    /// Returns the face the character is drawn with: the first of the families
+    /// in `font-family` which has its glyph, then the first of the other families in the registry.
+    /// If none has, the primary font draws its missing glyph.
+    pub fn font_for_char(&self, style: &ComputedStyle, c: char) -> &FontFace {
+        let (weight, font_style) = (style.font_weight, style.font_style);
+        let has_glyph = |face: &&FontFace| face.data.has_glyph(c);
+        style
+            .font_family
+            .iter()
+            .filter_map(|family| self.match_family(family, weight, font_style))
+            .find(has_glyph)
+            .or_else(|| {
+                // <https://www.w3.org/TR/css-fonts-4/#system-font-fallback>
+                self.families()
+                    .into_iter()
+                    .filter_map(|family| self.match_family(&family, weight, font_style))
+                    .find(has_glyph)
+            })
+            .unwrap_or_else(|| self.primary_font(style))
+    }
+
+    /// Measures the width of the text in px, drawing each character with the face for it.
+    pub fn measure(&self, style: &ComputedStyle, text: &str) -> f64 {
+        text.chars()
+            .map(|c| {
+                self.font_for_char(style, c)
+                    .data
+                    .advance(c, style.font_size)
+            })
+            .sum()
+    }
+
+    /// The names of the families in the order they are added, including those of the fallback.
+    fn families(&self) -> Vec<String> {
+        let mut families: Vec<String> = Vec::new();
+        let fallback = self.fallback.as_ref().map(|fallback| fallback.families());
+        for family in self
+            .faces
+            .iter()
+            .map(|face| face.family.to_string())
+            .chain(fallback.into_iter().flatten())
+        {
+            if !families.iter().any(|f| f.eq_ignore_ascii_case(&family)) {
+                families.push(family);
+            }
+        }
+        families
+    }
+
+    /// The face of the family the `serif` generic family is mapped to, or the first face.
+    fn default_font(&self, style: &ComputedStyle) -> &FontFace {
+        self.match_family("serif", style.font_weight, style.font_style)
+            .or_else(|| self.faces.first())
+            .or_else(|| {
+                self.fallback
+                    .as_ref()
+                    .map(|fallback| fallback.default_font(style))
+            })
+            .expect("the registry of the browser has the fixed font")
+    }
+}
+
+/// <https://www.w3.org/TR/css-fonts-4/#font-style-matching>
+///
+/// Ranks how far the weights of a face are from the desired weight. Smaller is better.
+///
+/// If the desired weight is between 400 and 500, heavier weights up to 500 are checked first,
+/// then lighter weights, then heavier weights above 500.
+/// Lighter weights are checked first for a weight below 400, and heavier weights above 500.
+fn weight_distance((min, max): (u16, u16), weight: u16) -> (u8, u16) {
+    if (min..=max).contains(&weight) {
+        return (0, 0);
+    }
+    let heavier = min > weight;
+    let distance = if heavier { min - weight } else { weight - max };
+    let tier = match weight {
+        400..=500 if heavier && min <= 500 => 1,
+        400..=500 if !heavier => 2,
+        400..=500 => 3,
+        0..=399 if heavier => 2,
+        0..=399 => 1,
+        _ if heavier => 1,
+        _ => 2,
+    };
+    (tier, distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bdf::BitmapFont;
+    use super::*;
+
+    /// A bitmap font in the family with the glyphs of the characters, each of which is `advance` wide.
+    fn font(family: &str, weight: u16, style: FontStyle, chars: &str, advance: i32) -> FontFace {
+        let mut bdf =
+            alloc::format!("STARTFONT 2.1\nSIZE 16 75 75\nFONTBOUNDINGBOX {advance} 16 0 -4\n");
+        for c in chars.chars() {
+            bdf.push_str(&alloc::format!(
+                "STARTCHAR x\nENCODING {}\nDWIDTH {advance} 0\nBBX 0 0 0 0\nBITMAP\nENDCHAR\n",
+                c as u32
+            ));
+        }
+        bdf.push_str("ENDFONT\n");
+        let data = FontData::Bitmap(BitmapFont::parse(&bdf).unwrap());
+        FontFace {
+            family: family.into(),
+            weight: (weight, weight),
+            style,
+            data: Rc::new(data),
+        }
+    }
+
+    fn style(families: &[&str], weight: u16, font_style: FontStyle) -> ComputedStyle {
+        ComputedStyle {
+            font_family: families.iter().map(|f| f.to_string()).collect(),
+            font_weight: weight,
+            font_style,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_match_weight() {
+        let mut registry = FontRegistry::default();
+        for weight in [300, 450, 600, 900] {
+            registry.add(font("Foo", weight, FontStyle::Normal, "a", 1));
+        }
+        let matched = |weight| {
+            registry
+                .match_family("FOO", weight, FontStyle::Normal)
+                .unwrap()
+                .weight
+                .0
+        };
+        assert_eq!(matched(450), 450);
+        // Up to 500, then lighter, then heavier.
+        assert_eq!(matched(400), 450);
+        assert_eq!(matched(470), 450);
+        assert_eq!(matched(500), 450);
+        // Lighter first below 400.
+        assert_eq!(matched(350), 300);
+        assert_eq!(matched(200), 300);
+        // Heavier first above 500.
+        assert_eq!(matched(700), 900);
+        assert_eq!(matched(550), 600);
+
+        let mut registry = FontRegistry::default();
+        registry.add(font("Bar", 100, FontStyle::Normal, "a", 1));
+        registry.add(font("Bar", 800, FontStyle::Normal, "a", 1));
+        let matched = |weight| {
+            registry
+                .match_family("Bar", weight, FontStyle::Normal)
+                .unwrap()
+                .weight
+                .0
+        };
+        assert_eq!(matched(400), 100);
+        assert_eq!(matched(600), 800);
+        assert!(registry
+            .match_family("Baz", 400, FontStyle::Normal)
+            .is_none());
+    }
+
+    #[test]
+    fn test_match_style() {
+        let mut registry = FontRegistry::default();
+        registry.add(font("Foo", 400, FontStyle::Normal, "a", 1));
+        registry.add(font("Foo", 700, FontStyle::Oblique, "a", 1));
+        let matched = |style| registry.match_family("Foo", 400, style).unwrap().style;
+        assert_eq!(matched(FontStyle::Italic), FontStyle::Oblique);
+        assert_eq!(matched(FontStyle::Normal), FontStyle::Normal);
+        registry.add(font("Foo", 400, FontStyle::Italic, "a", 1));
+        let matched = |style| registry.match_family("Foo", 400, style).unwrap().style;
+        assert_eq!(matched(FontStyle::Italic), FontStyle::Italic);
+        assert_eq!(matched(FontStyle::Oblique), FontStyle::Oblique);
+    }
+
+    #[test]
+    fn test_fallback() {
+        let mut installed = FontRegistry::default();
+        installed.add(font("Kana", 400, FontStyle::Normal, "あい", 16));
+        installed.add(font("Web", 400, FontStyle::Normal, "a", 3));
+        let mut registry = FontRegistry::with_fallback(Rc::new(installed));
+        // A web font shadows the installed family of the same name.
+        registry.add(font("Web", 400, FontStyle::Normal, "ab", 4));
+        registry.add(font("Digits", 400, FontStyle::Normal, "1", 5));
+
+        let style = style(&["Missing", "web", "monospace"], 400, FontStyle::Normal);
+        let family = |c| registry.font_for_char(&style, c).family.as_str();
+        assert_eq!(family('a'), "Web");
+        assert_eq!(family('z'), FIXED_FONT_FAMILY);
+        assert_eq!(family('1'), FIXED_FONT_FAMILY);
+        // Not in any family of `font-family`.
+        assert_eq!(family('あ'), "Kana");
+        assert_eq!(family('\u{1f600}'), "Web");
+        assert_eq!(registry.primary_font(&style).family, "Web");
+        // 4 + 4 + 8 + 16 + 8, the last of which is the missing glyph of the primary font.
+        assert_eq!(registry.measure(&style, "abzい\u{1f600}"), 40.0);
+
+        assert_eq!(registry.local("web").unwrap().data.advance('a', 16.0), 3.0);
+        let default = ComputedStyle::default();
+        assert_eq!(registry.primary_font(&default).family, FIXED_FONT_FAMILY);
+        assert_eq!(registry.measure(&default, "ab"), 16.0);
+        assert_eq!(registry.families().len(), 4);
+    }
+}
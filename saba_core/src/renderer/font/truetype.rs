@@ -0,0 +1,829 @@
+//! TrueType and OpenType fonts.
+//!
+//! <https://learn.microsoft.com/en-us/typography/opentype/spec/otff>
+//!
+//! Only the tables needed for laying out and painting text are read, i.e. the character-to-glyph
+//! mapping, the horizontal metrics and the TrueType outlines, which are rasterized into bitmaps
+//! without hinting or anti-aliasing.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::renderer::layout::computed_style::FontStyle;
+
+use super::bdf::BitmapGlyph;
+
+/// The number of line segments a quadratic Bézier curve of an outline is flattened into.
+const CURVE_SEGMENTS: usize = 8;
+
+/// The deepest nesting of composite glyphs, which guards against cycles of components.
+const MAX_COMPONENT_DEPTH: usize = 8;
+
+/// The largest width and height of a rasterized glyph in px.
+const MAX_BITMAP_SIZE: u32 = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrueTypeFont {
+    data: Vec<u8>,
+    /// <https://learn.microsoft.com/en-us/typography/opentype/spec/head>
+    pub units_per_em: u16,
+    /// <https://learn.microsoft.com/en-us/typography/opentype/spec/hhea>, in font units.
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    number_of_h_metrics: u16,
+    /// The offset of the `hmtx` table.
+    hmtx: usize,
+    /// The offset and the format of the `cmap` subtable in use.
+    cmap: Option<(usize, u16)>,
+    /// The offsets of the `glyf` and `loca` tables, and whether the offsets in `loca` are 32-bit.
+    /// None if the font has no TrueType outlines, e.g. an OpenType font with CFF outlines.
+    glyf: Option<(usize, usize, bool)>,
+    /// The typographic family name in the `name` table.
+    pub family: Option<String>,
+    /// `usWeightClass` in the `OS/2` table.
+    pub weight: u16,
+    /// Derived from `fsSelection` in the `OS/2` table.
+    pub style: FontStyle,
+}
+
+impl TrueTypeFont {
+    /// Returns true if the data starts with the version of a TrueType or OpenType font.
+    pub fn is_supported(data: &[u8]) -> bool {
+        matches!(data.get(..4), Some([0, 1, 0, 0] | b"true" | b"OTTO"))
+    }
+
+    /// Parses a font. Returns None if it is malformed or lacks a required table.
+    pub fn parse(data: Vec<u8>) -> Option<Self> {
+        if !Self::is_supported(&data) {
+            return None;
+        }
+        let reader = Reader(&data);
+        let head = reader.table(b"head")?;
+        let hhea = reader.table(b"hhea")?;
+        let hmtx = reader.table(b"hmtx")?;
+        let number_of_h_metrics = reader.u16(hhea + 34)?;
+        if number_of_h_metrics == 0 {
+            return None;
+        }
+        reader.u16(hmtx + 4 * (number_of_h_metrics as usize - 1))?;
+
+        let (weight, style) = match reader.table(b"OS/2") {
+            Some(os2) => {
+                // <https://learn.microsoft.com/en-us/typography/opentype/spec/os2#fsselection>
+                let fs_selection = reader.u16(os2 + 62).unwrap_or(0);
+                let style = if fs_selection & (1 << 9) != 0 {
+                    FontStyle::Oblique
+                } else if fs_selection & 1 != 0 {
+                    FontStyle::Italic
+                } else {
+                    FontStyle::Normal
+                };
+                (reader.u16(os2 + 4).unwrap_or(400).clamp(1, 1000), style)
+            }
+            None => (400, FontStyle::Normal),
+        };
+
+        Some(Self {
+            units_per_em: reader.u16(head + 18).filter(|u| *u > 0)?,
+            ascender: reader.i16(hhea + 4)?,
+            descender: reader.i16(hhea + 6)?,
+            line_gap: reader.i16(hhea + 8)?,
+            number_of_h_metrics,
+            hmtx,
+            cmap: reader
+                .table(b"cmap")
+                .and_then(|cmap| reader.cmap_subtable(cmap)),
+            // <https://learn.microsoft.com/en-us/typography/opentype/spec/head>
+            glyf: reader
+                .table(b"glyf")
+                .zip(reader.table(b"loca"))
+                .zip(reader.i16(head + 50))
+                .map(|((glyf, loca), index_to_loc_format)| (glyf, loca, index_to_loc_format == 1)),
+            family: reader
+                .table(b"name")
+                .and_then(|name| reader.family_name(name)),
+            weight,
+            style,
+            data,
+        })
+    }
+
+    /// <https://learn.microsoft.com/en-us/typography/opentype/spec/cmap>
+    ///
+    /// Returns the glyph of the character, or None if the font lacks it.
+    pub fn glyph_index(&self, c: char) -> Option<u16> {
+        let reader = Reader(&self.data);
+        let (offset, format) = self.cmap?;
+        let c = c as u32;
+        let glyph = match format {
+            // Segment mapping to delta values.
+            4 => {
+                let c = u16::try_from(c).ok()?;
+                let seg_count_x2 = reader.u16(offset + 6)? as usize;
+                let end_codes = offset + 14;
+                let start_codes = end_codes + seg_count_x2 + 2;
+                let id_deltas = start_codes + seg_count_x2;
+                let id_range_offsets = id_deltas + seg_count_x2;
+                let segment = (0..seg_count_x2 / 2)
+                    .map(|i| i * 2)
+                    .find(|i| reader.u16(end_codes + i).is_some_and(|end| end >= c))?;
+                let start = reader.u16(start_codes + segment)?;
+                if start > c {
+                    return None;
+                }
+                let delta = reader.u16(id_deltas + segment)?;
+                let range_offset = reader.u16(id_range_offsets + segment)? as usize;
+                if range_offset == 0 {
+                    c.wrapping_add(delta)
+                } else {
+                    let address =
+                        id_range_offsets + segment + range_offset + 2 * (c - start) as usize;
+                    match reader.u16(address)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                }
+            }
+            // Segmented coverage.
+            12 => {
+                // The groups are bounded by the length of the subtable, which is bounded by the data,
+                // so that a malformed count does not make the lookup run on.
+                let length = (reader.u32(offset + 4)? as usize).min(self.data.len() - offset);
+                let groups =
+                    (reader.u32(offset + 12)? as usize).min(length.saturating_sub(16) / 12);
+                (0..groups).find_map(|i| {
+                    let group = offset + 16 + 12 * i;
+                    let start = reader.u32(group)?;
+                    let end = reader.u32(group + 4)?;
+                    let start_glyph = reader.u32(group + 8)?;
+                    if !(start..=end).contains(&c) {
+                        return None;
+                    }
+                    u16::try_from(start_glyph.checked_add(c - start)?).ok()
+                })?
+            }
+            _ => return None,
+        };
+        // The glyph 0 is the missing glyph.
+        (glyph != 0).then_some(glyph)
+    }
+
+    /// <https://learn.microsoft.com/en-us/typography/opentype/spec/hmtx>
+    ///
+    /// Returns the advance width of the glyph in font units.
+    pub fn advance(&self, glyph: u16) -> u16 {
+        let index = glyph.min(self.number_of_h_metrics - 1) as usize;
+        // The table is checked to have the metrics when the font is parsed.
+        Reader(&self.data).u16(self.hmtx + 4 * index).unwrap_or(0)
+    }
+
+    /// Rasterizes the glyph at the font size, where a pixel is set if its center is inside
+    /// the outline by the nonzero winding rule.
+    ///
+    /// Returns None if the font has no TrueType outlines, the glyph is malformed,
+    /// or the bitmap would be too large.
+    pub fn rasterize(&self, glyph: u16, font_size: f64) -> Option<BitmapGlyph> {
+        let scale = font_size / self.units_per_em as f64;
+        if !scale.is_finite() {
+            return None;
+        }
+        let mut contours = Vec::new();
+        self.outline(glyph, &Transform::IDENTITY, 0, &mut contours)?;
+        let polygons: Vec<Vec<(f64, f64)>> = contours
+            .iter()
+            .map(|contour| {
+                flatten(contour)
+                    .into_iter()
+                    .map(|(x, y)| (x * scale, y * scale))
+                    .collect()
+            })
+            .collect();
+
+        let advance = floor(self.advance(glyph) as f64 * scale + 0.5) as i32;
+        let mut vertices = polygons.iter().flatten();
+        let Some(&(x, y)) = vertices.next() else {
+            // e.g. the glyph of a space.
+            return Some(BitmapGlyph {
+                advance,
+                width: 0,
+                height: 0,
+                x_offset: 0,
+                y_offset: 0,
+                bitmap: Vec::new(),
+            });
+        };
+        let (min_x, max_x, min_y, max_y) = vertices
+            .fold((x, x, y, y), |(x0, x1, y0, y1), (x, y)| {
+                (x0.min(*x), x1.max(*x), y0.min(*y), y1.max(*y))
+            });
+        let (left, bottom) = (floor(min_x), floor(min_y));
+        let width = -floor(-max_x) - left;
+        let height = -floor(-max_y) - bottom;
+        if width > MAX_BITMAP_SIZE as i64 || height > MAX_BITMAP_SIZE as i64 {
+            return None;
+        }
+        let (x_offset, y_offset) = (left as i32, bottom as i32);
+        let (width, height) = (width as u32, height as u32);
+
+        let mut bitmap = vec![vec![0u8; (width as usize).div_ceil(8)]; height as usize];
+        for (row, bits) in bitmap.iter_mut().enumerate() {
+            // The center of the pixels in the row, where y goes up.
+            let y = (y_offset + height as i32 - row as i32) as f64 - 0.5;
+            // The x of the edges crossing the row, with +1 for the upward ones and -1 for the downward ones.
+            let mut crossings: Vec<(f64, i32)> = polygons
+                .iter()
+                .flat_map(|polygon| polygon.windows(2))
+                .filter_map(|edge| {
+                    let ((x0, y0), (x1, y1)) = (edge[0], edge[1]);
+                    if y0 == y1 || y < y0.min(y1) || y >= y0.max(y1) {
+                        return None;
+                    }
+                    let x = x0 + (y - y0) * (x1 - x0) / (y1 - y0);
+                    Some((x, if y1 > y0 { 1 } else { -1 }))
+                })
+                .collect();
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut crossings = crossings.into_iter().peekable();
+            let mut winding = 0;
+            for column in 0..width as usize {
+                let x = (x_offset + column as i32) as f64 + 0.5;
+                while let Some((_, direction)) = crossings.next_if(|(crossing, _)| *crossing < x) {
+                    winding += direction;
+                }
+                if winding != 0 {
+                    bits[column / 8] |= 0x80 >> (column % 8);
+                }
+            }
+        }
+
+        Some(BitmapGlyph {
+            advance,
+            width,
+            height,
+            x_offset,
+            y_offset,
+            bitmap,
+        })
+    }
+
+    /// <https://learn.microsoft.com/en-us/typography/opentype/spec/glyf>
+    ///
+    /// Appends the contours of the glyph, transformed into the coordinates of the outermost glyph.
+    fn outline(
+        &self,
+        glyph: u16,
+        transform: &Transform,
+        depth: usize,
+        contours: &mut Vec<Vec<Point>>,
+    ) -> Option<()> {
+        let reader = Reader(&self.data);
+        let (glyf, loca, long_offsets) = self.glyf?;
+        // <https://learn.microsoft.com/en-us/typography/opentype/spec/loca>
+        let glyph = glyph as usize;
+        let (start, end) = if long_offsets {
+            (
+                reader.u32(loca + 4 * glyph)? as usize,
+                reader.u32(loca + 4 * glyph + 4)? as usize,
+            )
+        } else {
+            (
+                reader.u16(loca + 2 * glyph)? as usize * 2,
+                reader.u16(loca + 2 * glyph + 2)? as usize * 2,
+            )
+        };
+        if start >= end {
+            // The glyph has no outline.
+            return (start == end).then_some(());
+        }
+        let offset = glyf + start;
+        let number_of_contours = reader.i16(offset)?;
+        if number_of_contours >= 0 {
+            return self.simple_outline(offset, number_of_contours as usize, transform, contours);
+        }
+
+        // A composite glyph, whose components are glyphs placed by transforms.
+        if depth >= MAX_COMPONENT_DEPTH {
+            return None;
+        }
+        let f2dot14 = |offset| reader.i16(offset).map(|n| n as f64 / 16384.0);
+        let mut pos = offset + 10;
+        loop {
+            let flags = reader.u16(pos)?;
+            let component = reader.u16(pos + 2)?;
+            pos += 4;
+            let (dx, dy) = if flags & 0x0001 != 0 {
+                pos += 4;
+                (reader.i16(pos - 4)? as f64, reader.i16(pos - 2)? as f64)
+            } else {
+                pos += 2;
+                (
+                    reader.u8(pos - 2)? as i8 as f64,
+                    reader.u8(pos - 1)? as i8 as f64,
+                )
+            };
+            // Matching points of the components is not supported, so they are placed at the origin.
+            let (dx, dy) = if flags & 0x0002 != 0 {
+                (dx, dy)
+            } else {
+                (0.0, 0.0)
+            };
+            let (a, b, c, d) = if flags & 0x0008 != 0 {
+                pos += 2;
+                let scale = f2dot14(pos - 2)?;
+                (scale, 0.0, 0.0, scale)
+            } else if flags & 0x0040 != 0 {
+                pos += 4;
+                (f2dot14(pos - 4)?, 0.0, 0.0, f2dot14(pos - 2)?)
+            } else if flags & 0x0080 != 0 {
+                pos += 8;
+                (
+                    f2dot14(pos - 8)?,
+                    f2dot14(pos - 6)?,
+                    f2dot14(pos - 4)?,
+                    f2dot14(pos - 2)?,
+                )
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+            let transform = transform.then(&Transform([a, b, c, d, dx, dy]));
+            self.outline(component, &transform, depth + 1, contours)?;
+            if flags & 0x0020 == 0 {
+                return Some(());
+            }
+        }
+    }
+
+    fn simple_outline(
+        &self,
+        offset: usize,
+        number_of_contours: usize,
+        transform: &Transform,
+        contours: &mut Vec<Vec<Point>>,
+    ) -> Option<()> {
+        let reader = Reader(&self.data);
+        let end_points = (0..number_of_contours)
+            .map(|i| reader.u16(offset + 10 + 2 * i).map(usize::from))
+            .collect::<Option<Vec<_>>>()?;
+        let point_count = end_points.last().map_or(0, |end| end + 1);
+        let instruction_length = reader.u16(offset + 10 + 2 * number_of_contours)? as usize;
+        let mut pos = offset + 12 + 2 * number_of_contours + instruction_length;
+
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let flag = reader.u8(pos)?;
+            pos += 1;
+            let repeat = if flag & 0x08 != 0 {
+                pos += 1;
+                reader.u8(pos - 1)? as usize
+            } else {
+                0
+            };
+            flags.extend(core::iter::repeat(flag).take(repeat + 1));
+        }
+        flags.truncate(point_count);
+
+        // The coordinates are deltas from the previous point, either a byte with the sign in the flag,
+        // or a 16-bit value unless the flag says it is the same as the previous one.
+        let mut coordinates = |short: u8, same_or_positive: u8| {
+            let mut value = 0i32;
+            let mut values = Vec::with_capacity(point_count);
+            for flag in &flags {
+                if flag & short != 0 {
+                    let delta = reader.u8(pos)? as i32;
+                    pos += 1;
+                    value += if flag & same_or_positive != 0 {
+                        delta
+                    } else {
+                        -delta
+                    };
+                } else if flag & same_or_positive == 0 {
+                    value += reader.i16(pos)? as i32;
+                    pos += 2;
+                }
+                values.push(value as f64);
+            }
+            Some(values)
+        };
+        let xs = coordinates(0x02, 0x10)?;
+        let ys = coordinates(0x04, 0x20)?;
+
+        let mut start = 0;
+        for end in end_points {
+            if end < start {
+                return None;
+            }
+            let contour = (start..=end)
+                .map(|i| {
+                    let (x, y) = transform.apply(xs[i], ys[i]);
+                    Point {
+                        x,
+                        y,
+                        on_curve: flags[i] & 0x01 != 0,
+                    }
+                })
+                .collect();
+            contours.push(contour);
+            start = end + 1;
+        }
+        Some(())
+    }
+}
+
+/// Rounds toward negative infinity, which `f64` lacks without `std`.
+fn floor(value: f64) -> i64 {
+    let truncated = value as i64;
+    if (truncated as f64) > value {
+        truncated - 1
+    } else {
+        truncated
+    }
+}
+
+/// A point of an outline in font units.
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    x: f64,
+    y: f64,
+    /// False for the control points of quadratic Bézier curves.
+    on_curve: bool,
+}
+
+/// An affine transform `[a, b, c, d, e, f]`, which maps (x, y) to (ax + cy + e, bx + dy + f).
+struct Transform([f64; 6]);
+
+impl Transform {
+    const IDENTITY: Self = Self([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    /// The transform applying `inner` first, and then this.
+    fn then(&self, inner: &Self) -> Self {
+        let [a, b, c, d, _, _] = self.0;
+        let [ia, ib, ic, id, ie, if_] = inner.0;
+        let (e, f) = self.apply(ie, if_);
+        Self([
+            a * ia + c * ib,
+            b * ia + d * ib,
+            a * ic + c * id,
+            b * ic + d * id,
+            e,
+            f,
+        ])
+    }
+}
+
+/// Flattens a closed contour into the vertices of a closed polygon, whose last vertex is the first one.
+///
+/// Two consecutive control points imply an on-curve point at their midpoint.
+fn flatten(contour: &[Point]) -> Vec<(f64, f64)> {
+    let mut points = Vec::with_capacity(contour.len() * 2);
+    for (i, p) in contour.iter().enumerate() {
+        let next = contour[(i + 1) % contour.len()];
+        points.push(*p);
+        if !p.on_curve && !next.on_curve {
+            points.push(Point {
+                x: (p.x + next.x) / 2.0,
+                y: (p.y + next.y) / 2.0,
+                on_curve: true,
+            });
+        }
+    }
+    let Some(first) = points.iter().position(|p| p.on_curve) else {
+        return Vec::new();
+    };
+    points.rotate_left(first);
+
+    let mut vertices = vec![(points[0].x, points[0].y)];
+    let mut i = 1;
+    while i <= points.len() {
+        let p = points[i % points.len()];
+        if p.on_curve {
+            vertices.push((p.x, p.y));
+            i += 1;
+            continue;
+        }
+        let end = points[(i + 1) % points.len()];
+        let (x0, y0) = vertices[vertices.len() - 1];
+        for segment in 1..=CURVE_SEGMENTS {
+            let t = segment as f64 / CURVE_SEGMENTS as f64;
+            let (u, v, w) = ((1.0 - t) * (1.0 - t), 2.0 * (1.0 - t) * t, t * t);
+            vertices.push((u * x0 + v * p.x + w * end.x, u * y0 + v * p.y + w * end.y));
+        }
+        i += 2;
+    }
+    vertices
+}
+
+/// Reads big-endian values from the font data.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn u8(&self, offset: usize) -> Option<u8> {
+        self.0.get(offset).copied()
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.0.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&self, offset: usize) -> Option<i16> {
+        self.u16(offset).map(|n| n as i16)
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.0.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// <https://learn.microsoft.com/en-us/typography/opentype/spec/otff#table-directory>
+    ///
+    /// Returns the offset of the table.
+    fn table(&self, tag: &[u8; 4]) -> Option<usize> {
+        let num_tables = self.u16(4)? as usize;
+        (0..num_tables)
+            .find_map(|i| {
+                let record = 12 + 16 * i;
+                (self.0.get(record..record + 4)? == tag).then(|| self.u32(record + 8))?
+            })
+            .map(|offset| offset as usize)
+    }
+
+    /// Chooses the subtable mapping Unicode code points, preferring the one covering
+    /// the supplementary planes.
+    fn cmap_subtable(&self, cmap: usize) -> Option<(usize, u16)> {
+        let num_tables = self.u16(cmap + 2)? as usize;
+        (0..num_tables)
+            .filter_map(|i| {
+                let record = cmap + 4 + 8 * i;
+                let platform = self.u16(record)?;
+                let encoding = self.u16(record + 2)?;
+                let offset = cmap + self.u32(record + 4)? as usize;
+                let format = self.u16(offset)?;
+                // Unicode, or Windows with the Unicode BMP or full repertoire.
+                let is_unicode = platform == 0 || (platform == 3 && matches!(encoding, 1 | 10));
+                let priority = match format {
+                    12 => 2,
+                    4 => 1,
+                    _ => return None,
+                };
+                is_unicode.then_some((priority, offset, format))
+            })
+            .max_by_key(|(priority, _, _)| *priority)
+            .map(|(_, offset, format)| (offset, format))
+    }
+
+    /// <https://learn.microsoft.com/en-us/typography/opentype/spec/name>
+    ///
+    /// Returns the typographic family name, or the family name.
+    fn family_name(&self, name: usize) -> Option<String> {
+        let count = self.u16(name + 2)? as usize;
+        let storage = name + self.u16(name + 4)? as usize;
+        let find = |name_id: u16| {
+            (0..count).find_map(|i| {
+                let record = name + 6 + 12 * i;
+                let platform = self.u16(record)?;
+                if self.u16(record + 6)? != name_id || !matches!(platform, 0 | 3) {
+                    return None;
+                }
+                let length = self.u16(record + 8)? as usize;
+                let start = storage + self.u16(record + 10)? as usize;
+                // The names for the Unicode and Windows platforms are in UTF-16BE.
+                let units = self
+                    .0
+                    .get(start..start + length)?
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+                char::decode_utf16(units)
+                    .collect::<Result<String, _>>()
+                    .ok()
+            })
+        };
+        find(16).or_else(|| find(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    /// Builds a font with the glyphs 1 and 2 for 'A' and 'B' in format 4,
+    /// and the glyph 3 for U+1F600 in format 12.
+    ///
+    /// The glyph 1 is a 500 units square, the glyph 2 is the glyph 1 moved right by 500 units,
+    /// and the glyph 3 is a quadratic curve from (0, 0) through (500, 500) to (1000, 0).
+    fn test_font() -> Vec<u8> {
+        let be16 = |n: u16| n.to_be_bytes();
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&be16(1000));
+        let mut hhea = vec![0; 36];
+        hhea[4..6].copy_from_slice(&be16(800));
+        hhea[6..8].copy_from_slice(&(-200i16).to_be_bytes());
+        hhea[8..10].copy_from_slice(&be16(100));
+        hhea[34..36].copy_from_slice(&be16(3));
+        // The last advance is used for the glyphs after.
+        let hmtx = [500u16, 0, 600, 0, 700, 0]
+            .iter()
+            .flat_map(|n| be16(*n))
+            .collect::<Vec<_>>();
+        let mut os2 = vec![0; 78];
+        os2[4..6].copy_from_slice(&be16(700));
+        os2[62..64].copy_from_slice(&be16(1));
+
+        let format4 = [
+            4u16,
+            32,
+            0,
+            4,
+            0,
+            0,
+            0,
+            0x42,
+            0xffff,
+            0,
+            0x41,
+            0xffff,
+            0u16.wrapping_sub(0x40),
+            1,
+            0,
+            0,
+        ]
+        .iter()
+        .flat_map(|n| be16(*n))
+        .collect::<Vec<_>>();
+        let format12 = [12u32 << 16, 28, 0, 1, 0x1f600, 0x1f600, 3]
+            .iter()
+            .flat_map(|n| n.to_be_bytes())
+            .collect::<Vec<_>>();
+        let mut cmap = [0u16, 2, 3, 1, 0, 20, 3, 10]
+            .iter()
+            .flat_map(|n| be16(*n))
+            .collect::<Vec<_>>();
+        cmap.extend((20 + format4.len() as u32).to_be_bytes());
+        cmap.extend(&format4);
+        cmap.extend(&format12);
+
+        let family = "Test Sans"
+            .encode_utf16()
+            .flat_map(be16)
+            .collect::<Vec<_>>();
+        let mut name = [0u16, 1, 18, 3, 1, 0x409, 1, family.len() as u16, 0]
+            .iter()
+            .flat_map(|n| be16(*n))
+            .collect::<Vec<_>>();
+        name.extend(&family);
+
+        let words = |words: &[i16]| {
+            words
+                .iter()
+                .flat_map(|n| n.to_be_bytes())
+                .collect::<Vec<_>>()
+        };
+        // The flags of the points are on-curve, and the coordinates are 16-bit deltas.
+        let mut square = words(&[1, 0, 0, 500, 500, 3, 0]);
+        square.extend([1, 1, 1, 1]);
+        square.extend(words(&[0, 500, 0, -500, 0, 0, 500, 0]));
+        // The arguments are 16-bit offsets.
+        let moved_square = words(&[-1, 500, 0, 1000, 500, 0x0003, 1, 500, 0]);
+        let mut curve = words(&[1, 0, 0, 1000, 500, 2, 0]);
+        curve.extend([1, 0, 1]);
+        curve.extend(words(&[0, 500, 500, 0, 1000, -1000]));
+        // Padded for the offsets in 16-bit.
+        curve.push(0);
+        let mut glyf = Vec::new();
+        let mut loca = vec![0];
+        for glyph in [square, moved_square, curve] {
+            glyf.extend(glyph);
+            loca.push(glyf.len() as i16 / 2);
+        }
+        // The glyph 0 has no outline.
+        loca.insert(0, 0);
+        let loca = words(&loca);
+
+        let tables: [(&[u8; 4], Vec<u8>); 8] = [
+            (b"OS/2", os2),
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"name", name),
+        ];
+        let mut data = vec![0, 1, 0, 0];
+        data.extend(be16(tables.len() as u16));
+        data.extend([0; 6]);
+        let mut offset = 12 + 16 * tables.len();
+        let mut contents: Vec<u8> = Vec::new();
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            data.extend([0; 4]);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+            contents.extend(table);
+        }
+        data.extend(contents);
+        data
+    }
+
+    #[test]
+    fn test_parse() {
+        let font = TrueTypeFont::parse(test_font()).unwrap();
+        assert_eq!(font.units_per_em, 1000);
+        assert_eq!(
+            (font.ascender, font.descender, font.line_gap),
+            (800, -200, 100)
+        );
+        assert_eq!(font.family.as_deref(), Some("Test Sans"));
+        assert_eq!(font.weight, 700);
+        assert_eq!(font.style, FontStyle::Italic);
+
+        // The format 12 subtable is preferred.
+        assert_eq!(font.glyph_index('\u{1f600}'), Some(3));
+        assert_eq!(font.glyph_index('A'), None);
+        assert_eq!(font.advance(3), 700);
+        assert_eq!(font.advance(1), 600);
+
+        assert_eq!(TrueTypeFont::parse(b"OTTO".to_vec()), None);
+    }
+
+    #[test]
+    fn test_format4() {
+        let mut font = TrueTypeFont::parse(test_font()).unwrap();
+        let (offset, _) = font.cmap.unwrap();
+        font.cmap = Some((offset - 32, 4));
+        assert_eq!(font.glyph_index('A'), Some(1));
+        assert_eq!(font.glyph_index('B'), Some(2));
+        assert_eq!(font.glyph_index('C'), None);
+        assert_eq!(font.glyph_index('@'), None);
+        assert_eq!(font.glyph_index('\u{1f600}'), None);
+    }
+
+    #[test]
+    fn test_malformed_format12() {
+        let mut font = TrueTypeFont::parse(test_font()).unwrap();
+        let (offset, _) = font.cmap.unwrap();
+        // The count of the groups is beyond the length of the subtable.
+        font.data[offset + 12..offset + 16].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(font.glyph_index('\u{1f600}'), Some(3));
+        // The glyph of U+1F601 would overflow.
+        font.data[offset + 16..offset + 28].copy_from_slice(
+            &[0x1f600u32, 0x1f601, u32::MAX]
+                .map(u32::to_be_bytes)
+                .concat(),
+        );
+        assert_eq!(font.glyph_index('\u{1f601}'), None);
+    }
+
+    /// The rows of the bitmap, where `#` is a set pixel.
+    fn rows(glyph: &BitmapGlyph) -> Vec<String> {
+        (0..glyph.height)
+            .map(|y| {
+                (0..glyph.width)
+                    .map(|x| if glyph.pixel(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let font = TrueTypeFont::parse(test_font()).unwrap();
+
+        let square = font.rasterize(1, 10.0).unwrap();
+        assert_eq!(
+            (square.x_offset, square.y_offset, square.advance),
+            (0, 0, 6)
+        );
+        assert_eq!(rows(&square), ["#####"; 5]);
+
+        let moved_square = font.rasterize(2, 10.0).unwrap();
+        assert_eq!((moved_square.x_offset, moved_square.y_offset), (5, 0));
+        assert_eq!(rows(&moved_square), ["#####"; 5]);
+
+        let curve = font.rasterize(3, 10.0).unwrap();
+        assert_eq!(
+            (curve.x_offset, curve.y_offset, curve.width, curve.height),
+            (0, 0, 10, 5)
+        );
+        assert_eq!(rows(&curve)[4], "##########");
+        // The top of the curve is at the middle.
+        assert!(curve.pixel(4, 0) && curve.pixel(5, 0));
+        assert!(!curve.pixel(0, 0) && !curve.pixel(9, 0));
+
+        let empty = font.rasterize(0, 10.0).unwrap();
+        assert_eq!((empty.width, empty.height, empty.advance), (0, 0, 5));
+
+        assert_eq!(font.rasterize(1, 1e6), None);
+        assert_eq!(font.rasterize(4, 10.0), None);
+    }
+}
@@ -114,6 +114,14 @@ impl HtmlParser {
             },
             InsertionMode::InHead => match token {
                 HtmlToken::Char('\t' | '\n' | '\x0c' | '\r' | ' ') => StepOutput::default(),
+                HtmlToken::StartTag { tag, .. } if tag == "link" => {
+                    self.insert_element_for_token(token);
+                    self.stack_of_open_elements.pop();
+                    StepOutput::default()
+                }
+                HtmlToken::StartTag { tag, .. } if tag == "style" => {
+                    self.parse_raw_text_element(token)
+                }
                 HtmlToken::EndTag { tag } if tag == "head" => {
                     self.stack_of_open_elements.pop();
                     self.mode = InsertionMode::AfterHead;
@@ -257,6 +265,11 @@ impl HtmlParser {
                         ..Default::default()
                     }
                 }
+                HtmlToken::StartTag { tag, .. } if tag == "link" => {
+                    self.insert_element_for_token(token);
+                    self.stack_of_open_elements.pop();
+                    StepOutput::default()
+                }
                 HtmlToken::StartTag { tag, .. } if tag == "style" => {
                     self.parse_raw_text_element(token)
                 }
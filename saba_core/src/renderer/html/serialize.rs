@@ -1,56 +1,60 @@
-use crate::renderer::dom::node::{ElementKind, Node, NodeData};
+use crate::renderer::dom::node::{ElementKind, Node, NodeData, NodeEdge};
 use alloc::borrow::ToOwned;
 use alloc::string::{String, ToString};
 
 pub fn serialize(node: &Node) -> String {
     let mut res = String::new();
 
-    for child in node.children() {
-        let child = child.borrow();
-        match child.data() {
-            NodeData::Element(element) => {
-                let tag_name = element.kind.to_string();
-                res += "<";
-                res += &tag_name;
+    for edge in node.children().flat_map(Node::traverse) {
+        match edge {
+            NodeEdge::Start(child) => {
+                let child = child.borrow();
+                match child.data() {
+                    NodeData::Element(element) => {
+                        res += "<";
+                        res += &element.kind.to_string();
 
-                for attr in element.attributes.iter() {
-                    res += " ";
-                    res += &serialize_attribute_name(&attr.name);
-                    res += "=\"";
-                    res += &escape_attribute_value(&attr.value);
-                    res += "\"";
-                }
-
-                res += ">";
+                        for attr in element.attributes.iter() {
+                            res += " ";
+                            res += &serialize_attribute_name(&attr.name);
+                            res += "=\"";
+                            res += &escape_attribute_value(&attr.value);
+                            res += "\"";
+                        }
 
-                res += &serialize(&child);
-
-                res += "</";
-                res += &tag_name;
-                res += ">";
-            }
-            NodeData::Document => {}
-            NodeData::Text(text) => {
-                let parent = child.parent.upgrade();
-                let parent_kind = parent.and_then(|pn| {
-                    if let NodeData::Element(ref pe) = pn.borrow().data() {
-                        Some(pe.kind.clone())
-                    } else {
-                        None
+                        res += ">";
                     }
-                });
+                    NodeData::Document => {}
+                    NodeData::Text(text) => {
+                        let parent = child.parent.upgrade();
+                        let parent_kind = parent.and_then(|pn| {
+                            if let NodeData::Element(ref pe) = pn.borrow().data() {
+                                Some(pe.kind.clone())
+                            } else {
+                                None
+                            }
+                        });
 
-                match parent_kind {
-                    Some(ElementKind::Style | ElementKind::Script) => {
-                        // Append the text literally.
-                        res += &text;
-                    }
-                    _ => {
-                        // Escape the text.
-                        res += &escape_html(&text)
+                        match parent_kind {
+                            Some(ElementKind::Style | ElementKind::Script) => {
+                                // Append the text literally.
+                                res += text;
+                            }
+                            _ => {
+                                // Escape the text.
+                                res += &escape_html(text)
+                            }
+                        }
                     }
                 }
             }
+            NodeEdge::End(child) => {
+                if let NodeData::Element(element) = child.borrow().data() {
+                    res += "</";
+                    res += &element.kind.to_string();
+                    res += ">";
+                }
+            }
         }
     }
 
@@ -5,6 +5,8 @@ pub mod computed_style;
 pub mod layout_object;
 pub mod layout_view;
 pub mod line;
+pub mod line_break;
 pub mod paint;
 pub mod position;
 pub mod text;
+pub mod white_space;
@@ -4,7 +4,7 @@
 use core::{cell::RefCell, mem};
 
 use crate::renderer::dom::node::{Element, ElementKind, Node, NodeData};
-use alloc::rc::Rc;
+use alloc::rc::{Rc, Weak};
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -74,7 +74,10 @@ impl BlockBoxBlockChildrenBuilder {
         if let Some(inlines) = self.uncommitted_inlines.take() {
             self.committed_children.push(BlockBox {
                 data: BlockBoxData::Anonymous,
-                style: self.style.clone(),
+                style: ComputedStyle {
+                    display: DisplayType::Block,
+                    ..ComputedStyle::inherit(&self.style)
+                },
                 children: BlockBoxChildren::Inlines(inlines),
             });
         }
@@ -94,20 +97,47 @@ impl BlockBoxBlockChildrenBuilder {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct InlineBox {
     pub data: InlineBoxData,
     pub style: ComputedStyle,
     pub text: Option<String>,
     pub children: Vec<InlineBox>,
+    pub node: BoxNode,
+}
+
+/// Boxes are equal if they are laid out the same, whichever nodes generated them.
+impl PartialEq for InlineBox {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+            && self.style == other.style
+            && self.text == other.text
+            && self.children == other.children
+    }
 }
 
+impl Eq for InlineBox {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InlineBoxData {
     Element(Element),
     Anonymous,
 }
 
+/// The node which generated a box, by which a hit test finds the target of an event.
+#[derive(Debug, Clone, Default)]
+pub struct BoxNode(Weak<RefCell<Node>>);
+
+impl BoxNode {
+    pub fn new(node: &Rc<RefCell<Node>>) -> Self {
+        Self(Rc::downgrade(node))
+    }
+
+    pub fn node(&self) -> Option<Rc<RefCell<Node>>> {
+        self.0.upgrade()
+    }
+}
+
 pub fn construct_box_tree(layout_view: LayoutView) -> BlockBox {
     match layout_view.root {
         Some(node) => {
@@ -122,7 +152,8 @@ pub fn construct_box_tree(layout_view: LayoutView) -> BlockBox {
         None => BlockBox {
             data: BlockBoxData::Anonymous,
             style: ComputedStyle {
-                display: Some(DisplayType::Block),
+                display: DisplayType::Block,
+                ..Default::default()
             },
             children: BlockBoxChildren::Empty,
         },
@@ -140,7 +171,7 @@ fn produce_boxes(object: Rc<RefCell<LayoutObject>>) -> Vec<Box> {
     //   If all children are inline boxes, it produces an inline box.
     //   If some children are block boxes, it produces an anonymous block box containing the inline segments before the block, the block, and inline segments after the block.
     let b = match object.style.display {
-        Some(DisplayType::Block) => {
+        DisplayType::Block => {
             let children = object
                 .children()
                 .flat_map(produce_boxes)
@@ -175,7 +206,7 @@ fn produce_boxes(object: Rc<RefCell<LayoutObject>>) -> Vec<Box> {
                 children,
             })
         }
-        Some(DisplayType::Inline) => 'block: {
+        DisplayType::Inline => 'block: {
             let children = object.children().collect::<Vec<_>>();
             if children.len() == 1 {
                 if let NodeData::Text(text) = &children[0].borrow().node.borrow().data {
@@ -188,6 +219,7 @@ fn produce_boxes(object: Rc<RefCell<LayoutObject>>) -> Vec<Box> {
                         style: object.style.clone(),
                         text: Some(text.clone()),
                         children: Vec::new(),
+                        node: BoxNode::new(&object.node),
                     });
                 }
             }
@@ -222,19 +254,21 @@ fn produce_boxes(object: Rc<RefCell<LayoutObject>>) -> Vec<Box> {
                                 _ => unreachable!(),
                             })
                             .collect(),
+                        node: BoxNode::new(&object.node),
                     },
                     NodeData::Text(text) => InlineBox {
                         data: InlineBoxData::Anonymous,
                         style: object.style.clone(),
                         text: Some(text.clone()),
                         children: vec![],
+                        node: BoxNode::new(&object.node),
                     },
                     NodeData::Document => unreachable!(),
                 })
             }
         }
-        Some(DisplayType::None) => unreachable!(),
-        None => unreachable!(),
+        // An element with `display: none` generates no boxes.
+        DisplayType::None => return Vec::new(),
     };
 
     vec![b]
@@ -249,9 +283,18 @@ pub enum Box {
 #[cfg(test)]
 mod tests {
     use crate::renderer::{
-        css::parser::parse_css_stylesheet,
+        css::{
+            cascade::{Origin, Stylist},
+            loader::StyleSheetLoader,
+            media::Device,
+            restyle::StyleCache,
+            user_agent::user_agent_stylesheet,
+        },
         html::{parser::HtmlParser, token::HtmlTokenizer},
-        layout::layout_view::get_style_content,
+        layout::{
+            computed_style::{LengthPercentageOrAuto, Sides},
+            layout_object::LayoutSize,
+        },
     };
 
     use super::*;
@@ -261,84 +304,108 @@ mod tests {
     #[test]
     fn test_simple() {
         let html = r#"<!doctype html><html><head></head><body><a>inline1 inline1 inline1</a>inline2 inline2 inline2<a>inline3 inline3 inline3</a><p>block4 block4 block4</p><p>block5 block5 block5</p>inline6 inline6 inline6</body></html>"#;
+        // `p { margin-top: 1em; margin-bottom: 1em }` in the user agent stylesheet.
+        let p_style = ComputedStyle {
+            display: DisplayType::Block,
+            margin: Sides {
+                top: LengthPercentageOrAuto::Length(16.0),
+                right: LengthPercentageOrAuto::Length(0.0),
+                bottom: LengthPercentageOrAuto::Length(16.0),
+                left: LengthPercentageOrAuto::Length(0.0),
+            },
+            ..Default::default()
+        };
         let expected = BlockBox {
             data: BlockBoxData::Element(Element::new(ElementKind::Body)),
             style: ComputedStyle {
-                display: Some(DisplayType::Block),
+                display: DisplayType::Block,
+                // `body { margin: 8px }` in the user agent stylesheet.
+                margin: Sides::all(LengthPercentageOrAuto::Length(8.0)),
+                ..Default::default()
             },
             children: BlockBoxChildren::Blocks(vec![
                 BlockBox {
                     data: BlockBoxData::Anonymous,
                     style: ComputedStyle {
-                        display: Some(DisplayType::Block),
+                        display: DisplayType::Block,
+                        ..Default::default()
                     },
                     children: BlockBoxChildren::Inlines(vec![
                         InlineBox {
                             data: InlineBoxData::Element(Element::new(ElementKind::A)),
                             style: ComputedStyle {
-                                display: Some(DisplayType::Inline),
+                                display: DisplayType::Inline,
+                                ..Default::default()
                             },
                             text: Some("inline1 inline1 inline1".into()),
                             children: vec![],
+                            node: BoxNode::default(),
                         },
                         InlineBox {
                             data: InlineBoxData::Anonymous,
                             style: ComputedStyle {
-                                display: Some(DisplayType::Inline),
+                                display: DisplayType::Inline,
+                                ..Default::default()
                             },
                             text: Some("inline2 inline2 inline2".into()),
                             children: vec![],
+                            node: BoxNode::default(),
                         },
                         InlineBox {
                             data: InlineBoxData::Element(Element::new(ElementKind::A)),
                             style: ComputedStyle {
-                                display: Some(DisplayType::Inline),
+                                display: DisplayType::Inline,
+                                ..Default::default()
                             },
                             text: Some("inline3 inline3 inline3".into()),
                             children: vec![],
+                            node: BoxNode::default(),
                         },
                     ]),
                 },
                 BlockBox {
                     data: BlockBoxData::Element(Element::new(ElementKind::P)),
-                    style: ComputedStyle {
-                        display: Some(DisplayType::Block),
-                    },
+                    style: p_style.clone(),
                     children: BlockBoxChildren::Inlines(vec![InlineBox {
                         data: InlineBoxData::Anonymous,
                         style: ComputedStyle {
-                            display: Some(DisplayType::Inline),
+                            display: DisplayType::Inline,
+                            ..Default::default()
                         },
                         text: Some("block4 block4 block4".into()),
                         children: vec![],
+                        node: BoxNode::default(),
                     }]),
                 },
                 BlockBox {
                     data: BlockBoxData::Element(Element::new(ElementKind::P)),
-                    style: ComputedStyle {
-                        display: Some(DisplayType::Block),
-                    },
+                    style: p_style.clone(),
                     children: BlockBoxChildren::Inlines(vec![InlineBox {
                         data: InlineBoxData::Anonymous,
                         style: ComputedStyle {
-                            display: Some(DisplayType::Inline),
+                            display: DisplayType::Inline,
+                            ..Default::default()
                         },
                         text: Some("block5 block5 block5".into()),
                         children: vec![],
+                        node: BoxNode::default(),
                     }]),
                 },
                 BlockBox {
                     data: BlockBoxData::Anonymous,
                     style: ComputedStyle {
-                        display: Some(DisplayType::Block),
+                        display: DisplayType::Block,
+                        ..Default::default()
                     },
                     children: BlockBoxChildren::Inlines(vec![InlineBox {
                         data: InlineBoxData::Anonymous,
                         style: ComputedStyle {
-                            display: Some(DisplayType::Inline),
+                            display: DisplayType::Inline,
+                            ..Default::default()
                         },
                         text: Some("inline6 inline6 inline6".into()),
                         children: vec![],
+                        node: BoxNode::default(),
                     }]),
                 },
             ]),
@@ -347,9 +414,18 @@ mod tests {
         let t = HtmlTokenizer::new(html.into());
         let window = HtmlParser::new(t).construct_tree();
         let dom = window.borrow().document();
-        let style = get_style_content(dom.clone());
-        let cssom = parse_css_stylesheet(style);
-        let layout_view = LayoutView::layout(dom, &cssom);
+        let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+        stylist.add_stylesheet(&user_agent_stylesheet(), Origin::UserAgent);
+        stylist.add_style_sheet_list(&StyleSheetLoader::new(None, None).load(dom.clone()));
+        let layout_view = LayoutView::layout(
+            dom,
+            &stylist,
+            LayoutSize {
+                width: 800,
+                height: 600,
+            },
+            &mut StyleCache::default(),
+        );
         let actual = construct_box_tree(layout_view);
         assert_eq!(expected, actual);
     }
@@ -361,49 +437,59 @@ mod tests {
         let expected = BlockBox {
             data: BlockBoxData::Element(Element::new(ElementKind::Body)),
             style: ComputedStyle {
-                display: Some(DisplayType::Block),
+                display: DisplayType::Block,
+                ..Default::default()
             },
             children: BlockBoxChildren::Blocks(vec![
                 BlockBox {
                     data: BlockBoxData::Anonymous,
                     style: ComputedStyle {
-                        display: Some(DisplayType::Block),
+                        display: DisplayType::Block,
+                        ..Default::default()
                     },
                     children: BlockBoxChildren::Inlines(vec![InlineBox {
                         data: InlineBoxData::Element(Element::new(ElementKind::A)),
                         style: ComputedStyle {
-                            display: Some(DisplayType::Inline),
+                            display: DisplayType::Inline,
+                            ..Default::default()
                         },
                         text: Some("before p".into()),
                         children: vec![],
+                        node: BoxNode::default(),
                     }]),
                 },
                 BlockBox {
                     data: BlockBoxData::Element(Element::new(ElementKind::P)),
                     style: ComputedStyle {
-                        display: Some(DisplayType::Block),
+                        display: DisplayType::Block,
+                        ..Default::default()
                     },
                     children: BlockBoxChildren::Inlines(vec![InlineBox {
                         data: InlineBoxData::Anonymous,
                         style: ComputedStyle {
-                            display: Some(DisplayType::Inline),
+                            display: DisplayType::Inline,
+                            ..Default::default()
                         },
                         text: Some("inside p".into()),
                         children: vec![],
+                        node: BoxNode::default(),
                     }]),
                 },
                 BlockBox {
                     data: BlockBoxData::Anonymous,
                     style: ComputedStyle {
-                        display: Some(DisplayType::Block),
+                        display: DisplayType::Block,
+                        ..Default::default()
                     },
                     children: BlockBoxChildren::Inlines(vec![InlineBox {
                         data: InlineBoxData::Element(Element::new(ElementKind::A)),
                         style: ComputedStyle {
-                            display: Some(DisplayType::Inline),
+                            display: DisplayType::Inline,
+                            ..Default::default()
                         },
                         text: Some("after p".into()),
                         children: vec![],
+                        node: BoxNode::default(),
                     }]),
                 },
             ]),
@@ -1,11 +1,1583 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+//! <https://www.w3.org/TR/css-cascade-4/#computed>
+//!
+//! Servo: <https://github.com/servo/stylo/blob/main/style/properties/properties.mako.rs>
+
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut};
+
+use crate::renderer::css::animation::Animate;
+use crate::renderer::css::calc::CalcNode;
+use crate::renderer::css::cssom::CssDeclaration;
+use crate::renderer::css::custom_properties::{self, is_custom_property, CustomProperties};
+use crate::renderer::css::easing::EasingFunction;
+use crate::renderer::css::value::{Color, ComponentValue, Length, LengthUnit};
+
+/// The computed values of the supported properties.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ComputedStyle {
-    pub display: Option<DisplayType>,
+    /// <https://www.w3.org/TR/css-display-3/#the-display-properties>
+    pub display: DisplayType,
+    /// <https://www.w3.org/TR/css-color-4/#the-color-property>
+    pub color: Color,
+    /// <https://www.w3.org/TR/css-backgrounds-3/#background-color>
+    pub background_color: Color,
+    /// <https://www.w3.org/TR/css-fonts-4/#font-family-prop>
+    pub font_family: Vec<String>,
+    /// <https://www.w3.org/TR/css-fonts-4/#font-size-prop>, in px.
+    pub font_size: f64,
+    /// <https://www.w3.org/TR/css-fonts-4/#font-weight-prop>
+    pub font_weight: u16,
+    /// <https://www.w3.org/TR/css-fonts-4/#font-style-prop>
+    pub font_style: FontStyle,
+    /// <https://www.w3.org/TR/CSS2/visudet.html#propdef-line-height>
+    pub line_height: LineHeight,
+    /// <https://www.w3.org/TR/css-box-4/#margins>
+    pub margin: Sides<LengthPercentageOrAuto>,
+    /// <https://www.w3.org/TR/css-box-4/#paddings>
+    pub padding: Sides<LengthPercentage>,
+    /// <https://www.w3.org/TR/css-backgrounds-3/#border-width>, in px.
+    pub border_width: Sides<f64>,
+    /// <https://www.w3.org/TR/css-backgrounds-3/#border-style>
+    pub border_style: Sides<BorderStyle>,
+    /// <https://www.w3.org/TR/css-backgrounds-3/#border-color>
+    pub border_color: Sides<Color>,
+    /// <https://www.w3.org/TR/css-sizing-3/#preferred-size-properties>
+    pub width: LengthPercentageOrAuto,
+    pub height: LengthPercentageOrAuto,
+    /// <https://www.w3.org/TR/css-text-3/#text-align-property>
+    pub text_align: TextAlign,
+    /// <https://www.w3.org/TR/css-text-decor-3/#text-decoration-line-property>
+    pub text_decoration_line: TextDecorationLine,
+    /// <https://www.w3.org/TR/css-text-3/#text-transform-property>
+    pub text_transform: TextTransform,
+    /// <https://www.w3.org/TR/css-text-3/#white-space-property>
+    pub white_space: WhiteSpace,
+    /// <https://www.w3.org/TR/css-text-3/#word-break-property>
+    pub word_break: WordBreak,
+    /// <https://www.w3.org/TR/css-text-3/#line-break-property>
+    pub line_break: LineBreak,
+    /// <https://www.w3.org/TR/css-text-3/#overflow-wrap-property>
+    pub overflow_wrap: OverflowWrap,
+    /// <https://www.w3.org/TR/css-transitions-1/#transition-shorthand-property>
+    pub transition: Transitions,
+    /// <https://www.w3.org/TR/css-animations-1/#animation>
+    pub animation: Animations,
+    /// <https://www.w3.org/TR/css-variables-1/#custom-property>, which are all inherited.
+    pub custom_properties: CustomProperties,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+// Computed lengths are never NaN.
+impl Eq for ComputedStyle {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DisplayType {
     Block,
     Inline,
     None,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineHeight {
+    Normal,
+    /// A multiple of the font size, which is inherited as is.
+    Number(f64),
+    /// In px.
+    Length(f64),
+}
+
+/// Only the values of the same kind are interpolable.
+impl Animate for LineHeight {
+    fn animate(&self, other: &Self, progress: f64) -> Option<Self> {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => Some(Self::Number(a.animate(b, progress)?)),
+            (Self::Length(a), Self::Length(b)) => Some(Self::Length(a.animate(b, progress)?)),
+            _ => None,
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/css-values-4/#typedef-length-percentage>
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentage {
+    /// In px.
+    Length(f64),
+    Percentage(f64),
+    /// A math function mixing lengths in px and percentages, which can't be simplified further.
+    Calc(CalcNode),
+}
+
+impl LengthPercentage {
+    /// <https://www.w3.org/TR/css-values-4/#percentages>
+    ///
+    /// Resolves the value into px during layout, where percentages are relative to `basis`,
+    /// e.g. the width of the containing block.
+    pub fn resolve(&self, basis: f64) -> f64 {
+        match self {
+            Self::Length(px) => *px,
+            Self::Percentage(p) => basis * p / 100.0,
+            Self::Calc(calc) => calc.resolve(basis),
+        }
+    }
+
+    /// Clamps the value for properties which do not allow negative values.
+    fn clamp_non_negative(self) -> Self {
+        match self {
+            Self::Length(px) => Self::Length(px.max(0.0)),
+            Self::Percentage(p) => Self::Percentage(p.max(0.0)),
+            Self::Calc(calc) => calc.clamp_non_negative().into(),
+        }
+    }
+}
+
+/// Lengths and percentages are interpolated separately, and mixed ones as a math function.
+impl Animate for LengthPercentage {
+    fn animate(&self, other: &Self, progress: f64) -> Option<Self> {
+        let value = match (self, other) {
+            (Self::Length(a), Self::Length(b)) => Self::Length(a.animate(b, progress)?),
+            (Self::Percentage(a), Self::Percentage(b)) => Self::Percentage(a.animate(b, progress)?),
+            (a, b) => a.to_calc().interpolate(&b.to_calc(), progress).into(),
+        };
+        Some(value)
+    }
+}
+
+impl LengthPercentage {
+    fn to_calc(&self) -> CalcNode {
+        match self {
+            Self::Length(px) => CalcNode::Length(Length::px(*px)),
+            Self::Percentage(p) => CalcNode::Percentage(*p),
+            Self::Calc(calc) => calc.clone(),
+        }
+    }
+}
+
+impl From<CalcNode> for LengthPercentage {
+    /// The result of a math function, simplified at computed-value time.
+    fn from(calc: CalcNode) -> Self {
+        match calc {
+            CalcNode::Length(length) => Self::Length(length.value),
+            CalcNode::Percentage(p) => Self::Percentage(p),
+            calc => Self::Calc(calc),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentageOrAuto {
+    /// In px.
+    Length(f64),
+    Percentage(f64),
+    Calc(CalcNode),
+    Auto,
+}
+
+impl LengthPercentageOrAuto {
+    /// Resolves the value into px during layout. Returns None for `auto`.
+    pub fn resolve(&self, basis: f64) -> Option<f64> {
+        match self {
+            Self::Length(px) => Some(*px),
+            Self::Percentage(p) => Some(basis * p / 100.0),
+            Self::Calc(calc) => Some(calc.resolve(basis)),
+            Self::Auto => None,
+        }
+    }
+
+    fn clamp_non_negative(self) -> Self {
+        match LengthPercentage::try_from(self) {
+            Ok(value) => value.clamp_non_negative().into(),
+            Err(()) => Self::Auto,
+        }
+    }
+}
+
+impl TryFrom<LengthPercentageOrAuto> for LengthPercentage {
+    type Error = ();
+
+    fn try_from(value: LengthPercentageOrAuto) -> Result<Self, ()> {
+        match value {
+            LengthPercentageOrAuto::Length(px) => Ok(Self::Length(px)),
+            LengthPercentageOrAuto::Percentage(p) => Ok(Self::Percentage(p)),
+            LengthPercentageOrAuto::Calc(calc) => Ok(Self::Calc(calc)),
+            LengthPercentageOrAuto::Auto => Err(()),
+        }
+    }
+}
+
+/// `auto` is not interpolable.
+impl Animate for LengthPercentageOrAuto {
+    fn animate(&self, other: &Self, progress: f64) -> Option<Self> {
+        let a = LengthPercentage::try_from(self.clone()).ok()?;
+        let b = LengthPercentage::try_from(other.clone()).ok()?;
+        Some(a.animate(&b, progress)?.into())
+    }
+}
+
+/// <https://www.w3.org/TR/css-backgrounds-3/#typedef-line-style>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    None,
+    Hidden,
+    Dotted,
+    Dashed,
+    Solid,
+    Double,
+    Groove,
+    Ridge,
+    Inset,
+    Outset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+    Justify,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextDecorationLine {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextTransform {
+    None,
+    Capitalize,
+    Uppercase,
+    Lowercase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+    Nowrap,
+    PreWrap,
+    PreLine,
+    BreakSpaces,
+}
+
+impl WhiteSpace {
+    /// Whether sequences of spaces and tabs collapse, so that spaces at the end of a line are removed.
+    pub fn collapses_spaces(self) -> bool {
+        matches!(self, Self::Normal | Self::Nowrap | Self::PreLine)
+    }
+
+    /// Whether newlines are preserved as forced line breaks.
+    pub fn preserves_newlines(self) -> bool {
+        !matches!(self, Self::Normal | Self::Nowrap)
+    }
+
+    /// Whether lines may wrap at soft wrap opportunities.
+    pub fn wraps(self) -> bool {
+        !matches!(self, Self::Pre | Self::Nowrap)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordBreak {
+    Normal,
+    /// Breaks are allowed between any letters.
+    BreakAll,
+    /// Breaks are not allowed between CJK characters, as between letters.
+    KeepAll,
+    /// The same as `word-break: normal` with `overflow-wrap: anywhere`.
+    BreakWord,
+}
+
+/// How strictly the line breaking rules for CJK punctuation and small kana apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineBreak {
+    Auto,
+    Loose,
+    Normal,
+    Strict,
+    Anywhere,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowWrap {
+    Normal,
+    BreakWord,
+    Anywhere,
+}
+
+/// The computed values of the `transition-*` properties, whose lists have an item for each transition.
+///
+/// The lists of the other properties are repeated to match the length of `property`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transitions {
+    /// Empty for `none`.
+    pub property: Vec<TransitionProperty>,
+    /// In ms.
+    pub duration: Vec<f64>,
+    pub timing_function: Vec<EasingFunction>,
+    /// In ms.
+    pub delay: Vec<f64>,
+}
+
+impl Default for Transitions {
+    fn default() -> Self {
+        Self {
+            property: vec![TransitionProperty::All],
+            duration: vec![0.0],
+            timing_function: vec![EasingFunction::Ease],
+            delay: vec![0.0],
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/css-transitions-1/#single-transition-property>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransitionProperty {
+    All,
+    /// The name of a property in lowercase, which may be a shorthand or unsupported.
+    Property(String),
+}
+
+/// The computed values of the `animation-*` properties, whose lists have an item for each animation.
+///
+/// The lists of the other properties are repeated to match the length of `name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animations {
+    /// The names of the `@keyframes`, where None is `none`.
+    pub name: Vec<Option<String>>,
+    /// In ms.
+    pub duration: Vec<f64>,
+    pub timing_function: Vec<EasingFunction>,
+    /// In ms.
+    pub delay: Vec<f64>,
+    /// `infinite` is infinity.
+    pub iteration_count: Vec<f64>,
+    pub direction: Vec<AnimationDirection>,
+    pub fill_mode: Vec<AnimationFillMode>,
+    pub play_state: Vec<AnimationPlayState>,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self {
+            name: vec![None],
+            duration: vec![0.0],
+            timing_function: vec![EasingFunction::Ease],
+            delay: vec![0.0],
+            iteration_count: vec![1.0],
+            direction: vec![AnimationDirection::Normal],
+            fill_mode: vec![AnimationFillMode::None],
+            play_state: vec![AnimationPlayState::Running],
+        }
+    }
+}
+
+/// <https://www.w3.org/TR/css-animations-1/#animation-direction>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationDirection {
+    Normal,
+    Reverse,
+    Alternate,
+    AlternateReverse,
+}
+
+/// <https://www.w3.org/TR/css-animations-1/#animation-fill-mode>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFillMode {
+    None,
+    Forwards,
+    Backwards,
+    Both,
+}
+
+/// <https://www.w3.org/TR/css-animations-1/#animation-play-state>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationPlayState {
+    Running,
+    Paused,
+}
+
+/// Returns the item of a list property for the i-th transition or animation,
+/// repeating the list if it is shorter.
+pub fn list_item<T: Clone>(list: &[T], i: usize) -> T {
+    list[i % list.len()].clone()
+}
+
+impl From<LengthPercentage> for LengthPercentageOrAuto {
+    fn from(value: LengthPercentage) -> Self {
+        match value {
+            LengthPercentage::Length(px) => Self::Length(px),
+            LengthPercentage::Percentage(p) => Self::Percentage(p),
+            LengthPercentage::Calc(calc) => Self::Calc(calc),
+        }
+    }
+}
+
+/// The values of a box-model property for each side of a box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sides<T> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+impl<T: Clone> Sides<T> {
+    pub fn all(value: T) -> Self {
+        Self {
+            top: value.clone(),
+            right: value.clone(),
+            bottom: value.clone(),
+            left: value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl<T> Index<Side> for Sides<T> {
+    type Output = T;
+
+    fn index(&self, side: Side) -> &T {
+        match side {
+            Side::Top => &self.top,
+            Side::Right => &self.right,
+            Side::Bottom => &self.bottom,
+            Side::Left => &self.left,
+        }
+    }
+}
+
+impl<T> IndexMut<Side> for Sides<T> {
+    fn index_mut(&mut self, side: Side) -> &mut T {
+        match side {
+            Side::Top => &mut self.top,
+            Side::Right => &mut self.right,
+            Side::Bottom => &mut self.bottom,
+            Side::Left => &mut self.left,
+        }
+    }
+}
+
+/// The properties that have a value for each side, e.g. `margin-top`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SideProperty {
+    Margin,
+    Padding,
+    BorderWidth,
+    BorderStyle,
+    BorderColor,
+}
+
+/// Parses the name of a property with a value for each side, e.g. `border-top-width`.
+fn side_property(name: &str) -> Option<(SideProperty, Side)> {
+    let side = |side: &str| match side {
+        "top" => Some(Side::Top),
+        "right" => Some(Side::Right),
+        "bottom" => Some(Side::Bottom),
+        "left" => Some(Side::Left),
+        _ => None,
+    };
+    if let Some(s) = name.strip_prefix("margin-") {
+        return Some((SideProperty::Margin, side(s)?));
+    }
+    if let Some(s) = name.strip_prefix("padding-") {
+        return Some((SideProperty::Padding, side(s)?));
+    }
+    let (s, property) = name.strip_prefix("border-")?.split_once('-')?;
+    let property = match property {
+        "width" => SideProperty::BorderWidth,
+        "style" => SideProperty::BorderStyle,
+        "color" => SideProperty::BorderColor,
+        _ => return None,
+    };
+    Some((property, side(s)?))
+}
+
+/// <https://www.w3.org/TR/css-cascade-4/#inherited-property>
+const INHERITED_PROPERTIES: [&str; 12] = [
+    "color",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "line-height",
+    "text-align",
+    "text-transform",
+    "white-space",
+    "word-break",
+    "line-break",
+    "overflow-wrap",
+];
+
+/// <https://www.w3.org/TR/web-animations-1/#animation-type>
+///
+/// The supported properties whose values can be interpolated, which `transition-property: all` transitions.
+/// The other properties are discrete, and animated only by keyframes.
+pub const INTERPOLABLE_PROPERTIES: &[&str] = &[
+    "color",
+    "background-color",
+    "font-size",
+    "font-weight",
+    "line-height",
+    "margin-top",
+    "margin-right",
+    "margin-bottom",
+    "margin-left",
+    "padding-top",
+    "padding-right",
+    "padding-bottom",
+    "padding-left",
+    "border-top-width",
+    "border-right-width",
+    "border-bottom-width",
+    "border-left-width",
+    "border-top-color",
+    "border-right-color",
+    "border-bottom-color",
+    "border-left-color",
+    "width",
+    "height",
+];
+
+/// The properties that others depend on, e.g. `currentcolor` depends on `color`.
+/// They are computed before the others.
+const EARLY_PROPERTIES: [&str; 2] = ["color", "font-size"];
+
+/// The `medium` font size in px.
+const MEDIUM_FONT_SIZE: f64 = 16.0;
+
+/// The environment which relative lengths depend on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComputeContext {
+    /// The font size of the root element in px, which `rem` is relative to.
+    pub root_font_size: f64,
+    /// The size of the viewport in px, which `vw` and `vh` are relative to.
+    pub viewport_width: f64,
+    pub viewport_height: f64,
+}
+
+impl ComputeContext {
+    pub fn new(viewport_width: f64, viewport_height: f64) -> Self {
+        Self {
+            root_font_size: MEDIUM_FONT_SIZE,
+            viewport_width,
+            viewport_height,
+        }
+    }
+
+    /// Converts a length into px.
+    ///
+    /// <https://www.w3.org/TR/css-values-4/#absolute-lengths>
+    /// <https://www.w3.org/TR/css-values-4/#font-relative-lengths>
+    /// <https://www.w3.org/TR/css-values-4/#viewport-relative-lengths>
+    pub fn resolve_length(&self, length: Length, font_size: f64) -> f64 {
+        let unit = match length.unit {
+            LengthUnit::Px => 1.0,
+            LengthUnit::Cm => 96.0 / 2.54,
+            LengthUnit::Mm => 96.0 / 25.4,
+            LengthUnit::Q => 96.0 / 101.6,
+            LengthUnit::In => 96.0,
+            LengthUnit::Pt => 96.0 / 72.0,
+            LengthUnit::Pc => 16.0,
+            LengthUnit::Em => font_size,
+            LengthUnit::Rem => self.root_font_size,
+            // The advance of "0" is not available without font metrics,
+            // so it is assumed to be 0.5em as suggested by the spec.
+            LengthUnit::Ch => font_size / 2.0,
+            LengthUnit::Vw => self.viewport_width / 100.0,
+            LengthUnit::Vh => self.viewport_height / 100.0,
+        };
+        length.value * unit
+    }
+}
+
+impl Default for ComputedStyle {
+    /// The initial values of all the properties, as the style of an element without any declarations nor a parent.
+    fn default() -> Self {
+        let mut style = Self::initial_values();
+        style.fix_up();
+        style
+    }
+}
+
+impl ComputedStyle {
+    /// The initial values, before the adjustments depending on other properties.
+    fn initial_values() -> Self {
+        Self {
+            display: DisplayType::Inline,
+            color: Color::BLACK,
+            background_color: Color::TRANSPARENT,
+            font_family: vec!["serif".to_string()],
+            font_size: MEDIUM_FONT_SIZE,
+            font_weight: 400,
+            font_style: FontStyle::Normal,
+            line_height: LineHeight::Normal,
+            margin: Sides::all(LengthPercentageOrAuto::Length(0.0)),
+            padding: Sides::all(LengthPercentage::Length(0.0)),
+            // medium
+            border_width: Sides::all(3.0),
+            border_style: Sides::all(BorderStyle::None),
+            // currentcolor
+            border_color: Sides::all(Color::BLACK),
+            width: LengthPercentageOrAuto::Auto,
+            height: LengthPercentageOrAuto::Auto,
+            text_align: TextAlign::Start,
+            text_decoration_line: TextDecorationLine::default(),
+            text_transform: TextTransform::None,
+            white_space: WhiteSpace::Normal,
+            word_break: WordBreak::Normal,
+            line_break: LineBreak::Auto,
+            overflow_wrap: OverflowWrap::Normal,
+            transition: Transitions::default(),
+            animation: Animations::default(),
+            custom_properties: CustomProperties::default(),
+        }
+    }
+
+    /// Computes the style of an element from its cascaded declarations and the style of its parent.
+    ///
+    /// <https://www.w3.org/TR/css-cascade-4/#value-stages>
+    ///
+    /// The declarations must be sorted in the ascending order of precedence, so the last valid one wins.
+    /// Properties without any valid declaration are inherited from the parent if they are inherited properties,
+    /// or set to their initial values otherwise.
+    pub fn compute(
+        declarations: &[CssDeclaration],
+        parent: Option<&ComputedStyle>,
+        context: &ComputeContext,
+    ) -> Self {
+        let initial = Self::initial_values();
+        let is_root = parent.is_none();
+        let parent = parent.unwrap_or(&initial);
+        let mut context = *context;
+
+        let mut style = initial.clone();
+        for name in INHERITED_PROPERTIES {
+            style.copy_property(name, parent);
+        }
+        style.custom_properties =
+            custom_properties::compute(declarations, &parent.custom_properties);
+
+        let mut decided: Vec<String> = Vec::new();
+        for early in [true, false] {
+            if !early {
+                // The initial value of border colors is `currentcolor`.
+                style.border_color = Sides::all(style.color);
+                // `rem` in the root element is relative to its own font size, except in `font-size` itself.
+                if is_root {
+                    context.root_font_size = style.font_size;
+                }
+            }
+            for declaration in declarations.iter().rev() {
+                if is_custom_property(&declaration.property_name) {
+                    continue;
+                }
+                let name = declaration.property_name.to_ascii_lowercase();
+                if EARLY_PROPERTIES.contains(&name.as_str()) != early || decided.contains(&name) {
+                    continue;
+                }
+                // <https://www.w3.org/TR/css-variables-1/#invalid-at-computed-value-time>
+                // A declaration which is invalid after the substitution of `var()` still wins the cascade,
+                // and behaves as `unset`.
+                let value = match declaration.value.as_slice() {
+                    [ComponentValue::Unparsed(unparsed)] => Cow::Owned(
+                        custom_properties::resolve_unparsed(
+                            &name,
+                            unparsed,
+                            &style.custom_properties,
+                        )
+                        .unwrap_or_else(|| vec![ComponentValue::Keyword("unset".into())]),
+                    ),
+                    value => Cow::Borrowed(value),
+                };
+                if style
+                    .apply_declaration(&name, &value, parent, &initial, &context)
+                    .is_some()
+                {
+                    decided.push(name);
+                }
+            }
+        }
+
+        style.fix_up();
+        style
+    }
+
+    /// The style of an anonymous box, which only inherits the inherited properties from its parent.
+    ///
+    /// <https://www.w3.org/TR/css-display-3/#anonymous>
+    pub fn inherit(parent: &ComputedStyle) -> Self {
+        let mut style = Self::initial_values();
+        for name in INHERITED_PROPERTIES {
+            style.copy_property(name, parent);
+        }
+        style.custom_properties = Rc::clone(&parent.custom_properties);
+        style.border_color = Sides::all(style.color);
+        style.fix_up();
+        style
+    }
+
+    /// Adjusts the values depending on other properties.
+    fn fix_up(&mut self) {
+        for side in [Side::Top, Side::Right, Side::Bottom, Side::Left] {
+            // <https://www.w3.org/TR/css-backgrounds-3/#border-width>
+            // The computed value is zero if the border style is none or hidden.
+            if matches!(
+                self.border_style[side],
+                BorderStyle::None | BorderStyle::Hidden
+            ) {
+                self.border_width[side] = 0.0;
+            }
+        }
+    }
+
+    /// Sets the property to the value of the declaration.
+    /// Returns None if the property is not supported or the value is invalid.
+    fn apply_declaration(
+        &mut self,
+        name: &str,
+        value: &[ComponentValue],
+        parent: &ComputedStyle,
+        initial: &ComputedStyle,
+        context: &ComputeContext,
+    ) -> Option<()> {
+        let keyword = match value {
+            [ComponentValue::Keyword(keyword)] => Some(keyword.to_ascii_lowercase()),
+            _ => None,
+        };
+        let keyword = keyword.as_deref();
+
+        // <https://www.w3.org/TR/css-cascade-4/#defaulting-keywords>
+        match keyword {
+            Some("inherit") => return self.copy_property(name, parent),
+            Some("initial") => return self.copy_property(name, initial),
+            Some("unset") if INHERITED_PROPERTIES.contains(&name) => {
+                return self.copy_property(name, parent)
+            }
+            Some("unset") => return self.copy_property(name, initial),
+            _ => {}
+        }
+
+        if let Some((property, side)) = side_property(name) {
+            match property {
+                SideProperty::Margin => {
+                    self.margin[side] = match keyword {
+                        Some("auto") => LengthPercentageOrAuto::Auto,
+                        _ => self.length_percentage(value, context)?.into(),
+                    }
+                }
+                SideProperty::Padding => {
+                    self.padding[side] =
+                        self.length_percentage(value, context)?.clamp_non_negative()
+                }
+                SideProperty::BorderWidth => {
+                    // <https://www.w3.org/TR/css-backgrounds-3/#typedef-line-width>
+                    self.border_width[side] = match keyword {
+                        Some("thin") => 1.0,
+                        Some("medium") => 3.0,
+                        Some("thick") => 5.0,
+                        Some(_) => return None,
+                        None => match value {
+                            [ComponentValue::Length(length)] => {
+                                context.resolve_length(*length, self.font_size)
+                            }
+                            [ComponentValue::Calc(calc)] => calc
+                                .compute(&|length| context.resolve_length(length, self.font_size))
+                                .resolve(0.0)
+                                .max(0.0),
+                            _ => return None,
+                        },
+                    }
+                }
+                SideProperty::BorderStyle => {
+                    self.border_style[side] = match keyword? {
+                        "none" => BorderStyle::None,
+                        "hidden" => BorderStyle::Hidden,
+                        "dotted" => BorderStyle::Dotted,
+                        "dashed" => BorderStyle::Dashed,
+                        "solid" => BorderStyle::Solid,
+                        "double" => BorderStyle::Double,
+                        "groove" => BorderStyle::Groove,
+                        "ridge" => BorderStyle::Ridge,
+                        "inset" => BorderStyle::Inset,
+                        "outset" => BorderStyle::Outset,
+                        _ => return None,
+                    }
+                }
+                SideProperty::BorderColor => {
+                    self.border_color[side] = self.parse_color(value)?;
+                }
+            }
+            return Some(());
+        }
+
+        match name {
+            "display" => {
+                self.display = match keyword? {
+                    "block" => DisplayType::Block,
+                    "inline" => DisplayType::Inline,
+                    "none" => DisplayType::None,
+                    // List markers, atomic inlines and tables are not supported yet,
+                    // so they are laid out as the closest supported display types.
+                    "list-item" | "flow-root" => DisplayType::Block,
+                    "inline-block" => DisplayType::Inline,
+                    "table" | "table-caption" | "table-row-group" | "table-header-group"
+                    | "table-footer-group" | "table-row" | "table-cell" => DisplayType::Block,
+                    "table-column-group" | "table-column" => DisplayType::None,
+                    _ => return None,
+                }
+            }
+            "color" => {
+                // <https://www.w3.org/TR/css-color-4/#resolving-other-colors>
+                // `currentcolor` in the color property is treated as `inherit`.
+                self.color = match value {
+                    [ComponentValue::Keyword(k)] if k.eq_ignore_ascii_case("currentcolor") => {
+                        parent.color
+                    }
+                    _ => self.parse_color(value)?,
+                }
+            }
+            "background-color" => self.background_color = self.parse_color(value)?,
+            "font-family" => {
+                let mut families = Vec::new();
+                for family in value {
+                    match family {
+                        ComponentValue::Keyword(name) | ComponentValue::String(name) => {
+                            families.push(name.clone())
+                        }
+                        _ => return None,
+                    }
+                }
+                if families.is_empty() {
+                    return None;
+                }
+                self.font_family = families;
+            }
+            "font-size" => {
+                // <https://www.w3.org/TR/css-fonts-4/#absolute-size-mapping>
+                // <https://www.w3.org/TR/css-fonts-4/#relative-size-value>
+                self.font_size = match keyword {
+                    Some("xx-small") => MEDIUM_FONT_SIZE * 3.0 / 5.0,
+                    Some("x-small") => MEDIUM_FONT_SIZE * 3.0 / 4.0,
+                    Some("small") => MEDIUM_FONT_SIZE * 8.0 / 9.0,
+                    Some("medium") => MEDIUM_FONT_SIZE,
+                    Some("large") => MEDIUM_FONT_SIZE * 6.0 / 5.0,
+                    Some("x-large") => MEDIUM_FONT_SIZE * 3.0 / 2.0,
+                    Some("xx-large") => MEDIUM_FONT_SIZE * 2.0,
+                    Some("xxx-large") => MEDIUM_FONT_SIZE * 3.0,
+                    Some("smaller") => parent.font_size / 1.2,
+                    Some("larger") => parent.font_size * 1.2,
+                    Some(_) => return None,
+                    // Relative lengths and percentages are relative to the parent's font size.
+                    None => match value {
+                        [ComponentValue::Length(length)] => {
+                            context.resolve_length(*length, parent.font_size)
+                        }
+                        [ComponentValue::Percentage(p)] => parent.font_size * p / 100.0,
+                        [ComponentValue::Calc(calc)] => calc
+                            .compute(&|length| context.resolve_length(length, parent.font_size))
+                            .resolve(parent.font_size)
+                            .max(0.0),
+                        _ => return None,
+                    },
+                }
+            }
+            "font-weight" => {
+                // <https://www.w3.org/TR/css-fonts-4/#relative-weights>
+                let parent_weight = parent.font_weight;
+                self.font_weight = match (keyword, value) {
+                    (Some("normal"), _) => 400,
+                    (Some("bold"), _) => 700,
+                    (Some("bolder"), _) => match parent_weight {
+                        0..=349 => 400,
+                        350..=549 => 700,
+                        550..=899 => 900,
+                        _ => parent_weight,
+                    },
+                    (Some("lighter"), _) => match parent_weight {
+                        0..=99 => parent_weight,
+                        100..=549 => 100,
+                        550..=749 => 400,
+                        _ => 700,
+                    },
+                    (None, [value]) => number(value)?.clamp(1.0, 1000.0) as u16,
+                    _ => return None,
+                }
+            }
+            "font-style" => {
+                self.font_style = match keyword? {
+                    "normal" => FontStyle::Normal,
+                    "italic" => FontStyle::Italic,
+                    "oblique" => FontStyle::Oblique,
+                    _ => return None,
+                }
+            }
+            "line-height" => {
+                // <https://www.w3.org/TR/CSS2/visudet.html#propdef-line-height>
+                // Percentages are relative to the font size of the element itself.
+                self.line_height = match (keyword, value) {
+                    (Some("normal"), _) => LineHeight::Normal,
+                    (None, [v]) if number(v).is_some() => LineHeight::Number(number(v)?.max(0.0)),
+                    (None, _) => LineHeight::Length(
+                        self.length_percentage(value, context)?
+                            .resolve(self.font_size)
+                            .max(0.0),
+                    ),
+                    _ => return None,
+                }
+            }
+            "width" | "height" => {
+                let value = match keyword {
+                    Some("auto") => LengthPercentageOrAuto::Auto,
+                    Some(_) => return None,
+                    None => self
+                        .length_percentage(value, context)?
+                        .clamp_non_negative()
+                        .into(),
+                };
+                if name == "width" {
+                    self.width = value;
+                } else {
+                    self.height = value;
+                }
+            }
+            "text-align" => {
+                self.text_align = match keyword? {
+                    "start" => TextAlign::Start,
+                    "end" => TextAlign::End,
+                    "left" => TextAlign::Left,
+                    "right" => TextAlign::Right,
+                    "center" => TextAlign::Center,
+                    "justify" => TextAlign::Justify,
+                    _ => return None,
+                }
+            }
+            "text-decoration-line" => {
+                let mut line = TextDecorationLine::default();
+                for v in value {
+                    let ComponentValue::Keyword(keyword) = v else {
+                        return None;
+                    };
+                    match keyword.to_ascii_lowercase().as_str() {
+                        "none" => {}
+                        "underline" => line.underline = true,
+                        "overline" => line.overline = true,
+                        "line-through" => line.line_through = true,
+                        _ => return None,
+                    }
+                }
+                self.text_decoration_line = line;
+            }
+            "text-transform" => {
+                self.text_transform = match keyword? {
+                    "none" => TextTransform::None,
+                    "capitalize" => TextTransform::Capitalize,
+                    "uppercase" => TextTransform::Uppercase,
+                    "lowercase" => TextTransform::Lowercase,
+                    _ => return None,
+                }
+            }
+            "white-space" => {
+                self.white_space = match keyword? {
+                    "normal" => WhiteSpace::Normal,
+                    "pre" => WhiteSpace::Pre,
+                    "nowrap" => WhiteSpace::Nowrap,
+                    "pre-wrap" => WhiteSpace::PreWrap,
+                    "pre-line" => WhiteSpace::PreLine,
+                    "break-spaces" => WhiteSpace::BreakSpaces,
+                    _ => return None,
+                }
+            }
+            "word-break" => {
+                self.word_break = match keyword? {
+                    "normal" => WordBreak::Normal,
+                    "break-all" => WordBreak::BreakAll,
+                    "keep-all" => WordBreak::KeepAll,
+                    "break-word" => WordBreak::BreakWord,
+                    _ => return None,
+                }
+            }
+            "line-break" => {
+                self.line_break = match keyword? {
+                    "auto" => LineBreak::Auto,
+                    "loose" => LineBreak::Loose,
+                    "normal" => LineBreak::Normal,
+                    "strict" => LineBreak::Strict,
+                    "anywhere" => LineBreak::Anywhere,
+                    _ => return None,
+                }
+            }
+            "overflow-wrap" => {
+                self.overflow_wrap = match keyword? {
+                    "normal" => OverflowWrap::Normal,
+                    "break-word" => OverflowWrap::BreakWord,
+                    "anywhere" => OverflowWrap::Anywhere,
+                    _ => return None,
+                }
+            }
+            "transition-property" => {
+                self.transition.property = match keyword {
+                    Some("none") => Vec::new(),
+                    _ => keywords(value, |k| match k {
+                        "all" => Some(TransitionProperty::All),
+                        k => Some(TransitionProperty::Property(k.to_string())),
+                    })?,
+                }
+            }
+            "transition-duration" => self.transition.duration = times(value)?,
+            "transition-timing-function" => self.transition.timing_function = easings(value)?,
+            "transition-delay" => self.transition.delay = times(value)?,
+            "animation-name" => {
+                let mut names = Vec::new();
+                for v in value {
+                    names.push(match v {
+                        ComponentValue::Keyword(k) if k == "none" => None,
+                        ComponentValue::CustomIdent(name) | ComponentValue::String(name) => {
+                            Some(name.clone())
+                        }
+                        _ => return None,
+                    });
+                }
+                self.animation.name = names;
+            }
+            "animation-duration" => self.animation.duration = times(value)?,
+            "animation-timing-function" => self.animation.timing_function = easings(value)?,
+            "animation-delay" => self.animation.delay = times(value)?,
+            "animation-iteration-count" => {
+                let mut counts = Vec::new();
+                for v in value {
+                    counts.push(match v {
+                        ComponentValue::Keyword(k) if k == "infinite" => f64::INFINITY,
+                        v => number(v)?,
+                    });
+                }
+                self.animation.iteration_count = counts;
+            }
+            "animation-direction" => {
+                self.animation.direction = keywords(value, |k| match k {
+                    "normal" => Some(AnimationDirection::Normal),
+                    "reverse" => Some(AnimationDirection::Reverse),
+                    "alternate" => Some(AnimationDirection::Alternate),
+                    "alternate-reverse" => Some(AnimationDirection::AlternateReverse),
+                    _ => None,
+                })?
+            }
+            "animation-fill-mode" => {
+                self.animation.fill_mode = keywords(value, |k| match k {
+                    "none" => Some(AnimationFillMode::None),
+                    "forwards" => Some(AnimationFillMode::Forwards),
+                    "backwards" => Some(AnimationFillMode::Backwards),
+                    "both" => Some(AnimationFillMode::Both),
+                    _ => None,
+                })?
+            }
+            "animation-play-state" => {
+                self.animation.play_state = keywords(value, |k| match k {
+                    "running" => Some(AnimationPlayState::Running),
+                    "paused" => Some(AnimationPlayState::Paused),
+                    _ => None,
+                })?
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Parses a color for properties other than `color`, where `currentcolor` is the value of `color`.
+    fn parse_color(&self, value: &[ComponentValue]) -> Option<Color> {
+        match value {
+            [ComponentValue::Color(color)] => Some(*color),
+            [ComponentValue::Keyword(keyword)] if keyword.eq_ignore_ascii_case("currentcolor") => {
+                Some(self.color)
+            }
+            [ComponentValue::Keyword(keyword)] => Color::from_name(keyword),
+            _ => None,
+        }
+    }
+
+    /// Computes a `<length-percentage>`, where lengths are converted into px.
+    /// Font-relative lengths are relative to the font size of this style.
+    fn length_percentage(
+        &self,
+        value: &[ComponentValue],
+        context: &ComputeContext,
+    ) -> Option<LengthPercentage> {
+        match value {
+            [ComponentValue::Length(length)] => Some(LengthPercentage::Length(
+                context.resolve_length(*length, self.font_size),
+            )),
+            [ComponentValue::Percentage(p)] => Some(LengthPercentage::Percentage(*p)),
+            [ComponentValue::Calc(calc)] => Some(
+                calc.compute(&|length| context.resolve_length(length, self.font_size))
+                    .into(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Copies the value of the property from another style, e.g. the parent's for `inherit`.
+    /// Returns None if the property is not supported.
+    fn copy_property(&mut self, name: &str, from: &ComputedStyle) -> Option<()> {
+        if let Some((property, side)) = side_property(name) {
+            match property {
+                SideProperty::Margin => self.margin[side] = from.margin[side].clone(),
+                SideProperty::Padding => self.padding[side] = from.padding[side].clone(),
+                SideProperty::BorderWidth => self.border_width[side] = from.border_width[side],
+                SideProperty::BorderStyle => self.border_style[side] = from.border_style[side],
+                SideProperty::BorderColor => self.border_color[side] = from.border_color[side],
+            }
+            return Some(());
+        }
+
+        match name {
+            "display" => self.display = from.display,
+            "color" => self.color = from.color,
+            "background-color" => self.background_color = from.background_color,
+            "font-family" => self.font_family = from.font_family.clone(),
+            "font-size" => self.font_size = from.font_size,
+            "font-weight" => self.font_weight = from.font_weight,
+            "font-style" => self.font_style = from.font_style,
+            "line-height" => self.line_height = from.line_height,
+            "width" => self.width = from.width.clone(),
+            "height" => self.height = from.height.clone(),
+            "text-align" => self.text_align = from.text_align,
+            "text-decoration-line" => self.text_decoration_line = from.text_decoration_line,
+            "text-transform" => self.text_transform = from.text_transform,
+            "white-space" => self.white_space = from.white_space,
+            "word-break" => self.word_break = from.word_break,
+            "line-break" => self.line_break = from.line_break,
+            "overflow-wrap" => self.overflow_wrap = from.overflow_wrap,
+            "transition-property" => self.transition.property = from.transition.property.clone(),
+            "transition-duration" => self.transition.duration = from.transition.duration.clone(),
+            "transition-timing-function" => {
+                self.transition.timing_function = from.transition.timing_function.clone()
+            }
+            "transition-delay" => self.transition.delay = from.transition.delay.clone(),
+            "animation-name" => self.animation.name = from.animation.name.clone(),
+            "animation-duration" => self.animation.duration = from.animation.duration.clone(),
+            "animation-timing-function" => {
+                self.animation.timing_function = from.animation.timing_function.clone()
+            }
+            "animation-delay" => self.animation.delay = from.animation.delay.clone(),
+            "animation-iteration-count" => {
+                self.animation.iteration_count = from.animation.iteration_count.clone()
+            }
+            "animation-direction" => self.animation.direction = from.animation.direction.clone(),
+            "animation-fill-mode" => self.animation.fill_mode = from.animation.fill_mode.clone(),
+            "animation-play-state" => self.animation.play_state = from.animation.play_state.clone(),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Whether the property has the same value in both styles.
+    pub fn property_eq(&self, other: &ComputedStyle, name: &str) -> bool {
+        let mut style = self.clone();
+        style.copy_property(name, other).is_none() || style == *self
+    }
+
+    /// <https://www.w3.org/TR/web-animations-1/#animating-properties>
+    ///
+    /// Sets the property to the value between those of two styles at the progress,
+    /// which may be out of [0, 1] with some easing functions. The values which are not interpolable,
+    /// e.g. keywords, flip from one to the other at the middle.
+    /// Returns None if the property is not supported.
+    pub fn interpolate_property(
+        &mut self,
+        name: &str,
+        from: &ComputedStyle,
+        to: &ComputedStyle,
+        progress: f64,
+    ) -> Option<()> {
+        let discrete = if progress < 0.5 { from } else { to };
+        if let Some((property, side)) = side_property(name) {
+            match property {
+                SideProperty::Margin => {
+                    self.margin[side] = interpolate(&from.margin[side], &to.margin[side], progress)
+                }
+                SideProperty::Padding => {
+                    self.padding[side] =
+                        interpolate(&from.padding[side], &to.padding[side], progress)
+                            .clamp_non_negative()
+                }
+                SideProperty::BorderWidth => {
+                    self.border_width[side] =
+                        interpolate(&from.border_width[side], &to.border_width[side], progress)
+                            .max(0.0)
+                }
+                SideProperty::BorderColor => {
+                    self.border_color[side] =
+                        interpolate(&from.border_color[side], &to.border_color[side], progress)
+                }
+                SideProperty::BorderStyle => return self.copy_property(name, discrete),
+            }
+            return Some(());
+        }
+
+        match name {
+            "color" => self.color = interpolate(&from.color, &to.color, progress),
+            "background-color" => {
+                self.background_color =
+                    interpolate(&from.background_color, &to.background_color, progress)
+            }
+            "font-size" => {
+                self.font_size = interpolate(&from.font_size, &to.font_size, progress).max(0.0)
+            }
+            "font-weight" => {
+                // <https://www.w3.org/TR/css-fonts-4/#font-weight-prop>
+                // Interpolated as a number, which is rounded and clamped.
+                let weight = interpolate(
+                    &(from.font_weight as f64),
+                    &(to.font_weight as f64),
+                    progress,
+                );
+                self.font_weight = (weight.clamp(1.0, 1000.0) + 0.5) as u16;
+            }
+            "line-height" => {
+                self.line_height = match interpolate(&from.line_height, &to.line_height, progress) {
+                    LineHeight::Number(n) => LineHeight::Number(n.max(0.0)),
+                    LineHeight::Length(px) => LineHeight::Length(px.max(0.0)),
+                    LineHeight::Normal => LineHeight::Normal,
+                }
+            }
+            "width" => {
+                self.width = interpolate(&from.width, &to.width, progress).clamp_non_negative()
+            }
+            "height" => {
+                self.height = interpolate(&from.height, &to.height, progress).clamp_non_negative()
+            }
+            _ => return self.copy_property(name, discrete),
+        }
+        Some(())
+    }
+}
+
+/// Interpolates the values, or takes one of them if they are not interpolable.
+fn interpolate<T: Animate + Clone>(from: &T, to: &T, progress: f64) -> T {
+    from.animate(to, progress).unwrap_or_else(|| {
+        if progress < 0.5 {
+            from.clone()
+        } else {
+            to.clone()
+        }
+    })
+}
+
+/// Computes a list of `<time>` in ms.
+fn times(value: &[ComponentValue]) -> Option<Vec<f64>> {
+    value
+        .iter()
+        .map(|v| match v {
+            ComponentValue::Time(s) => Some(s * 1000.0),
+            _ => None,
+        })
+        .collect()
+}
+
+fn easings(value: &[ComponentValue]) -> Option<Vec<EasingFunction>> {
+    value
+        .iter()
+        .map(|v| match v {
+            ComponentValue::Easing(easing) => Some(*easing),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Computes a list of keywords.
+fn keywords<T>(value: &[ComponentValue], f: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    value
+        .iter()
+        .map(|v| match v {
+            ComponentValue::Keyword(k) => f(k),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Computes a `<number>`, including math functions.
+fn number(value: &ComponentValue) -> Option<f64> {
+    match value {
+        ComponentValue::Number(n) => Some(*n),
+        ComponentValue::Calc(calc) => calc.as_number(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::css::parser::parse_style_attribute;
+
+    use super::*;
+
+    const CONTEXT: ComputeContext = ComputeContext {
+        root_font_size: MEDIUM_FONT_SIZE,
+        viewport_width: 800.0,
+        viewport_height: 600.0,
+    };
+
+    fn declaration(name: &str, value: &str) -> CssDeclaration {
+        CssDeclaration {
+            property_name: name.into(),
+            value: vec![ComponentValue::Keyword(value.into())],
+            important: false,
+        }
+    }
+
+    fn compute(style: &str, parent: Option<&ComputedStyle>) -> ComputedStyle {
+        let declarations = parse_style_attribute(style.into()).declarations;
+        ComputedStyle::compute(&declarations, parent, &CONTEXT)
+    }
+
+    #[test]
+    fn test_initial_values() {
+        let style = ComputedStyle::compute(&[], None, &CONTEXT);
+        assert_eq!(style, ComputedStyle::default());
+        assert_eq!(style.display, DisplayType::Inline);
+        assert_eq!(style.border_width, Sides::all(0.0));
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let parent = ComputedStyle::compute(
+            &[
+                declaration("color", "red"),
+                declaration("background-color", "blue"),
+                declaration("font-weight", "bold"),
+                declaration("display", "block"),
+            ],
+            None,
+            &CONTEXT,
+        );
+        let child = ComputedStyle::compute(
+            &[declaration("font-size", "larger")],
+            Some(&parent),
+            &CONTEXT,
+        );
+
+        assert_eq!(child.color, Color::rgb(255, 0, 0));
+        assert_eq!(child.font_weight, 700);
+        assert_eq!(child.font_size, MEDIUM_FONT_SIZE * 1.2);
+        // Non-inherited properties have their initial values.
+        assert_eq!(child.background_color, Color::TRANSPARENT);
+        assert_eq!(child.display, DisplayType::Inline);
+
+        let child = ComputedStyle::compute(
+            &[
+                declaration("background-color", "inherit"),
+                declaration("color", "initial"),
+                declaration("font-weight", "unset"),
+                declaration("display", "unset"),
+            ],
+            Some(&parent),
+            &CONTEXT,
+        );
+        assert_eq!(child.background_color, Color::rgb(0, 0, 255));
+        assert_eq!(child.color, Color::BLACK);
+        assert_eq!(child.font_weight, 700);
+        assert_eq!(child.display, DisplayType::Inline);
+    }
+
+    #[test]
+    fn test_last_valid_declaration_wins() {
+        let style = ComputedStyle::compute(
+            &[
+                declaration("display", "block"),
+                declaration("DISPLAY", "none"),
+                // Invalid, so the previous one wins.
+                declaration("display", "foo"),
+                declaration("unknown", "none"),
+            ],
+            None,
+            &CONTEXT,
+        );
+        assert_eq!(style.display, DisplayType::None);
+    }
+
+    #[test]
+    fn test_custom_properties() {
+        let root = compute(
+            "--fg: green; --size: 2em; --sides: 1px 2px; --bad: foo; color: red; \
+             font-size: var(--size); margin: var(--sides); padding-left: var(--size, 1px)",
+            None,
+        );
+        assert_eq!(root.font_size, 32.0);
+        assert_eq!(root.margin.top, LengthPercentageOrAuto::Length(1.0));
+        assert_eq!(root.margin.left, LengthPercentageOrAuto::Length(2.0));
+        assert_eq!(root.padding.left, LengthPercentage::Length(64.0));
+
+        // Substituted with the inherited values, or the fallbacks.
+        let child = compute(
+            "--size: 10px; color: var(--fg); width: var(--missing, var(--size)); \
+             border-top: var(--size) solid var(--fg)",
+            Some(&root),
+        );
+        assert_eq!(child.color, Color::rgb(0, 128, 0));
+        assert_eq!(child.width, LengthPercentageOrAuto::Length(10.0));
+        assert_eq!(child.border_width.top, 10.0);
+        assert_eq!(child.border_color.top, Color::rgb(0, 128, 0));
+
+        // Invalid at computed-value time, so the declarations behave as `unset`
+        // instead of falling back to the earlier ones.
+        let child = compute(
+            "color: blue; color: var(--bad); display: block; display: var(--missing); \
+             margin: var(--bad)",
+            Some(&root),
+        );
+        assert_eq!(child.color, Color::rgb(255, 0, 0));
+        assert_eq!(child.display, DisplayType::Inline);
+        assert_eq!(child.margin.top, LengthPercentageOrAuto::Length(0.0));
+
+        // Cycles make the properties invalid.
+        let child = compute(
+            "--a: var(--b); --b: var(--a); color: var(--a, blue); width: var(--c, 5px); --c: var(--c)",
+            Some(&root),
+        );
+        assert_eq!(child.color, Color::rgb(0, 0, 255));
+        assert_eq!(child.width, LengthPercentageOrAuto::Length(5.0));
+        assert_eq!(
+            ComputedStyle::inherit(&child).custom_properties,
+            child.custom_properties
+        );
+    }
+
+    #[test]
+    fn test_math_functions() {
+        let style = compute(
+            "font-size: calc(50% + 0.5em + 4px); width: calc(100% - 2em); \
+             height: min(50%, 10em + 10px); padding-left: calc(1px - 5px); \
+             padding-right: calc(10% - 1em); margin-left: clamp(1px, 2em, 3px); \
+             border-left: calc(1em / 4) solid; line-height: calc(1.5 * 2); \
+             font-weight: calc(100 * 9.5)",
+            None,
+        );
+        // Percentages and `em` are relative to the parent's font size.
+        assert_eq!(style.font_size, 20.0);
+        assert_eq!(style.font_weight, 950);
+        assert_eq!(style.line_height, LineHeight::Number(3.0));
+        assert_eq!(style.border_width.left, 5.0);
+        assert_eq!(style.margin.left, LengthPercentageOrAuto::Length(3.0));
+        // Clamped to be non-negative.
+        assert_eq!(style.padding.left, LengthPercentage::Length(0.0));
+
+        // Percentages are resolved against the containing block during layout.
+        assert_eq!(style.width.resolve(200.0), Some(160.0));
+        assert_eq!(style.height.resolve(200.0), Some(100.0));
+        assert_eq!(style.height.resolve(1000.0), Some(210.0));
+        assert_eq!(style.padding.right.resolve(100.0), 0.0);
+        assert_eq!(style.padding.right.resolve(1000.0), 80.0);
+    }
+
+    #[test]
+    fn test_dependent_values() {
+        let style = ComputedStyle::compute(
+            &[
+                declaration("border-top-color", "currentcolor"),
+                declaration("border-top-style", "solid"),
+                declaration("border-left-width", "thick"),
+                declaration("color", "green"),
+            ],
+            None,
+            &CONTEXT,
+        );
+        assert_eq!(style.border_color.top, Color::rgb(0, 128, 0));
+        assert_eq!(style.border_color.right, Color::rgb(0, 128, 0));
+        assert_eq!(style.border_width.top, 3.0);
+        // The border style is none.
+        assert_eq!(style.border_width.left, 0.0);
+    }
+
+    #[test]
+    fn test_lengths() {
+        let root = compute(
+            "font-size: 20px; margin-top: 1rem; margin-bottom: 2em",
+            None,
+        );
+        assert_eq!(root.font_size, 20.0);
+        // `rem` in the root element is relative to its own font size.
+        assert_eq!(root.margin.top, LengthPercentageOrAuto::Length(20.0));
+        assert_eq!(root.margin.bottom, LengthPercentageOrAuto::Length(40.0));
+
+        let context = ComputeContext {
+            root_font_size: root.font_size,
+            ..CONTEXT
+        };
+        let declarations = parse_style_attribute(
+            "font-size: 150%; padding-left: 1em; padding-right: 2ch; margin-left: 10vw; \
+             margin-right: -5%; width: 50vh; height: 72pt; border-top-width: 1in; \
+             line-height: 200%; margin-top: 2rem"
+                .into(),
+        )
+        .declarations;
+        let child = ComputedStyle::compute(&declarations, Some(&root), &context);
+        assert_eq!(child.font_size, 30.0);
+        assert_eq!(child.padding.left, LengthPercentage::Length(30.0));
+        assert_eq!(child.padding.right, LengthPercentage::Length(30.0));
+        assert_eq!(child.margin.left, LengthPercentageOrAuto::Length(80.0));
+        assert_eq!(child.margin.right, LengthPercentageOrAuto::Percentage(-5.0));
+        assert_eq!(child.margin.top, LengthPercentageOrAuto::Length(40.0));
+        assert_eq!(child.width, LengthPercentageOrAuto::Length(300.0));
+        assert_eq!(child.height, LengthPercentageOrAuto::Length(96.0));
+        // The border style is none.
+        assert_eq!(child.border_width.top, 0.0);
+        assert_eq!(child.line_height, LineHeight::Length(60.0));
+
+        let grandchild = compute("font-size: 0.5em", Some(&child));
+        assert_eq!(grandchild.font_size, 15.0);
+        // The computed line height is inherited, rather than the percentage.
+        assert_eq!(grandchild.line_height, LineHeight::Length(60.0));
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let style = compute(
+            "color: rgb(0 0 255 / 50%); border-left-color: #0f0; border-left-style: solid; \
+             border-left-width: 2px; font-family: Foo Bar, monospace; font-weight: 550; \
+             text-decoration-line: underline overline; line-height: 1.5",
+            None,
+        );
+        assert_eq!(
+            style.color,
+            Color {
+                r: 0,
+                g: 0,
+                b: 255,
+                a: 128
+            }
+        );
+        assert_eq!(style.border_color.top, style.color);
+        assert_eq!(style.border_color.left, Color::rgb(0, 255, 0));
+        assert_eq!(style.border_width.left, 2.0);
+        assert_eq!(style.font_family, vec!["Foo Bar", "monospace"]);
+        assert_eq!(style.font_weight, 550);
+        assert_eq!(
+            style.text_decoration_line,
+            TextDecorationLine {
+                underline: true,
+                overline: true,
+                line_through: false
+            }
+        );
+        assert_eq!(style.line_height, LineHeight::Number(1.5));
+    }
+}
@@ -27,7 +27,7 @@ pub struct LayoutPoint {
     pub y: i64,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LayoutSize {
     pub width: i64,
     pub height: i64,
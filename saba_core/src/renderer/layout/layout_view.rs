@@ -1,18 +1,15 @@
 use alloc::rc::{Rc, Weak};
-use alloc::string::String;
-use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
-use crate::display_item::{self, DisplayItem};
-use crate::renderer::dom::node::{Element, NodeData};
-use crate::renderer::layout::computed_style::{ComputedStyle, DisplayType};
-use crate::renderer::{
-    css::cssom::CssStyleSheet,
-    dom::node::{ElementKind, Node},
-};
+use crate::renderer::css::animation::AnimationContext;
+use crate::renderer::css::cascade::Stylist;
+use crate::renderer::css::matching::{AncestorBloomFilter, MatchingContext};
+use crate::renderer::css::restyle::{RestyleHint, StyleCache};
+use crate::renderer::dom::node::{ElementKind, Node, NodeData, NodeEdge};
+use crate::renderer::layout::computed_style::{ComputeContext, ComputedStyle};
 
-use super::layout_object::{LayoutObject, LayoutPoint, LayoutSize};
+use super::layout_object::{LayoutObject, LayoutSize};
 
 // TODO: refactoring
 #[derive(Debug, Clone)]
@@ -21,97 +18,252 @@ pub struct LayoutView {
 }
 
 impl LayoutView {
-    pub fn layout(dom: Rc<RefCell<Node>>, cssom: &CssStyleSheet) -> LayoutView {
+    /// Styles and lays out the body of the document.
+    ///
+    /// The styles are taken from the cache, except for the nodes invalidated since the last layout
+    /// and the descendants of the nodes whose styles changed.
+    pub fn layout(
+        dom: Rc<RefCell<Node>>,
+        stylist: &Stylist,
+        viewport_size: LayoutSize,
+        cache: &mut StyleCache,
+    ) -> LayoutView {
         let body_dom = Node::get_element_by_tag_name(dom, ElementKind::Body);
-        LayoutView {
-            root: build_layout_tree(&body_dom, &None, cssom),
-        }
+        let compute_context =
+            ComputeContext::new(viewport_size.width as f64, viewport_size.height as f64);
+        let previous_context = cache.compute_context;
+        // Lengths relative to the viewport change with it.
+        let resized = previous_context.is_some_and(|previous| {
+            (previous.viewport_width, previous.viewport_height)
+                != (
+                    compute_context.viewport_width,
+                    compute_context.viewport_height,
+                )
+        });
+        cache.matched = 0;
+
+        let mut context = StyleContext {
+            stylist,
+            bloom_filter: AncestorBloomFilter::new(),
+            compute_context,
+            cache,
+            recompute_all: resized,
+        };
+        let root = body_dom.map(|body_dom| {
+            // The styles of the ancestors of the body, from the root.
+            let mut ancestors: Vec<_> = Node::ancestors(Rc::clone(&body_dom)).collect();
+            ancestors.reverse();
+            let mut parent_style = None;
+            let mut restyle = Restyle::default();
+            for ancestor in ancestors {
+                if let NodeData::Element(e) = &ancestor.borrow().data {
+                    let (style, next) = context.style(&ancestor, parent_style.as_ref(), restyle);
+                    if parent_style.is_none() {
+                        context.compute_context.root_font_size = style.font_size;
+                        // `rem` changes with the font size of the root.
+                        if previous_context
+                            .is_some_and(|previous| previous.root_font_size != style.font_size)
+                        {
+                            context.recompute_all = true;
+                        }
+                    }
+                    parent_style = Some(style);
+                    restyle = next;
+                    context.bloom_filter.push_element(e);
+                }
+            }
+
+            build_layout_tree(body_dom, parent_style.as_ref(), restyle, &mut context)
+        });
+
+        context.cache.compute_context = Some(context.compute_context);
+        context.cache.prune();
+        LayoutView { root }
     }
 }
 
-fn build_layout_tree(
-    node: &Option<Rc<RefCell<Node>>>,
-    parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
-    cssom: &CssStyleSheet,
-) -> Option<Rc<RefCell<LayoutObject>>> {
-    // TODO
-    node.as_ref().map(|node| {
-        let obj = Rc::new(RefCell::new(LayoutObject {
-            node: Rc::clone(&node),
-            first_child: None,
-            next_sibling: None,
-            parent: parent_obj
-                .as_ref()
-                .map(Rc::downgrade)
-                .unwrap_or_else(Weak::new),
-
-            // TODO: use cssom
-            style: default_style(&node.borrow().data),
-        }));
-        let first_child =
-            build_layout_tree(&node.borrow().first_child, &Some(Rc::clone(&obj)), cssom);
-        let next_sibling =
-            build_layout_tree(&node.borrow().next_sibling, &Some(Rc::clone(&obj)), cssom);
+/// What the restyle of a node requires of its children.
+#[derive(Debug, Clone, Copy, Default)]
+struct Restyle {
+    /// The selectors need matching again, as an ancestor is marked with
+    /// [RestyleHint::RESTYLE_DESCENDANTS].
+    rematch: bool,
+    /// The computed values need computing again, as the style of the parent changed.
+    recompute: bool,
+}
 
+/// The state shared while computing the styles of the nodes in a tree.
+struct StyleContext<'a> {
+    stylist: &'a Stylist,
+    /// The filter of the ancestors of the node being styled.
+    bloom_filter: AncestorBloomFilter,
+    compute_context: ComputeContext,
+    cache: &'a mut StyleCache,
+    /// Whether the context changed, so that all the computed values need computing again.
+    recompute_all: bool,
+}
+
+impl StyleContext<'_> {
+    /// Returns the style of the node, and what its restyle requires of its children.
+    ///
+    /// The selectors are matched only if the node is invalidated, and the computed values
+    /// are computed only if the declarations or the parent style changed, or the node is animating.
+    fn style(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        parent_style: Option<&ComputedStyle>,
+        restyle: Restyle,
+    ) -> (ComputedStyle, Restyle) {
+        let data = self.cache.entry(node);
+        let hint = core::mem::take(&mut data.hint);
+        let rematch = restyle.rematch
+            || hint.contains(RestyleHint::RESTYLE_SELF)
+            || data.declarations.is_none();
+        if rematch {
+            let declarations = match &node.borrow().data {
+                NodeData::Element(_) => {
+                    self.cache.matched += 1;
+                    let context = MatchingContext {
+                        bloom_filter: Some(&self.bloom_filter),
+                        ..Default::default()
+                    };
+                    self.stylist
+                        .cascaded_declarations(Rc::clone(node), &context)
+                }
+                // Text is styled by inheriting from its parent.
+                _ => Vec::new(),
+            };
+            self.cache.entry(node).declarations = Some(declarations);
+        }
+
+        let now = self.cache.current_time;
+        let data = self.cache.entry(node);
+        let mut changed = false;
+        if rematch
+            || restyle.recompute
+            || self.recompute_all
+            || hint.contains(RestyleHint::RECASCADE_SELF)
+            || data.style.is_none()
         {
-            let mut obj = obj.borrow_mut();
-            obj.first_child = first_child;
-            obj.next_sibling = next_sibling;
+            let declarations = data.declarations.as_deref().unwrap_or_default();
+            let base = ComputedStyle::compute(declarations, parent_style, &self.compute_context);
+            let context = AnimationContext {
+                stylist: self.stylist,
+                declarations,
+                parent_style,
+                compute_context: &self.compute_context,
+                now,
+            };
+            let style = data.animations.update(base, data.style.as_ref(), &context);
+            changed = data.style.as_ref() != Some(&style);
+            data.style = Some(style);
         }
+        let style = data.style.clone().expect("the style is computed above");
+        (
+            style,
+            Restyle {
+                rematch: restyle.rematch || hint.contains(RestyleHint::RESTYLE_DESCENDANTS),
+                recompute: changed,
+            },
+        )
+    }
+}
 
-        obj
-    })
+/// A layout object being built, whose children are built before it is finished.
+struct OpenObject {
+    obj: Rc<RefCell<LayoutObject>>,
+    style: ComputedStyle,
+    /// What the restyle of the node requires of its children.
+    restyle: Restyle,
+    children: Vec<Rc<RefCell<LayoutObject>>>,
 }
 
-fn default_style(node_data: &NodeData) -> ComputedStyle {
-    let display = match node_data {
-        NodeData::Element(Element {
-            kind: ElementKind::A | ElementKind::Textarea,
-            ..
-        }) => DisplayType::Inline,
-        NodeData::Element(Element {
-            kind: ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P,
-            ..
-        }) => DisplayType::Block,
-        NodeData::Element(Element {
-            kind: ElementKind::Head | ElementKind::Script | ElementKind::Style,
-            ..
-        }) => DisplayType::None,
-        NodeData::Element(Element {
-            kind: ElementKind::Html,
-            ..
-        }) => unreachable!(),
-        NodeData::Element(Element {
-            kind: ElementKind::Img | ElementKind::Svg | ElementKind::SvgStyle | ElementKind::SvgA,
-            ..
-        }) => unimplemented!(),
-        NodeData::Text(_) => DisplayType::Inline,
-        NodeData::Document => unreachable!(),
-    };
+/// Builds the layout objects for the elements and the texts in `root` and its descendants, in tree order.
+fn build_layout_tree(
+    root: Rc<RefCell<Node>>,
+    parent_style: Option<&ComputedStyle>,
+    restyle: Restyle,
+    context: &mut StyleContext,
+) -> Rc<RefCell<LayoutObject>> {
+    // The objects of the ancestors of the node visited, from `root`.
+    let mut open: Vec<OpenObject> = Vec::new();
+    for edge in Node::traverse(root) {
+        match edge {
+            NodeEdge::Start(node) => {
+                if !is_laid_out(&node) {
+                    continue;
+                }
+                let (parent_obj, parent_style, parent_restyle) = match open.last() {
+                    Some(parent) => (
+                        Rc::downgrade(&parent.obj),
+                        Some(&parent.style),
+                        parent.restyle,
+                    ),
+                    None => (Weak::new(), parent_style, restyle),
+                };
+                let (style, restyle) = context.style(&node, parent_style, parent_restyle);
+                if let NodeData::Element(e) = &node.borrow().data {
+                    context.bloom_filter.push_element(e);
+                }
+                open.push(OpenObject {
+                    obj: Rc::new(RefCell::new(LayoutObject {
+                        node: Rc::clone(&node),
+                        first_child: None,
+                        next_sibling: None,
+                        parent: parent_obj,
+                        style: style.clone(),
+                    })),
+                    style,
+                    restyle,
+                    children: Vec::new(),
+                });
+            }
+            NodeEdge::End(node) => {
+                if !is_laid_out(&node) {
+                    continue;
+                }
+                let finished = open.pop().expect("the object is opened at the start edge");
+                if let NodeData::Element(e) = &node.borrow().data {
+                    context.bloom_filter.pop_element(e);
+                }
 
-    ComputedStyle {
-        display: Some(display),
+                // Link the children from the last one
+                let mut next_sibling = None;
+                for child in finished.children.into_iter().rev() {
+                    child.borrow_mut().next_sibling = next_sibling.take();
+                    next_sibling = Some(child);
+                }
+                finished.obj.borrow_mut().first_child = next_sibling;
+
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(finished.obj),
+                    None => return finished.obj,
+                }
+            }
+        }
     }
+    unreachable!("the traversal ends at the end edge of the root")
 }
 
-// TODO: find specification of the list of stylesheet sources
-pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
-    Node::get_element_by_tag_name(root, ElementKind::Style)
-        .map(|elem| elem.borrow().text_content())
-        .unwrap_or_default()
+/// Only elements and texts are laid out, e.g. not comments.
+fn is_laid_out(node: &Rc<RefCell<Node>>) -> bool {
+    matches!(node.borrow().data, NodeData::Element(_) | NodeData::Text(_))
 }
 
 #[cfg(test)]
 mod tests {
     use crate::renderer::{
         css::{
-            parser::{parse_css_stylesheet, StyleSheetParser},
-            token::CssTokenizer,
+            cascade::Origin, loader::StyleSheetLoader, media::Device,
+            user_agent::user_agent_stylesheet, value::Color,
         },
         dom::node::{Element, ElementKind, NodeData},
-        html::{parser::HtmlParser, token::HtmlTokenizer},
+        dom::test_util::{element, parse},
+        layout::computed_style::DisplayType,
     };
 
+    use alloc::boxed::Box;
+
     use super::*;
 
     #[test]
@@ -125,12 +277,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_styles() {
+        let html = r#"<html><head></head><body><p class="x" style="color: green">text</p><p>hidden</p><style>
+            body { color: red; background-color: blue }
+            p { display: none }
+            .x { display: inline; font-weight: bold }
+        </style></body></html>"#;
+        let layout_view = create_layout_view(html);
+        let body = layout_view.root.unwrap();
+        let body_style = body.borrow().style.clone();
+        assert_eq!(body_style.display, DisplayType::Block);
+        assert_eq!(body_style.color, Color::rgb(255, 0, 0));
+        assert_eq!(body_style.background_color, Color::rgb(0, 0, 255));
+
+        let children: Vec<_> = body.borrow().children().collect();
+        let p_x = children[0].borrow();
+        assert_eq!(p_x.style.display, DisplayType::Inline);
+        assert_eq!(p_x.style.font_weight, 700);
+        assert_eq!(p_x.style.color, Color::rgb(0, 128, 0));
+        assert_eq!(p_x.style.background_color, Color::TRANSPARENT);
+
+        let text = p_x.first_child.clone().unwrap();
+        assert_eq!(text.borrow().style.display, DisplayType::Inline);
+        assert_eq!(text.borrow().style.font_weight, 700);
+        assert_eq!(text.borrow().style.color, Color::rgb(0, 128, 0));
+
+        assert_eq!(children[1].borrow().style.display, DisplayType::None);
+    }
+
+    #[test]
+    fn test_user_agent_styles() {
+        let html = r#"<html><head></head><body><h1>title</h1><a href="/">link</a><img src="a.png"><svg><a></a></svg><script>s</script></body></html>"#;
+        let layout_view = create_layout_view(html);
+        let body = layout_view.root.unwrap();
+        let styles: Vec<_> = body
+            .borrow()
+            .children()
+            .map(|c| c.borrow().style.clone())
+            .collect();
+
+        assert_eq!(body.borrow().style.display, DisplayType::Block);
+        assert_eq!(styles[0].display, DisplayType::Block);
+        assert_eq!(styles[0].font_weight, 700);
+        assert_eq!(styles[1].display, DisplayType::Inline);
+        assert!(styles[1].text_decoration_line.underline);
+        assert_eq!(styles[2].display, DisplayType::Inline);
+        assert_eq!(styles[3].display, DisplayType::Inline);
+        assert_eq!(styles[4].display, DisplayType::None);
+    }
+
+    #[test]
+    fn test_incremental_restyle() {
+        let html = r#"<html><head><style>
+            .red { color: red }
+            .red a { font-weight: bold }
+        </style></head><body><p>a<a>b</a></p><h1>c</h1></body></html>"#;
+        let window = parse(html);
+        let dom = window.borrow().document();
+        let observer = StyleCache::observe(&window, Box::new(|_, _| {}));
+        let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+        stylist.add_stylesheet(&user_agent_stylesheet(), Origin::UserAgent);
+        stylist.add_style_sheet_list(&StyleSheetLoader::new(None, None).load(dom.clone()));
+        let size = LayoutSize {
+            width: 800,
+            height: 600,
+        };
+        let mut cache = StyleCache::default();
+        let layout = |cache: &mut StyleCache| {
+            cache.invalidate(&observer.take_records(), stylist.invalidation_map());
+            let body = LayoutView::layout(Rc::clone(&dom), &stylist, size, cache)
+                .root
+                .unwrap();
+            let p = body.borrow().first_child.clone().unwrap();
+            let a = p.borrow().first_child.clone().unwrap();
+            let a = a.borrow().next_sibling.clone().unwrap();
+            let h1 = p.borrow().next_sibling.clone().unwrap();
+            let styles = [&p, &a, &h1].map(|obj| {
+                let style = &obj.borrow().style;
+                (style.color, style.font_weight)
+            });
+            (cache.matched_count(), styles)
+        };
+
+        let black = Color::rgb(0, 0, 0);
+        let (matched, styles) = layout(&mut cache);
+        // html, body, p, a and h1.
+        assert_eq!(matched, 5);
+        assert_eq!(styles, [(black, 400), (black, 400), (black, 700)]);
+
+        // Nothing is matched, but the styles are taken from the cache.
+        assert_eq!(layout(&mut cache), (0, styles));
+
+        let p = element(&window, ElementKind::P);
+        Node::set_attribute(Rc::clone(&p), "class", "red").unwrap();
+        let (matched, styles) = layout(&mut cache);
+        // p and its descendant a.
+        assert_eq!(matched, 2);
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(styles, [(red, 400), (red, 700), (black, 700)]);
+
+        // Inherited by the descendants without matching.
+        Node::set_attribute(Rc::clone(&p), "style", "color: blue").unwrap();
+        let (matched, styles) = layout(&mut cache);
+        assert_eq!(matched, 1);
+        let blue = Color::rgb(0, 0, 255);
+        assert_eq!(styles, [(blue, 400), (blue, 700), (black, 700)]);
+
+        Node::set_attribute(Rc::clone(&p), "title", "unused").unwrap();
+        assert_eq!(layout(&mut cache).0, 0);
+    }
+
+    #[test]
+    fn test_animation() {
+        let html = r#"<html><head><style>
+            @keyframes fade { to { color: blue } }
+            p { animation: fade 1s linear }
+        </style></head><body><p>a<a>b</a></p></body></html>"#;
+        let window = parse(html);
+        let dom = window.borrow().document();
+        let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+        stylist.add_stylesheet(&user_agent_stylesheet(), Origin::UserAgent);
+        stylist.add_style_sheet_list(&StyleSheetLoader::new(None, None).load(dom.clone()));
+        let size = LayoutSize {
+            width: 800,
+            height: 600,
+        };
+        let mut cache = StyleCache::default();
+        let mut layout = |now| {
+            cache.update_animations(now);
+            let body = LayoutView::layout(Rc::clone(&dom), &stylist, size, &mut cache)
+                .root
+                .unwrap();
+            let p = body.borrow().first_child.clone().unwrap();
+            let a = p.borrow().first_child.clone().unwrap();
+            let a = a.borrow().next_sibling.clone().unwrap();
+            let colors = [&p, &a].map(|obj| obj.borrow().style.color);
+            (cache.matched_count(), colors, cache.is_animating())
+        };
+
+        let black = Color::rgb(0, 0, 0);
+        // html, body, p and a.
+        assert_eq!(layout(0.0), (4, [black; 2], true));
+        // Sampled at the new time without matching, and inherited by the descendants.
+        let navy = Color::rgb(0, 0, 128);
+        assert_eq!(layout(500.0), (0, [navy; 2], true));
+        // The animation has ended.
+        assert_eq!(layout(1000.0), (0, [black; 2], false));
+        assert_eq!(layout(1500.0), (0, [black; 2], false));
+    }
+
     fn create_layout_view(html: &str) -> LayoutView {
-        let t = HtmlTokenizer::new(html.into());
-        let window = HtmlParser::new(t).construct_tree();
+        let window = parse(html);
         let dom = window.borrow().document();
-        let style = get_style_content(dom.clone());
-        let cssom = parse_css_stylesheet(style);
-        LayoutView::layout(dom, &cssom)
+        let mut stylist = Stylist::new(Device::new(800.0, 600.0));
+        stylist.add_stylesheet(&user_agent_stylesheet(), Origin::UserAgent);
+        stylist.add_style_sheet_list(&StyleSheetLoader::new(None, None).load(dom.clone()));
+        LayoutView::layout(
+            dom,
+            &stylist,
+            LayoutSize {
+                width: 800,
+                height: 600,
+            },
+            &mut StyleCache::default(),
+        )
     }
 }
@@ -1,15 +1,20 @@
 //! <https://www.w3.org/TR/CSS2/visuren.html#inline-formatting>
 //! <https://developer.mozilla.org/en-US/docs/Web/CSS/CSS_display/Visual_formatting_model#line_boxes>
+//! <https://www.w3.org/TR/css-text-3/#line-breaking>
+//!
+//! Servo: <https://github.com/servo/servo/blob/main/components/layout_2020/flow/inline/line_breaker.rs>
 
-use crate::renderer::dom::node::{Element, ElementKind};
-use alloc::string::String;
+use core::mem;
+use core::ops::Range;
+
+use crate::renderer::font::matching::FontRegistry;
 use alloc::vec;
 use alloc::vec::Vec;
 
 use super::{
     box_tree::InlineBox,
-    computed_style::{ComputedStyle, DisplayType},
-    layout_view::LayoutView,
+    computed_style::{ComputedStyle, OverflowWrap, WhiteSpace, WordBreak},
+    line_break::{break_opportunities, BreakOpportunity, LineBreakRules},
 };
 
 /// A box containing contents within a single line.
@@ -18,14 +23,369 @@ pub struct LineBox {
     pub children: Vec<InlineBox>,
 }
 
-pub fn split_inline_box(tree: Vec<InlineBox>, max_width: i64) -> Vec<LineBox> {
-    // TODO
-    vec![LineBox { children: tree }]
+/// Breaks the inline boxes of a block into lines no wider than `max_width` where possible.
+///
+/// Text is broken at soft wrap opportunities, and an inline box spanning several lines
+/// is split into a fragment on each line, keeping its ancestors.
+pub fn split_inline_box(
+    tree: Vec<InlineBox>,
+    max_width: i64,
+    fonts: &FontRegistry,
+) -> Vec<LineBox> {
+    let mut leaves = Vec::new();
+    for inline in &tree {
+        collect_leaves(inline, &mut leaves);
+    }
+    let segments = segments(&leaves, fonts);
+    let mut lines = fill_lines(segments, max_width as f64, &leaves, fonts);
+    for line in &mut lines {
+        trim(line, &leaves);
+    }
+
+    lines
+        .iter()
+        .map(|line| {
+            let mut leaf = 0;
+            LineBox {
+                children: tree
+                    .iter()
+                    .filter_map(|inline| fragment(inline, &mut leaf, line))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// An inline box without children, which holds the text of an inline formatting context.
+struct Leaf<'a> {
+    style: &'a ComputedStyle,
+    text: Option<&'a str>,
+}
+
+fn collect_leaves<'a>(inline: &'a InlineBox, leaves: &mut Vec<Leaf<'a>>) {
+    if inline.children.is_empty() {
+        leaves.push(Leaf {
+            style: &inline.style,
+            text: inline.text.as_deref(),
+        });
+    } else {
+        for child in &inline.children {
+            collect_leaves(child, leaves);
+        }
+    }
+}
+
+/// A piece of the text of a leaf, which has no break opportunity but at its end.
+#[derive(Debug, Clone)]
+struct Segment {
+    leaf: usize,
+    /// The range of the text of the leaf, without a forced line break at the end.
+    range: Range<usize>,
+    width: f64,
+    /// The width of the spaces at the end, which hang over the end of a line.
+    ///
+    /// <https://www.w3.org/TR/css-text-3/#hanging>
+    hang: f64,
+    break_after: BreakOpportunity,
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n')
+}
+
+/// Whether the character is a forced line break, which is not rendered.
+fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// <https://www.w3.org/TR/css-text-3/#white-space-property>
+///
+/// Tailors the break opportunity after a character by `white-space`.
+fn tailor(
+    opportunity: BreakOpportunity,
+    c: char,
+    next: Option<char>,
+    white_space: WhiteSpace,
+) -> BreakOpportunity {
+    match opportunity {
+        BreakOpportunity::Mandatory => BreakOpportunity::Mandatory,
+        _ if !white_space.wraps() => BreakOpportunity::None,
+        // Every preserved space is followed by an opportunity with `break-spaces`.
+        _ if white_space == WhiteSpace::BreakSpaces && is_space(c) && next.is_some() => {
+            BreakOpportunity::Allowed
+        }
+        opportunity => opportunity,
+    }
+}
+
+fn segment(
+    index: usize,
+    leaf: &Leaf,
+    mut range: Range<usize>,
+    break_after: BreakOpportunity,
+    fonts: &FontRegistry,
+) -> Segment {
+    let text = leaf.text.unwrap_or_default();
+    if break_after == BreakOpportunity::Mandatory {
+        // The line break itself is not rendered.
+        range.end = range.start + text[range.clone()].trim_end_matches(is_line_break).len();
+    }
+    let text = &text[range.clone()];
+    let width = fonts.measure(leaf.style, text);
+    // Preserved spaces hang with `pre-wrap`, but are wrapped with `break-spaces`.
+    let hang = match leaf.style.white_space {
+        WhiteSpace::Normal | WhiteSpace::Nowrap | WhiteSpace::PreLine | WhiteSpace::PreWrap => {
+            width - fonts.measure(leaf.style, text.trim_end_matches(is_space))
+        }
+        WhiteSpace::Pre | WhiteSpace::BreakSpaces => 0.0,
+    };
+    Segment {
+        leaf: index,
+        range,
+        width,
+        hang,
+        break_after,
+    }
+}
+
+/// Splits the text of the leaves at the break opportunities.
+///
+/// The break opportunities are decided across the boxes, so that text in adjacent boxes
+/// is not broken if there is no space between them.
+fn segments(leaves: &[Leaf], fonts: &FontRegistry) -> Vec<Segment> {
+    let chars: Vec<(char, LineBreakRules)> = leaves
+        .iter()
+        .flat_map(|leaf| {
+            let rules = LineBreakRules {
+                line_break: leaf.style.line_break,
+                word_break: leaf.style.word_break,
+            };
+            let preserves_newlines = leaf.style.white_space.preserves_newlines();
+            leaf.text.unwrap_or_default().chars().map(move |c| match c {
+                // Newlines which are not preserved are spaces.
+                '\n' | '\r' if !preserves_newlines => (' ', rules),
+                c => (c, rules),
+            })
+        })
+        .collect();
+    let opportunities = break_opportunities(&chars);
+
+    let mut segments = Vec::new();
+    let mut k = 0;
+    for (i, leaf) in leaves.iter().enumerate() {
+        let text = leaf.text.unwrap_or_default();
+        if text.is_empty() {
+            segments.push(segment(i, leaf, 0..0, BreakOpportunity::None, fonts));
+            continue;
+        }
+        let mut start = 0;
+        for (j, c) in text.char_indices() {
+            let end = j + c.len_utf8();
+            let next = chars.get(k + 1).map(|&(c, _)| c);
+            let break_after = tailor(opportunities[k], chars[k].0, next, leaf.style.white_space);
+            k += 1;
+            if break_after != BreakOpportunity::None || end == text.len() {
+                segments.push(segment(i, leaf, start..end, break_after, fonts));
+                start = end;
+            }
+        }
+    }
+    segments
+}
+
+/// Whether the text may break between any characters if it overflows otherwise.
+///
+/// <https://www.w3.org/TR/css-text-3/#overflow-wrap-property>
+fn can_break_anywhere(style: &ComputedStyle) -> bool {
+    style.white_space.wraps()
+        && (style.overflow_wrap != OverflowWrap::Normal || style.word_break == WordBreak::BreakWord)
+}
+
+/// Splits the segments into words of a character each.
+fn split_characters(word: &[Segment], leaves: &[Leaf], fonts: &FontRegistry) -> Vec<Vec<Segment>> {
+    let mut characters = Vec::new();
+    for (i, s) in word.iter().enumerate() {
+        let leaf = &leaves[s.leaf];
+        let text = leaf.text.unwrap_or_default();
+        if s.range.is_empty() {
+            characters.push(vec![s.clone()]);
+            continue;
+        }
+        for (j, c) in text[s.range.clone()].char_indices() {
+            let start = s.range.start + j;
+            let end = start + c.len_utf8();
+            let last = i == word.len() - 1 && end == s.range.end;
+            let mut character = segment(s.leaf, leaf, start..end, BreakOpportunity::Allowed, fonts);
+            if last {
+                character.break_after = s.break_after;
+            }
+            characters.push(vec![character]);
+        }
+    }
+    characters
+}
+
+/// Places the segments on lines greedily.
+///
+/// The segments between break opportunities are placed together, which are moved to
+/// the next line if they overflow the line.
+fn fill_lines(
+    segments: Vec<Segment>,
+    max_width: f64,
+    leaves: &[Leaf],
+    fonts: &FontRegistry,
+) -> Vec<Vec<Segment>> {
+    let mut words: Vec<Vec<Segment>> = Vec::new();
+    let mut word = Vec::new();
+    for segment in segments {
+        let break_after = segment.break_after;
+        word.push(segment);
+        if break_after != BreakOpportunity::None {
+            words.push(mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let mut lines = Vec::new();
+    let mut line: Vec<Segment> = Vec::new();
+    let mut width = 0.0;
+    let mut i = 0;
+    while i < words.len() {
+        let word_width: f64 = words[i].iter().map(|s| s.width).sum();
+        let hang = words[i].last().map_or(0.0, |s| s.hang);
+        let overflows = word_width - hang > 0.0 && width + word_width - hang > max_width;
+        if overflows && !line.is_empty() {
+            lines.push(mem::take(&mut line));
+            width = 0.0;
+            continue;
+        }
+        if overflows
+            && words[i]
+                .iter()
+                .all(|s| can_break_anywhere(leaves[s.leaf].style))
+        {
+            // The word does not fit on a line by itself, so it is broken where it overflows.
+            let characters = split_characters(&words[i], leaves, fonts);
+            if characters.len() > 1 {
+                words.splice(i..=i, characters);
+                continue;
+            }
+        }
+
+        let word = mem::take(&mut words[i]);
+        let mandatory = word
+            .last()
+            .is_some_and(|s| s.break_after == BreakOpportunity::Mandatory);
+        width += word_width;
+        line.extend(word);
+        i += 1;
+        if mandatory {
+            lines.push(mem::take(&mut line));
+            width = 0.0;
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// <https://www.w3.org/TR/css-text-3/#white-space-phase-2>
+///
+/// Removes the collapsible spaces at the start and the end of a line, skipping the boxes
+/// without text, e.g. empty elements.
+fn trim(line: &mut Vec<Segment>, leaves: &[Leaf]) {
+    let mut i = 0;
+    while let Some(segment) = line.get_mut(i) {
+        let leaf = &leaves[segment.leaf];
+        let text = &leaf.text.unwrap_or_default()[segment.range.clone()];
+        if text.is_empty() {
+            i += 1;
+            continue;
+        }
+        if !leaf.style.white_space.collapses_spaces() {
+            break;
+        }
+        segment.range.start = segment.range.end - text.trim_start_matches(is_space).len();
+        if !segment.range.is_empty() {
+            break;
+        }
+        line.remove(i);
+    }
+
+    let mut i = line.len();
+    while i > 0 {
+        i -= 1;
+        let segment = &mut line[i];
+        let leaf = &leaves[segment.leaf];
+        let text = &leaf.text.unwrap_or_default()[segment.range.clone()];
+        if text.is_empty() {
+            continue;
+        }
+        if !leaf.style.white_space.collapses_spaces() {
+            break;
+        }
+        segment.range.end = segment.range.start + text.trim_end_matches(is_space).len();
+        if !segment.range.is_empty() {
+            break;
+        }
+        line.remove(i);
+    }
+}
+
+/// The fragment of the inline box on the line, or None if none of its text is on the line.
+///
+/// `leaf` is the index of the first leaf in the box, which is advanced past the box.
+fn fragment(inline: &InlineBox, leaf: &mut usize, line: &[Segment]) -> Option<InlineBox> {
+    if inline.children.is_empty() {
+        let index = *leaf;
+        *leaf += 1;
+        let mut ranges = line
+            .iter()
+            .filter(|s| s.leaf == index)
+            .map(|s| s.range.clone());
+        let first = ranges.next()?;
+        let end = ranges.last().map_or(first.end, |range| range.end);
+        return Some(InlineBox {
+            data: inline.data.clone(),
+            style: inline.style.clone(),
+            text: inline
+                .text
+                .as_ref()
+                .map(|text| text[first.start..end].into()),
+            children: Vec::new(),
+            node: inline.node.clone(),
+        });
+    }
+
+    let children: Vec<InlineBox> = inline
+        .children
+        .iter()
+        .filter_map(|child| fragment(child, leaf, line))
+        .collect();
+    if children.is_empty() {
+        return None;
+    }
+    Some(InlineBox {
+        data: inline.data.clone(),
+        style: inline.style.clone(),
+        text: inline.text.clone(),
+        children,
+        node: inline.node.clone(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::renderer::layout::box_tree::InlineBoxData;
+    use crate::renderer::dom::node::{Element, ElementKind};
+    use crate::renderer::layout::box_tree::{BoxNode, InlineBoxData};
+    use crate::renderer::layout::computed_style::DisplayType;
+    use alloc::string::String;
     use pretty_assertions::assert_eq;
 
     use super::*;
@@ -35,10 +395,12 @@ mod tests {
         let tree = vec![InlineBox {
             data: InlineBoxData::Anonymous,
             style: ComputedStyle {
-                display: Some(DisplayType::Inline),
+                display: DisplayType::Inline,
+                ..Default::default()
             },
             text: Some("text text text text text text".into()),
             children: Vec::new(),
+            node: BoxNode::default(),
         }];
 
         let expected = vec![
@@ -46,25 +408,184 @@ mod tests {
                 children: vec![InlineBox {
                     data: InlineBoxData::Anonymous,
                     style: ComputedStyle {
-                        display: Some(DisplayType::Inline),
+                        display: DisplayType::Inline,
+                        ..Default::default()
                     },
                     text: Some("text text text text".into()),
                     children: Vec::new(),
+                    node: BoxNode::default(),
                 }],
             },
             LineBox {
                 children: vec![InlineBox {
                     data: InlineBoxData::Anonymous,
                     style: ComputedStyle {
-                        display: Some(DisplayType::Inline),
+                        display: DisplayType::Inline,
+                        ..Default::default()
                     },
                     text: Some("text text".into()),
                     children: Vec::new(),
+                    node: BoxNode::default(),
                 }],
             },
         ];
 
-        let actual = split_inline_box(tree, 8 * (5 * 4 + 1));
+        let actual = split_inline_box(tree, 8 * (5 * 4 + 1), &FontRegistry::default());
         assert_eq!(expected, actual);
     }
+
+    fn text(text: &str, style: &ComputedStyle) -> InlineBox {
+        InlineBox {
+            data: InlineBoxData::Anonymous,
+            style: style.clone(),
+            text: Some(text.into()),
+            children: Vec::new(),
+            node: BoxNode::default(),
+        }
+    }
+
+    /// The texts of the leaves on each line, with the path of the element fragments.
+    fn lines(tree: Vec<InlineBox>, max_chars: i64) -> Vec<Vec<String>> {
+        fn texts(inline: &InlineBox, prefix: &str, result: &mut Vec<String>) {
+            let prefix = match &inline.data {
+                InlineBoxData::Element(_) => alloc::format!("{prefix}a>"),
+                InlineBoxData::Anonymous => prefix.into(),
+            };
+            match &inline.text {
+                Some(text) => result.push(alloc::format!("{prefix}{text}")),
+                None => {
+                    for child in &inline.children {
+                        texts(child, &prefix, result);
+                    }
+                }
+            }
+        }
+        split_inline_box(tree, 8 * max_chars, &FontRegistry::default())
+            .iter()
+            .map(|line| {
+                let mut result = Vec::new();
+                for inline in &line.children {
+                    texts(inline, "", &mut result);
+                }
+                result
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_boxes() {
+        let style = ComputedStyle::default();
+        let a = InlineBox {
+            data: InlineBoxData::Element(Element::new(ElementKind::A)),
+            style: style.clone(),
+            text: None,
+            children: vec![text("bb cc", &style), text("dd", &style)],
+            node: BoxNode::default(),
+        };
+        // No break between the boxes without a space between them, and the spaces at the end
+        // of the lines are removed.
+        assert_eq!(
+            lines(vec![text("x aa", &style), a, text("ee ff", &style)], 5),
+            vec![
+                vec!["x"],
+                vec!["aa", "a>bb"],
+                vec!["a>cc", "a>dd", "ee"],
+                vec!["ff"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_white_space() {
+        // Collapsible spaces at the start and the end of a line are removed, even in other boxes.
+        let style = ComputedStyle::default();
+        assert_eq!(
+            lines(vec![text("  ", &style), text(" aa ", &style)], 10),
+            vec![vec!["aa"]]
+        );
+
+        let nowrap = ComputedStyle {
+            white_space: WhiteSpace::Nowrap,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(vec![text("aa bb cc", &nowrap)], 4),
+            vec![vec!["aa bb cc"]]
+        );
+
+        let pre = ComputedStyle {
+            white_space: WhiteSpace::Pre,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(vec![text("aa  bb\n\ncc ", &pre)], 4),
+            vec![vec!["aa  bb"], vec![""], vec!["cc "]]
+        );
+
+        // Spaces hang at the end of a line with `pre-wrap`.
+        let pre_wrap = ComputedStyle {
+            white_space: WhiteSpace::PreWrap,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(vec![text("aa   bb", &pre_wrap)], 3),
+            vec![vec!["aa   "], vec!["bb"]]
+        );
+        // But wrap with `break-spaces`.
+        let break_spaces = ComputedStyle {
+            white_space: WhiteSpace::BreakSpaces,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(vec![text("aa   bb", &break_spaces)], 3),
+            vec![vec!["aa "], vec!["  "], vec!["bb"]]
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        // A long word overflows by default.
+        let style = ComputedStyle::default();
+        assert_eq!(
+            lines(vec![text("a abcdefg b", &style)], 4),
+            vec![vec!["a"], vec!["abcdefg"], vec!["b"]]
+        );
+
+        let anywhere = ComputedStyle {
+            overflow_wrap: OverflowWrap::Anywhere,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(vec![text("a abcdefg b", &anywhere)], 4),
+            vec![vec!["a"], vec!["abcd"], vec!["efg"], vec!["b"]]
+        );
+
+        let break_all = ComputedStyle {
+            word_break: WordBreak::BreakAll,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(vec![text("a abcdefg b", &break_all)], 4),
+            vec![vec!["a ab"], vec!["cdef"], vec!["g b"]]
+        );
+    }
+
+    #[test]
+    fn test_japanese() {
+        // Japanese text breaks between characters, but no line starts with `、`.
+        let style = ComputedStyle::default();
+        assert_eq!(
+            lines(vec![text("あいうえお、かきく", &style)], 5),
+            vec![vec!["あいうえ"], vec!["お、かきく"]]
+        );
+
+        let keep_all = ComputedStyle {
+            word_break: WordBreak::KeepAll,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(vec![text("あいう、えおか", &keep_all)], 5),
+            vec![vec!["あいう、"], vec!["えおか"]]
+        );
+    }
 }
@@ -0,0 +1,749 @@
+//! <https://www.unicode.org/reports/tr14/>
+//!
+//! The Unicode line breaking algorithm, tailored by the `line-break` and `word-break` properties.
+//! The line breaking classes are approximated with the ranges of the common scripts and
+//! punctuation, which are enough for European and CJK text.
+//!
+//! Servo: <https://github.com/servo/servo/blob/main/components/layout_2020/flow/text_run.rs>
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::computed_style::{LineBreak, WordBreak};
+
+/// Whether a line may or must break after a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakOpportunity {
+    None,
+    /// <https://www.w3.org/TR/css-text-3/#soft-wrap-opportunity>
+    Allowed,
+    /// <https://www.w3.org/TR/css-text-3/#forced-line-break>
+    Mandatory,
+}
+
+/// The properties of the element a character is in, which tailor the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineBreakRules {
+    pub line_break: LineBreak,
+    pub word_break: WordBreak,
+}
+
+/// <https://www.unicode.org/reports/tr14/#Table1>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+enum Class {
+    /// Mandatory break.
+    BK,
+    CR,
+    LF,
+    /// Combining mark.
+    CM,
+    /// Next line.
+    NL,
+    /// Word joiner.
+    WJ,
+    /// Zero width space.
+    ZW,
+    /// Non-breaking ("glue").
+    GL,
+    SP,
+    ZWJ,
+    /// Break opportunity before and after.
+    B2,
+    /// Break after.
+    BA,
+    /// Break before.
+    BB,
+    /// Hyphen.
+    HY,
+    /// Contingent break.
+    CB,
+    /// Close punctuation.
+    CL,
+    /// Close parenthesis.
+    CP,
+    /// Exclamation or interrogation.
+    EX,
+    /// Inseparable.
+    IN,
+    /// Nonstarter.
+    NS,
+    /// Open punctuation.
+    OP,
+    /// Quotation.
+    QU,
+    /// Infix numeric separator.
+    IS,
+    NU,
+    /// Postfix numeric.
+    PO,
+    /// Prefix numeric.
+    PR,
+    /// Symbols allowing break after.
+    SY,
+    /// Ambiguous, which is resolved to AL.
+    AI,
+    /// Alphabetic.
+    AL,
+    /// Conditional Japanese starter, i.e. small kana and the prolonged sound mark.
+    CJ,
+    /// Emoji base and modifier.
+    EB,
+    EM,
+    /// Hangul syllables.
+    H2,
+    H3,
+    /// Hebrew letter.
+    HL,
+    /// Ideographic.
+    ID,
+    /// Hangul jamo.
+    JL,
+    JV,
+    JT,
+    /// Regional indicator.
+    RI,
+    /// Complex context, e.g. Thai, which is resolved to AL without a dictionary.
+    SA,
+}
+
+use Class::*;
+
+/// The classes of the ranges of code points, sorted. The others are AL.
+const CLASSES: &[(u32, u32, Class)] = &[
+    (0x00, 0x08, CM),
+    (0x09, 0x09, BA),
+    (0x0A, 0x0A, LF),
+    (0x0B, 0x0C, BK),
+    (0x0D, 0x0D, CR),
+    (0x0E, 0x1F, CM),
+    (0x20, 0x20, SP),
+    (0x21, 0x21, EX),
+    (0x22, 0x22, QU),
+    (0x24, 0x24, PR),
+    (0x25, 0x25, PO),
+    (0x27, 0x27, QU),
+    (0x28, 0x28, OP),
+    (0x29, 0x29, CP),
+    (0x2B, 0x2B, PR),
+    (0x2C, 0x2C, IS),
+    (0x2D, 0x2D, HY),
+    (0x2E, 0x2E, IS),
+    (0x2F, 0x2F, SY),
+    (0x30, 0x39, NU),
+    (0x3A, 0x3B, IS),
+    (0x3F, 0x3F, EX),
+    (0x5B, 0x5B, OP),
+    (0x5C, 0x5C, PR),
+    (0x5D, 0x5D, CP),
+    (0x7B, 0x7B, OP),
+    (0x7C, 0x7C, BA),
+    (0x7D, 0x7D, CL),
+    (0x7F, 0x84, CM),
+    (0x85, 0x85, NL),
+    (0x86, 0x9F, CM),
+    (0xA0, 0xA0, GL),
+    (0xA1, 0xA1, OP),
+    (0xA2, 0xA2, PO),
+    (0xA3, 0xA5, PR),
+    (0xA7, 0xA8, AI),
+    (0xAB, 0xAB, QU),
+    (0xAD, 0xAD, BA),
+    (0xB0, 0xB0, PO),
+    (0xB1, 0xB1, PR),
+    (0xB4, 0xB4, BB),
+    (0xBB, 0xBB, QU),
+    (0xBF, 0xBF, OP),
+    (0x0300, 0x036F, CM),
+    (0x0483, 0x0489, CM),
+    (0x0591, 0x05BD, CM),
+    (0x05D0, 0x05EA, HL),
+    (0x0610, 0x061A, CM),
+    (0x064B, 0x065F, CM),
+    (0x0E00, 0x0E7F, SA),
+    (0x0E80, 0x0EFF, SA),
+    (0x1000, 0x109F, SA),
+    (0x1100, 0x115F, JL),
+    (0x1160, 0x11A7, JV),
+    (0x11A8, 0x11FF, JT),
+    (0x1780, 0x17FF, SA),
+    (0x2000, 0x2006, BA),
+    (0x2007, 0x2007, GL),
+    (0x2008, 0x200A, BA),
+    (0x200B, 0x200B, ZW),
+    (0x200C, 0x200C, CM),
+    (0x200D, 0x200D, ZWJ),
+    (0x200E, 0x200F, CM),
+    (0x2010, 0x2010, BA),
+    (0x2011, 0x2011, GL),
+    (0x2012, 0x2013, BA),
+    (0x2014, 0x2014, B2),
+    (0x2015, 0x2016, AI),
+    (0x2018, 0x2019, QU),
+    (0x201A, 0x201A, OP),
+    (0x201B, 0x201D, QU),
+    (0x201E, 0x201E, OP),
+    (0x201F, 0x201F, QU),
+    (0x2020, 0x2021, AI),
+    (0x2024, 0x2026, IN),
+    (0x2027, 0x2027, BA),
+    (0x2028, 0x2029, BK),
+    (0x202A, 0x202E, CM),
+    (0x202F, 0x202F, GL),
+    (0x2030, 0x2037, PO),
+    (0x2039, 0x203A, QU),
+    (0x203C, 0x203D, NS),
+    (0x2044, 0x2044, IS),
+    (0x2047, 0x2049, NS),
+    (0x2060, 0x2060, WJ),
+    (0x20A0, 0x20CF, PR),
+    (0x20D0, 0x20FF, CM),
+    (0x2103, 0x2103, PO),
+    (0x2E80, 0x2FFF, ID),
+    (0x3000, 0x3000, BA),
+    (0x3001, 0x3002, CL),
+    (0x3003, 0x3004, ID),
+    (0x3005, 0x3005, NS),
+    (0x3006, 0x3007, ID),
+    (0x3008, 0x3008, OP),
+    (0x3009, 0x3009, CL),
+    (0x300A, 0x300A, OP),
+    (0x300B, 0x300B, CL),
+    (0x300C, 0x300C, OP),
+    (0x300D, 0x300D, CL),
+    (0x300E, 0x300E, OP),
+    (0x300F, 0x300F, CL),
+    (0x3010, 0x3010, OP),
+    (0x3011, 0x3011, CL),
+    (0x3012, 0x3013, ID),
+    (0x3014, 0x3014, OP),
+    (0x3015, 0x3015, CL),
+    (0x3016, 0x3016, OP),
+    (0x3017, 0x3017, CL),
+    (0x3018, 0x3018, OP),
+    (0x3019, 0x3019, CL),
+    (0x301A, 0x301A, OP),
+    (0x301B, 0x301B, CL),
+    (0x301C, 0x301C, NS),
+    (0x301D, 0x301D, OP),
+    (0x301E, 0x301F, CL),
+    (0x3020, 0x3029, ID),
+    (0x302A, 0x302F, CM),
+    (0x3030, 0x303A, ID),
+    (0x303B, 0x303C, NS),
+    (0x303D, 0x303F, ID),
+    (0x3041, 0x3041, CJ),
+    (0x3042, 0x3042, ID),
+    (0x3043, 0x3043, CJ),
+    (0x3044, 0x3044, ID),
+    (0x3045, 0x3045, CJ),
+    (0x3046, 0x3046, ID),
+    (0x3047, 0x3047, CJ),
+    (0x3048, 0x3048, ID),
+    (0x3049, 0x3049, CJ),
+    (0x304A, 0x3062, ID),
+    (0x3063, 0x3063, CJ),
+    (0x3064, 0x3082, ID),
+    (0x3083, 0x3083, CJ),
+    (0x3084, 0x3084, ID),
+    (0x3085, 0x3085, CJ),
+    (0x3086, 0x3086, ID),
+    (0x3087, 0x3087, CJ),
+    (0x3088, 0x308D, ID),
+    (0x308E, 0x308E, CJ),
+    (0x308F, 0x3094, ID),
+    (0x3095, 0x3096, CJ),
+    (0x3099, 0x309A, CM),
+    (0x309B, 0x309E, NS),
+    (0x309F, 0x309F, ID),
+    (0x30A0, 0x30A0, NS),
+    (0x30A1, 0x30A1, CJ),
+    (0x30A2, 0x30A2, ID),
+    (0x30A3, 0x30A3, CJ),
+    (0x30A4, 0x30A4, ID),
+    (0x30A5, 0x30A5, CJ),
+    (0x30A6, 0x30A6, ID),
+    (0x30A7, 0x30A7, CJ),
+    (0x30A8, 0x30A8, ID),
+    (0x30A9, 0x30A9, CJ),
+    (0x30AA, 0x30C2, ID),
+    (0x30C3, 0x30C3, CJ),
+    (0x30C4, 0x30E2, ID),
+    (0x30E3, 0x30E3, CJ),
+    (0x30E4, 0x30E4, ID),
+    (0x30E5, 0x30E5, CJ),
+    (0x30E6, 0x30E6, ID),
+    (0x30E7, 0x30E7, CJ),
+    (0x30E8, 0x30ED, ID),
+    (0x30EE, 0x30EE, CJ),
+    (0x30EF, 0x30F4, ID),
+    (0x30F5, 0x30F6, CJ),
+    (0x30F7, 0x30FA, ID),
+    (0x30FB, 0x30FB, NS),
+    (0x30FC, 0x30FC, CJ),
+    (0x30FD, 0x30FE, NS),
+    (0x30FF, 0x31EF, ID),
+    (0x31F0, 0x31FF, CJ),
+    (0x3200, 0x4DBF, ID),
+    (0x4E00, 0xA4CF, ID),
+    (0xF900, 0xFAFF, ID),
+    (0xFE00, 0xFE0F, CM),
+    (0xFE20, 0xFE2F, CM),
+    (0xFE30, 0xFE4F, ID),
+    (0xFEFF, 0xFEFF, WJ),
+    (0xFF01, 0xFF01, EX),
+    (0xFF02, 0xFF03, ID),
+    (0xFF04, 0xFF04, PR),
+    (0xFF05, 0xFF05, PO),
+    (0xFF06, 0xFF07, ID),
+    (0xFF08, 0xFF08, OP),
+    (0xFF09, 0xFF09, CL),
+    (0xFF0A, 0xFF0B, ID),
+    (0xFF0C, 0xFF0C, CL),
+    (0xFF0D, 0xFF0D, ID),
+    (0xFF0E, 0xFF0E, CL),
+    (0xFF0F, 0xFF19, ID),
+    (0xFF1A, 0xFF1B, NS),
+    (0xFF1C, 0xFF1E, ID),
+    (0xFF1F, 0xFF1F, EX),
+    (0xFF20, 0xFF3A, ID),
+    (0xFF3B, 0xFF3B, OP),
+    (0xFF3C, 0xFF3C, ID),
+    (0xFF3D, 0xFF3D, CL),
+    (0xFF3E, 0xFF5A, ID),
+    (0xFF5B, 0xFF5B, OP),
+    (0xFF5C, 0xFF5C, ID),
+    (0xFF5D, 0xFF5D, CL),
+    (0xFF5E, 0xFF5E, ID),
+    (0xFF5F, 0xFF5F, OP),
+    (0xFF60, 0xFF61, CL),
+    (0xFF62, 0xFF62, OP),
+    (0xFF63, 0xFF64, CL),
+    (0xFF65, 0xFF65, NS),
+    (0xFF67, 0xFF70, CJ),
+    (0xFF9E, 0xFF9F, NS),
+    (0xFFE0, 0xFFE0, PO),
+    (0xFFE1, 0xFFE1, PR),
+    (0xFFE2, 0xFFE4, ID),
+    (0xFFE5, 0xFFE6, PR),
+    (0x1F1E6, 0x1F1FF, RI),
+    (0x1F300, 0x1F3FA, ID),
+    (0x1F3FB, 0x1F3FF, EM),
+    (0x1F400, 0x1F441, ID),
+    (0x1F442, 0x1F443, EB),
+    (0x1F444, 0x1F445, ID),
+    (0x1F446, 0x1F450, EB),
+    (0x1F451, 0x1F5FF, ID),
+    (0x1F600, 0x1F644, ID),
+    (0x1F645, 0x1F647, EB),
+    (0x1F648, 0x1F64A, ID),
+    (0x1F64B, 0x1F64F, EB),
+    (0x1F680, 0x1F6FF, ID),
+    (0x1F900, 0x1F9FF, ID),
+    (0x20000, 0x3FFFD, ID),
+    (0xE0001, 0xE007F, CM),
+    (0xE0100, 0xE01EF, CM),
+];
+
+/// <https://www.unicode.org/reports/tr14/#Properties>
+fn class(c: char) -> Class {
+    let code = c as u32;
+    // Hangul syllables are LV (H2) at every 28th code point, and LVT (H3) otherwise.
+    if (0xAC00..=0xD7A3).contains(&code) {
+        return if (code - 0xAC00) % 28 == 0 { H2 } else { H3 };
+    }
+    match CLASSES.binary_search_by(|&(start, end, _)| {
+        if end < code {
+            core::cmp::Ordering::Less
+        } else if start > code {
+            core::cmp::Ordering::Greater
+        } else {
+            core::cmp::Ordering::Equal
+        }
+    }) {
+        Ok(i) => CLASSES[i].2,
+        Err(_) => AL,
+    }
+}
+
+/// Resolves the class of the character for the rules.
+///
+/// - <https://www.unicode.org/reports/tr14/#LB1>
+/// - <https://www.w3.org/TR/css-text-3/#line-break-property>
+/// - <https://www.w3.org/TR/css-text-3/#word-break-property>
+fn resolve(c: char, rules: LineBreakRules) -> Class {
+    let loose = rules.line_break == LineBreak::Loose;
+    let class = match class(c) {
+        AI | SA => AL,
+        // Breaks before small kana and the prolonged sound mark are allowed but with `strict`.
+        CJ if rules.line_break == LineBreak::Strict => NS,
+        CJ => ID,
+        // Breaks before the CJK hyphens are allowed but with `strict`.
+        NS if matches!(c, '\u{301C}' | '\u{30A0}') && rules.line_break != LineBreak::Strict => ID,
+        // `loose` also allows breaks before iteration marks, centered punctuation,
+        // inseparable characters and fullwidth postfixes, and after fullwidth prefixes.
+        NS | EX | IN
+            if loose
+                && matches!(
+                    c,
+                    '\u{3005}'
+                        | '\u{303B}'
+                        | '\u{309D}'
+                        | '\u{309E}'
+                        | '\u{30FD}'
+                        | '\u{30FE}'
+                        | '\u{30FB}'
+                        | '\u{FF1A}'
+                        | '\u{FF1B}'
+                        | '\u{FF65}'
+                        | '\u{203C}'
+                        | '\u{2047}'
+                        | '\u{2048}'
+                        | '\u{2049}'
+                        | '\u{FF01}'
+                        | '\u{FF1F}'
+                        | '\u{2024}'
+                        | '\u{2025}'
+                        | '\u{2026}'
+                ) =>
+        {
+            ID
+        }
+        PO | PR
+            if loose
+                && matches!(
+                    c,
+                    '\u{00B0}'
+                        | '\u{2030}'
+                        | '\u{2032}'
+                        | '\u{2033}'
+                        | '\u{2103}'
+                        | '\u{FF05}'
+                        | '\u{FFE0}'
+                        | '\u{FF04}'
+                        | '\u{FFE1}'
+                        | '\u{FFE5}'
+                        | '\u{FFE6}'
+                ) =>
+        {
+            ID
+        }
+        class => class,
+    };
+    match (rules.word_break, class) {
+        // Letters and numbers break like ideographs.
+        (WordBreak::BreakAll, AL | HL | NU) => ID,
+        // Ideographs and Hangul do not break between themselves, like letters.
+        (WordBreak::KeepAll, ID | H2 | H3 | JL | JV | JT) => AL,
+        (_, class) => class,
+    }
+}
+
+/// Returns whether a line may or must break after each character of the text.
+///
+/// The characters are paired with the rules of their elements, and the rules of the character
+/// before a break decide whether it is allowed.
+pub fn break_opportunities(text: &[(char, LineBreakRules)]) -> Vec<BreakOpportunity> {
+    let classes: Vec<Class> = text.iter().map(|&(c, rules)| resolve(c, rules)).collect();
+    let mut result = vec![BreakOpportunity::None; text.len()];
+    let Some(&first) = classes.first() else {
+        return result;
+    };
+
+    // The class of the previous character, where combining marks take the class of their base.
+    // <https://www.unicode.org/reports/tr14/#LB9>
+    let mut before = match first {
+        CM | ZWJ => AL,
+        class => class,
+    };
+    // The class before `before`, and the class before the spaces ending at `before`.
+    let mut before_before = before;
+    let mut before_spaces = before;
+    // The number of regional indicators in a row ending at `before`.
+    let mut regional_indicators = usize::from(before == RI);
+
+    for i in 1..text.len() {
+        let raw = classes[i - 1];
+        let mut after = classes[i];
+        let rules = text[i - 1].1;
+        // LB4, LB5: Always break after hard line breaks, but not between CR and LF.
+        let mandatory = match raw {
+            BK | LF | NL => true,
+            CR => after != LF,
+            _ => false,
+        };
+        if mandatory {
+            result[i - 1] = BreakOpportunity::Mandatory;
+        } else if matches!(after, BK | CR | LF | NL) {
+            // LB6: Do not break before hard line breaks.
+        } else if matches!(after, CM | ZWJ) && !matches!(before, BK | CR | LF | NL | SP | ZW) {
+            // LB9: Do not break a combining character sequence, which is treated as its base.
+            continue;
+        } else {
+            if matches!(after, CM | ZWJ) {
+                // LB10: Treat any remaining combining mark as AL.
+                after = AL;
+            }
+            let allowed = if rules.line_break == LineBreak::Anywhere {
+                // <https://www.w3.org/TR/css-text-3/#valdef-line-break-anywhere>
+                true
+            } else {
+                allows_break(
+                    raw,
+                    before,
+                    before_before,
+                    before_spaces,
+                    after,
+                    text[i].0,
+                    regional_indicators,
+                )
+            };
+            if allowed {
+                result[i - 1] = BreakOpportunity::Allowed;
+            }
+        }
+
+        if after == CM || after == ZWJ {
+            after = AL;
+        }
+        before_before = before;
+        before = after;
+        if after != SP {
+            before_spaces = after;
+        }
+        regional_indicators = if after == RI {
+            regional_indicators + 1
+        } else {
+            0
+        };
+    }
+
+    // LB3: Always break at the end of text, which is mandatory after a hard line break.
+    let last = text.len() - 1;
+    if matches!(classes[last], BK | CR | LF | NL) {
+        result[last] = BreakOpportunity::Mandatory;
+    }
+    result
+}
+
+/// The rules LB7 to LB31 for the break between `before` and `after`.
+///
+/// `raw` is the class of the character before the break, which is ZWJ after a joiner.
+fn allows_break(
+    raw: Class,
+    before: Class,
+    before_before: Class,
+    before_spaces: Class,
+    after: Class,
+    next: char,
+    regional_indicators: usize,
+) -> bool {
+    let after_spaces = |class: Class| before == class || (before == SP && before_spaces == class);
+    // LB7: Do not break before spaces or zero width space.
+    if matches!(after, SP | ZW) {
+        return false;
+    }
+    // LB8: Break before any character following a zero-width space, even if spaces intervene.
+    if after_spaces(ZW) {
+        return true;
+    }
+    // LB8a: Do not break after a zero width joiner.
+    if raw == ZWJ {
+        return false;
+    }
+    // LB11: Do not break before or after word joiner.
+    if after == WJ || before == WJ {
+        return false;
+    }
+    // LB12, LB12a: Do not break after or before non-breaking characters.
+    if before == GL || (after == GL && !matches!(before, SP | BA | HY)) {
+        return false;
+    }
+    // LB13: Do not break before closing punctuation, even after spaces.
+    // This prohibits lines starting with e.g. `、。」`.
+    if matches!(after, CL | CP | EX | IS | SY) {
+        return false;
+    }
+    // LB14: Do not break after opening punctuation, even after spaces.
+    if after_spaces(OP) {
+        return false;
+    }
+    // LB15: Do not break within quotation and opening punctuation.
+    if after == OP && after_spaces(QU) {
+        return false;
+    }
+    // LB16: Do not break between closing punctuation and a nonstarter.
+    if after == NS && (after_spaces(CL) || after_spaces(CP)) {
+        return false;
+    }
+    // LB17: Do not break within B2 pairs.
+    if after == B2 && after_spaces(B2) {
+        return false;
+    }
+    // LB18: Break after spaces.
+    if before == SP {
+        return true;
+    }
+    // LB19: Do not break before or after quotation marks.
+    if after == QU || before == QU {
+        return false;
+    }
+    // LB20: Break before and after contingent breaks.
+    if after == CB || before == CB {
+        return true;
+    }
+    // LB21, LB21a, LB21b: Do not break before hyphens and nonstarters, nor after break-before
+    // characters, a Hebrew hyphen nor a symbol before a Hebrew letter.
+    if matches!(after, BA | HY | NS) || before == BB {
+        return false;
+    }
+    if matches!(before, HY | BA) && before_before == HL {
+        return false;
+    }
+    if before == SY && after == HL {
+        return false;
+    }
+    // LB22: Do not break before ellipses.
+    if after == IN {
+        return false;
+    }
+    // LB23, LB23a, LB24: Do not break within letters, numbers and their prefixes and postfixes.
+    let letter = |class: Class| matches!(class, AL | HL);
+    if (letter(before) && after == NU) || (before == NU && letter(after)) {
+        return false;
+    }
+    if (before == PR && matches!(after, ID | EB | EM))
+        || (matches!(before, ID | EB | EM) && after == PO)
+    {
+        return false;
+    }
+    if (matches!(before, PR | PO) && letter(after)) || (letter(before) && matches!(after, PR | PO))
+    {
+        return false;
+    }
+    // LB25: Do not break within numbers, e.g. `$(12.35)`.
+    if matches!(
+        (before, after),
+        (CL | CP | NU, PO | PR) | (PO | PR, OP | NU) | (HY | IS | NU | SY, NU)
+    ) {
+        return false;
+    }
+    // LB26, LB27: Do not break within Korean syllable blocks.
+    let hangul = |class: Class| matches!(class, JL | JV | JT | H2 | H3);
+    if matches!(
+        (before, after),
+        (JL, JL | JV | H2 | H3) | (JV | H2, JV | JT) | (JT | H3, JT)
+    ) || (hangul(before) && after == PO)
+        || (before == PR && hangul(after))
+    {
+        return false;
+    }
+    // LB28, LB29: Do not break between letters, nor after infix separators before letters.
+    if (letter(before) || before == IS) && letter(after) {
+        return false;
+    }
+    // LB30: Do not break between letters or numbers and non East Asian parentheses.
+    let east_asian = (next as u32) >= 0x2E80;
+    if (letter(before) || before == NU) && after == OP && !east_asian {
+        return false;
+    }
+    if before == CP && (letter(after) || after == NU) {
+        return false;
+    }
+    // LB30a: Break between pairs of regional indicators.
+    if before == RI && after == RI && regional_indicators % 2 == 1 {
+        return false;
+    }
+    // LB30b: Do not break between emoji bases and modifiers.
+    if before == EB && after == EM {
+        return false;
+    }
+    // LB31: Break everywhere else.
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::*;
+
+    const NORMAL: LineBreakRules = LineBreakRules {
+        line_break: LineBreak::Auto,
+        word_break: WordBreak::Normal,
+    };
+
+    /// Marks the allowed breaks with `|` and the mandatory ones with `!`.
+    fn breaks(text: &str, rules: LineBreakRules) -> String {
+        let chars: Vec<(char, LineBreakRules)> = text.chars().map(|c| (c, rules)).collect();
+        let mut result = String::new();
+        for ((c, _), opportunity) in chars.iter().zip(break_opportunities(&chars)) {
+            result.push(*c);
+            match opportunity {
+                BreakOpportunity::None => {}
+                BreakOpportunity::Allowed => result.push('|'),
+                BreakOpportunity::Mandatory => result.push('!'),
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_latin() {
+        assert_eq!(breaks("Hello,  world!", NORMAL), "Hello,  |world!");
+        assert_eq!(breaks("well-known", NORMAL), "well-|known");
+        assert_eq!(breaks("($1,000.50) (a)", NORMAL), "($1,000.50) |(a)");
+        assert_eq!(breaks("\"quoted\" text", NORMAL), "\"quoted\" |text");
+        assert_eq!(breaks("a\r\nb\nc", NORMAL), "a\r\n!b\n!c");
+        assert_eq!(breaks("no\u{00A0}break", NORMAL), "no\u{00A0}break");
+        // A combining mark is not separated from its base.
+        assert_eq!(breaks("e\u{0301} x", NORMAL), "e\u{0301} |x");
+    }
+
+    #[test]
+    fn test_japanese() {
+        // No line starts with closing punctuation, nor ends with opening punctuation.
+        assert_eq!(breaks("「日本語」です。", NORMAL), "「日|本|語」|で|す。");
+        assert_eq!(breaks("漢字、かな", NORMAL), "漢|字、|か|な");
+        // Latin words in Japanese text are not broken.
+        assert_eq!(breaks("私はRustが好き", NORMAL), "私|は|Rust|が|好|き");
+
+        // Small kana start a line but with `strict`.
+        assert_eq!(breaks("ちょっと", NORMAL), "ち|ょ|っ|と");
+        let strict = LineBreakRules {
+            line_break: LineBreak::Strict,
+            ..NORMAL
+        };
+        assert_eq!(breaks("ちょっと", strict), "ちょっ|と");
+        // Iteration marks start a line only with `loose`.
+        assert_eq!(breaks("人々", NORMAL), "人々");
+        let loose = LineBreakRules {
+            line_break: LineBreak::Loose,
+            ..NORMAL
+        };
+        assert_eq!(breaks("人々", loose), "人|々");
+        // Except for hard breaks, `anywhere` breaks between every character.
+        let anywhere = LineBreakRules {
+            line_break: LineBreak::Anywhere,
+            ..NORMAL
+        };
+        assert_eq!(breaks("語。a", anywhere), "語|。|a");
+    }
+
+    #[test]
+    fn test_word_break() {
+        let keep_all = LineBreakRules {
+            word_break: WordBreak::KeepAll,
+            ..NORMAL
+        };
+        assert_eq!(breaks("日本語、です", keep_all), "日本語、|です");
+        assert_eq!(breaks("한국어 문장", keep_all), "한국어 |문장");
+        assert_eq!(breaks("한국어", NORMAL), "한|국|어");
+
+        let break_all = LineBreakRules {
+            word_break: WordBreak::BreakAll,
+            ..NORMAL
+        };
+        assert_eq!(breaks("abc, 12", break_all), "a|b|c, |1|2");
+    }
+}
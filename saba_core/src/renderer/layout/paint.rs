@@ -12,7 +12,13 @@ pub fn paint(tree: PositionedBlockBox) -> Vec<DisplayItem> {
     paint_block(tree, &LayoutPoint { x: 0, y: 0 })
 }
 
+/// `left_top` is the position of the margin box.
 fn paint_block(tree: PositionedBlockBox, left_top: &LayoutPoint) -> Vec<DisplayItem> {
+    // The content box is inside the margin, border and padding.
+    let left_top = &LayoutPoint {
+        x: left_top.x + tree.margin.left + tree.border.left + tree.padding.left,
+        y: left_top.y + tree.margin.top + tree.border.top + tree.padding.top,
+    };
     match tree.children {
         PositionedBlockBoxChildren::Empty => Vec::new(),
         PositionedBlockBoxChildren::Blocks(blocks) => {
@@ -20,7 +26,7 @@ fn paint_block(tree: PositionedBlockBox, left_top: &LayoutPoint) -> Vec<DisplayI
             let mut display_items = Vec::new();
 
             for block in blocks {
-                let block_height = block.size.height;
+                let block_height = block.outer_height();
                 display_items.extend(paint_block(block, &left_top));
                 // Blocks stack vertically.
                 left_top = LayoutPoint {
@@ -72,6 +78,9 @@ fn paint_inline(inline: PositionedInlineBox, left_top: &LayoutPoint) -> Vec<Disp
             text,
             style: inline.style,
             layout_point: left_top.clone(),
+            layout_size: inline.size,
+            glyph_runs: inline.glyph_runs,
+            node: inline.node,
         });
         assert!(inline.children.is_empty());
     }
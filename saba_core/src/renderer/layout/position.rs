@@ -1,10 +1,12 @@
 use super::{
-    box_tree::{BlockBox, BlockBoxChildren, BlockBoxData, InlineBox, InlineBoxData},
-    computed_style::ComputedStyle,
+    box_tree::{BlockBox, BlockBoxChildren, BlockBoxData, BoxNode, InlineBox, InlineBoxData},
+    computed_style::{ComputedStyle, LengthPercentageOrAuto, Sides},
     layout_object::{LayoutPoint, LayoutSize},
     line::{split_inline_box, LineBox},
-    text::size_of_text,
+    text::{glyph_runs, size_of_text, GlyphRun},
+    white_space::process_white_space,
 };
+use crate::renderer::font::matching::FontRegistry;
 use alloc::string::String;
 use alloc::vec::Vec;
 
@@ -13,7 +15,25 @@ pub struct PositionedBlockBox {
     pub data: BlockBoxData,
     pub style: ComputedStyle,
     pub children: PositionedBlockBoxChildren,
+    /// The size of the content box.
     pub size: LayoutSize,
+    /// The used widths of the margins, borders and paddings around the content box, in px.
+    pub margin: Sides<i64>,
+    pub border: Sides<i64>,
+    pub padding: Sides<i64>,
+}
+
+impl PositionedBlockBox {
+    /// The height of the margin box, by which the blocks stack vertically.
+    pub fn outer_height(&self) -> i64 {
+        self.margin.top
+            + self.border.top
+            + self.padding.top
+            + self.size.height
+            + self.padding.bottom
+            + self.border.bottom
+            + self.margin.bottom
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,58 +49,192 @@ pub struct PositionedLineBox {
     pub size: LayoutSize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct PositionedInlineBox {
     pub data: InlineBoxData,
     pub style: ComputedStyle,
     pub text: Option<String>,
+    /// The glyphs of the text, if any.
+    pub glyph_runs: Vec<GlyphRun>,
     pub children: Vec<PositionedInlineBox>,
     pub size: LayoutSize,
+    pub node: BoxNode,
+}
+
+/// Boxes are equal if they are laid out the same, whichever nodes generated them.
+impl PartialEq for PositionedInlineBox {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+            && self.style == other.style
+            && self.text == other.text
+            && self.glyph_runs == other.glyph_runs
+            && self.children == other.children
+            && self.size == other.size
+    }
 }
 
-pub fn position(tree: BlockBox, viewport_size: LayoutSize) -> PositionedBlockBox {
-    // 1. Width of blocks are always that of viewport.
+impl Eq for PositionedInlineBox {}
+
+/// Text is measured with the fonts in the registry.
+pub fn position(
+    tree: BlockBox,
+    viewport_size: LayoutSize,
+    fonts: &FontRegistry,
+) -> PositionedBlockBox {
+    // 1. The initial containing block is the viewport.
+    //    Width, margins, borders and paddings of a block are resolved against its containing block,
+    //    whose content box is the containing block of its children.
     // 2. Inline boxes can be fragmented into lines, based on that width.
     // 3. Width & Height of each inline box can be determined from bottom to up.
-    // 4. Height of each block box can be determined from bottom to up.
+    // 4. Height of each block box can be determined from bottom to up, unless specified.
 
-    position_block_box(tree, viewport_size.width)
+    position_block_box(tree, viewport_size.width, Some(viewport_size.height), fonts)
 }
 
-fn position_block_box(tree: BlockBox, width: i64) -> PositionedBlockBox {
+/// `containing_height` is None if the height of the containing block depends on its content.
+///
+/// Vertical margins don't collapse.
+fn position_block_box(
+    tree: BlockBox,
+    containing_width: i64,
+    containing_height: Option<i64>,
+    fonts: &FontRegistry,
+) -> PositionedBlockBox {
+    // <https://www.w3.org/TR/css-display-3/#anonymous>
+    // Anonymous boxes take the initial values of the properties not inherited, e.g. no margins.
+    let anonymous_style;
+    let style = match tree.data {
+        BlockBoxData::Element(_) => &tree.style,
+        BlockBoxData::Anonymous => {
+            anonymous_style = ComputedStyle::inherit(&tree.style);
+            &anonymous_style
+        }
+    };
+
+    // <https://www.w3.org/TR/CSS2/box.html#margin-properties>
+    // <https://www.w3.org/TR/CSS2/box.html#padding-properties>
+    // Percentages are relative to the width of the containing block, even for the top and bottom.
+    let basis = containing_width as f64;
+    let margin = Sides {
+        top: style.margin.top.resolve(basis),
+        right: style.margin.right.resolve(basis),
+        bottom: style.margin.bottom.resolve(basis),
+        left: style.margin.left.resolve(basis),
+    };
+    let border = Sides {
+        top: style.border_width.top as i64,
+        right: style.border_width.right as i64,
+        bottom: style.border_width.bottom as i64,
+        left: style.border_width.left as i64,
+    };
+    let padding = Sides {
+        top: style.padding.top.resolve(basis) as i64,
+        right: style.padding.right.resolve(basis) as i64,
+        bottom: style.padding.bottom.resolve(basis) as i64,
+        left: style.padding.left.resolve(basis) as i64,
+    };
+
+    let (width, margin_left, margin_right) = resolve_horizontal(
+        style
+            .width
+            .resolve(basis)
+            .map(|width| width.max(0.0) as i64),
+        margin.left.map(|margin| margin as i64),
+        margin.right.map(|margin| margin as i64),
+        border.left + padding.left + padding.right + border.right,
+        containing_width,
+    );
+
+    // <https://www.w3.org/TR/CSS2/visudet.html#the-height-property>
+    // Percentages are relative to the height of the containing block, and treated as auto if it depends on the content.
+    let specified_height = match (&style.height, containing_height) {
+        (LengthPercentageOrAuto::Length(px), _) => Some(px.max(0.0) as i64),
+        (height, Some(containing_height)) => height
+            .resolve(containing_height as f64)
+            .map(|height| height.max(0.0) as i64),
+        (_, None) => None,
+    };
+
     let children = match tree.children {
         BlockBoxChildren::Empty => PositionedBlockBoxChildren::Empty,
         BlockBoxChildren::Blocks(blocks) => PositionedBlockBoxChildren::Blocks(
             blocks
                 .into_iter()
-                .map(|block| position_block_box(block, width))
+                .map(|block| position_block_box(block, width, specified_height, fonts))
                 .collect(),
         ),
-        BlockBoxChildren::Inlines(inlines) => {
-            let lines = split_inline_box(inlines, width);
+        BlockBoxChildren::Inlines(mut inlines) => {
+            process_white_space(&mut inlines);
+            let lines = split_inline_box(inlines, width, fonts);
             let positioned = lines
                 .into_iter()
-                .map(|line| position_line(line, width))
+                .map(|line| position_line(line, width, fonts))
                 .collect();
             PositionedBlockBoxChildren::Inlines(positioned)
         }
     };
 
-    let height = height_of_block_children(&children);
+    let height = specified_height.unwrap_or_else(|| height_of_block_children(&children));
 
     PositionedBlockBox {
         data: tree.data,
         style: tree.style,
         children,
         size: LayoutSize { width, height },
+        margin: Sides {
+            top: margin.top.unwrap_or(0.0) as i64,
+            right: margin_right,
+            bottom: margin.bottom.unwrap_or(0.0) as i64,
+            left: margin_left,
+        },
+        border,
+        padding,
+    }
+}
+
+/// <https://www.w3.org/TR/CSS2/visudet.html#blockwidth>
+///
+/// Resolves the used width and the used left and right margins of a block, None of which is auto,
+/// so that the margin box fills the containing block.
+/// `borders_and_paddings` is the sum of the widths of the left and right borders and paddings.
+fn resolve_horizontal(
+    width: Option<i64>,
+    margin_left: Option<i64>,
+    margin_right: Option<i64>,
+    borders_and_paddings: i64,
+    containing_width: i64,
+) -> (i64, i64, i64) {
+    let available = containing_width - borders_and_paddings;
+    match width {
+        // If width is auto, auto margins become zero and the width takes up the rest.
+        None => {
+            let margin_left = margin_left.unwrap_or(0);
+            let margin_right = margin_right.unwrap_or(0);
+            let width = (available - margin_left - margin_right).max(0);
+            (width, margin_left, margin_right)
+        }
+        Some(width) => {
+            let rest = available - width;
+            match (margin_left, margin_right) {
+                // Centered horizontally.
+                (None, None) => {
+                    let margin = (rest / 2).max(0);
+                    (width, margin, rest - margin)
+                }
+                (None, Some(margin_right)) => (width, rest - margin_right, margin_right),
+                (Some(margin_left), None) => (width, margin_left, rest - margin_left),
+                // Over-constrained, in which case the right margin is ignored in a left-to-right block.
+                (Some(margin_left), Some(_)) => (width, margin_left, rest - margin_left),
+            }
+        }
     }
 }
 
-fn position_line(line: LineBox, width: i64) -> PositionedLineBox {
+fn position_line(line: LineBox, width: i64, fonts: &FontRegistry) -> PositionedLineBox {
     let children = line
         .children
         .into_iter()
-        .map(position_inline)
+        .map(|inline| position_inline(inline, fonts))
         .collect::<Vec<_>>();
     let LayoutSize { height, .. } = size_of_inline_children(&children[..]);
     PositionedLineBox {
@@ -89,17 +243,20 @@ fn position_line(line: LineBox, width: i64) -> PositionedLineBox {
     }
 }
 
-fn position_inline(inline: InlineBox) -> PositionedInlineBox {
+fn position_inline(inline: InlineBox, fonts: &FontRegistry) -> PositionedInlineBox {
     let children = inline
         .children
         .into_iter()
-        .map(position_inline)
+        .map(|inline| position_inline(inline, fonts))
         .collect::<Vec<_>>();
 
-    let size = if let Some(text) = &inline.text {
-        size_of_text(text)
+    let (size, glyph_runs) = if let Some(text) = &inline.text {
+        (
+            size_of_text(text, &inline.style, fonts),
+            glyph_runs(text, &inline.style, fonts),
+        )
     } else {
-        size_of_inline_children(&children[..])
+        (size_of_inline_children(&children[..]), Vec::new())
     };
 
     PositionedInlineBox {
@@ -108,6 +265,8 @@ fn position_inline(inline: InlineBox) -> PositionedInlineBox {
         data: inline.data,
         style: inline.style,
         text: inline.text,
+        glyph_runs,
+        node: inline.node,
     }
 }
 
@@ -122,7 +281,7 @@ fn height_of_block_children(children: &PositionedBlockBoxChildren) -> i64 {
     use PositionedBlockBoxChildren::*;
     match children {
         Empty => 0,
-        Blocks(blocks) => blocks.iter().map(|c| c.size.height).sum(),
+        Blocks(blocks) => blocks.iter().map(|c| c.outer_height()).sum(),
         Inlines(lines) => lines.iter().map(|c| c.size.height).sum(),
     }
 }
@@ -1,12 +1,144 @@
+use super::computed_style::{ComputedStyle, LineHeight};
 use super::layout_object::LayoutSize;
+use crate::renderer::font::face::FontData;
+use crate::renderer::font::matching::FontRegistry;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
 
-// TODO: consider font size
-pub fn size_of_text(text: &str) -> LayoutSize {
+/// A part of a text drawn with a font, e.g. the characters the first available family has the glyphs for.
+///
+/// The glyphs are drawn at the font size of the style, each advancing the next by [FontData::advance].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphRun {
+    pub font: Rc<FontData>,
+    pub text: String,
+    /// The position of the origin of the first glyph relative to the left of the text, in px.
+    pub x: f64,
+    /// The position of the baseline relative to the top of the text, in px.
+    pub baseline: f64,
+}
+
+// Positions are never NaN.
+impl Eq for GlyphRun {}
+
+/// Measures the text drawn with the fonts matched against the style.
+///
+/// The width is the sum of the advances of the glyphs, and the height is the line height.
+pub fn size_of_text(text: &str, style: &ComputedStyle, fonts: &FontRegistry) -> LayoutSize {
     LayoutSize {
-        width: CHAR_WIDTH * (text.len() as i64),
-        height: CHAR_HEIGHT,
+        width: fonts.measure(style, text) as i64,
+        height: line_height(style, fonts),
     }
 }
 
-const CHAR_WIDTH: i64 = 8;
-const CHAR_HEIGHT: i64 = 16;
+/// Splits the text into runs of the characters drawn with the same face, as measured by [size_of_text].
+///
+/// The glyphs are aligned on the baseline of the primary font, centered in the line height.
+/// <https://www.w3.org/TR/CSS2/visudet.html#leading>
+pub fn glyph_runs(text: &str, style: &ComputedStyle, fonts: &FontRegistry) -> Vec<GlyphRun> {
+    let metrics = fonts.primary_font(style).data.metrics(style.font_size);
+    let half_leading = (line_height(style, fonts) as f64 - metrics.ascent - metrics.descent) / 2.0;
+    let baseline = half_leading + metrics.ascent;
+
+    let mut runs: Vec<GlyphRun> = Vec::new();
+    let mut x = 0.0;
+    for c in text.chars() {
+        let font = &fonts.font_for_char(style, c).data;
+        match runs.last_mut() {
+            Some(run) if Rc::ptr_eq(&run.font, font) => run.text.push(c),
+            _ => runs.push(GlyphRun {
+                font: Rc::clone(font),
+                text: c.into(),
+                x,
+                baseline,
+            }),
+        }
+        x += font.advance(c, style.font_size);
+    }
+    runs
+}
+
+/// <https://www.w3.org/TR/CSS2/visudet.html#propdef-line-height>
+fn line_height(style: &ComputedStyle, fonts: &FontRegistry) -> i64 {
+    let height = match style.line_height {
+        // The metrics of the primary font.
+        LineHeight::Normal => {
+            let metrics = fonts.primary_font(style).data.metrics(style.font_size);
+            metrics.ascent + metrics.descent + metrics.line_gap
+        }
+        LineHeight::Number(number) => number * style.font_size,
+        LineHeight::Length(length) => length,
+    };
+    height as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::renderer::font::bdf::BitmapFont;
+    use crate::renderer::font::face::FontFace;
+
+    use super::*;
+
+    #[test]
+    fn test_size_of_text() {
+        let fonts = FontRegistry::default();
+        let style = ComputedStyle::default();
+        assert_eq!(
+            size_of_text("hello", &style, &fonts),
+            LayoutSize {
+                width: 40,
+                height: 16
+            }
+        );
+
+        let style = ComputedStyle {
+            font_family: vec!["monospace".into()],
+            font_size: 32.0,
+            line_height: LineHeight::Number(1.5),
+            ..Default::default()
+        };
+        assert_eq!(
+            size_of_text("hi", &style, &fonts),
+            LayoutSize {
+                width: 32,
+                height: 48
+            }
+        );
+    }
+
+    #[test]
+    fn test_glyph_runs() {
+        let mut fonts = FontRegistry::default();
+        let kana = BitmapFont::parse(
+            "STARTFONT 2.1\nSIZE 16 75 75\nFONTBOUNDINGBOX 16 16 0 -2\n\
+             STARTCHAR a\nENCODING 12354\nDWIDTH 16 0\nBBX 0 0 0 0\nBITMAP\nENDCHAR\nENDFONT\n",
+        )
+        .unwrap();
+        fonts.add(FontFace::with_family("Kana".into(), FontData::Bitmap(kana)));
+        let style = ComputedStyle {
+            line_height: LineHeight::Number(1.5),
+            ..Default::default()
+        };
+
+        let runs = glyph_runs("abあc", &style, &fonts);
+        let runs: Vec<_> = runs
+            .iter()
+            .map(|run| {
+                (
+                    matches!(*run.font, FontData::Fixed(_)),
+                    run.text.as_str(),
+                    run.x,
+                )
+            })
+            .collect();
+        assert_eq!(
+            runs,
+            [(true, "ab", 0.0), (false, "あ", 16.0), (true, "c", 32.0)]
+        );
+        // The line is 24px high, and the fixed font is 12px high above the baseline and 4px below.
+        assert_eq!(glyph_runs("a", &style, &fonts)[0].baseline, 16.0);
+    }
+}
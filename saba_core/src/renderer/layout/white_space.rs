@@ -0,0 +1,224 @@
+//! <https://www.w3.org/TR/css-text-3/#white-space-processing>
+//!
+//! The first phase of white space processing, which collapses the spaces and transforms
+//! the segment breaks in the text of an inline formatting context before line breaking.
+//! The spaces at the start and the end of lines are removed in the second phase, in [super::line].
+//!
+//! Servo: <https://github.com/servo/servo/blob/main/components/layout_2020/flow/inline/construct.rs>
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::box_tree::InlineBox;
+use super::computed_style::WhiteSpace;
+
+/// <https://www.w3.org/TR/css-text-3/#white-space-phase-1>
+///
+/// Collapses the white space in the text of the inline boxes. Spaces collapse across the boxes,
+/// so that a space at the start of a box collapses with a space at the end of the previous one.
+pub fn process_white_space(inlines: &mut [InlineBox]) {
+    let mut leaves = Vec::new();
+    for inline in inlines.iter_mut() {
+        collect_leaves(inline, &mut leaves);
+    }
+
+    // The characters of all the boxes, with the index of their box, which are None if removed.
+    let mut chars: Vec<(Option<char>, usize, WhiteSpace)> = Vec::new();
+    for (i, leaf) in leaves.iter().enumerate() {
+        let white_space = leaf.style.white_space;
+        if let Some(text) = &leaf.text {
+            // A carriage return followed by a newline is a single segment break.
+            let text = text.replace("\r\n", "\n");
+            chars.extend(text.chars().map(|c| (Some(c), i, white_space)));
+        }
+    }
+    collapse(&mut chars);
+
+    let mut texts: Vec<String> = leaves.iter().map(|_| String::new()).collect();
+    for (c, i, _) in chars {
+        if let Some(c) = c {
+            texts[i].push(c);
+        }
+    }
+    for (leaf, text) in leaves.iter_mut().zip(texts) {
+        if leaf.text.is_some() {
+            leaf.text = Some(text);
+        }
+    }
+}
+
+fn collect_leaves<'a>(inline: &'a mut InlineBox, leaves: &mut Vec<&'a mut InlineBox>) {
+    if inline.children.is_empty() {
+        leaves.push(inline);
+    } else {
+        for child in inline.children.iter_mut() {
+            collect_leaves(child, leaves);
+        }
+    }
+}
+
+fn collapse(chars: &mut [(Option<char>, usize, WhiteSpace)]) {
+    let collapsible = |c: Option<char>, white_space: WhiteSpace, of: &[char]| {
+        white_space.collapses_spaces() && c.is_some_and(|c| of.contains(&c))
+    };
+
+    // 1. Spaces and tabs before and after a segment break are removed.
+    for i in 0..chars.len() {
+        let (c, _, white_space) = chars[i];
+        if !collapsible(c, white_space, &['\n']) {
+            continue;
+        }
+        for j in (0..i).rev() {
+            let (c, _, white_space) = chars[j];
+            if !collapsible(c, white_space, &[' ', '\t']) {
+                break;
+            }
+            chars[j].0 = None;
+        }
+        for next in chars[i + 1..].iter_mut() {
+            if !collapsible(next.0, next.2, &[' ', '\t']) {
+                break;
+            }
+            next.0 = None;
+        }
+    }
+
+    // 2. Segment breaks which are not preserved are removed after another segment break,
+    // and between East Asian wide characters, and the others are transformed into spaces.
+    // <https://www.w3.org/TR/css-text-3/#line-break-transform>
+    let is_collapsible_break = |(c, _, white_space): (Option<char>, usize, WhiteSpace)| {
+        c == Some('\n') && white_space.collapses_spaces() && !white_space.preserves_newlines()
+    };
+    let mut before = None;
+    for char in chars.iter_mut() {
+        if is_collapsible_break(*char) && before == Some('\n') {
+            char.0 = None;
+        }
+        before = char.0.or(before);
+    }
+    let mut before = None;
+    for i in 0..chars.len() {
+        if is_collapsible_break(chars[i]) {
+            let after = chars[i + 1..].iter().find_map(|&(c, ..)| c);
+            chars[i].0 = match (before, after) {
+                (Some(before), Some(after))
+                    if is_east_asian_wide(before) && is_east_asian_wide(after) =>
+                {
+                    None
+                }
+                _ => Some(' '),
+            };
+        }
+        before = chars[i].0.or(before);
+    }
+
+    // 3. Tabs are transformed into spaces.
+    // 4. A space following another collapsible space collapses, even across the boxes.
+    let mut after_space = false;
+    for (c, _, white_space) in chars.iter_mut() {
+        let Some(character) = *c else {
+            continue;
+        };
+        if white_space.collapses_spaces() && matches!(character, ' ' | '\t') {
+            *c = if after_space { None } else { Some(' ') };
+            after_space = true;
+        } else {
+            after_space = false;
+        }
+    }
+}
+
+/// Whether the character is East Asian Fullwidth, Wide or Halfwidth but not Hangul,
+/// between which segment breaks are removed, as they are written without spaces.
+///
+/// <https://www.w3.org/TR/css-text-4/#line-break-transform>
+fn is_east_asian_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x2E80..=0x303E
+            | 0x3041..=0x312F
+            | 0x3190..=0x4DBF
+            | 0x4E00..=0xA4CF
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF9F
+            | 0xFFE0..=0xFFE6
+            | 0x20000..=0x3FFFD
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::renderer::layout::box_tree::{BoxNode, InlineBoxData};
+    use crate::renderer::layout::computed_style::ComputedStyle;
+
+    use super::*;
+
+    /// Processes the texts in boxes with the styles, and returns the texts.
+    fn process(texts: &[(&str, WhiteSpace)]) -> Vec<String> {
+        let mut inlines: Vec<InlineBox> = texts
+            .iter()
+            .map(|(text, white_space)| InlineBox {
+                data: InlineBoxData::Anonymous,
+                style: ComputedStyle {
+                    white_space: *white_space,
+                    ..Default::default()
+                },
+                text: Some((*text).into()),
+                children: Vec::new(),
+                node: BoxNode::default(),
+            })
+            .collect();
+        process_white_space(&mut inlines);
+        inlines
+            .into_iter()
+            .map(|inline| inline.text.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_collapse() {
+        use WhiteSpace::*;
+        assert_eq!(
+            process(&[("\n    a  \t b \n\n  c\r\n", Normal)]),
+            vec![" a b c "]
+        );
+        // Spaces collapse across the boxes.
+        assert_eq!(
+            process(&[
+                ("a ", Normal),
+                (" b ", Normal),
+                ("  ", Nowrap),
+                ("c", Normal)
+            ]),
+            vec!["a ", "b ", "", "c"]
+        );
+        // But not with preserved ones.
+        assert_eq!(
+            process(&[("a ", Pre), (" b ", Normal), (" c", BreakSpaces)]),
+            vec!["a ", " b ", " c"]
+        );
+        // Segment breaks between Japanese characters are removed.
+        assert_eq!(
+            process(&[("日本\n語の\nRust\n文章", Normal)]),
+            vec!["日本語の Rust 文章"]
+        );
+    }
+
+    #[test]
+    fn test_preserve() {
+        use WhiteSpace::*;
+        assert_eq!(
+            process(&[("  a \n  b  \n\nc", PreLine)]),
+            vec![" a\nb\n\nc"]
+        );
+        for white_space in [Pre, PreWrap, BreakSpaces] {
+            assert_eq!(
+                process(&[("  a \t\r\n  b", white_space)]),
+                vec!["  a \t\n  b"]
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use alloc::boxed::Box;
 use alloc::rc::{Rc, Weak};
 use alloc::vec;
 use alloc::vec::Vec;
@@ -9,30 +10,66 @@ use crate::{
     renderer::layout::computed_style::{ComputedStyle, DisplayType},
 };
 
-use super::css::cssom::CssStyleSheet;
-use super::css::parser::parse_css_stylesheet;
-use super::dom::node::Window;
+use super::css::cascade::{Origin, Stylist};
+use super::css::cssom::{CssStyleSheet, StyleSheetList};
+use super::css::loader::StyleSheetLoader;
+use super::css::media::Device;
+use super::css::restyle::StyleCache;
+use super::dom::event::Event;
+use super::dom::mutation_observer::{MutationObserver, MutationRecord};
+use super::dom::node::{ElementKind, Node, NodeData, Window};
+use super::font::loader::FontLoader;
+use super::font::matching::FontRegistry;
 use super::html::parser::HtmlParser;
 use super::html::token::HtmlTokenizer;
 use super::layout::box_tree::construct_box_tree;
-use super::layout::layout_object::LayoutSize;
-use super::layout::layout_view::{get_style_content, LayoutView};
+use super::layout::layout_object::{LayoutPoint, LayoutSize};
+use super::layout::layout_view::LayoutView;
 use super::layout::paint::paint;
 use super::layout::position::position;
+use crate::error::Error;
+use crate::url::Url;
 use alloc::string::String;
 
 #[derive(Debug, Clone)]
 pub struct Page {
     browser: Weak<RefCell<Browser>>,
     frame: Option<Rc<RefCell<Window>>>,
-    style: Option<CssStyleSheet>,
+    /// The URL of the document, against which the URLs in it are resolved.
+    url: Option<Url>,
+    /// Shared among pages, since it is parsed once when the browser starts.
+    user_agent_stylesheet: Rc<CssStyleSheet>,
+    /// The user stylesheet of the browser the stylist was built with.
+    user_stylesheet: Option<Rc<CssStyleSheet>>,
+    style_sheets: StyleSheetList,
+    /// Built lazily for the viewport given to [Page::display_items].
+    stylist: Option<Stylist>,
+    /// The installed fonts and the fonts of the `@font-face` rules in the style sheets.
+    fonts: FontRegistry,
+    /// The styles of the nodes, kept between calls to [Page::display_items].
+    style_cache: StyleCache,
+    /// Observes the whole document to invalidate the styles.
+    mutation_observer: Option<Rc<MutationObserver>>,
+    /// The mutations delivered to the observer since the last call to [Page::display_items].
+    mutations: Rc<RefCell<Vec<MutationRecord>>>,
+    /// The display items painted last, with the viewport size, reused if nothing changed.
+    display_items: Option<(LayoutSize, Vec<DisplayItem>)>,
 }
 impl Page {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(user_agent_stylesheet: Rc<CssStyleSheet>) -> Self {
         Self {
             browser: Weak::new(),
             frame: None,
-            style: None,
+            url: None,
+            user_agent_stylesheet,
+            user_stylesheet: None,
+            style_sheets: StyleSheetList::default(),
+            stylist: None,
+            fonts: FontRegistry::default(),
+            style_cache: StyleCache::default(),
+            mutation_observer: None,
+            mutations: Rc::new(RefCell::new(Vec::new())),
+            display_items: None,
         }
     }
 
@@ -40,27 +77,369 @@ impl Page {
         self.browser = browser;
     }
 
+    /// Sets the URL of the document to be populated next.
+    pub fn set_url(&mut self, url: Url) {
+        self.url = Some(url);
+    }
+
+    /// Parses the HTML and loads the stylesheets and the web fonts of the document,
+    /// fetching external ones through the browser's fetcher.
     pub fn populate_frame(&mut self, html: String) {
         let frame = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
-        let dom = frame.borrow().document();
-        let style = get_style_content(dom);
-        let cssom = parse_css_stylesheet(style);
+        frame.borrow_mut().set_url(self.url.clone());
+        if let Some(observer) = self.mutation_observer.take() {
+            observer.disconnect();
+        }
+        self.mutations.borrow_mut().clear();
+        self.mutation_observer = Some(self.observe_mutations(&frame));
+        self.style_cache = StyleCache::default();
 
         self.frame = Some(frame);
-        self.style = Some(cssom);
+        self.load_style_sheets();
+    }
+
+    /// Fetches the document at the URL through the browser's fetcher and populates the frame with it,
+    /// e.g. to navigate to the URL of a link clicked.
+    pub fn load(&mut self, url: Url) -> Result<(), Error> {
+        let fetcher = self
+            .browser
+            .upgrade()
+            .and_then(|browser| browser.borrow().fetcher())
+            .ok_or(Error::Network("no fetcher is set to the browser".into()))?;
+        let response = fetcher.fetch(&url)?;
+        self.set_url(url);
+        self.populate_frame(response.body());
+        Ok(())
+    }
+
+    /// Loads the stylesheets and the web fonts of the document.
+    fn load_style_sheets(&mut self) {
+        let Some(dom) = self.document() else {
+            return;
+        };
+        let browser = self.browser.upgrade();
+        let fetcher = browser
+            .as_ref()
+            .and_then(|browser| browser.borrow().fetcher());
+        let installed = browser.map_or_else(
+            || Rc::new(FontRegistry::default()),
+            |browser| browser.borrow().fonts(),
+        );
+        self.style_sheets = StyleSheetLoader::new(self.url.as_ref(), fetcher.as_deref()).load(dom);
+        self.fonts = FontLoader::new(self.url.as_ref(), fetcher.as_deref())
+            .load(&self.style_sheets, installed);
+        self.stylist = None;
+    }
+
+    /// Observes all the mutations of the document, which invalidate the styles.
+    fn observe_mutations(&self, frame: &Rc<RefCell<Window>>) -> Rc<MutationObserver> {
+        let mutations = Rc::clone(&self.mutations);
+        StyleCache::observe(
+            frame,
+            Box::new(move |records, _| mutations.borrow_mut().extend(records)),
+        )
+    }
+
+    /// Takes the mutations since the last call, including those not delivered to the observer yet.
+    fn take_mutations(&mut self) -> Vec<MutationRecord> {
+        let mut records = core::mem::take(&mut *self.mutations.borrow_mut());
+        if let Some(observer) = &self.mutation_observer {
+            records.extend(observer.take_records());
+        }
+        records
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-documentorshadowroot-stylesheets>
+    pub fn style_sheets(&self) -> &StyleSheetList {
+        &self.style_sheets
+    }
+
+    /// <https://www.w3.org/TR/cssom-1/#dom-stylesheet-disabled>
+    pub fn set_style_sheet_disabled(&mut self, index: usize, disabled: bool) {
+        if self.style_sheets.set_disabled(index, disabled) {
+            self.stylist = None;
+        }
+    }
+
+    /// Rebuilds the stylist for the device if the stylesheets, including the user stylesheet of the browser, changed,
+    /// or the result of any media query in them would change.
+    /// Returns true if rebuilt, in which case all the styles are invalidated.
+    fn update_stylist(&mut self, device: &Device) -> bool {
+        let user_stylesheet = self
+            .browser
+            .upgrade()
+            .and_then(|browser| browser.borrow().user_stylesheet());
+        let user_stylesheet_changed = match (&user_stylesheet, &self.user_stylesheet) {
+            (Some(new), Some(old)) => !Rc::ptr_eq(new, old),
+            (new, old) => new.is_some() != old.is_some(),
+        };
+        if !user_stylesheet_changed
+            && !self
+                .stylist
+                .as_ref()
+                .map_or(true, |stylist| stylist.media_results_differ(device))
+        {
+            return false;
+        }
+        let mut stylist = Stylist::new(*device);
+        stylist.add_stylesheet(&self.user_agent_stylesheet, Origin::UserAgent);
+        if let Some(user_stylesheet) = &user_stylesheet {
+            stylist.add_stylesheet(user_stylesheet, Origin::User);
+        }
+        self.user_stylesheet = user_stylesheet;
+        stylist.add_style_sheet_list(&self.style_sheets);
+        self.stylist = Some(stylist);
+        self.style_cache.invalidate_all();
+        true
+    }
+
+    pub fn document(&self) -> Option<Rc<RefCell<Node>>> {
+        self.frame.as_ref().map(|frame| frame.borrow().document())
+    }
+
+    /// Dispatches an event coming from the UI, e.g. a click, to the target node.
+    ///
+    /// Native listeners can be registered beforehand with [Node::add_event_listener].
+    /// Returns false if the event was canceled.
+    pub fn dispatch_event(
+        &mut self,
+        target: Rc<RefCell<Node>>,
+        event: &mut Event,
+    ) -> Result<bool, Error> {
+        let result = Node::dispatch_event(target, event);
+        if let Some(frame) = &self.frame {
+            Window::perform_microtask_checkpoint(frame);
+        }
+        result
     }
 
-    pub fn display_items(&self, viewport_size: LayoutSize) -> Vec<DisplayItem> {
-        if let (Some(frame), Some(cssom)) = (&self.frame, &self.style) {
-            let dom = frame.borrow().document();
-            let layout_view = LayoutView::layout(dom, cssom);
+    /// Returns the node whose text is painted at the point in the viewport, e.g. the target of a click,
+    /// by the display items painted last.
+    pub fn hit_test(&self, point: &LayoutPoint) -> Option<Rc<RefCell<Node>>> {
+        let (_, display_items) = self.display_items.as_ref()?;
+        display_items
+            .iter()
+            .rev()
+            .find_map(|item| item.hit_test(point))
+    }
 
-            let box_tree = construct_box_tree(layout_view);
-            let positioned = position(box_tree, viewport_size);
+    /// Returns the URL to navigate to, requested by e.g. clicking a link.
+    pub fn take_navigation_request(&mut self) -> Option<Url> {
+        self.frame
+            .as_ref()
+            .and_then(|frame| frame.borrow_mut().take_navigation_request())
+    }
 
-            paint(positioned)
-        } else {
-            Vec::new()
+    /// Styles and lays out the document for the viewport, re-evaluating media queries if it changed.
+    ///
+    /// Only the nodes affected by the mutations since the last call, and the nodes animating,
+    /// are restyled, and the display items are reused if nothing changed.
+    pub fn display_items(&mut self, viewport_size: LayoutSize) -> Vec<DisplayItem> {
+        let Some(dom) = self.document() else {
+            return Vec::new();
+        };
+        let mutations = self.take_mutations();
+        if mutations.iter().any(affects_style_sheets) {
+            self.load_style_sheets();
         }
+        let device = Device::new(viewport_size.width as f64, viewport_size.height as f64);
+        let rebuilt = self.update_stylist(&device);
+        let now = self
+            .browser
+            .upgrade()
+            .map_or(0.0, |browser| browser.borrow().current_time());
+        let animating = self.style_cache.update_animations(now);
+        if let Some((size, display_items)) = &self.display_items {
+            if !rebuilt && !animating && mutations.is_empty() && *size == viewport_size {
+                return display_items.clone();
+            }
+        }
+
+        let stylist = self.stylist.as_ref().expect("the stylist is updated above");
+        self.style_cache
+            .invalidate(&mutations, stylist.invalidation_map());
+        let layout_view = LayoutView::layout(dom, stylist, viewport_size, &mut self.style_cache);
+
+        let box_tree = construct_box_tree(layout_view);
+        let positioned = position(box_tree, viewport_size, &self.fonts);
+
+        let display_items = paint(positioned);
+        self.display_items = Some((viewport_size, display_items.clone()));
+        display_items
+    }
+
+    /// Whether transitions or animations are running, so that the page needs painting again
+    /// after the browser advances its clock.
+    pub fn needs_frame(&self) -> bool {
+        self.style_cache.is_animating()
+    }
+}
+
+/// Whether the mutation may change the stylesheets, i.e. involves a `<style>` or `<link>` element.
+fn affects_style_sheets(record: &MutationRecord) -> bool {
+    let is_style_sheet_owner = |node: Rc<RefCell<Node>>| {
+        matches!(
+            &node.borrow().data,
+            NodeData::Element(e) if matches!(e.tag_name(), ElementKind::Style | ElementKind::Link)
+        )
+    };
+    Node::inclusive_ancestors(Rc::clone(&record.target)).any(is_style_sheet_owner)
+        || record
+            .added_nodes
+            .iter()
+            .chain(&record.removed_nodes)
+            .any(|node| Node::preorder(Rc::clone(node)).any(is_style_sheet_owner))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::test_util::TestFetcher;
+    use crate::renderer::css::value::Color;
+    use crate::renderer::dom::event::EventInit;
+
+    use super::*;
+
+    const VIEWPORT: LayoutSize = LayoutSize {
+        width: 800,
+        height: 600,
+    };
+
+    /// The point at the middle of the text painted first that is equal to `text`.
+    fn point_of(display_items: &[DisplayItem], text: &str) -> LayoutPoint {
+        display_items
+            .iter()
+            .find_map(|item| match item {
+                DisplayItem::Text {
+                    text: t,
+                    layout_point,
+                    layout_size,
+                    ..
+                } if t == text => Some(LayoutPoint {
+                    x: layout_point.x + layout_size.width / 2,
+                    y: layout_point.y + layout_size.height / 2,
+                }),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_click_link() {
+        let browser = Browser::new();
+        browser
+            .borrow_mut()
+            .set_fetcher(Rc::new(TestFetcher::new(vec![(
+                "example.com:80/next.html",
+                "<html><head></head><body><p>next page</p></body></html>",
+            )])));
+        let page = browser.borrow().current_page();
+        let mut page = page.borrow_mut();
+        page.set_url(
+            Url::new("http://example.com/dir/index.html".into())
+                .parse()
+                .unwrap(),
+        );
+        page.populate_frame(
+            r#"<html><head></head><body><p>text <a href="../next.html">link</a></p></body></html>"#
+                .into(),
+        );
+
+        // What the UI does on a click: hit test, dispatch the event, and navigate if requested.
+        let display_items = page.display_items(VIEWPORT);
+        let target = page.hit_test(&point_of(&display_items, "link")).unwrap();
+        assert_eq!("link", target.borrow().text_content());
+        let mut event = Event::new(
+            "click",
+            EventInit {
+                bubbles: true,
+                cancelable: true,
+            },
+        );
+        assert_eq!(Ok(true), page.dispatch_event(target, &mut event));
+        let url = page.take_navigation_request().unwrap();
+        page.load(url).unwrap();
+
+        let display_items = page.display_items(VIEWPORT);
+        let texts: Vec<_> = display_items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["next page"], texts);
+
+        // Nothing is painted outside the texts.
+        assert!(page
+            .hit_test(&LayoutPoint {
+                x: VIEWPORT.width - 1,
+                y: VIEWPORT.height - 1
+            })
+            .is_none());
+    }
+
+    #[test]
+    fn test_user_stylesheet() {
+        let browser = Browser::new();
+        let page = browser.borrow().current_page();
+        page.borrow_mut().populate_frame(
+            "<html><head><style>h1 { color: blue; } p { color: blue; }</style></head>\
+             <body><h1>heading</h1><p>paragraph</p><a>link</a></body></html>"
+                .into(),
+        );
+        let color_of = |text: &str| {
+            page.borrow_mut()
+                .display_items(VIEWPORT)
+                .into_iter()
+                .find_map(|item| match item {
+                    DisplayItem::Text { text: t, style, .. } if t == text => Some(style.color),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(Color::BLACK, color_of("link"));
+
+        // Takes effect on the page painted already.
+        browser.borrow_mut().set_user_stylesheet(
+            "a { color: red; } h1 { color: red; } p { color: red !important; }".into(),
+        );
+        // Overrides the user-agent stylesheet.
+        assert_eq!(Color::rgb(255, 0, 0), color_of("link"));
+        // Overridden by the author stylesheet, except for !important.
+        assert_eq!(Color::rgb(0, 0, 255), color_of("heading"));
+        assert_eq!(Color::rgb(255, 0, 0), color_of("paragraph"));
+    }
+
+    #[test]
+    fn test_box_model() {
+        let browser = Browser::new();
+        let page = browser.borrow().current_page();
+        let mut page = page.borrow_mut();
+        page.populate_frame(
+            "<html><head><style>h1 { margin: 0; height: 100px; } \
+             p { width: 50%; margin: 0 auto; padding: calc(10% - 2px); }</style></head>\
+             <body><h1>a</h1><p>text</p></body></html>"
+                .into(),
+        );
+        let layout_point = page
+            .display_items(VIEWPORT)
+            .into_iter()
+            .find_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } if text == "text" => Some(layout_point),
+                _ => None,
+            })
+            .unwrap();
+        // The content box of the body is 784px wide at (8, 8), by the margin of the user-agent stylesheet.
+        // The heading is 100px high, and the paragraph is 392px wide with the paddings of 76px,
+        // centered by the margins of 120px.
+        assert_eq!(
+            LayoutPoint {
+                x: 8 + 120 + 76,
+                y: 8 + 100 + 76
+            },
+            layout_point
+        );
     }
 }
@@ -1,4 +1,5 @@
 use alloc::{
+    format,
     string::{String, ToString},
     vec::Vec,
 };
@@ -50,6 +51,38 @@ impl Url {
         self.searchpart.clone()
     }
 
+    /// Parses `input` relative to this URL, e.g. the `href` of a `<link>` element.
+    ///
+    /// <https://url.spec.whatwg.org/#concept-basic-url-parser>
+    pub fn join(&self, input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let input = input.split_once('#').map_or(input, |(url, _fragment)| url);
+        let origin = format!("http://{}:{}", self.host, self.port);
+        let url = if input.contains("://") {
+            input.to_string()
+        } else if let Some(rest) = input.strip_prefix("//") {
+            format!("http://{}", rest)
+        } else if input.starts_with('/') {
+            format!("{}{}", origin, remove_dot_segments(input))
+        } else if input.starts_with('?') {
+            format!("{}/{}{}", origin, self.path, input)
+        } else if input.is_empty() {
+            return Ok(self.clone());
+        } else {
+            // Replace the last segment of the path.
+            let directory = match self.path.rfind('/') {
+                Some(index) => &self.path[..=index],
+                None => "",
+            };
+            format!(
+                "{}{}",
+                origin,
+                remove_dot_segments(&format!("/{}{}", directory, input))
+            )
+        };
+        Url::new(url).parse()
+    }
+
     fn is_http(&self) -> bool {
         self.url.starts_with("http://")
     }
@@ -110,6 +143,41 @@ impl Url {
     }
 }
 
+/// Resolves `.` and `..` segments in an absolute path, keeping the query as is.
+///
+/// <https://datatracker.ietf.org/doc/html/rfc3986#section-5.2.4>
+fn remove_dot_segments(path: &str) -> String {
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path, None),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    let mut input = path.split('/').skip(1).peekable();
+    while let Some(segment) = input.next() {
+        let is_last = input.peek().is_none();
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => {
+                segments.push(segment);
+                continue;
+            }
+        }
+        // `a/.` and `a/..` refer to a directory.
+        if is_last {
+            segments.push("");
+        }
+    }
+    let mut result = format!("/{}", segments.join("/"));
+    if let Some(query) = query {
+        result.push('?');
+        result.push_str(query);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +247,37 @@ mod tests {
         assert_eq!(expected, Url::new(url).parse());
     }
 
+    #[test]
+    fn test_join() {
+        let base = Url::new("http://example.com:8888/a/b/index.html?x=1".to_string())
+            .parse()
+            .unwrap();
+        let join = |input: &str| {
+            base.join(input)
+                .map(|url| format!("{}:{}/{}?{}", url.host, url.port, url.path, url.searchpart))
+        };
+        assert_eq!(
+            join("style.css"),
+            Ok("example.com:8888/a/b/style.css?".into())
+        );
+        assert_eq!(
+            join("../c/./style.css?v=2"),
+            Ok("example.com:8888/a/c/style.css?v=2".into())
+        );
+        assert_eq!(
+            join("/style.css#top"),
+            Ok("example.com:8888/style.css?".into())
+        );
+        assert_eq!(
+            join("?y=2"),
+            Ok("example.com:8888/a/b/index.html?y=2".into())
+        );
+        assert_eq!(join("//other.com/s.css"), Ok("other.com:80/s.css?".into()));
+        assert_eq!(join("http://other.com:81/"), Ok("other.com:81/?".into()));
+        assert_eq!(join("../../.."), Ok("example.com:8888/?".into()));
+        assert!(join("https://example.com/").is_err());
+    }
+
     #[test]
     fn test_no_scheme() {
         let url = "example.com".to_string();
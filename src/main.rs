@@ -13,6 +13,9 @@ use ui_wasabi::app::WasabiUI;
 
 fn main() -> u64 {
     let browser = Browser::new();
+    browser
+        .borrow_mut()
+        .set_fetcher(Rc::new(HttpClient::new()));
 
     let ui = Rc::new(RefCell::new(WasabiUI::new(browser)));
     match ui.borrow_mut().start() {
@@ -2,19 +2,30 @@ use core::cell::RefCell;
 
 use alloc::format;
 use alloc::rc::Rc;
+use alloc::string::{String, ToString};
 use alloc::vec;
+use noli::prelude::SystemApi;
+use noli::sys::api::MouseEvent;
+use noli::sys::wasabi::Api;
 use noli::window::StringSize;
 use noli::window::Window;
 use saba_core::display_item::DisplayItem;
+use saba_core::renderer::css::value::Color;
+use saba_core::renderer::dom::event::{Event, EventInit};
+use saba_core::renderer::font::bdf::BitmapGlyph;
+use saba_core::renderer::font::face::{FixedFont, FontData};
 use saba_core::renderer::layout::computed_style::{ComputedStyle, DisplayType};
 use saba_core::renderer::layout::layout_object::LayoutPoint;
 use saba_core::renderer::layout::layout_object::LayoutSize;
+use saba_core::renderer::layout::text::GlyphRun;
 use saba_core::{browser::Browser, error::Error};
 
 #[derive(Debug)]
 pub struct WasabiUI {
     browser: Rc<RefCell<Browser>>,
     window: Window,
+    /// Whether a mouse button was down at the last input, so that holding it is a single click.
+    mouse_down: bool,
 }
 
 type Result<T> = core::result::Result<T, Error>;
@@ -32,16 +43,17 @@ impl WasabiUI {
                 WINDOW_HEIGHT,
             )
             .unwrap(),
+            mouse_down: false,
         }
     }
 
     pub fn start(&mut self) -> Result<()> {
         self.setup()?;
 
-        self.run_app()?;
-
         self.test_display_page()?;
 
+        self.run_app()?;
+
         Ok(())
     }
 
@@ -52,9 +64,108 @@ impl WasabiUI {
     }
 
     fn run_app(&mut self) -> Result<()> {
+        loop {
+            self.handle_mouse_input()?;
+            self.run_animation_frame()?;
+        }
+    }
+
+    /// Repaints the page if a frame is due while its transitions or animations are running.
+    /// It returns without waiting, so that the inputs are handled between the frames.
+    fn run_animation_frame(&mut self) -> Result<()> {
+        let now = Api::get_time_ms() as f64;
+        if self.browser.borrow_mut().tick(now) {
+            self.update_ui()?;
+        }
         Ok(())
     }
 
+    fn handle_mouse_input(&mut self) -> Result<()> {
+        let Some(MouseEvent { button, position }) = Api::get_mouse_cursor_info() else {
+            return Ok(());
+        };
+        let pressed = button.l() || button.c() || button.r();
+        let clicked = pressed && !self.mouse_down;
+        self.mouse_down = pressed;
+        if !clicked {
+            return Ok(());
+        }
+
+        // The position relative to the content area, where the page is painted.
+        let point = LayoutPoint {
+            x: position.x - WINDOW_INIT_X_POS - WINDOW_PADDING,
+            y: position.y - WINDOW_INIT_Y_POS - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - WINDOW_PADDING,
+        };
+        if !(0..CONTENT_AREA_WIDTH).contains(&point.x)
+            || !(0..CONTENT_AREA_HEIGHT).contains(&point.y)
+        {
+            return Ok(());
+        }
+        self.click(point)
+    }
+
+    /// Dispatches a click to the node at the point, and navigates if it activated a link.
+    fn click(&mut self, point: LayoutPoint) -> Result<()> {
+        let page = self.browser.borrow().current_page();
+        let Some(target) = page.borrow().hit_test(&point) else {
+            return Ok(());
+        };
+        let mut event = Event::new(
+            "click",
+            EventInit {
+                bubbles: true,
+                cancelable: true,
+            },
+        );
+        page.borrow_mut().dispatch_event(target, &mut event)?;
+
+        let navigation_request = page.borrow_mut().take_navigation_request();
+        if let Some(url) = navigation_request {
+            // A link failing to load keeps the current page, instead of terminating the browser.
+            let result = page.borrow_mut().load(url);
+            match result {
+                Ok(()) => self.show_status("")?,
+                Err(error) => self.show_status(&format!("failed to load the page: {:?}", error))?,
+            }
+        }
+        self.update_ui()
+    }
+
+    /// Shows the message in the toolbar, cut to its width.
+    fn show_status(&mut self, message: &str) -> Result<()> {
+        let max_chars = (CONTENT_AREA_WIDTH / FixedFont::UI.cell_width as i64) as usize;
+        let message: String = message.chars().take(max_chars).collect();
+        self.window
+            .fill_rect(WHITE, 0, 0, WINDOW_WIDTH, TOOLBAR_HEIGHT)
+            .map_err(|error| {
+                Error::InvalidUI(format!("failed to clear the toolbar: {:?}", error))
+            })?;
+        self.window
+            .draw_string(
+                BLACK,
+                WINDOW_PADDING,
+                WINDOW_PADDING,
+                &message,
+                StringSize::Medium,
+                false,
+            )
+            .map_err(|error| Error::InvalidUI(format!("failed to show the status: {:?}", error)))?;
+        self.window.flush();
+        Ok(())
+    }
+
+    fn clear_content_area(&mut self) -> Result<()> {
+        self.window
+            .fill_rect(
+                WHITE,
+                0,
+                TOOLBAR_HEIGHT,
+                WINDOW_WIDTH,
+                WINDOW_HEIGHT - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
+            )
+            .map_err(|error| Error::InvalidUI(format!("failed to clear the page: {:?}", error)))
+    }
+
     /// A method just for development
     fn test_display_page(&mut self) -> Result<()> {
         let html = r#"<!doctype html><html><head></head><body><a>inline1 inline1 inline1</a>inline2 inline2 inline2<a>inline3 inline3 inline3<p>block4 block4 block4</p><p>block5 block5 block5</p>inline6 inline6 inline6</body></html>"#;
@@ -68,36 +179,30 @@ impl WasabiUI {
         Ok(())
     }
 
+    /// Repaints the content area, which is cleared first so that nothing painted by the previous
+    /// frame is left, e.g. a text that has moved.
     fn update_ui(&mut self) -> Result<()> {
         let display_items =
             self.browser
                 .borrow()
                 .current_page()
-                .borrow()
+                .borrow_mut()
                 .display_items(LayoutSize {
                     width: CONTENT_AREA_WIDTH,
                     height: CONTENT_AREA_HEIGHT,
                 });
 
+        self.clear_content_area()?;
         for item in display_items {
             match item {
                 DisplayItem::Text {
-                    text,
                     style,
                     layout_point,
-                } => self
-                    .window
-                    .draw_string(
-                        BLACK, // TODO: use style
-                        layout_point.x + WINDOW_PADDING,
-                        layout_point.y + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                        &text,
-                        StringSize::Medium, // TODO: use style
-                        false,              // TODO: use style
-                    )
-                    .map_err(|error| {
-                        Error::InvalidUI(format!("failed to draw string: {:?}", error))
-                    }),
+                    glyph_runs,
+                    ..
+                } => glyph_runs
+                    .iter()
+                    .try_for_each(|run| self.draw_glyph_run(run, &style, &layout_point)),
                 _ => {
                     todo!()
                 }
@@ -107,6 +212,108 @@ impl WasabiUI {
 
         Ok(())
     }
+
+    /// Draws the glyphs of the text at the point in the content area, each advancing the next.
+    fn draw_glyph_run(
+        &mut self,
+        run: &GlyphRun,
+        style: &ComputedStyle,
+        layout_point: &LayoutPoint,
+    ) -> Result<()> {
+        let color = rgb(style.color);
+        let mut x = (layout_point.x + WINDOW_PADDING) as f64 + run.x;
+        let baseline = (layout_point.y + WINDOW_PADDING + TOOLBAR_HEIGHT) as f64 + run.baseline;
+        for c in run.text.chars() {
+            match &*run.font {
+                FontData::Bitmap(font) => {
+                    if let Some(glyph) = font.glyph(c).or_else(|| font.default_glyph()) {
+                        let scale = style.font_size / font.pixel_size as f64;
+                        self.draw_bitmap_glyph(glyph, color, x, baseline, scale)?;
+                    }
+                }
+                FontData::TrueType(font) => {
+                    let glyph = font.glyph_index(c).unwrap_or(0);
+                    if let Some(glyph) = font.rasterize(glyph, style.font_size) {
+                        self.draw_bitmap_glyph(&glyph, color, x, baseline, 1.0)?;
+                    }
+                }
+                FontData::Fixed(_) => self.draw_os_glyph(c, color, x, baseline, style.font_size)?,
+            }
+            x += run.font.advance(c, style.font_size);
+        }
+
+        // <https://www.w3.org/TR/css-text-decor-3/#text-decoration-line-property>
+        if style.text_decoration_line.underline {
+            let left = (layout_point.x + WINDOW_PADDING) as f64 + run.x;
+            self.fill_rect(color, left, baseline + 1.0, x - left, 1.0)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the pixels of the bitmap, each of which is a square of `scale` px,
+    /// with its origin at the point on the baseline.
+    fn draw_bitmap_glyph(
+        &mut self,
+        glyph: &BitmapGlyph,
+        color: u32,
+        x: f64,
+        baseline: f64,
+        scale: f64,
+    ) -> Result<()> {
+        let left = x + glyph.x_offset as f64 * scale;
+        let top = baseline - (glyph.y_offset + glyph.height as i32) as f64 * scale;
+        for row in 0..glyph.height {
+            for column in 0..glyph.width {
+                if glyph.pixel(column, row) {
+                    self.fill_rect(
+                        color,
+                        left + column as f64 * scale,
+                        top + row as f64 * scale,
+                        scale,
+                        scale,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the character with the font of the OS, whose size is the nearest to the font size.
+    fn draw_os_glyph(
+        &mut self,
+        c: char,
+        color: u32,
+        x: f64,
+        baseline: f64,
+        font_size: f64,
+    ) -> Result<()> {
+        let (size, scale) = match font_size {
+            size if size < 24.0 => (StringSize::Medium, 1),
+            size if size < 40.0 => (StringSize::Large, 2),
+            _ => (StringSize::XLarge, 3),
+        };
+        let top = baseline as i64 - FixedFont::UI.ascent as i64 * scale;
+        self.window
+            .draw_string(color, x as i64, top, &c.to_string(), size, false)
+            .map_err(|error| Error::InvalidUI(format!("failed to draw string: {:?}", error)))
+    }
+
+    fn fill_rect(&mut self, color: u32, x: f64, y: f64, width: f64, height: f64) -> Result<()> {
+        self.window
+            .fill_rect(
+                color,
+                x as i64,
+                y as i64,
+                (width.ceil() as i64).max(1),
+                (height.ceil() as i64).max(1),
+            )
+            .map_err(|error| Error::InvalidUI(format!("failed to fill rect: {:?}", error)))
+    }
+}
+
+/// The color in 0xRRGGBB, ignoring the alpha channel.
+fn rgb(color: Color) -> u32 {
+    ((color.r as u32) << 16) | ((color.g as u32) << 8) | color.b as u32
 }
 
 pub const WINDOW_WIDTH: i64 = 600;
@@ -120,10 +327,6 @@ pub const CONTENT_AREA_WIDTH: i64 = WINDOW_WIDTH - WINDOW_PADDING * 2;
 pub const CONTENT_AREA_HEIGHT: i64 =
     WINDOW_HEIGHT - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - WINDOW_PADDING * 2;
 
-pub const CHAR_WIDTH: i64 = 8;
-pub const CHAR_HEIGHT: i64 = 16;
-pub const CHAR_HEIGHT_WITH_PADDING: i64 = CHAR_HEIGHT + 4;
-
 pub const WINDOW_INIT_X_POS: i64 = 30;
 pub const WINDOW_INIT_Y_POS: i64 = 50;
 